@@ -0,0 +1,227 @@
+use super::buffer::OverflowPolicy;
+use super::record::{Record, RecordQueue};
+#[cfg(feature = "serde")]
+use super::serialize::SerializeJson;
+use super::{ULog, ULogData, Value};
+use core::cell::{Cell, RefCell};
+use core::future::{poll_fn, Future};
+use core::task::{Poll, Waker};
+
+/// An asynchronous logger, like a network socket or a writer of an async runtime,
+/// which is handed complete statements captured as [`Record`]s.
+///
+/// Statements are logged from synchronous code, so they are queued by an [`AsyncQueueLogger`],
+/// and a task then forwards them to the asynchronous logger with [`AsyncQueueLogger::run`].
+pub trait ULogAsync {
+    /// Logs a complete statement.
+    fn log_record<const SIZE: usize>(&self, record: &Record<SIZE>) -> impl Future<Output = ()>;
+
+    /// See [`ULog::flush`]; defaults to doing nothing.
+    fn flush(&self) -> impl Future<Output = ()> {
+        async {}
+    }
+}
+
+impl<Logger: ULogAsync + ?Sized> ULogAsync for &Logger {
+    fn log_record<const SIZE: usize>(&self, record: &Record<SIZE>) -> impl Future<Output = ()> {
+        (**self).log_record(record)
+    }
+
+    fn flush(&self) -> impl Future<Output = ()> {
+        (**self).flush()
+    }
+}
+
+/// Queues logging statements without blocking, until a task forwards them to a [`ULogAsync`],
+/// so that asynchronous loggers can be used without blocking the executor.
+///
+/// When the queue is full, statements are dropped according to the [`OverflowPolicy`];
+/// since the caller can't wait for the task, [`OverflowPolicy::Block`] drops the new statement.
+/// Up to `STATEMENTS` statements are kept, each of them taking up to `SIZE` bytes (see [`Record`]).
+///
+/// The queue is meant to be shared between tasks of a single-threaded executor, and isn't [`Sync`].
+///
+/// ```
+/// use ulog::asynchronous::{AsyncQueueLogger, ULogAsync};
+/// use ulog::buffer::OverflowPolicy;
+/// use ulog::record::Record;
+///
+/// struct Socket;
+///
+/// impl ULogAsync for Socket {
+///     async fn log_record<const SIZE: usize>(&self, record: &Record<SIZE>) {
+///         // socket.write_all(record.to_string().as_bytes()).await
+///     }
+/// }
+///
+/// async fn logging_task(logger: &AsyncQueueLogger<8, 128>) -> ! {
+///     logger.run(&Socket).await
+/// }
+///
+/// let logger = AsyncQueueLogger::<8, 128>::new(OverflowPolicy::DropOldest);
+/// ulog::info!(logger, "Queued until the logging task runs");
+/// ```
+pub struct AsyncQueueLogger<const STATEMENTS: usize, const SIZE: usize> {
+    policy: OverflowPolicy,
+    current: RefCell<Option<Record<SIZE>>>,
+    queue: RefCell<RecordQueue<STATEMENTS, SIZE>>,
+    dropped: Cell<usize>,
+    waker: RefCell<Option<Waker>>,
+}
+
+impl<const STATEMENTS: usize, const SIZE: usize> AsyncQueueLogger<STATEMENTS, SIZE> {
+    pub fn new(policy: OverflowPolicy) -> Self {
+        Self {
+            policy,
+            current: RefCell::new(None),
+            queue: RefCell::new(RecordQueue::new()),
+            dropped: Cell::new(0),
+            waker: RefCell::new(None),
+        }
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Returns the number of statements waiting in the queue.
+    pub fn len(&self) -> usize {
+        self.queue.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.borrow().is_empty()
+    }
+
+    /// Returns the number of statements dropped because of the [`OverflowPolicy`].
+    pub fn dropped(&self) -> usize {
+        self.dropped.get()
+    }
+
+    /// Waits until the queue holds at least one statement; only the last task waiting gets woken up.
+    pub fn wait(&self) -> impl Future<Output = ()> + '_ {
+        poll_fn(|context| {
+            if self.queue.borrow().is_empty() {
+                *self.waker.borrow_mut() = Some(context.waker().clone());
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        })
+    }
+
+    /// Forwards all of the queued statements to `logger`, from the oldest to the newest.
+    pub async fn drain(&self, logger: &impl ULogAsync) {
+        loop {
+            let Some(record) = self.queue.borrow_mut().pop() else {
+                break;
+            };
+            logger.log_record(&record).await;
+        }
+    }
+
+    /// Forwards the statements to `logger` as they get queued, forever; meant to be the body of a logging task.
+    pub async fn run(&self, logger: &impl ULogAsync) -> ! {
+        loop {
+            self.wait().await;
+            self.drain(logger).await;
+        }
+    }
+}
+
+impl<const STATEMENTS: usize, const SIZE: usize> ULog for AsyncQueueLogger<STATEMENTS, SIZE> {
+    fn log_str(&self, _log_data: &ULogData, string: &str) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_str(string);
+        }
+    }
+
+    fn log_str_continued(&self, _log_data: &ULogData, string: &str) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_str_continued(string);
+        }
+    }
+
+    fn log_fmt(&self, _log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_fmt(args);
+        }
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_format(key, value);
+        }
+    }
+
+    fn log_value(&self, _log_data: &ULogData, key: &str, value: Value<'_>) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_value(key, value);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, _log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_serialize(key, value);
+        }
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        *self.current.borrow_mut() = Some(Record::new(log_data.clone()));
+    }
+
+    fn log_end(&self, _log_data: &ULogData) {
+        let Some(record) = self.current.borrow_mut().take() else {
+            return;
+        };
+
+        if self.policy.push(&self.queue, record, || {}) {
+            self.dropped.set(self.dropped.get() + 1);
+        }
+        if let Some(waker) = self.waker.borrow_mut().take() {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+    use std::pin::pin;
+    use std::task::Context;
+
+    #[derive(Default)]
+    struct VecLogger {
+        logs: RefCell<Vec<String>>,
+    }
+
+    impl ULogAsync for VecLogger {
+        async fn log_record<const SIZE: usize>(&self, record: &Record<SIZE>) {
+            self.logs.borrow_mut().push(record.to_string());
+        }
+    }
+
+    #[test]
+    fn test_async_queue_logger() {
+        let logger = AsyncQueueLogger::<2, 64>::new(OverflowPolicy::Block);
+        let sink = VecLogger::default();
+        let mut context = Context::from_waker(Waker::noop());
+
+        let mut task = pin!(logger.run(&sink));
+        assert!(task.as_mut().poll(&mut context).is_pending());
+
+        info!(logger, "Hello");
+        warn!(logger, "world", "value" => 32);
+        error!(logger, "Dropped");
+        assert_eq!(logger.len(), 2);
+        assert_eq!(logger.dropped(), 1);
+
+        assert!(task.as_mut().poll(&mut context).is_pending());
+        assert!(logger.is_empty());
+        assert_eq!(sink.logs.borrow().len(), 2);
+        assert!(sink.logs.borrow()[0].ends_with(" Hello"));
+        assert!(sink.logs.borrow()[1].ends_with(" world value=32"));
+    }
+}
@@ -0,0 +1,80 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+// The read-modify-write operations used by the loggers and the global slots. Targets without atomic
+// compare-and-swap, like `thumbv6m-none-eabi` or `riscv32imc-unknown-none-elf`, only have atomic loads and stores;
+// with the `critical-section` feature, these operations are then made of a load and a store in a critical section.
+
+#[cfg(target_has_atomic = "ptr")]
+pub(crate) fn fetch_add(atomic: &AtomicUsize, value: usize, ordering: Ordering) -> usize {
+    atomic.fetch_add(value, ordering)
+}
+
+#[cfg(all(not(target_has_atomic = "ptr"), feature = "critical-section"))]
+pub(crate) fn fetch_add(atomic: &AtomicUsize, value: usize, _ordering: Ordering) -> usize {
+    critical_section::with(|_| {
+        let previous = atomic.load(Ordering::Acquire);
+        atomic.store(previous.wrapping_add(value), Ordering::Release);
+        previous
+    })
+}
+
+#[cfg(target_has_atomic = "ptr")]
+pub(crate) fn compare_exchange(
+    atomic: &AtomicUsize,
+    current: usize,
+    new: usize,
+) -> Result<usize, usize> {
+    atomic.compare_exchange(current, new, Ordering::Acquire, Ordering::Relaxed)
+}
+
+#[cfg(all(not(target_has_atomic = "ptr"), feature = "critical-section"))]
+pub(crate) fn compare_exchange(
+    atomic: &AtomicUsize,
+    current: usize,
+    new: usize,
+) -> Result<usize, usize> {
+    critical_section::with(|_| {
+        let previous = atomic.load(Ordering::Acquire);
+        if previous == current {
+            atomic.store(new, Ordering::Release);
+            Ok(previous)
+        } else {
+            Err(previous)
+        }
+    })
+}
+
+/// Counts a hit of the call site of [`log_every_n!`](crate::log_every_n), returning the number of hits before it.
+///
+/// Without the atomic operations, the counter is updated with a load and a store, so concurrent hits might
+/// occasionally be counted once.
+#[doc(hidden)]
+pub fn count_hit(hits: &AtomicUsize) -> usize {
+    #[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+    return fetch_add(hits, 1, Ordering::Relaxed);
+
+    #[cfg(not(any(target_has_atomic = "ptr", feature = "critical-section")))]
+    {
+        let previous = hits.load(Ordering::Relaxed);
+        hits.store(previous.wrapping_add(1), Ordering::Relaxed);
+        previous
+    }
+}
+
+/// Returns whether this is the first hit of the call site of [`log_once!`](crate::log_once) and its variants.
+///
+/// Without the atomic operations, the flag is checked and set with a load and a store, so two threads
+/// hitting the call site at the same time might both see the first hit.
+#[doc(hidden)]
+pub fn first_hit(hit: &AtomicUsize) -> bool {
+    #[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+    return compare_exchange(hit, 0, 1).is_ok();
+
+    #[cfg(not(any(target_has_atomic = "ptr", feature = "critical-section")))]
+    {
+        hit.load(Ordering::Relaxed) == 0 && {
+            hit.store(1, Ordering::Relaxed);
+            true
+        }
+    }
+}
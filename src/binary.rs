@@ -0,0 +1,493 @@
+use super::format::SinkWriter;
+#[cfg(feature = "serde")]
+use super::serialize::SerializeJson;
+use super::sink::ByteSink;
+use super::{ULog, ULogData, Value};
+use core::cell::{Cell, RefCell};
+use core::fmt::{self, Debug, Write};
+
+/// The version of the binary format, written at the start of each frame.
+pub const FORMAT_VERSION: u8 = 4;
+
+/// Set in the metadata flags of a frame when it holds the [column](ULogData::column) of the statement.
+pub const FLAG_COLUMN: u8 = 1 << 0;
+/// Set in the metadata flags of a frame when it holds the [target](ULogData::target) of the statement.
+pub const FLAG_TARGET: u8 = 1 << 1;
+/// Set in the metadata flags of a frame when it holds the [module](ULogData::module) of the statement.
+pub const FLAG_MODULE: u8 = 1 << 2;
+/// Set in the metadata flags of a frame when it holds the [timestamp](ULogData::timestamp) of the statement.
+pub const FLAG_TIMESTAMP: u8 = 1 << 3;
+/// Set in the metadata flags of a frame when it holds the [sequence number](ULogData::sequence) of the statement.
+pub const FLAG_SEQUENCE: u8 = 1 << 4;
+/// Set in the metadata flags of a frame when it holds the [thread](ULogData::thread) of the statement.
+pub const FLAG_THREAD: u8 = 1 << 5;
+/// Set in the metadata flags of a frame when it holds the [user metadata](ULogData::user) of the statement.
+pub const FLAG_USER: u8 = 1 << 6;
+/// Set in the metadata flags of a frame when the [name of its level](ULogData::level_name) isn't the one of
+/// its standard level, as with user-defined levels.
+pub const FLAG_LEVEL_NAME: u8 = 1 << 7;
+
+/// Marks the end of a frame.
+pub const TAG_END: u8 = 0;
+/// Followed by a string of the message.
+pub const TAG_STR: u8 = 1;
+/// Followed by the key and the value of a key-value pair, as strings.
+pub const TAG_KV: u8 = 2;
+/// Followed by the key, as a string, and a [`Value::I64`], as a zigzag-encoded varint.
+pub const TAG_I64: u8 = 3;
+/// Followed by the key, as a string, and a [`Value::U64`], as a varint.
+pub const TAG_U64: u8 = 4;
+/// Followed by the key, as a string, and a [`Value::F64`], as 8 little-endian bytes.
+pub const TAG_F64: u8 = 5;
+/// Followed by the key, as a string, and a [`Value::Bool`], as a byte.
+pub const TAG_BOOL: u8 = 6;
+/// Followed by the key and a [`Value::Str`], as strings.
+pub const TAG_STR_VALUE: u8 = 7;
+/// Followed by the key, as a string, and [`Value::Bytes`], as a varint length and the bytes.
+pub const TAG_BYTES: u8 = 8;
+/// Followed by the key and a value logged with [`log_serialize`](ULog::log_serialize), written as JSON, as strings.
+pub const TAG_JSON: u8 = 9;
+/// Followed by a string continuing the previous one of the message, without a separator,
+/// see [`log_str_continued`](ULog::log_str_continued).
+pub const TAG_STR_CONTINUED: u8 = 10;
+
+/// Writes `value` as a LEB128 varint: 7 bits per byte, least significant first,
+/// with the high bit set on all bytes but the last.
+fn write_varint(sink: &(impl ByteSink + ?Sized), mut value: u64) {
+    let mut bytes = [0u8; 10];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes[len] = byte;
+            len += 1;
+            break;
+        }
+        bytes[len] = byte | 0x80;
+        len += 1;
+    }
+    sink.write_bytes(&bytes[..len]);
+}
+
+fn write_string(sink: &(impl ByteSink + ?Sized), string: &str) {
+    write_varint(sink, string.len() as u64);
+    sink.write_bytes(string.as_bytes());
+}
+
+/// Counts the bytes written through it.
+struct Length(usize);
+
+impl Write for Length {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        self.0 += string.len();
+        Ok(())
+    }
+}
+
+/// Serializes logging statements into compact binary frames, written as they are logged to a [`ByteSink`];
+/// requires the `binary` feature.
+///
+/// Values are formatted using their [`Debug`] implementation, which is done twice to know their length beforehand,
+/// except for the [`Value`]s logged with [`log_value`](ULog::log_value), which are encoded natively,
+/// and the values logged with `log_serialize`, which are written as JSON.
+/// Frames have the following layout, where varints are LEB128-encoded and strings are made of their length,
+/// as a varint, followed by their UTF-8 bytes:
+///
+/// | Field     | Encoding                                                                               |
+/// |-----------|----------------------------------------------------------------------------------------|
+/// | version   | [`FORMAT_VERSION`], as a byte                                                          |
+/// | level     | a byte, as given by [`ULogLevel::as_u8`](crate::ULogLevel::as_u8)                     |
+/// | file      | a varint `n`; if `n` is odd, `n >> 1` is the index of the file in the file table, otherwise `n >> 1` bytes of the file name follow |
+/// | line      | a varint                                                                               |
+/// | flags     | a byte, made of the `FLAG_*` constants of the metadata that follows                   |
+/// | metadata  | in the order of their flags: the [`FLAG_COLUMN`] as a varint, the [`FLAG_TARGET`] and the [`FLAG_MODULE`] as strings, the [`FLAG_TIMESTAMP`] as a varint of nanoseconds, the [`FLAG_SEQUENCE`], [`FLAG_THREAD`] and [`FLAG_USER`] as varints, and the [`FLAG_LEVEL_NAME`] as a string |
+/// | entries   | [`TAG_STR`] or [`TAG_STR_CONTINUED`] followed by a string, [`TAG_KV`] followed by two strings, or the tag of a typed [`Value`] (like [`TAG_I64`]) followed by its key and its encoding, in the order they were logged |
+/// | end       | [`TAG_END`]                                                                            |
+///
+/// The file table, set with [`with_file_table`](BinaryLogger::with_file_table), avoids sending the file names
+/// over and over again; the receiving end needs the same table to decode them.
+pub struct BinaryLogger<'a, Sink> {
+    sink: Sink,
+    files: &'a [&'a str],
+}
+
+impl<'a, Sink: ByteSink> BinaryLogger<'a, Sink> {
+    pub fn new(sink: Sink) -> Self {
+        Self { sink, files: &[] }
+    }
+
+    /// Sets the table of file names that are sent as their index in the table.
+    pub fn with_file_table(mut self, files: &'a [&'a str]) -> Self {
+        self.files = files;
+        self
+    }
+
+    pub fn sink(&self) -> &Sink {
+        &self.sink
+    }
+
+    pub fn into_inner(self) -> Sink {
+        self.sink
+    }
+}
+
+impl<Sink: ByteSink> ULog for BinaryLogger<'_, Sink> {
+    fn log_str(&self, _log_data: &ULogData, string: &str) {
+        self.sink.write_bytes(&[TAG_STR]);
+        write_string(&self.sink, string);
+    }
+
+    fn log_str_continued(&self, _log_data: &ULogData, string: &str) {
+        self.sink.write_bytes(&[TAG_STR_CONTINUED]);
+        write_string(&self.sink, string);
+    }
+
+    fn log_fmt(&self, _log_data: &ULogData, args: fmt::Arguments<'_>) {
+        self.sink.write_bytes(&[TAG_STR]);
+
+        let mut length = Length(0);
+        let _ = length.write_fmt(args);
+        write_varint(&self.sink, length.0 as u64);
+        let _ = SinkWriter(&self.sink).write_fmt(args);
+    }
+
+    fn log_format<T: Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+        self.sink.write_bytes(&[TAG_KV]);
+        write_string(&self.sink, key);
+
+        let mut length = Length(0);
+        let _ = write!(length, "{:?}", value);
+        write_varint(&self.sink, length.0 as u64);
+        let _ = write!(SinkWriter(&self.sink), "{:?}", value);
+    }
+
+    fn log_value(&self, _log_data: &ULogData, key: &str, value: Value<'_>) {
+        let tag = match value {
+            Value::I64(_) => TAG_I64,
+            Value::U64(_) => TAG_U64,
+            Value::F64(_) => TAG_F64,
+            Value::Bool(_) => TAG_BOOL,
+            Value::Str(_) => TAG_STR_VALUE,
+            Value::Bytes(_) => TAG_BYTES,
+        };
+        self.sink.write_bytes(&[tag]);
+        write_string(&self.sink, key);
+
+        match value {
+            Value::I64(value) => write_varint(&self.sink, ((value << 1) ^ (value >> 63)) as u64),
+            Value::U64(value) => write_varint(&self.sink, value),
+            Value::F64(value) => self.sink.write_bytes(&value.to_le_bytes()),
+            Value::Bool(value) => self.sink.write_bytes(&[value as u8]),
+            Value::Str(value) => write_string(&self.sink, value),
+            Value::Bytes(bytes) => {
+                write_varint(&self.sink, bytes.len() as u64);
+                self.sink.write_bytes(bytes);
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, _log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        self.sink.write_bytes(&[TAG_JSON]);
+        write_string(&self.sink, key);
+
+        let mut length = Length(0);
+        let _ = value.write_json(&mut length);
+        write_varint(&self.sink, length.0 as u64);
+        let _ = value.write_json(&mut SinkWriter(&self.sink));
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.sink
+            .write_bytes(&[FORMAT_VERSION, log_data.level.as_u8()]);
+
+        match self.files.iter().position(|file| *file == log_data.file) {
+            Some(index) => write_varint(&self.sink, (index as u64) << 1 | 1),
+            None => {
+                write_varint(&self.sink, (log_data.file.len() as u64) << 1);
+                self.sink.write_bytes(log_data.file.as_bytes());
+            }
+        }
+
+        write_varint(&self.sink, u64::from(log_data.line));
+
+        let level_name =
+            (log_data.level_name != log_data.level.as_str()).then_some(log_data.level_name);
+        let flags = [
+            (FLAG_COLUMN, log_data.column.is_some()),
+            (FLAG_TARGET, log_data.target.is_some()),
+            (FLAG_MODULE, log_data.module.is_some()),
+            (FLAG_TIMESTAMP, log_data.timestamp.is_some()),
+            (FLAG_SEQUENCE, log_data.sequence.is_some()),
+            (FLAG_THREAD, log_data.thread.is_some()),
+            (FLAG_USER, log_data.user.is_some()),
+            (FLAG_LEVEL_NAME, level_name.is_some()),
+        ]
+        .into_iter()
+        .filter(|(_, set)| *set)
+        .fold(0, |flags, (flag, _)| flags | flag);
+        self.sink.write_bytes(&[flags]);
+
+        if let Some(column) = log_data.column {
+            write_varint(&self.sink, u64::from(column));
+        }
+        if let Some(target) = log_data.target {
+            write_string(&self.sink, target);
+        }
+        if let Some(module) = log_data.module {
+            write_string(&self.sink, module);
+        }
+        if let Some(timestamp) = log_data.timestamp {
+            write_varint(
+                &self.sink,
+                u64::try_from(timestamp.as_nanos()).unwrap_or(u64::MAX),
+            );
+        }
+        for value in [log_data.sequence, log_data.thread, log_data.user]
+            .into_iter()
+            .flatten()
+        {
+            write_varint(&self.sink, u64::from(value));
+        }
+        if let Some(level_name) = level_name {
+            write_string(&self.sink, level_name);
+        }
+    }
+
+    fn log_end(&self, _log_data: &ULogData) {
+        self.sink.write_bytes(&[TAG_END]);
+        self.sink.end_frame();
+    }
+
+    fn flush(&self) {
+        self.sink.flush();
+    }
+}
+
+/// The largest number of bytes in a COBS block, which get encoded behind a `0xff` code.
+const COBS_BLOCK: usize = 254;
+
+/// Frames everything written to it using Consistent Overhead Byte Stuffing (COBS), before forwarding it to a [`ByteSink`].
+///
+/// COBS removes all of the zero bytes from a frame with an overhead of one byte every 254 bytes,
+/// so that each frame can be followed by a zero byte. On a raw stream like an UART, the receiving end can thus always
+/// find where frames start and end, even after losing bytes; see [`cobs_decode`].
+/// Frames end when [`end_frame`](ByteSink::end_frame) gets called, which [`BinaryLogger`] does after each statement:
+///
+/// ```
+/// use ulog::binary::{BinaryLogger, CobsEncoder};
+///
+/// let logger = BinaryLogger::new(CobsEncoder::new(|bytes: &[u8]| {
+///     // Write `bytes` to the UART
+/// }));
+///
+/// ulog::info!(logger, "Hello");
+/// ```
+pub struct CobsEncoder<Sink> {
+    sink: Sink,
+    block: RefCell<[u8; COBS_BLOCK]>,
+    len: Cell<usize>,
+    /// Whether a full block was just written, in which case the frame can end without another block
+    after_full_block: Cell<bool>,
+}
+
+impl<Sink: ByteSink> CobsEncoder<Sink> {
+    pub fn new(sink: Sink) -> Self {
+        Self {
+            sink,
+            block: RefCell::new([0; COBS_BLOCK]),
+            len: Cell::new(0),
+            after_full_block: Cell::new(false),
+        }
+    }
+
+    pub fn sink(&self) -> &Sink {
+        &self.sink
+    }
+
+    pub fn into_inner(self) -> Sink {
+        self.sink
+    }
+
+    fn write_block(&self) {
+        let len = self.len.replace(0);
+        self.sink.write_bytes(&[len as u8 + 1]);
+        self.sink.write_bytes(&self.block.borrow()[..len]);
+        self.after_full_block.set(len == COBS_BLOCK);
+    }
+}
+
+impl<Sink: ByteSink> ByteSink for CobsEncoder<Sink> {
+    fn write_bytes(&self, bytes: &[u8]) {
+        for &byte in bytes {
+            if byte == 0 {
+                self.write_block();
+                self.after_full_block.set(false);
+                continue;
+            }
+
+            let len = self.len.get();
+            self.block.borrow_mut()[len] = byte;
+            self.len.set(len + 1);
+            self.after_full_block.set(false);
+
+            if len + 1 == COBS_BLOCK {
+                self.write_block();
+            }
+        }
+    }
+
+    fn end_frame(&self) {
+        if !(self.after_full_block.get() && self.len.get() == 0) {
+            self.write_block();
+        }
+        self.after_full_block.set(false);
+        self.sink.write_bytes(&[0]);
+        self.sink.end_frame();
+    }
+
+    fn flush(&self) {
+        self.sink.flush();
+    }
+}
+
+/// Why [`cobs_decode`] failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CobsError {
+    /// The frame contains a zero byte, or a code pointing past its end.
+    Malformed,
+}
+
+/// Decodes a COBS frame in place, without its trailing zero byte, and returns the length of the decoded data.
+///
+/// On a stream, split the received bytes on zero bytes and decode each part; a malformed or truncated frame
+/// only loses itself.
+pub fn cobs_decode(frame: &mut [u8]) -> Result<usize, CobsError> {
+    let mut read = 0;
+    let mut written = 0;
+
+    while read < frame.len() {
+        let code = frame[read] as usize;
+        if code == 0 || read + code > frame.len() {
+            return Err(CobsError::Malformed);
+        }
+        read += 1;
+
+        for _ in 1..code {
+            if frame[read] == 0 {
+                return Err(CobsError::Malformed);
+            }
+            frame[written] = frame[read];
+            read += 1;
+            written += 1;
+        }
+
+        if code != COBS_BLOCK + 1 && read < frame.len() {
+            frame[written] = 0;
+            written += 1;
+        }
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_binary_encoding() {
+        let output = RefCell::new(Vec::new());
+        let sink = |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes);
+        let logger = BinaryLogger::new(&sink);
+        let with_table = BinaryLogger::new(&sink).with_file_table(&["src/main.rs", file!()]);
+
+        let mut log_data = ULogData::new(ULogLevel::Error, 300, file!());
+        log_data.thread = None;
+        logger.log_begin(&log_data);
+        logger.log_str(&log_data, "Hi");
+        logger.log_format(&log_data, "v", &32);
+        logger.log_end(&log_data);
+        with_table.log_begin(&log_data);
+        with_table.log_end(&log_data);
+
+        let mut expected = vec![FORMAT_VERSION, 4, 13 << 1];
+        expected.extend_from_slice(b"src/binary.rs");
+        expected.extend_from_slice(&[0xac, 0x02, 0]);
+        expected.extend_from_slice(&[TAG_STR, 2, b'H', b'i']);
+        expected.extend_from_slice(&[TAG_KV, 1, b'v', 2, b'3', b'2']);
+        expected.push(TAG_END);
+        expected.extend_from_slice(&[FORMAT_VERSION, 4, 1 << 1 | 1, 0xac, 0x02, 0, TAG_END]);
+
+        assert_eq!(output.into_inner(), expected);
+    }
+
+    #[test]
+    fn test_binary_metadata() {
+        let output = RefCell::new(Vec::new());
+        let logger = BinaryLogger::new(|bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes));
+
+        let mut log_data = ULogData::new(ULogLevel::Info, 1, "a")
+            .with_column(5)
+            .with_target("radio")
+            .with_timestamp(core::time::Duration::from_nanos(300))
+            .with_user(2);
+        log_data.thread = None;
+        logger.log_begin(&log_data);
+        logger.log_end(&log_data);
+
+        let mut expected = vec![FORMAT_VERSION, 2, 1 << 1, b'a', 1];
+        expected.push(FLAG_COLUMN | FLAG_TARGET | FLAG_TIMESTAMP | FLAG_USER);
+        expected.push(5);
+        expected.extend_from_slice(&[5, b'r', b'a', b'd', b'i', b'o']);
+        expected.extend_from_slice(&[0xac, 0x02, 2, TAG_END]);
+
+        assert_eq!(output.into_inner(), expected);
+    }
+
+    #[test]
+    fn test_cobs() {
+        let long = (1..=255).collect::<Vec<u8>>();
+        let cases: [(&[u8], &[u8]); 7] = [
+            (&[], &[0x01, 0x00]),
+            (&[0x00], &[0x01, 0x01, 0x00]),
+            (&[0x00, 0x00], &[0x01, 0x01, 0x01, 0x00]),
+            (
+                &[0x11, 0x22, 0x00, 0x33],
+                &[0x03, 0x11, 0x22, 0x02, 0x33, 0x00],
+            ),
+            (
+                &[0x11, 0x00, 0x00, 0x00],
+                &[0x02, 0x11, 0x01, 0x01, 0x01, 0x00],
+            ),
+            (&long[..254], &[&[0xff], &long[..254], &[0x00]].concat()),
+            (
+                &long,
+                &[&[0xff], &long[..254], &[0x02, 0xff, 0x00]].concat(),
+            ),
+        ];
+
+        for (data, expected) in cases {
+            let output = RefCell::new(Vec::new());
+            let encoder =
+                CobsEncoder::new(|bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes));
+            for chunk in data.chunks(3) {
+                encoder.write_bytes(chunk);
+            }
+            encoder.end_frame();
+
+            let mut output = output.into_inner();
+            assert_eq!(output, expected);
+
+            let len = output.len() - 1;
+            let len = cobs_decode(&mut output[..len]).unwrap();
+            assert_eq!(&output[..len], data);
+        }
+
+        assert_eq!(cobs_decode(&mut [0x03, 0x11]), Err(CobsError::Malformed));
+        assert_eq!(cobs_decode(&mut [0x02, 0x00]), Err(CobsError::Malformed));
+    }
+}
@@ -0,0 +1,178 @@
+#[cfg(feature = "serde")]
+use super::serialize::SerializeJson;
+use super::{DynULog, ULog, ULogData, Value};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+
+/// A handle to a logger subscribed to a [`BroadcastLogger`], used to unsubscribe it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Subscription(u64);
+
+/// Forwards logging statements to a list of loggers, which can be subscribed and unsubscribed at runtime.
+///
+/// Unlike [`ChainLogger`](crate::common::ChainLogger), the list of loggers does not need to be known at compile time,
+/// which is useful for plugins or tests attaching and detaching sinks.
+/// Loggers cannot be subscribed or unsubscribed from within a logging statement.
+///
+/// ```
+/// use ulog::broadcast::BroadcastLogger;
+/// use ulog::common::StubLogger;
+///
+/// let logger = BroadcastLogger::new();
+/// let subscription = logger.subscribe(StubLogger);
+///
+/// ulog::info!(logger, "Hello");
+///
+/// logger.unsubscribe(subscription);
+/// ```
+#[derive(Default)]
+pub struct BroadcastLogger<'a> {
+    subscribers: RefCell<Vec<(Subscription, Box<dyn DynULog + 'a>)>>,
+    next_id: Cell<u64>,
+}
+
+impl<'a> BroadcastLogger<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `logger` to the list of loggers receiving statements.
+    pub fn subscribe(&self, logger: impl ULog + 'a) -> Subscription {
+        let subscription = Subscription(self.next_id.get());
+        self.next_id.set(subscription.0 + 1);
+
+        self.subscribers
+            .borrow_mut()
+            .push((subscription, Box::new(logger)));
+        subscription
+    }
+
+    /// Removes a logger from the list of loggers receiving statements, and returns it.
+    pub fn unsubscribe(&self, subscription: Subscription) -> Option<Box<dyn DynULog + 'a>> {
+        let mut subscribers = self.subscribers.borrow_mut();
+        let index = subscribers
+            .iter()
+            .position(|(other, _)| *other == subscription)?;
+
+        Some(subscribers.remove(index).1)
+    }
+
+    /// Returns the number of subscribed loggers.
+    pub fn len(&self) -> usize {
+        self.subscribers.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.borrow().is_empty()
+    }
+}
+
+impl ULog for BroadcastLogger<'_> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        for (_, logger) in self.subscribers.borrow().iter() {
+            logger.log_str(log_data, string);
+        }
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        for (_, logger) in self.subscribers.borrow().iter() {
+            logger.log_str_continued(log_data, string);
+        }
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        for (_, logger) in self.subscribers.borrow().iter() {
+            logger.log_fmt(log_data, args);
+        }
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        for (_, logger) in self.subscribers.borrow().iter() {
+            logger.log_format(log_data, key, value);
+        }
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        for (_, logger) in self.subscribers.borrow().iter() {
+            logger.log_value(log_data, key, value);
+        }
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        for (_, logger) in self.subscribers.borrow().iter() {
+            logger.log_bytes(log_data, key, bytes);
+        }
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        for (_, logger) in self.subscribers.borrow().iter() {
+            logger.log_display(log_data, key, value);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        for (_, logger) in self.subscribers.borrow().iter() {
+            logger.log_serialize(log_data, key, value);
+        }
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        for (_, logger) in self.subscribers.borrow().iter() {
+            logger.log_begin(log_data);
+        }
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        for (_, logger) in self.subscribers.borrow().iter() {
+            logger.log_end(log_data);
+        }
+    }
+
+    fn is_available(&self) -> bool {
+        self.subscribers
+            .borrow()
+            .iter()
+            .any(|(_, logger)| logger.is_available())
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.subscribers
+            .borrow()
+            .iter()
+            .any(|(_, logger)| logger.enabled(log_data))
+    }
+
+    fn flush(&self) {
+        for (_, logger) in self.subscribers.borrow().iter() {
+            logger.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::TestLogger;
+    use crate::*;
+
+    #[test]
+    fn test_subscriptions() {
+        let first = TestLogger::default();
+        let second = TestLogger::default();
+        let logger = BroadcastLogger::new();
+
+        let subscription = logger.subscribe(&first);
+        info!(logger, "Hello");
+        logger.subscribe(&second);
+        info!(logger, "world");
+        assert!(logger.unsubscribe(subscription).is_some());
+        assert!(logger.unsubscribe(subscription).is_none());
+        info!(logger, "!");
+        drop(logger);
+
+        assert_eq!(first.logs.into_inner().len(), 2 * 3);
+        assert_eq!(second.logs.into_inner().len(), 2 * 3);
+    }
+}
@@ -0,0 +1,654 @@
+use super::record::{Record, RecordQueue};
+#[cfg(feature = "serde")]
+use super::serialize::SerializeJson;
+use super::{DisplayAsDebug, Level, ULog, ULogData, ULogLevel, Value};
+use core::cell::{Cell, RefCell};
+
+/// What a logger holding a bounded queue of statements does with a new statement when that queue is full.
+///
+/// Shared by [`BufferedLogger`], [`NonBlockingLogger`], the `ChannelLogger` of the `std` feature,
+/// the `AsyncQueueLogger` of the `async` feature and the `EmbassyLogger` of the `embassy` feature,
+/// which all count the statements they dropped. A logger refuses the policies it can't follow: [`NonBlockingLogger`]
+/// can't block its caller, and `ChannelLogger` can't drop the statements already sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Waits for the queue to have room for the new statement, for instance by flushing it to the wrapped logger.
+    Block,
+    /// The new statement is dropped.
+    DropNewest,
+    /// The oldest statement in the queue is dropped to make room for the new one.
+    DropOldest,
+    /// Panics, for when losing statements is not acceptable.
+    Panic,
+}
+
+impl OverflowPolicy {
+    /// Pushes `record` into `queue`, calling `make_room` beforehand if the queue is full and the policy is to block.
+    /// Returns whether a statement was dropped.
+    pub(crate) fn push<const STATEMENTS: usize, const SIZE: usize>(
+        self,
+        queue: &RefCell<RecordQueue<STATEMENTS, SIZE>>,
+        record: Record<SIZE>,
+        make_room: impl FnOnce(),
+    ) -> bool {
+        if queue.borrow().is_full() {
+            match self {
+                OverflowPolicy::Block => make_room(),
+                OverflowPolicy::DropNewest => return true,
+                OverflowPolicy::DropOldest => {
+                    queue.borrow_mut().push_overwrite(record);
+                    return true;
+                }
+                OverflowPolicy::Panic => panic!("ulog: the queue of statements is full"),
+            }
+        }
+
+        queue.borrow_mut().push(record).is_err()
+    }
+}
+
+/// Accumulates complete logging statements, and only forwards them to the wrapped logger
+/// once [`flush`](BufferedLogger::flush) is called or once the buffer is full.
+///
+/// What happens when the buffer is full can be changed with [`with_policy`](BufferedLogger::with_policy);
+/// by default ([`OverflowPolicy::Block`]) the buffer gets flushed as soon as it is full, and statements are
+/// forwarded right away if it can't hold any.
+/// Up to `STATEMENTS` statements are kept, each of them taking up to `SIZE` bytes (see [`Record`]).
+/// Statements that are still in the buffer when it is dropped are lost, use [`into_inner`](BufferedLogger::into_inner)
+/// to flush them and get the wrapped logger back.
+pub struct BufferedLogger<Logger, const STATEMENTS: usize, const SIZE: usize> {
+    logger: Logger,
+    policy: OverflowPolicy,
+    current: RefCell<Option<Record<SIZE>>>,
+    queue: RefCell<RecordQueue<STATEMENTS, SIZE>>,
+    dropped: Cell<usize>,
+}
+
+impl<Logger: ULog, const STATEMENTS: usize, const SIZE: usize>
+    BufferedLogger<Logger, STATEMENTS, SIZE>
+{
+    pub fn new(logger: Logger) -> Self {
+        Self {
+            logger,
+            policy: OverflowPolicy::Block,
+            current: RefCell::new(None),
+            queue: RefCell::new(RecordQueue::new()),
+            dropped: Cell::new(0),
+        }
+    }
+
+    /// Sets what happens to new statements when the buffer is full.
+    pub fn with_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Returns the number of statements dropped because of the [`OverflowPolicy`].
+    pub fn dropped(&self) -> usize {
+        self.dropped.get()
+    }
+
+    /// Returns the number of statements waiting in the buffer.
+    pub fn len(&self) -> usize {
+        self.queue.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.borrow().is_empty()
+    }
+
+    /// Forwards all of the buffered statements to the wrapped logger, from the oldest to the newest,
+    /// then [flushes](ULog::flush) the wrapped logger.
+    pub fn flush(&self) {
+        self.replay();
+        self.logger.flush();
+    }
+
+    fn replay(&self) {
+        loop {
+            let Some(record) = self.queue.borrow_mut().pop() else {
+                break;
+            };
+            record.replay(&self.logger);
+        }
+    }
+
+    /// Flushes the buffer and returns the wrapped logger.
+    pub fn into_inner(self) -> Logger {
+        self.flush();
+        self.logger
+    }
+}
+
+impl<Logger: ULog, const STATEMENTS: usize, const SIZE: usize> ULog
+    for BufferedLogger<Logger, STATEMENTS, SIZE>
+{
+    fn log_str(&self, _log_data: &ULogData, string: &str) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_str(string);
+        }
+    }
+
+    fn log_str_continued(&self, _log_data: &ULogData, string: &str) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_str_continued(string);
+        }
+    }
+
+    fn log_fmt(&self, _log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_fmt(args);
+        }
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_format(key, value);
+        }
+    }
+
+    fn log_value(&self, _log_data: &ULogData, key: &str, value: Value<'_>) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_value(key, value);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, _log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_serialize(key, value);
+        }
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        *self.current.borrow_mut() = Some(Record::new(log_data.clone()));
+    }
+
+    fn log_end(&self, _log_data: &ULogData) {
+        let Some(record) = self.current.borrow_mut().take() else {
+            return;
+        };
+
+        if self.policy == OverflowPolicy::Block {
+            // Only rejected without any room, as the buffer is flushed once full
+            let rejected = self.queue.borrow_mut().push(record).err();
+            match rejected {
+                Some(record) => record.replay(&self.logger),
+                None if self.queue.borrow().is_full() => self.replay(),
+                None => {}
+            }
+        } else if self.policy.push(&self.queue, record, || {}) {
+            self.dropped.set(self.dropped.get() + 1);
+        }
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        BufferedLogger::flush(self);
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.logger.enabled(log_data)
+    }
+}
+
+/// Queues logging statements without ever blocking the caller, until [`drain`](NonBlockingLogger::drain)
+/// is called to forward them to the wrapped logger (for instance from an idle loop).
+///
+/// When the queue is full, statements are dropped according to the [`OverflowPolicy`],
+/// and the number of dropped statements can be queried with [`dropped`](NonBlockingLogger::dropped).
+/// As the caller must not wait for the wrapped logger, [`OverflowPolicy::Block`] is refused.
+/// Up to `STATEMENTS` statements are kept, each of them taking up to `SIZE` bytes (see [`Record`]).
+pub struct NonBlockingLogger<Logger, const STATEMENTS: usize, const SIZE: usize> {
+    logger: Logger,
+    policy: OverflowPolicy,
+    current: RefCell<Option<Record<SIZE>>>,
+    queue: RefCell<RecordQueue<STATEMENTS, SIZE>>,
+    dropped: Cell<usize>,
+}
+
+impl<Logger: ULog, const STATEMENTS: usize, const SIZE: usize>
+    NonBlockingLogger<Logger, STATEMENTS, SIZE>
+{
+    /// Creates a logger queuing statements for `logger`; panics if `policy` is [`OverflowPolicy::Block`].
+    pub fn new(logger: Logger, policy: OverflowPolicy) -> Self {
+        assert!(
+            policy != OverflowPolicy::Block,
+            "ulog: a NonBlockingLogger can't block when its queue is full"
+        );
+
+        Self {
+            logger,
+            policy,
+            current: RefCell::new(None),
+            queue: RefCell::new(RecordQueue::new()),
+            dropped: Cell::new(0),
+        }
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Returns the number of statements waiting in the queue.
+    pub fn len(&self) -> usize {
+        self.queue.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.borrow().is_empty()
+    }
+
+    /// Returns the number of statements dropped since the creation of the logger or the last call to
+    /// [`reset_dropped`](NonBlockingLogger::reset_dropped).
+    pub fn dropped(&self) -> usize {
+        self.dropped.get()
+    }
+
+    pub fn reset_dropped(&self) {
+        self.dropped.set(0);
+    }
+
+    /// Forwards all of the queued statements to the wrapped logger, from the oldest to the newest.
+    pub fn drain(&self) {
+        loop {
+            let Some(record) = self.queue.borrow_mut().pop() else {
+                break;
+            };
+            record.replay(&self.logger);
+        }
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+}
+
+impl<Logger: ULog, const STATEMENTS: usize, const SIZE: usize> ULog
+    for NonBlockingLogger<Logger, STATEMENTS, SIZE>
+{
+    fn log_str(&self, _log_data: &ULogData, string: &str) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_str(string);
+        }
+    }
+
+    fn log_str_continued(&self, _log_data: &ULogData, string: &str) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_str_continued(string);
+        }
+    }
+
+    fn log_fmt(&self, _log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_fmt(args);
+        }
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_format(key, value);
+        }
+    }
+
+    fn log_value(&self, _log_data: &ULogData, key: &str, value: Value<'_>) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_value(key, value);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, _log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_serialize(key, value);
+        }
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        *self.current.borrow_mut() = Some(Record::new(log_data.clone()));
+    }
+
+    fn log_end(&self, _log_data: &ULogData) {
+        let Some(record) = self.current.borrow_mut().take() else {
+            return;
+        };
+
+        if self.policy.push(&self.queue, record, || {}) {
+            self.dropped.set(self.dropped.get() + 1);
+        }
+    }
+
+    /// Drains the queue, then flushes the wrapped logger.
+    fn flush(&self) {
+        self.drain();
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.logger.enabled(log_data)
+    }
+}
+
+/// Forwards logging statements to the wrapped logger, while retaining the last `STATEMENTS` statements
+/// at or above the [`Error`](ULogLevel::Error) level (or another level set with
+/// [`with_min_level`](LastErrorLogger::with_min_level)).
+///
+/// This lets a status display or endpoint show the recent failures without storing the full logs.
+/// Each statement takes up to `SIZE` bytes (see [`Record`]).
+pub struct LastErrorLogger<Logger, const STATEMENTS: usize, const SIZE: usize> {
+    logger: Logger,
+    min_level: ULogLevel,
+    current: RefCell<Option<Record<SIZE>>>,
+    recent: RefCell<RecordQueue<STATEMENTS, SIZE>>,
+}
+
+impl<Logger: ULog, const STATEMENTS: usize, const SIZE: usize>
+    LastErrorLogger<Logger, STATEMENTS, SIZE>
+{
+    pub fn new(logger: Logger) -> Self {
+        Self {
+            logger,
+            min_level: ULogLevel::Error,
+            current: RefCell::new(None),
+            recent: RefCell::new(RecordQueue::new()),
+        }
+    }
+
+    /// Sets the minimum level of the statements to retain.
+    pub fn with_min_level(mut self, min_level: impl Level) -> Self {
+        self.min_level = min_level.to_ulog_level();
+        self
+    }
+
+    /// Returns the number of retained statements.
+    pub fn len(&self) -> usize {
+        self.recent.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recent.borrow().is_empty()
+    }
+
+    /// Calls `f` on each of the retained statements, from the oldest to the newest.
+    /// The records can be printed using their [`Display`](core::fmt::Display) implementation.
+    pub fn for_each_recent(&self, mut f: impl FnMut(&Record<SIZE>)) {
+        for record in self.recent.borrow().iter() {
+            f(record);
+        }
+    }
+
+    /// Returns a copy of the most recent retained statement.
+    pub fn last(&self) -> Option<Record<SIZE>> {
+        self.recent.borrow().iter().last().cloned()
+    }
+
+    /// Forgets all of the retained statements.
+    pub fn clear(&self) {
+        let mut recent = self.recent.borrow_mut();
+        while recent.pop().is_some() {}
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+}
+
+impl<Logger: ULog, const STATEMENTS: usize, const SIZE: usize> ULog
+    for LastErrorLogger<Logger, STATEMENTS, SIZE>
+{
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_str(string);
+        }
+        self.logger.log_str(log_data, string);
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_str_continued(string);
+        }
+        self.logger.log_str_continued(log_data, string);
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_fmt(args);
+        }
+        self.logger.log_fmt(log_data, args);
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_format(key, value);
+        }
+        self.logger.log_format(log_data, key, value);
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_value(key, value);
+        }
+        self.logger.log_value(log_data, key, value);
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_value(key, Value::Bytes(bytes));
+        }
+        self.logger.log_bytes(log_data, key, bytes);
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_format(key, &DisplayAsDebug(value));
+        }
+        self.logger.log_display(log_data, key, value);
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_serialize(key, value);
+        }
+        self.logger.log_serialize(log_data, key, value);
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        if log_data.level >= self.min_level {
+            *self.current.borrow_mut() = Some(Record::new(log_data.clone()));
+        }
+        self.logger.log_begin(log_data);
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        if let Some(record) = self.current.borrow_mut().take() {
+            self.recent.borrow_mut().push_overwrite(record);
+        }
+        self.logger.log_end(log_data);
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    /// Statements that would be retained are enabled even if the wrapped logger filters them out.
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        log_data.level >= self.min_level || self.logger.enabled(log_data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::TestLogger;
+    use crate::*;
+
+    #[test]
+    fn test_buffered_flush() {
+        let logger = BufferedLogger::<_, 3, 64>::new(TestLogger::default());
+
+        info!(logger, "Hello");
+        warn!(logger, "world", "value" => 32);
+        assert_eq!(logger.len(), 2);
+
+        logger.flush();
+        assert!(logger.is_empty());
+
+        for _ in 0..4 {
+            info!(logger, "Hello");
+        }
+        assert_eq!(logger.len(), 1);
+
+        let logs = logger.into_inner().logs.into_inner();
+        assert_eq!(logs.len(), 4 * 3 + 3 + 4);
+        assert_eq!(
+            &logs[..7],
+            &[
+                (ULogLevel::Info, String::from("__BEGIN__")),
+                (ULogLevel::Info, String::from("Hello")),
+                (ULogLevel::Info, String::from("__END__")),
+                (ULogLevel::Warning, String::from("__BEGIN__")),
+                (ULogLevel::Warning, String::from("world")),
+                (ULogLevel::Warning, String::from("value => 32")),
+                (ULogLevel::Warning, String::from("__END__")),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_buffered_serialize() {
+        use crate::format::{FormattedLogger, JsonFormatter};
+
+        let output = RefCell::new(Vec::new());
+        let logger = BufferedLogger::<_, 2, 128>::new(FormattedLogger::new(
+            JsonFormatter::new(),
+            |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes),
+        ));
+
+        info!(logger, "Sampled", "readings" =># [1, 2]);
+        logger.flush();
+
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        assert!(output.ends_with("\"msg\":\"Sampled\",\"readings\":[1,2]}\n"));
+    }
+
+    #[test]
+    fn test_non_blocking_overflow_policy() {
+        for policy in [OverflowPolicy::DropNewest, OverflowPolicy::DropOldest] {
+            let logger = NonBlockingLogger::<_, 2, 64>::new(TestLogger::default(), policy);
+
+            for value in 0..5 {
+                info!(logger, "Hello", "value" => value);
+            }
+            assert_eq!(logger.dropped(), 3);
+
+            logger.drain();
+            let values = logger
+                .into_inner()
+                .logs
+                .into_inner()
+                .into_iter()
+                .filter(|log| log.1.starts_with("value"))
+                .map(|log| log.1)
+                .collect::<Vec<_>>();
+
+            match policy {
+                OverflowPolicy::DropNewest => assert_eq!(values, ["value => 0", "value => 1"]),
+                _ => assert_eq!(values, ["value => 3", "value => 4"]),
+            }
+        }
+    }
+
+    #[test]
+    fn test_buffered_block() {
+        // Flushed as soon as it is full
+        let logger = BufferedLogger::<_, 2, 64>::new(TestLogger::default());
+        info!(logger, "Hello");
+        info!(logger, "world");
+        assert!(logger.is_empty());
+        assert_eq!(logger.dropped(), 0);
+
+        // Forwarded right away without any room
+        let logger = BufferedLogger::<_, 0, 64>::new(TestLogger::default());
+        info!(logger, "Hello");
+        assert_eq!(logger.dropped(), 0);
+        assert_eq!(logger.into_inner().logs.into_inner()[1].1, "Hello");
+    }
+
+    #[test]
+    fn test_no_room() {
+        for policy in [OverflowPolicy::DropNewest, OverflowPolicy::DropOldest] {
+            let logger = NonBlockingLogger::<_, 0, 64>::new(TestLogger::default(), policy);
+            info!(logger, "Hello");
+            info!(logger, "world");
+            assert_eq!(logger.dropped(), 2);
+
+            let logger = BufferedLogger::<_, 0, 64>::new(TestLogger::default()).with_policy(policy);
+            info!(logger, "Hello");
+            assert_eq!(logger.dropped(), 1);
+            assert!(logger.into_inner().logs.into_inner().is_empty());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_non_blocking_refuses_block() {
+        let _ = NonBlockingLogger::<_, 2, 64>::new(common::StubLogger, OverflowPolicy::Block);
+    }
+
+    #[test]
+    fn test_last_errors() {
+        let logger = LastErrorLogger::<_, 2, 64>::new(common::StubLogger);
+
+        error!(logger, "First error");
+        info!(logger, "Not an error");
+        error!(logger, "Second error", "code" => 2);
+        critical!(logger, "Third error", "code" => 3);
+
+        let mut recent = Vec::new();
+        logger.for_each_recent(|record| recent.push(record.to_string()));
+
+        assert_eq!(recent.len(), 2);
+        assert!(recent[0].starts_with("ERROR src/buffer.rs:"));
+        assert!(recent[0].ends_with(" Second error code=2"));
+        assert!(recent[1].starts_with("CRITICAL src/buffer.rs:"));
+        assert!(recent[1].ends_with(" Third error code=3"));
+    }
+
+    #[test]
+    fn test_enabled() {
+        let data = |level| ULogData::new(level, 1, "main.rs");
+
+        let buffered =
+            BufferedLogger::<_, 2, 64>::new(TestLogger::default().min_level(ULogLevel::Warning));
+        assert!(!buffered.enabled(&data(ULogLevel::Info)));
+        assert!(buffered.enabled(&data(ULogLevel::Error)));
+
+        let last_errors =
+            LastErrorLogger::<_, 2, 64>::new(TestLogger::default().min_level(ULogLevel::Critical));
+        assert!(!last_errors.enabled(&data(ULogLevel::Warning)));
+        assert!(last_errors.enabled(&data(ULogLevel::Error)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_overflow_panic() {
+        let logger = BufferedLogger::<_, 1, 64>::new(TestLogger::default())
+            .with_policy(OverflowPolicy::Panic);
+
+        info!(logger, "Hello");
+        info!(logger, "world");
+    }
+}
@@ -0,0 +1,188 @@
+use super::{Level, ULog, ULogData, Value};
+use core::fmt::{self, Arguments, Debug, Display, Write};
+use core::panic::Location;
+
+/// Builds a logging statement by chaining method calls, as an alternative to the macros;
+/// returned by [`ULog::at`].
+///
+/// The statement is begun by the first call adding something to it, and ended by [`emit`](StatementBuilder::emit)
+/// or when the builder is dropped.
+///
+/// The builder also implements [`Write`], so that long messages can be streamed into it in several parts,
+/// which loggers join back into a single piece of text through [`ULog::log_str_continued`]:
+///
+/// ```
+/// use core::fmt::Write;
+/// use ulog::{ULog, ULogLevel};
+/// # let logger = ulog::common::StubLogger;
+///
+/// let mut statement = logger.at(ULogLevel::Debug);
+/// for byte in [0xde, 0xad, 0xbe, 0xef] {
+///     let _ = write!(statement, "{byte:02x}");
+/// }
+/// statement.emit(); // Logs `deadbeef`
+/// ```
+///
+/// ```
+/// use ulog::{ULog, ULogLevel};
+/// # let logger = ulog::common::StubLogger;
+/// # let (ip, port, retries) = ("10.0.0.2", 8080, 0);
+///
+/// let mut statement = logger.at(ULogLevel::Info).msg("connected").field("ip", ip).field("port", port);
+/// if retries > 0 {
+///     statement = statement.field("retries", retries);
+/// }
+/// statement.emit();
+/// ```
+pub struct StatementBuilder<'a, Logger: ULog + ?Sized> {
+    logger: &'a Logger,
+    log_data: ULogData,
+    begun: bool,
+    in_text: bool,
+}
+
+impl<'a, Logger: ULog + ?Sized> StatementBuilder<'a, Logger> {
+    /// Creates a builder for a statement logged at `level`, located where this is called.
+    #[track_caller]
+    pub fn new(logger: &'a Logger, level: impl Level) -> Self {
+        let location = Location::caller();
+
+        Self {
+            logger,
+            log_data: ULogData::new(level, location.line(), location.file())
+                .with_column(location.column()),
+            begun: false,
+            in_text: false,
+        }
+    }
+
+    /// Sets the [target](ULogData::target) of the statement; does nothing once the statement is begun.
+    pub fn target(mut self, target: &'static str) -> Self {
+        if !self.begun {
+            self.log_data.target = Some(target);
+        }
+        self
+    }
+
+    /// Logs a message, like the string given to the macros.
+    pub fn msg(mut self, string: &str) -> Self {
+        self.begin();
+        self.logger.log_str(&self.log_data, string);
+        self
+    }
+
+    /// Logs a formatted message, built with [`format_args!`].
+    pub fn msg_fmt(mut self, args: Arguments<'_>) -> Self {
+        self.begin();
+        self.logger.log_fmt(&self.log_data, args);
+        self
+    }
+
+    /// Logs a key-value pair, using the [`Debug`] implementation of `value`.
+    pub fn field<T: Debug>(mut self, key: &str, value: T) -> Self {
+        self.begin();
+        self.logger.log_format(&self.log_data, key, &value);
+        self
+    }
+
+    /// Logs a key-value pair, using the [`Display`] implementation of `value`.
+    pub fn display<T: Display>(mut self, key: &str, value: T) -> Self {
+        self.begin();
+        self.logger.log_display(&self.log_data, key, &value);
+        self
+    }
+
+    /// Logs a key-value pair holding a typed [`Value`].
+    pub fn value<'v>(mut self, key: &str, value: impl Into<Value<'v>>) -> Self {
+        self.begin();
+        self.logger.log_value(&self.log_data, key, value.into());
+        self
+    }
+
+    /// Logs a key-value pair holding a raw binary payload, see [`ULog::log_bytes`].
+    pub fn bytes(mut self, key: &str, bytes: &[u8]) -> Self {
+        self.begin();
+        self.logger.log_bytes(&self.log_data, key, bytes);
+        self
+    }
+
+    /// Ends the statement; equivalent to dropping the builder.
+    pub fn emit(self) {}
+
+    fn begin(&mut self) {
+        self.in_text = false;
+        if !self.begun {
+            self.begun = true;
+            self.logger.log_begin(&self.log_data);
+        }
+    }
+}
+
+impl<Logger: ULog + ?Sized> Write for StatementBuilder<'_, Logger> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        if self.in_text {
+            self.logger.log_str_continued(&self.log_data, string);
+        } else {
+            self.begin();
+            self.logger.log_str(&self.log_data, string);
+            self.in_text = true;
+        }
+        Ok(())
+    }
+}
+
+impl<Logger: ULog + ?Sized> Drop for StatementBuilder<'_, Logger> {
+    fn drop(&mut self) {
+        self.begin();
+        self.logger.log_end(&self.log_data);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::TestLogger;
+    use crate::ULogLevel;
+
+    #[test]
+    fn test_statement_builder() {
+        let logger = TestLogger::default();
+
+        logger
+            .at(ULogLevel::Info)
+            .msg("connected")
+            .field("ip", "10.0.0.2")
+            .display("port", 8080)
+            .value("secure", true)
+            .emit();
+        logger
+            .at(ULogLevel::Warning)
+            .msg_fmt(format_args!("{} retries", 3));
+
+        let mut statement = logger.at(ULogLevel::Debug).msg("bytes");
+        write!(statement, "{:02x}", 0xab).unwrap();
+        write!(statement, "{:02x}", 0xcd).unwrap();
+        statement.field("count", 2).emit();
+
+        assert_eq!(
+            &logger.logs.into_inner()[..],
+            &[
+                (ULogLevel::Info, String::from("__BEGIN__")),
+                (ULogLevel::Info, String::from("connected")),
+                (ULogLevel::Info, String::from("ip => \"10.0.0.2\"")),
+                (ULogLevel::Info, String::from("port => 8080")),
+                (ULogLevel::Info, String::from("secure => true")),
+                (ULogLevel::Info, String::from("__END__")),
+                (ULogLevel::Warning, String::from("__BEGIN__")),
+                (ULogLevel::Warning, String::from("3 retries")),
+                (ULogLevel::Warning, String::from("__END__")),
+                (ULogLevel::Debug, String::from("__BEGIN__")),
+                (ULogLevel::Debug, String::from("bytes")),
+                (ULogLevel::Debug, String::from("ab")),
+                (ULogLevel::Debug, String::from("cd")),
+                (ULogLevel::Debug, String::from("count => 2")),
+                (ULogLevel::Debug, String::from("__END__")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use super::ULogLevel;
+use core::{mem, ptr, slice, str};
+
+/// A call site of the logging macros, registered in the `ulog_call_sites` linker section when its message is a
+/// literal.
+///
+/// The catalog lets a firmware image be audited for the statements it contains, and lets statements be logged
+/// as the [`id`](CallSite::id) of their call site, leaving the strings to host tools.
+///
+/// Host tools can read the section from the ELF file as an array of call sites, with the following layout,
+/// where the pointers are addresses of UTF-8 strings in the image:
+///
+/// | Field         | Type           | Description                                                     |
+/// |---------------|----------------|-----------------------------------------------------------------|
+/// | `file`        | pointer        | The file of the statement                                       |
+/// | `file_len`    | `usize`        | The length of `file`, in bytes                                  |
+/// | `message`     | pointer        | The message literal of the statement                            |
+/// | `message_len` | `usize`        | The length of `message`, in bytes                               |
+/// | `line`        | `u32`          | The line of the statement                                       |
+/// | `column`      | `u32`          | The column of the statement                                     |
+/// | `level`       | `u8`           | The level as given by [`ULogLevel::as_u8`], or 255 when unknown |
+///
+/// The level is known for the level macros, like [`info!`](crate::info!), but not for [`ulog!`](crate::ulog!),
+/// which accepts any expression as level.
+///
+/// The section is located with the `__start_ulog_call_sites` and `__stop_ulog_call_sites` symbols, which the GNU
+/// and LLVM linkers define on ELF targets; with a custom linker script, it needs to be kept, for instance with:
+///
+/// ```text
+/// SECTIONS {
+///     ulog_call_sites : {
+///         __start_ulog_call_sites = .;
+///         KEEP(*(ulog_call_sites));
+///         __stop_ulog_call_sites = .;
+///     } > FLASH
+/// }
+/// INSERT AFTER .rodata;
+/// ```
+#[repr(C)]
+#[derive(Debug)]
+pub struct CallSite {
+    file: *const u8,
+    file_len: usize,
+    message: *const u8,
+    message_len: usize,
+    line: u32,
+    column: u32,
+    level: u8,
+}
+
+// The pointers point to `'static` strings, and are never written to
+unsafe impl Sync for CallSite {}
+
+const UNKNOWN_LEVEL: u8 = u8::MAX;
+
+impl CallSite {
+    /// Creates a call site, for the macros.
+    #[doc(hidden)]
+    pub const fn new(
+        level: Option<ULogLevel>,
+        file: &'static str,
+        line: u32,
+        column: u32,
+        message: &'static str,
+    ) -> Self {
+        Self {
+            file: file.as_ptr(),
+            file_len: file.len(),
+            message: message.as_ptr(),
+            message_len: message.len(),
+            line,
+            column,
+            level: match level {
+                Some(level) => level.as_u8(),
+                None => UNKNOWN_LEVEL,
+            },
+        }
+    }
+
+    /// The file of the statement.
+    pub fn file(&self) -> &'static str {
+        unsafe { str::from_utf8_unchecked(slice::from_raw_parts(self.file, self.file_len)) }
+    }
+
+    /// The line of the statement.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// The column of the statement.
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+
+    /// The message literal of the statement, which may contain format specifiers.
+    pub fn message(&self) -> &'static str {
+        unsafe { str::from_utf8_unchecked(slice::from_raw_parts(self.message, self.message_len)) }
+    }
+
+    /// The level of the statement, if it is known.
+    pub fn level(&self) -> Option<ULogLevel> {
+        ULogLevel::try_from(self.level).ok()
+    }
+
+    /// The index of the call site in the [catalog](call_sites), which is the same for the host tools reading it.
+    pub fn id(&'static self) -> usize {
+        let start = call_sites().as_ptr();
+        (self as *const CallSite as usize - start as usize) / mem::size_of::<CallSite>()
+    }
+}
+
+extern "C" {
+    static __start_ulog_call_sites: CallSite;
+    static __stop_ulog_call_sites: CallSite;
+}
+
+/// Returns the call sites registered by the macros in the whole program, in the order of the linker section.
+pub fn call_sites() -> &'static [CallSite] {
+    unsafe {
+        let start = ptr::addr_of!(__start_ulog_call_sites);
+        let stop = ptr::addr_of!(__stop_ulog_call_sites);
+        let len = (stop as usize - start as usize) / mem::size_of::<CallSite>();
+
+        slice::from_raw_parts(start, len)
+    }
+}
+
+/// Returns the call site of the statement at `line` in `file`, as found in [`ULogData`](crate::ULogData);
+/// the first one if there are several on the same line.
+pub fn find(file: &str, line: u32) -> Option<&'static CallSite> {
+    call_sites()
+        .iter()
+        .find(|call_site| call_site.line == line && call_site.file() == file)
+}
+
+/// Returns the call site of the statement at `line` and `column` in `file`, which is unique.
+pub fn find_at(file: &str, line: u32, column: u32) -> Option<&'static CallSite> {
+    call_sites().iter().find(|call_site| {
+        call_site.line == line && call_site.column == column && call_site.file() == file
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::StubLogger;
+    use crate::info;
+
+    #[test]
+    fn test_call_sites() {
+        let logger = StubLogger;
+        crate::warn!(logger, "disk {} full", 90);
+        let line = line!() - 1;
+        crate::ulog!(ULogLevel::Info, logger, "any level");
+
+        let call_site = find(file!(), line).unwrap();
+        assert_eq!(call_site.message(), "disk {} full");
+        assert_eq!(call_site.level(), Some(ULogLevel::Warning));
+        assert!(core::ptr::eq(&call_sites()[call_site.id()], call_site));
+
+        let call_site = find(file!(), line + 2).unwrap();
+        assert_eq!(call_site.message(), "any level");
+        assert_eq!(call_site.level(), None);
+
+        let line = line!() + 1;
+        let _ = (info!(logger, "a"), info!(logger, "b"));
+        let mut on_line = call_sites()
+            .iter()
+            .filter(|call_site| call_site.line() == line && call_site.file() == file!());
+        let (first, second) = (on_line.next().unwrap(), on_line.next().unwrap());
+        assert_ne!(first.column(), second.column());
+        for call_site in [first, second] {
+            let found = find_at(file!(), line, call_site.column()).unwrap();
+            assert!(core::ptr::eq(found, call_site));
+        }
+    }
+}
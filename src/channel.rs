@@ -0,0 +1,259 @@
+use super::buffer::OverflowPolicy;
+use super::record::Record;
+#[cfg(feature = "serde")]
+use super::serialize::SerializeJson;
+use super::{ULog, ULogData, Value};
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Captures logging statements and sends them over a channel to a worker thread, which owns the actual logger.
+///
+/// Logging thus never blocks on the I/O done by the actual logger.
+/// Each statement takes up to `SIZE` bytes (see [`Record`]). The logger can be cloned to log from several threads;
+/// once all of the clones are dropped, the worker thread stops and gives the actual logger back.
+///
+/// Bounded channels (see [`spawn_bounded`](ChannelLogger::spawn_bounded)) handle overflows according to an
+/// [`OverflowPolicy`]; as the oldest statements belong to the receiving end, [`OverflowPolicy::DropOldest`]
+/// is refused.
+pub struct ChannelLogger<const SIZE: usize> {
+    sender: ChannelSender<SIZE>,
+    policy: OverflowPolicy,
+    current: RefCell<Option<Record<SIZE>>>,
+    disconnected: Cell<bool>,
+    /// Shared between the clones of the logger
+    dropped: Arc<AtomicUsize>,
+}
+
+#[derive(Clone)]
+enum ChannelSender<const SIZE: usize> {
+    Unbounded(Sender<Record<SIZE>>),
+    Bounded(SyncSender<Record<SIZE>>),
+}
+
+impl<const SIZE: usize> ChannelLogger<SIZE> {
+    /// Creates a logger sending its statements to `sender`; use [`forward`] to forward them from the receiving end.
+    pub fn new(sender: Sender<Record<SIZE>>) -> Self {
+        Self::with_sender(ChannelSender::Unbounded(sender), OverflowPolicy::Block)
+    }
+
+    /// Creates a logger sending its statements to a bounded channel, handling overflows according to `policy`;
+    /// panics if `policy` is [`OverflowPolicy::DropOldest`].
+    pub fn new_bounded(sender: SyncSender<Record<SIZE>>, policy: OverflowPolicy) -> Self {
+        assert!(
+            policy != OverflowPolicy::DropOldest,
+            "ulog: a ChannelLogger can't drop the oldest statements, which belong to the receiving end"
+        );
+        Self::with_sender(ChannelSender::Bounded(sender), policy)
+    }
+
+    fn with_sender(sender: ChannelSender<SIZE>, policy: OverflowPolicy) -> Self {
+        Self {
+            sender,
+            policy,
+            current: RefCell::new(None),
+            disconnected: Cell::new(false),
+            dropped: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Returns the number of statements dropped because the channel was full, by this logger and its clones.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Spawns a worker thread forwarding statements to `logger`, and returns a `ChannelLogger` sending statements to it.
+    pub fn spawn<Logger: ULog + Send + 'static>(logger: Logger) -> (Self, JoinHandle<Logger>) {
+        let (sender, receiver) = mpsc::channel();
+        let handle = std::thread::spawn(move || {
+            forward(&receiver, &logger);
+            logger
+        });
+
+        (Self::new(sender), handle)
+    }
+
+    /// Like [`spawn`](ChannelLogger::spawn), but with a channel holding up to `capacity` statements,
+    /// and handling overflows according to `policy`; panics if `policy` is [`OverflowPolicy::DropOldest`].
+    pub fn spawn_bounded<Logger: ULog + Send + 'static>(
+        logger: Logger,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> (Self, JoinHandle<Logger>) {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let channel_logger = Self::new_bounded(sender, policy);
+        let handle = std::thread::spawn(move || {
+            forward(&receiver, &logger);
+            logger
+        });
+
+        (channel_logger, handle)
+    }
+
+    fn send(&self, record: Record<SIZE>) {
+        let sender = match &self.sender {
+            ChannelSender::Unbounded(sender) => {
+                if sender.send(record).is_err() {
+                    self.disconnected.set(true);
+                }
+                return;
+            }
+            ChannelSender::Bounded(sender) => sender,
+        };
+
+        let result = match self.policy {
+            OverflowPolicy::Block => sender
+                .send(record)
+                .map_err(|_| TrySendError::Disconnected(())),
+            _ => sender.try_send(record).map_err(|error| match error {
+                TrySendError::Full(_) => TrySendError::Full(()),
+                TrySendError::Disconnected(_) => TrySendError::Disconnected(()),
+            }),
+        };
+
+        match result {
+            Ok(()) => {}
+            Err(TrySendError::Disconnected(())) => self.disconnected.set(true),
+            Err(TrySendError::Full(())) => {
+                if self.policy == OverflowPolicy::Panic {
+                    panic!("ulog: the queue of statements is full");
+                }
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Replays the records coming from `receiver` to `logger`, until all of the senders are dropped.
+pub fn forward<Logger: ULog, const SIZE: usize>(
+    receiver: &Receiver<Record<SIZE>>,
+    logger: &Logger,
+) {
+    for record in receiver {
+        record.replay(logger);
+    }
+}
+
+impl<const SIZE: usize> Clone for ChannelLogger<SIZE> {
+    fn clone(&self) -> Self {
+        Self {
+            dropped: self.dropped.clone(),
+            ..Self::with_sender(self.sender.clone(), self.policy)
+        }
+    }
+}
+
+impl<const SIZE: usize> ULog for ChannelLogger<SIZE> {
+    fn log_str(&self, _log_data: &ULogData, string: &str) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_str(string);
+        }
+    }
+
+    fn log_str_continued(&self, _log_data: &ULogData, string: &str) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_str_continued(string);
+        }
+    }
+
+    fn log_fmt(&self, _log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_fmt(args);
+        }
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_format(key, value);
+        }
+    }
+
+    fn log_value(&self, _log_data: &ULogData, key: &str, value: Value<'_>) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_value(key, value);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, _log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_serialize(key, value);
+        }
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        *self.current.borrow_mut() = Some(Record::new(log_data.clone()));
+    }
+
+    fn log_end(&self, _log_data: &ULogData) {
+        if let Some(record) = self.current.borrow_mut().take() {
+            self.send(record);
+        }
+    }
+
+    /// Returns `false` once the worker thread has stopped.
+    fn is_available(&self) -> bool {
+        !self.disconnected.get()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::TestLogger;
+    use crate::*;
+
+    #[test]
+    fn test_channel_logger() {
+        let (logger, handle) = ChannelLogger::<64>::spawn(TestLogger::default());
+
+        let other_logger = logger.clone();
+        std::thread::spawn(move || info!(other_logger, "Hello"))
+            .join()
+            .unwrap();
+        error!(logger, "world", "value" => 32);
+        drop(logger);
+
+        assert_eq!(
+            &handle.join().unwrap().logs.into_inner()[..],
+            &[
+                (ULogLevel::Info, String::from("__BEGIN__")),
+                (ULogLevel::Info, String::from("Hello")),
+                (ULogLevel::Info, String::from("__END__")),
+                (ULogLevel::Error, String::from("__BEGIN__")),
+                (ULogLevel::Error, String::from("world")),
+                (ULogLevel::Error, String::from("value => 32")),
+                (ULogLevel::Error, String::from("__END__")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bounded_channel() {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let logger = ChannelLogger::<64>::new_bounded(sender, OverflowPolicy::DropNewest);
+
+        let other_logger = logger.clone();
+        info!(logger, "Hello");
+        info!(other_logger, "world");
+        assert_eq!(logger.dropped(), 1);
+        drop((logger, other_logger));
+
+        let forwarded = TestLogger::default();
+        forward(&receiver, &forwarded);
+        assert_eq!(forwarded.logs.into_inner()[1].1, "Hello");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bounded_channel_drop_oldest() {
+        let (sender, _receiver) = mpsc::sync_channel(1);
+        let _ = ChannelLogger::<64>::new_bounded(sender, OverflowPolicy::DropOldest);
+    }
+}
@@ -1,4 +1,13 @@
-use super::{ULog, ULogData, ULogLevel};
+#[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+use super::atomic;
+use super::record::{TextBuffer, Verbatim};
+#[cfg(feature = "serde")]
+use super::serialize::{RawJson, SerializeJson, Serialized};
+use super::{Level, ULog, ULogData, ULogLevel, Value};
+use core::cell::{Cell, RefCell};
+use core::fmt::Write;
+#[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 /// A logger that does not log anything, useful for conditionally turning off logging.
 pub struct StubLogger;
@@ -9,6 +18,16 @@ impl ULog for StubLogger {
         // Noop
     }
 
+    #[inline(always)]
+    fn log_str_continued(&self, _log_data: &ULogData, _string: &str) {
+        // Noop
+    }
+
+    #[inline(always)]
+    fn log_fmt(&self, _log_data: &ULogData, _args: core::fmt::Arguments<'_>) {
+        // Noop
+    }
+
     #[inline(always)]
     fn log_format<T: core::fmt::Debug>(&self, _log_data: &ULogData, _key: &str, _value: &T) {
         // Noop
@@ -23,6 +42,11 @@ impl ULog for StubLogger {
     fn log_end(&self, _log_data: &ULogData) {
         // Noop
     }
+
+    #[inline(always)]
+    fn enabled(&self, _log_data: &ULogData) -> bool {
+        false
+    }
 }
 
 /// Chains or composes two or more loggers together, forwarding any logging statements to all of them.
@@ -49,11 +73,42 @@ impl<Parent: ULog, Current: ULog> ULog for ChainLogger<Parent, Current> {
         self.current.log_str(log_data, string);
     }
 
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.parent.log_str_continued(log_data, string);
+        self.current.log_str_continued(log_data, string);
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.parent.log_fmt(log_data, args);
+        self.current.log_fmt(log_data, args);
+    }
+
     fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
         self.parent.log_format(log_data, key, value);
         self.current.log_format(log_data, key, value);
     }
 
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.parent.log_value(log_data, key, value);
+        self.current.log_value(log_data, key, value);
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        self.parent.log_bytes(log_data, key, bytes);
+        self.current.log_bytes(log_data, key, bytes);
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.parent.log_display(log_data, key, value);
+        self.current.log_display(log_data, key, value);
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        self.parent.log_serialize(log_data, key, value);
+        self.current.log_serialize(log_data, key, value);
+    }
+
     fn log_begin(&self, log_data: &ULogData) {
         self.parent.log_begin(log_data);
         self.current.log_begin(log_data);
@@ -63,6 +118,19 @@ impl<Parent: ULog, Current: ULog> ULog for ChainLogger<Parent, Current> {
         self.parent.log_end(log_data);
         self.current.log_end(log_data);
     }
+
+    fn is_available(&self) -> bool {
+        self.parent.is_available() || self.current.is_available()
+    }
+
+    fn flush(&self) {
+        self.parent.flush();
+        self.current.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.parent.enabled(log_data) || self.current.enabled(log_data)
+    }
 }
 
 /// Restricts the logs going to the wrapped logger to be above a minimum level threshold.
@@ -74,8 +142,11 @@ pub struct MinLevelLogger<Logger> {
 }
 
 impl<Logger: ULog> MinLevelLogger<Logger> {
-    pub fn new(logger: Logger, min_level: ULogLevel) -> Self {
-        Self { logger, min_level }
+    pub fn new(logger: Logger, min_level: impl Level) -> Self {
+        Self {
+            logger,
+            min_level: min_level.to_ulog_level(),
+        }
     }
 
     pub fn min_level(&self) -> ULogLevel {
@@ -94,12 +165,49 @@ impl<Logger: ULog> ULog for MinLevelLogger<Logger> {
         }
     }
 
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        if log_data.level >= self.min_level {
+            self.logger.log_str_continued(log_data, string);
+        }
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        if log_data.level >= self.min_level {
+            self.logger.log_fmt(log_data, args);
+        }
+    }
+
     fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
         if log_data.level >= self.min_level {
             self.logger.log_format(log_data, key, value);
         }
     }
 
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        if log_data.level >= self.min_level {
+            self.logger.log_value(log_data, key, value);
+        }
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        if log_data.level >= self.min_level {
+            self.logger.log_bytes(log_data, key, bytes);
+        }
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        if log_data.level >= self.min_level {
+            self.logger.log_display(log_data, key, value);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        if log_data.level >= self.min_level {
+            self.logger.log_serialize(log_data, key, value);
+        }
+    }
+
     fn log_begin(&self, log_data: &ULogData) {
         if log_data.level >= self.min_level {
             self.logger.log_begin(log_data);
@@ -111,4 +219,1727 @@ impl<Logger: ULog> ULog for MinLevelLogger<Logger> {
             self.logger.log_end(log_data);
         }
     }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        log_data.level >= self.min_level && self.logger.enabled(log_data)
+    }
+}
+
+/// Restricts the logs going to the wrapped logger to be above a minimum level, which can be set per
+/// [target](ULogData::target); a target like `"wifi"` also covers its sub-targets, like `"wifi::scan"`,
+/// and the most specific target wins.
+///
+/// As the macros default the target to the module path, levels can also be set per module, like `"my_app::net"`.
+///
+/// ```
+/// use ulog::common::{StubLogger, TargetLevelLogger};
+/// use ulog::ULogLevel;
+///
+/// let logger = TargetLevelLogger::new(StubLogger, ULogLevel::Info, &[("wifi", ULogLevel::Debug)]);
+///
+/// ulog::debug!(target: "wifi::scan", logger, "Found network", "rssi" => -60); // Logged
+/// ulog::debug!(logger, "Polling"); // Not logged
+/// ```
+#[derive(Debug, Clone)]
+pub struct TargetLevelLogger<'a, Logger> {
+    logger: Logger,
+    min_level: ULogLevel,
+    targets: &'a [(&'a str, ULogLevel)],
+}
+
+impl<'a, Logger: ULog> TargetLevelLogger<'a, Logger> {
+    pub fn new(logger: Logger, min_level: impl Level, targets: &'a [(&'a str, ULogLevel)]) -> Self {
+        Self {
+            logger,
+            min_level: min_level.to_ulog_level(),
+            targets,
+        }
+    }
+
+    /// Returns the minimum level of statements with the given target.
+    pub fn level_for(&self, target: Option<&str>) -> ULogLevel {
+        let Some(target) = target else {
+            return self.min_level;
+        };
+
+        self.targets
+            .iter()
+            .filter(|(prefix, _)| {
+                target
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.min_level, |(_, level)| *level)
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+
+    fn passes(&self, log_data: &ULogData) -> bool {
+        log_data.level >= self.level_for(log_data.target)
+    }
+}
+
+impl<Logger: ULog> ULog for TargetLevelLogger<'_, Logger> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        if self.passes(log_data) {
+            self.logger.log_str(log_data, string);
+        }
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        if self.passes(log_data) {
+            self.logger.log_str_continued(log_data, string);
+        }
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        if self.passes(log_data) {
+            self.logger.log_fmt(log_data, args);
+        }
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        if self.passes(log_data) {
+            self.logger.log_format(log_data, key, value);
+        }
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        if self.passes(log_data) {
+            self.logger.log_value(log_data, key, value);
+        }
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        if self.passes(log_data) {
+            self.logger.log_bytes(log_data, key, bytes);
+        }
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        if self.passes(log_data) {
+            self.logger.log_display(log_data, key, value);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        if self.passes(log_data) {
+            self.logger.log_serialize(log_data, key, value);
+        }
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        if self.passes(log_data) {
+            self.logger.log_begin(log_data);
+        }
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        if self.passes(log_data) {
+            self.logger.log_end(log_data);
+        }
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.passes(log_data) && self.logger.enabled(log_data)
+    }
+}
+
+/// Forwards logging statements to a primary logger, switching to a secondary logger while the primary one
+/// reports itself as unavailable through [`ULog::is_available`].
+///
+/// The choice is made at the beginning of each logging statement, so a statement is never split between both loggers.
+/// Can be quickly constructed using [`ULog::failover`].
+#[derive(Debug, Clone)]
+pub struct FailoverLogger<Primary, Secondary> {
+    primary: Primary,
+    secondary: Secondary,
+    use_secondary: Cell<bool>,
+}
+
+impl<Primary: ULog, Secondary: ULog> FailoverLogger<Primary, Secondary> {
+    pub fn new(primary: Primary, secondary: Secondary) -> Self {
+        Self {
+            primary,
+            secondary,
+            use_secondary: Cell::new(false),
+        }
+    }
+
+    pub fn primary(&self) -> &Primary {
+        &self.primary
+    }
+
+    pub fn secondary(&self) -> &Secondary {
+        &self.secondary
+    }
+
+    /// Returns whether the last logging statement was sent to the secondary logger.
+    pub fn is_using_secondary(&self) -> bool {
+        self.use_secondary.get()
+    }
+
+    pub fn into_inner(self) -> (Primary, Secondary) {
+        (self.primary, self.secondary)
+    }
+}
+
+impl<Primary: ULog, Secondary: ULog> ULog for FailoverLogger<Primary, Secondary> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        if self.use_secondary.get() {
+            self.secondary.log_str(log_data, string);
+        } else {
+            self.primary.log_str(log_data, string);
+        }
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        if self.use_secondary.get() {
+            self.secondary.log_str_continued(log_data, string);
+        } else {
+            self.primary.log_str_continued(log_data, string);
+        }
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        if self.use_secondary.get() {
+            self.secondary.log_fmt(log_data, args);
+        } else {
+            self.primary.log_fmt(log_data, args);
+        }
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        if self.use_secondary.get() {
+            self.secondary.log_format(log_data, key, value);
+        } else {
+            self.primary.log_format(log_data, key, value);
+        }
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        if self.use_secondary.get() {
+            self.secondary.log_value(log_data, key, value);
+        } else {
+            self.primary.log_value(log_data, key, value);
+        }
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        if self.use_secondary.get() {
+            self.secondary.log_bytes(log_data, key, bytes);
+        } else {
+            self.primary.log_bytes(log_data, key, bytes);
+        }
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        if self.use_secondary.get() {
+            self.secondary.log_display(log_data, key, value);
+        } else {
+            self.primary.log_display(log_data, key, value);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        if self.use_secondary.get() {
+            self.secondary.log_serialize(log_data, key, value);
+        } else {
+            self.primary.log_serialize(log_data, key, value);
+        }
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.use_secondary.set(!self.primary.is_available());
+
+        if self.use_secondary.get() {
+            self.secondary.log_begin(log_data);
+        } else {
+            self.primary.log_begin(log_data);
+        }
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        if self.use_secondary.get() {
+            self.secondary.log_end(log_data);
+        } else {
+            self.primary.log_end(log_data);
+        }
+    }
+
+    fn is_available(&self) -> bool {
+        self.primary.is_available() || self.secondary.is_available()
+    }
+
+    fn flush(&self) {
+        self.primary.flush();
+        self.secondary.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        if self.primary.is_available() {
+            self.primary.enabled(log_data)
+        } else {
+            self.secondary.enabled(log_data)
+        }
+    }
+}
+
+/// A part of a logging statement, as passed through the closure of a [`TransformLogger`].
+#[derive(Clone, Copy, Debug)]
+pub enum Entry<'a> {
+    /// A string passed to [`ULog::log_str`].
+    Str(&'a str),
+    /// A string passed to [`ULog::log_str_continued`], continuing the previous one.
+    StrContinued(&'a str),
+    /// A formatted message passed to [`ULog::log_fmt`].
+    Fmt(core::fmt::Arguments<'a>),
+    /// A key-value pair passed to [`ULog::log_format`].
+    Field(&'a str, &'a dyn core::fmt::Debug),
+    /// A key-value pair passed to [`ULog::log_value`].
+    Value(&'a str, Value<'a>),
+    /// A key-value pair passed to [`ULog::log_bytes`].
+    Bytes(&'a str, &'a [u8]),
+    /// A key-value pair passed to [`ULog::log_serialize`], requires the `serde` feature.
+    #[cfg(feature = "serde")]
+    Serialized(&'a str, Serialized<'a>),
+}
+
+/// Passes every string and key-value pair of a logging statement through a closure before forwarding them.
+///
+/// The closure receives the log data, the current [`Entry`] and an `emit` function forwarding entries to the
+/// wrapped logger; it may call `emit` with a rewritten entry, call it several times to enrich the statement,
+/// or not at all to drop the entry.
+///
+/// ```
+/// use ulog::common::{Entry, StubLogger, TransformLogger};
+///
+/// let logger = TransformLogger::new(StubLogger, |_log_data, entry, emit| match entry {
+///     Entry::Field("password", _) => emit(Entry::Field("password", &"<redacted>")),
+///     entry => emit(entry),
+/// });
+///
+/// ulog::info!(logger, "Logging in", "user" => "admin", "password" => "hunter2");
+/// ```
+#[derive(Clone)]
+pub struct TransformLogger<Logger, F> {
+    logger: Logger,
+    transform: F,
+}
+
+impl<Logger, F> TransformLogger<Logger, F>
+where
+    Logger: ULog,
+    F: Fn(&ULogData, Entry<'_>, &mut dyn FnMut(Entry<'_>)),
+{
+    pub fn new(logger: Logger, transform: F) -> Self {
+        Self { logger, transform }
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+
+    fn forward(&self, log_data: &ULogData, entry: Entry<'_>) {
+        match entry {
+            Entry::Str(string) => self.logger.log_str(log_data, string),
+            Entry::StrContinued(string) => self.logger.log_str_continued(log_data, string),
+            Entry::Fmt(args) => self.logger.log_fmt(log_data, args),
+            Entry::Field(key, value) => self.logger.log_format(log_data, key, &value),
+            Entry::Value(key, value) => self.logger.log_value(log_data, key, value),
+            Entry::Bytes(key, bytes) => self.logger.log_bytes(log_data, key, bytes),
+            #[cfg(feature = "serde")]
+            Entry::Serialized(key, value) => self.logger.log_serialize(log_data, key, value.0),
+        }
+    }
+}
+
+impl<Logger, F> ULog for TransformLogger<Logger, F>
+where
+    Logger: ULog,
+    F: Fn(&ULogData, Entry<'_>, &mut dyn FnMut(Entry<'_>)),
+{
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        (self.transform)(log_data, Entry::Str(string), &mut |entry| {
+            self.forward(log_data, entry)
+        });
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        (self.transform)(log_data, Entry::StrContinued(string), &mut |entry| {
+            self.forward(log_data, entry)
+        });
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        (self.transform)(log_data, Entry::Fmt(args), &mut |entry| {
+            self.forward(log_data, entry)
+        });
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        (self.transform)(log_data, Entry::Field(key, value), &mut |entry| {
+            self.forward(log_data, entry)
+        });
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        (self.transform)(log_data, Entry::Value(key, value), &mut |entry| {
+            self.forward(log_data, entry)
+        });
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        (self.transform)(log_data, Entry::Bytes(key, bytes), &mut |entry| {
+            self.forward(log_data, entry)
+        });
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        (self.transform)(
+            log_data,
+            Entry::Serialized(key, Serialized(value)),
+            &mut |entry| self.forward(log_data, entry),
+        );
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.logger.log_begin(log_data);
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        self.logger.log_end(log_data);
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.logger.enabled(log_data)
+    }
+}
+
+/// A set of key-value pairs that can be logged at once, like the context of a [`ContextLogger`].
+///
+/// Implemented for key-value tuples and for arrays and slices of them, with keys like `&str`, `String` or
+/// `Cow<str>`; use `&dyn Debug` values to mix different value types.
+pub trait KeyValues {
+    /// Calls [`ULog::log_format`] on `logger` for each key-value pair.
+    fn log_key_values<Logger: ULog>(&self, logger: &Logger, log_data: &ULogData);
+}
+
+impl<K: AsRef<str>, T: core::fmt::Debug> KeyValues for (K, T) {
+    fn log_key_values<Logger: ULog>(&self, logger: &Logger, log_data: &ULogData) {
+        logger.log_format(log_data, self.0.as_ref(), &self.1);
+    }
+}
+
+impl<K: AsRef<str>, T: core::fmt::Debug> KeyValues for [(K, T)] {
+    fn log_key_values<Logger: ULog>(&self, logger: &Logger, log_data: &ULogData) {
+        for (key, value) in self {
+            logger.log_format(log_data, key.as_ref(), value);
+        }
+    }
+}
+
+impl<K: AsRef<str>, T: core::fmt::Debug, const N: usize> KeyValues for [(K, T); N] {
+    fn log_key_values<Logger: ULog>(&self, logger: &Logger, log_data: &ULogData) {
+        self[..].log_key_values(logger, log_data);
+    }
+}
+
+impl<K: KeyValues + ?Sized> KeyValues for &K {
+    fn log_key_values<Logger: ULog>(&self, logger: &Logger, log_data: &ULogData) {
+        (**self).log_key_values(logger, log_data);
+    }
+}
+
+/// Appends a fixed set of key-value pairs (like a device id or a firmware version) to every logging statement,
+/// after the statement's own key-value pairs.
+/// Can be quickly constructed using [`ULog::with_context`].
+///
+/// ```
+/// use ulog::ULog;
+/// use ulog::common::StubLogger;
+///
+/// let logger = StubLogger.with_context([("device", "sensor-12"), ("firmware", "1.4.2")]);
+///
+/// ulog::info!(logger, "Booted");
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContextLogger<Logger, Context> {
+    logger: Logger,
+    context: Context,
+}
+
+impl<Logger: ULog, Context: KeyValues> ContextLogger<Logger, Context> {
+    pub fn new(logger: Logger, context: Context) -> Self {
+        Self { logger, context }
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    pub fn into_inner(self) -> (Logger, Context) {
+        (self.logger, self.context)
+    }
+}
+
+impl<Logger: ULog, Context: KeyValues> ULog for ContextLogger<Logger, Context> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str(log_data, string);
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str_continued(log_data, string);
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.logger.log_fmt(log_data, args);
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_format(log_data, key, value);
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.logger.log_value(log_data, key, value);
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        self.logger.log_bytes(log_data, key, bytes);
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_display(log_data, key, value);
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        self.logger.log_serialize(log_data, key, value);
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.logger.log_begin(log_data);
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        self.context.log_key_values(&self.logger, log_data);
+        self.logger.log_end(log_data);
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.logger.enabled(log_data)
+    }
+}
+
+/// Appends the key-value pairs of the currently active scopes to every logging statement.
+///
+/// A scope is opened with [`scope`](ScopedContextLogger::scope), and lasts until the returned guard is dropped;
+/// this is useful to tag every statement with, for instance, the id of the request being handled.
+/// Up to `DEPTH` scopes can be active at once, each key and value being copied into `SIZE` bytes,
+/// so that keys can be computed at runtime; scopes opened past that depth are ignored.
+///
+/// ```
+/// use ulog::common::{ScopedContextLogger, StubLogger};
+///
+/// let logger = ScopedContextLogger::<_, 4, 16>::new(StubLogger);
+///
+/// {
+///     let _scope = logger.scope("request_id", &1234);
+///     ulog::info!(logger, "Handling request"); // Logged with `request_id => 1234`
+/// }
+/// ```
+pub struct ScopedContextLogger<Logger, const DEPTH: usize, const SIZE: usize> {
+    logger: Logger,
+    scopes: RefCell<[(TextBuffer<SIZE>, TextBuffer<SIZE>); DEPTH]>,
+    depth: Cell<usize>,
+}
+
+impl<Logger: ULog, const DEPTH: usize, const SIZE: usize> ScopedContextLogger<Logger, DEPTH, SIZE> {
+    pub fn new(logger: Logger) -> Self {
+        Self {
+            logger,
+            scopes: RefCell::new(core::array::from_fn(|_| {
+                (TextBuffer::new(), TextBuffer::new())
+            })),
+            depth: Cell::new(0),
+        }
+    }
+
+    /// Opens a scope in which statements get the `key => value` pair appended, until the returned guard is dropped.
+    pub fn scope<T: core::fmt::Debug>(
+        &self,
+        key: &str,
+        value: &T,
+    ) -> ContextScope<'_, Logger, DEPTH, SIZE> {
+        let depth = self.depth.get();
+
+        if depth < DEPTH {
+            let scope = &mut self.scopes.borrow_mut()[depth];
+            scope.0.clear();
+            scope.1.clear();
+            // Writing to a TextBuffer never fails
+            let _ = scope.0.write_str(key);
+            let _ = write!(scope.1, "{:?}", value);
+        }
+        self.depth.set(depth + 1);
+
+        ContextScope {
+            logger: self,
+            depth,
+        }
+    }
+
+    /// Returns the number of currently active scopes.
+    pub fn depth(&self) -> usize {
+        self.depth.get()
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+}
+
+impl<Logger: ULog, const DEPTH: usize, const SIZE: usize> ULog
+    for ScopedContextLogger<Logger, DEPTH, SIZE>
+{
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str(log_data, string);
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str_continued(log_data, string);
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.logger.log_fmt(log_data, args);
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_format(log_data, key, value);
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.logger.log_value(log_data, key, value);
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        self.logger.log_bytes(log_data, key, bytes);
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_display(log_data, key, value);
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        self.logger.log_serialize(log_data, key, value);
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.logger.log_begin(log_data);
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        let depth = self.depth.get().min(DEPTH);
+        for (key, value) in &self.scopes.borrow()[..depth] {
+            self.logger
+                .log_format(log_data, key.as_str(), &Verbatim(value.as_str()));
+        }
+        self.logger.log_end(log_data);
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.logger.enabled(log_data)
+    }
+}
+
+/// A guard returned by [`ScopedContextLogger::scope`], which closes the scope when dropped.
+#[must_use = "the scope is closed as soon as the guard is dropped"]
+pub struct ContextScope<'a, Logger: ULog, const DEPTH: usize, const SIZE: usize> {
+    logger: &'a ScopedContextLogger<Logger, DEPTH, SIZE>,
+    depth: usize,
+}
+
+impl<Logger: ULog, const DEPTH: usize, const SIZE: usize> Drop
+    for ContextScope<'_, Logger, DEPTH, SIZE>
+{
+    fn drop(&mut self) {
+        // Also closes any inner scope that would still be open
+        self.logger.depth.set(self.depth);
+    }
+}
+
+/// An identifier used to stitch together the statements of a same operation across tasks or devices,
+/// as logged by [`CorrelationLogger`]. Formatted as 32 hexadecimal digits.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct CorrelationId(pub [u8; 16]);
+
+impl CorrelationId {
+    pub fn as_u128(&self) -> u128 {
+        u128::from_be_bytes(self.0)
+    }
+
+    /// Deterministically derives the id of the `index`-th child operation from this id.
+    pub fn child(&self, index: u64) -> Self {
+        // splitmix64's finalizer, which spreads each input bit over the whole output
+        fn mix(mut x: u64) -> u64 {
+            x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+            x ^ (x >> 31)
+        }
+
+        let id = self.as_u128();
+        let high = mix((id >> 64) as u64 ^ mix(index.wrapping_add(0x9e3779b97f4a7c15)));
+        let low = mix(id as u64 ^ mix(high ^ index));
+
+        Self::from(((high as u128) << 64) | low as u128)
+    }
+}
+
+impl From<u128> for CorrelationId {
+    fn from(value: u128) -> Self {
+        Self(value.to_be_bytes())
+    }
+}
+
+impl From<u64> for CorrelationId {
+    fn from(value: u64) -> Self {
+        Self::from(value as u128)
+    }
+}
+
+impl From<[u8; 16]> for CorrelationId {
+    fn from(value: [u8; 16]) -> Self {
+        Self(value)
+    }
+}
+
+impl core::fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:032x}", self.as_u128())
+    }
+}
+
+impl core::fmt::Debug for CorrelationId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(self, f)
+    }
+}
+
+/// Stamps every logging statement with a `trace_id` key, holding a [`CorrelationId`] set by the application.
+#[derive(Debug, Clone)]
+pub struct CorrelationLogger<Logger> {
+    logger: Logger,
+    id: Cell<CorrelationId>,
+}
+
+impl<Logger: ULog> CorrelationLogger<Logger> {
+    pub fn new(logger: Logger, id: impl Into<CorrelationId>) -> Self {
+        Self {
+            logger,
+            id: Cell::new(id.into()),
+        }
+    }
+
+    pub fn id(&self) -> CorrelationId {
+        self.id.get()
+    }
+
+    pub fn set_id(&self, id: impl Into<CorrelationId>) {
+        self.id.set(id.into());
+    }
+
+    /// Returns a logger for the `index`-th child operation, stamping statements with [`CorrelationId::child`].
+    pub fn child(&self, index: u64) -> CorrelationLogger<&Logger> {
+        CorrelationLogger::new(&self.logger, self.id().child(index))
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+}
+
+impl<Logger: ULog> ULog for CorrelationLogger<Logger> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str(log_data, string);
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str_continued(log_data, string);
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.logger.log_fmt(log_data, args);
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_format(log_data, key, value);
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.logger.log_value(log_data, key, value);
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        self.logger.log_bytes(log_data, key, bytes);
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_display(log_data, key, value);
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        self.logger.log_serialize(log_data, key, value);
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.logger.log_begin(log_data);
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        self.logger.log_format(log_data, "trace_id", &self.id());
+        self.logger.log_end(log_data);
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.logger.enabled(log_data)
+    }
+}
+
+pub(crate) const LEVEL_COUNT: usize = ULogLevel::all_levels().len();
+
+/// Counts the logging statements of each level, before forwarding them to the wrapped logger.
+///
+/// Useful to report statistics like "42 warnings, 3 errors since boot" without having to parse the logs.
+/// The counters are atomic, so they can be read from another thread or interrupt; on targets without atomic
+/// compare-and-swap, like `thumbv6m-none-eabi`, this requires the `critical-section` feature.
+#[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+#[derive(Debug)]
+pub struct CounterLogger<Logger> {
+    logger: Logger,
+    counts: [AtomicUsize; LEVEL_COUNT],
+}
+
+#[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+impl<Logger: ULog> CounterLogger<Logger> {
+    pub fn new(logger: Logger) -> Self {
+        Self {
+            logger,
+            counts: core::array::from_fn(|_| AtomicUsize::new(0)),
+        }
+    }
+
+    /// Returns the number of statements logged at `level`.
+    pub fn count(&self, level: ULogLevel) -> usize {
+        self.counts[level as usize].load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of statements logged at each level, in ascending order of levels.
+    pub fn counts(&self) -> impl Iterator<Item = (ULogLevel, usize)> + '_ {
+        ULogLevel::all_levels()
+            .into_iter()
+            .map(|level| (level, self.count(level)))
+    }
+
+    /// Returns the total number of statements logged.
+    pub fn total(&self) -> usize {
+        self.counts().map(|(_, count)| count).sum()
+    }
+
+    /// Resets all of the counters to zero.
+    pub fn reset(&self) {
+        for count in &self.counts {
+            count.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+}
+
+#[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+impl<Logger: ULog> ULog for CounterLogger<Logger> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str(log_data, string);
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str_continued(log_data, string);
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.logger.log_fmt(log_data, args);
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_format(log_data, key, value);
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.logger.log_value(log_data, key, value);
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        self.logger.log_bytes(log_data, key, bytes);
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_display(log_data, key, value);
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        self.logger.log_serialize(log_data, key, value);
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        atomic::fetch_add(&self.counts[log_data.level as usize], 1, Ordering::Relaxed);
+        self.logger.log_begin(log_data);
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        self.logger.log_end(log_data);
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.logger.enabled(log_data)
+    }
+}
+
+/// Numbers each logging statement when it begins, in its [`sequence`](ULogData::sequence), before forwarding it
+/// to the wrapped logger.
+///
+/// Statements are numbered from zero, wrapping around after [`u32::MAX`]; a gap in the numbers seen downstream
+/// means that statements were dropped, for instance by a full queue, and numbers out of order mean that
+/// statements were reordered by the transport. Statements that already carry a sequence number keep it.
+///
+/// The counter is atomic, so the logger can be shared between threads or interrupts, each statement getting its
+/// own number in the log data given to [`log_begin`](ULog::log_begin); the other calls of a statement carry the
+/// number of the last statement that began. On targets without atomic compare-and-swap, this requires the
+/// `critical-section` feature.
+///
+/// ```
+/// use ulog::common::{CallbackLogger, SequenceLogger};
+///
+/// let logger = SequenceLogger::new(CallbackLogger::<_, 64>::new(|log_data, text| {
+///     println!("#{} {text}", log_data.sequence.unwrap());
+/// }));
+/// ulog::info!(logger, "Numbered 0");
+/// ulog::info!(logger, "Numbered 1");
+/// assert_eq!(logger.next_sequence(), 2);
+/// ```
+#[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+#[derive(Debug)]
+pub struct SequenceLogger<Logger> {
+    logger: Logger,
+    next: AtomicUsize,
+}
+
+#[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+impl<Logger: ULog> SequenceLogger<Logger> {
+    pub fn new(logger: Logger) -> Self {
+        Self {
+            logger,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the sequence number of the next statement.
+    pub fn next_sequence(&self) -> u32 {
+        self.next.load(Ordering::Relaxed) as u32
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+
+    fn stamp(&self, log_data: &ULogData, sequence: u32) -> ULogData {
+        let mut log_data = log_data.clone();
+        if log_data.sequence.is_none() {
+            log_data.sequence = Some(sequence);
+        }
+        log_data
+    }
+
+    /// Stamps the calls following `log_begin` with the number of the last statement that began.
+    fn stamp_current(&self, log_data: &ULogData) -> ULogData {
+        self.stamp(log_data, self.next_sequence().wrapping_sub(1))
+    }
+}
+
+#[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+impl<Logger: ULog> ULog for SequenceLogger<Logger> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str(&self.stamp_current(log_data), string);
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.logger
+            .log_str_continued(&self.stamp_current(log_data), string);
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.logger.log_fmt(&self.stamp_current(log_data), args);
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger
+            .log_format(&self.stamp_current(log_data), key, value);
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.logger
+            .log_value(&self.stamp_current(log_data), key, value);
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        self.logger
+            .log_bytes(&self.stamp_current(log_data), key, bytes);
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger
+            .log_display(&self.stamp_current(log_data), key, value);
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        self.logger
+            .log_serialize(&self.stamp_current(log_data), key, value);
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        let sequence = atomic::fetch_add(&self.next, 1, Ordering::Relaxed) as u32;
+        self.logger.log_begin(&self.stamp(log_data, sequence));
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        self.logger.log_end(&self.stamp_current(log_data));
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.logger.enabled(log_data)
+    }
+}
+
+/// Calls a closure with each completed logging statement, formatted as `message key=value` into `SIZE` bytes.
+///
+/// This lets applications react to logs (for instance by blinking a LED on errors)
+/// without implementing [`ULog`] themselves. The callback may itself log to the same logger.
+///
+/// ```
+/// use ulog::ULogLevel;
+/// use ulog::common::CallbackLogger;
+///
+/// let logger = CallbackLogger::<_, 64>::new(|log_data, text| {
+///     if log_data.level >= ULogLevel::Error {
+///         println!("Something went wrong: {text}");
+///     }
+/// });
+///
+/// ulog::error!(logger, "Sensor disconnected", "sensor" => 3);
+/// ```
+pub struct CallbackLogger<F, const SIZE: usize> {
+    callback: F,
+    buffer: RefCell<TextBuffer<SIZE>>,
+}
+
+impl<F: Fn(&ULogData, &str), const SIZE: usize> CallbackLogger<F, SIZE> {
+    pub fn new(callback: F) -> Self {
+        Self {
+            callback,
+            buffer: RefCell::new(TextBuffer::new()),
+        }
+    }
+
+    pub fn into_inner(self) -> F {
+        self.callback
+    }
+}
+
+impl<F: Fn(&ULogData, &str), const SIZE: usize> ULog for CallbackLogger<F, SIZE> {
+    fn log_str(&self, _log_data: &ULogData, string: &str) {
+        let mut buffer = self.buffer.borrow_mut();
+        if !buffer.is_empty() {
+            let _ = buffer.write_char(' ');
+        }
+        let _ = buffer.write_str(string);
+    }
+
+    fn log_str_continued(&self, _log_data: &ULogData, string: &str) {
+        let _ = self.buffer.borrow_mut().write_str(string);
+    }
+
+    fn log_fmt(&self, _log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        let mut buffer = self.buffer.borrow_mut();
+        if !buffer.is_empty() {
+            let _ = buffer.write_char(' ');
+        }
+        let _ = buffer.write_fmt(args);
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+        let mut buffer = self.buffer.borrow_mut();
+        if !buffer.is_empty() {
+            let _ = buffer.write_char(' ');
+        }
+        let _ = write!(buffer, "{}={:?}", key, value);
+    }
+
+    fn log_value(&self, _log_data: &ULogData, key: &str, value: Value<'_>) {
+        let mut buffer = self.buffer.borrow_mut();
+        if !buffer.is_empty() {
+            let _ = buffer.write_char(' ');
+        }
+        let _ = write!(buffer, "{}={:?}", key, value);
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, _log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        let mut buffer = self.buffer.borrow_mut();
+        if !buffer.is_empty() {
+            let _ = buffer.write_char(' ');
+        }
+        let _ = write!(buffer, "{}={}", key, Serialized(value));
+    }
+
+    fn log_begin(&self, _log_data: &ULogData) {
+        self.buffer.borrow_mut().clear();
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        // Taken out of the logger, so that the callback can log too
+        let buffer = core::mem::take(&mut *self.buffer.borrow_mut());
+        (self.callback)(log_data, buffer.as_str());
+    }
+}
+
+/// Indents the message of logging statements according to the current nesting depth,
+/// so that hierarchical operations (boot stages, parsing) read naturally.
+///
+/// The depth is increased by [`enter`](IndentLogger::enter) until the returned guard is dropped.
+/// Indented messages are truncated to `SIZE` bytes.
+///
+/// ```
+/// use ulog::common::{IndentLogger, StubLogger};
+///
+/// let logger = IndentLogger::<_, 64>::new(StubLogger);
+///
+/// ulog::info!(logger, "Booting");
+/// {
+///     let _guard = logger.enter();
+///     ulog::info!(logger, "Mounting filesystem"); // Logged as "  Mounting filesystem"
+/// }
+/// ```
+pub struct IndentLogger<Logger, const SIZE: usize> {
+    logger: Logger,
+    indent: &'static str,
+    depth: Cell<usize>,
+    indented: Cell<bool>,
+    buffer: RefCell<TextBuffer<SIZE>>,
+}
+
+impl<Logger: ULog, const SIZE: usize> IndentLogger<Logger, SIZE> {
+    /// Creates an `IndentLogger`, indenting by two spaces per level by default.
+    pub fn new(logger: Logger) -> Self {
+        Self {
+            logger,
+            indent: "  ",
+            depth: Cell::new(0),
+            indented: Cell::new(false),
+            buffer: RefCell::new(TextBuffer::new()),
+        }
+    }
+
+    /// Sets the string repeated once per level of indentation.
+    pub fn with_indent(mut self, indent: &'static str) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Increases the indentation until the returned guard is dropped.
+    pub fn enter(&self) -> IndentGuard<'_, Logger, SIZE> {
+        self.depth.set(self.depth.get() + 1);
+        IndentGuard { logger: self }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth.get()
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+}
+
+/// The indentation of a message, made of `indent` repeated for each level of depth.
+struct Indentation<'a>(&'a str, usize);
+
+impl core::fmt::Display for Indentation<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for _ in 0..self.1 {
+            f.write_str(self.0)?;
+        }
+        Ok(())
+    }
+}
+
+impl<Logger: ULog, const SIZE: usize> ULog for IndentLogger<Logger, SIZE> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        if self.indented.replace(true) || self.depth.get() == 0 {
+            self.logger.log_str(log_data, string);
+            return;
+        }
+
+        let mut buffer = self.buffer.borrow_mut();
+        buffer.clear();
+        for _ in 0..self.depth.get() {
+            let _ = buffer.write_str(self.indent);
+        }
+        let _ = buffer.write_str(string);
+
+        self.logger.log_str(log_data, buffer.as_str());
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        if self.indented.get() {
+            self.logger.log_str_continued(log_data, string);
+        } else {
+            self.log_str(log_data, string);
+        }
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        if self.indented.replace(true) || self.depth.get() == 0 {
+            self.logger.log_fmt(log_data, args);
+            return;
+        }
+
+        let indentation = Indentation(self.indent, self.depth.get());
+        self.logger
+            .log_fmt(log_data, format_args!("{}{}", indentation, args));
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_format(log_data, key, value);
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.logger.log_value(log_data, key, value);
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        self.logger.log_bytes(log_data, key, bytes);
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_display(log_data, key, value);
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        self.logger.log_serialize(log_data, key, value);
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.indented.set(false);
+        self.logger.log_begin(log_data);
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        self.logger.log_end(log_data);
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.logger.enabled(log_data)
+    }
+}
+
+/// A guard returned by [`IndentLogger::enter`], which decreases the indentation when dropped.
+#[must_use = "the indentation is decreased as soon as the guard is dropped"]
+pub struct IndentGuard<'a, Logger: ULog, const SIZE: usize> {
+    logger: &'a IndentLogger<Logger, SIZE>,
+}
+
+impl<Logger: ULog, const SIZE: usize> Drop for IndentGuard<'_, Logger, SIZE> {
+    fn drop(&mut self) {
+        let depth = &self.logger.depth;
+        depth.set(depth.get().saturating_sub(1));
+    }
+}
+
+/// Which keys a [`KeyFilterLogger`] lets through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyFilter<'a> {
+    /// Drops the key-value pairs whose key is in the list.
+    Deny(&'a [&'a str]),
+    /// Only keeps the key-value pairs whose key is in the list.
+    Allow(&'a [&'a str]),
+}
+
+impl KeyFilter<'_> {
+    /// Returns whether key-value pairs with the given key are let through.
+    pub fn allows(&self, key: &str) -> bool {
+        match self {
+            KeyFilter::Deny(keys) => !keys.contains(&key),
+            KeyFilter::Allow(keys) => keys.contains(&key),
+        }
+    }
+}
+
+/// Drops the key-value pairs whose key is rejected by a [`KeyFilter`], before forwarding the statements
+/// to the wrapped logger; useful to trim the noisy fields of a verbose library.
+///
+/// ```
+/// use ulog::common::{KeyFilter, KeyFilterLogger, StubLogger};
+///
+/// let logger = KeyFilterLogger::new(StubLogger, KeyFilter::Deny(&["payload"]));
+///
+/// ulog::info!(logger, "Received packet", "size" => 3, "payload" => [1, 2, 3]); // Logged without the payload
+/// ```
+#[derive(Debug, Clone)]
+pub struct KeyFilterLogger<'a, Logger> {
+    logger: Logger,
+    filter: KeyFilter<'a>,
+}
+
+impl<'a, Logger: ULog> KeyFilterLogger<'a, Logger> {
+    pub fn new(logger: Logger, filter: KeyFilter<'a>) -> Self {
+        Self { logger, filter }
+    }
+
+    pub fn filter(&self) -> KeyFilter<'a> {
+        self.filter
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+}
+
+impl<Logger: ULog> ULog for KeyFilterLogger<'_, Logger> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str(log_data, string);
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str_continued(log_data, string);
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.logger.log_fmt(log_data, args);
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        if self.filter.allows(key) {
+            self.logger.log_format(log_data, key, value);
+        }
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        if self.filter.allows(key) {
+            self.logger.log_value(log_data, key, value);
+        }
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        if self.filter.allows(key) {
+            self.logger.log_bytes(log_data, key, bytes);
+        }
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        if self.filter.allows(key) {
+            self.logger.log_display(log_data, key, value);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        if self.filter.allows(key) {
+            self.logger.log_serialize(log_data, key, value);
+        }
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.logger.log_begin(log_data);
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        self.logger.log_end(log_data);
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.logger.enabled(log_data)
+    }
+}
+
+/// Formats values into a bounded buffer before forwarding them, cutting anything longer than `max_len` bytes
+/// and replacing it with an ellipsis and the original length, like `[1, 2, 3… (1234 bytes)`.
+///
+/// This prevents a single large value from flooding the output.
+/// `max_len` is capped to `SIZE`, the size of the buffer.
+pub struct TruncateLogger<Logger, const SIZE: usize> {
+    logger: Logger,
+    max_len: usize,
+    buffer: RefCell<TextBuffer<SIZE>>,
+}
+
+impl<Logger: ULog, const SIZE: usize> TruncateLogger<Logger, SIZE> {
+    pub fn new(logger: Logger, max_len: usize) -> Self {
+        Self {
+            logger,
+            max_len: max_len.min(SIZE),
+            buffer: RefCell::new(TextBuffer::new()),
+        }
+    }
+
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+
+    /// Renders a value into the buffer, returning the first `SIZE` bytes of the text and its full length.
+    ///
+    /// The buffer is taken out of the logger, so that the wrapped logger can log through it too.
+    fn render(
+        &self,
+        format: impl FnOnce(&mut dyn Write) -> core::fmt::Result,
+    ) -> (TextBuffer<SIZE>, usize) {
+        let mut buffer = core::mem::take(&mut *self.buffer.borrow_mut());
+        buffer.clear();
+        let mut writer = CountingWriter {
+            inner: &mut buffer,
+            count: 0,
+        };
+        let _ = format(&mut writer);
+        let len = writer.count;
+        (buffer, len)
+    }
+
+    /// Returns the length of the `Debug` representation of `value`, which values are measured with.
+    fn debug_len<T: core::fmt::Debug>(&self, value: &T) -> usize {
+        self.render(|out| write!(out, "{:?}", value)).1
+    }
+
+    /// Cuts `text` to `max_len` bytes, on a character boundary.
+    fn cut<'a>(&self, text: &'a str) -> &'a str {
+        let mut cut = self.max_len.min(text.len());
+        while !text.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        &text[..cut]
+    }
+}
+
+/// Counts the bytes written through it, including the ones the inner writer had to drop.
+struct CountingWriter<'a, W: Write> {
+    inner: &'a mut W,
+    count: usize,
+}
+
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write_str(&mut self, string: &str) -> core::fmt::Result {
+        self.count += string.len();
+        self.inner.write_str(string)
+    }
+}
+
+impl<Logger: ULog, const SIZE: usize> ULog for TruncateLogger<Logger, SIZE> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str(log_data, string);
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str_continued(log_data, string);
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.logger.log_fmt(log_data, args);
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        let (buffer, len) = self.render(|out| write!(out, "{:?}", value));
+
+        if len <= self.max_len {
+            self.logger
+                .log_format(log_data, key, &Verbatim(buffer.as_str()));
+        } else {
+            self.logger.log_format(
+                log_data,
+                key,
+                &format_args!("{}… ({} bytes)", self.cut(buffer.as_str()), len),
+            );
+        }
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        // Short strings and bytes are forwarded as they are, for the formatters to render them natively
+        match value {
+            Value::Str(_) | Value::Bytes(_) if self.debug_len(&value) > self.max_len => {
+                self.log_format(log_data, key, &value)
+            }
+            _ => self.logger.log_value(log_data, key, value),
+        }
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        // Measured like the other values, with their `Debug` representation
+        if self.debug_len(&bytes) <= self.max_len {
+            self.logger.log_bytes(log_data, key, bytes);
+        } else {
+            self.log_format(log_data, key, &bytes);
+        }
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        let (buffer, len) = self.render(|out| write!(out, "{}", value));
+
+        if len <= self.max_len {
+            self.logger.log_display(log_data, key, &buffer.as_str());
+        } else {
+            self.logger.log_display(
+                log_data,
+                key,
+                &format_args!("{}… ({} bytes)", self.cut(buffer.as_str()), len),
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        let (buffer, len) = self.render(|out| value.write_json(out));
+
+        // JSON cut short isn't JSON anymore, so it is only kept as JSON when it fits
+        if len <= self.max_len {
+            self.logger
+                .log_serialize(log_data, key, &RawJson(buffer.as_str()));
+        } else {
+            self.log_format(log_data, key, &Serialized(value));
+        }
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.logger.log_begin(log_data);
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        self.logger.log_end(log_data);
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.logger.enabled(log_data)
+    }
+}
+
+/// Forwards logging statements to the wrapped logger, then [flushes](ULog::flush) it and panics after any statement
+/// at or above `level` has been forwarded; useful to crash early on critical statements in debug builds,
+/// or to make tests fail on unexpected errors.
+///
+/// Instead of panicking, a hook can be called with [`with_hook`](PanicOnLevelLogger::with_hook),
+/// for instance to abort or to reset the device.
+#[derive(Debug, Clone)]
+pub struct PanicOnLevelLogger<Logger, Hook = fn(&ULogData)> {
+    logger: Logger,
+    level: ULogLevel,
+    hook: Option<Hook>,
+}
+
+impl<Logger: ULog> PanicOnLevelLogger<Logger> {
+    pub fn new(logger: Logger, level: impl Level) -> Self {
+        Self {
+            logger,
+            level: level.to_ulog_level(),
+            hook: None,
+        }
+    }
+}
+
+impl<Logger: ULog, Hook: Fn(&ULogData)> PanicOnLevelLogger<Logger, Hook> {
+    /// Sets the hook called instead of panicking.
+    pub fn with_hook<NewHook: Fn(&ULogData)>(
+        self,
+        hook: NewHook,
+    ) -> PanicOnLevelLogger<Logger, NewHook> {
+        PanicOnLevelLogger {
+            logger: self.logger,
+            level: self.level,
+            hook: Some(hook),
+        }
+    }
+
+    pub fn level(&self) -> ULogLevel {
+        self.level
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+}
+
+impl<Logger: ULog, Hook: Fn(&ULogData)> ULog for PanicOnLevelLogger<Logger, Hook> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str(log_data, string);
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str_continued(log_data, string);
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.logger.log_fmt(log_data, args);
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_format(log_data, key, value);
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.logger.log_value(log_data, key, value);
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        self.logger.log_bytes(log_data, key, bytes);
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_display(log_data, key, value);
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        self.logger.log_serialize(log_data, key, value);
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.logger.log_begin(log_data);
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        self.logger.log_end(log_data);
+
+        if log_data.level >= self.level {
+            match &self.hook {
+                Some(hook) => hook(log_data),
+                None => {
+                    self.logger.flush();
+                    panic!(
+                        "ulog: a statement was logged at the {} level ({}:{})",
+                        log_data.level_name, log_data.file, log_data.line
+                    )
+                }
+            }
+        }
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        log_data.level >= self.level || self.logger.enabled(log_data)
+    }
 }
@@ -0,0 +1,123 @@
+use super::ULogLevel;
+
+pub use crate::__compat_debug as debug;
+pub use crate::__compat_error as error;
+pub use crate::__compat_info as info;
+pub use crate::__compat_log as log;
+pub use crate::__compat_trace as trace;
+pub use crate::__compat_warn as warn;
+
+/// The levels of the `log` crate, converted to the matching [`ULogLevel`].
+///
+/// Unlike with `log`, the levels are ordered from the most to the least verbose, as with [`ULogLevel`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl super::Level for Level {
+    fn as_str(&self) -> &'static str {
+        self.to_ulog_level().as_str()
+    }
+
+    fn as_short_str(&self) -> &'static str {
+        self.to_ulog_level().as_short_str()
+    }
+
+    fn to_ulog_level(&self) -> ULogLevel {
+        match self {
+            Level::Trace => ULogLevel::Trace,
+            Level::Debug => ULogLevel::Debug,
+            Level::Info => ULogLevel::Info,
+            Level::Warn => ULogLevel::Warning,
+            Level::Error => ULogLevel::Error,
+        }
+    }
+}
+
+/// Logs a statement at the given level with the syntax of `log::log!`, for [`compat`](crate::compat).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __compat_log {
+    ( target: $target:expr, $level:expr, $($arg:tt)+ ) => {{
+        let log_data = $crate::ULogData::new($level, line!(), file!())
+            .with_column(column!())
+            .with_module(module_path!())
+            .with_target($target);
+        let logger = $crate::global::GlobalLogger;
+
+        if $crate::ULog::enabled(&logger, &log_data) {
+            $crate::ULog::log_begin(&logger, &log_data);
+            $crate::ULog::log_fmt(&logger, &log_data, format_args!($($arg)+));
+            $crate::ULog::log_end(&logger, &log_data);
+        }
+    }};
+
+    ( $level:expr, $($arg:tt)+ ) => {
+        $crate::__compat_log!(target: module_path!(), $level, $($arg)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __compat_trace {
+    ( target: $target:expr, $($arg:tt)+ ) => {
+        $crate::__compat_log!(target: $target, $crate::compat::Level::Trace, $($arg)+)
+    };
+
+    ( $($arg:tt)+ ) => {
+        $crate::__compat_log!($crate::compat::Level::Trace, $($arg)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __compat_debug {
+    ( target: $target:expr, $($arg:tt)+ ) => {
+        $crate::__compat_log!(target: $target, $crate::compat::Level::Debug, $($arg)+)
+    };
+
+    ( $($arg:tt)+ ) => {
+        $crate::__compat_log!($crate::compat::Level::Debug, $($arg)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __compat_info {
+    ( target: $target:expr, $($arg:tt)+ ) => {
+        $crate::__compat_log!(target: $target, $crate::compat::Level::Info, $($arg)+)
+    };
+
+    ( $($arg:tt)+ ) => {
+        $crate::__compat_log!($crate::compat::Level::Info, $($arg)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __compat_warn {
+    ( target: $target:expr, $($arg:tt)+ ) => {
+        $crate::__compat_log!(target: $target, $crate::compat::Level::Warn, $($arg)+)
+    };
+
+    ( $($arg:tt)+ ) => {
+        $crate::__compat_log!($crate::compat::Level::Warn, $($arg)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __compat_error {
+    ( target: $target:expr, $($arg:tt)+ ) => {
+        $crate::__compat_log!(target: $target, $crate::compat::Level::Error, $($arg)+)
+    };
+
+    ( $($arg:tt)+ ) => {
+        $crate::__compat_log!($crate::compat::Level::Error, $($arg)+)
+    };
+}
@@ -0,0 +1,538 @@
+use super::binary::{
+    cobs_decode, CobsError, FLAG_COLUMN, FLAG_LEVEL_NAME, FLAG_MODULE, FLAG_SEQUENCE, FLAG_TARGET,
+    FLAG_THREAD, FLAG_TIMESTAMP, FLAG_USER, FORMAT_VERSION, TAG_BOOL, TAG_BYTES, TAG_END, TAG_F64,
+    TAG_I64, TAG_JSON, TAG_KV, TAG_STR, TAG_STR_CONTINUED, TAG_STR_VALUE, TAG_U64,
+};
+use super::time::Rfc3339;
+use super::ULogLevel;
+use core::time::Duration;
+use std::fmt;
+
+/// A statement decoded from a frame of the binary format, see [`BinaryLogger`](crate::binary::BinaryLogger).
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedRecord {
+    pub level: ULogLevel,
+    /// The name of the level the statement was made at, see [`ULogData::level_name`](crate::ULogData::level_name).
+    pub level_name: String,
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    pub target: Option<String>,
+    pub module: Option<String>,
+    pub timestamp: Option<Duration>,
+    pub sequence: Option<u32>,
+    pub thread: Option<u32>,
+    pub user: Option<u32>,
+    /// The strings of the statement, joined with spaces.
+    pub message: String,
+    /// The key-value pairs of the statement.
+    pub fields: Vec<(String, DecodedValue)>,
+}
+
+/// The value of a key-value pair of a [`DecodedRecord`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecodedValue {
+    /// A value formatted by the device using its `Debug` implementation.
+    Debug(String),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Bool(bool),
+    Str(String),
+    Bytes(Vec<u8>),
+    /// A value serialized by the device as JSON, see [`TAG_JSON`].
+    Json(String),
+}
+
+impl fmt::Display for DecodedValue {
+    /// Formats the value like its `Debug` implementation on the device would.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodedValue::Debug(value) => f.write_str(value),
+            DecodedValue::I64(value) => write!(f, "{:?}", value),
+            DecodedValue::U64(value) => write!(f, "{:?}", value),
+            DecodedValue::F64(value) => write!(f, "{:?}", value),
+            DecodedValue::Bool(value) => write!(f, "{:?}", value),
+            DecodedValue::Str(value) => write!(f, "{:?}", value),
+            DecodedValue::Bytes(value) => write!(f, "{:?}", value),
+            DecodedValue::Json(value) => f.write_str(value),
+        }
+    }
+}
+
+impl fmt::Display for DecodedRecord {
+    /// Formats the statement as `LEVEL file:line message key=value`, prefixed with its timestamp if it has one,
+    /// like [`TextFormatter`](crate::format::TextFormatter) does.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(timestamp) = self.timestamp {
+            write!(f, "{} ", Rfc3339(timestamp))?;
+        }
+        write!(f, "{} {}:{}", self.level_name, self.file, self.line)?;
+        if !self.message.is_empty() {
+            write!(f, " {}", self.message)?;
+        }
+        for (key, value) in &self.fields {
+            write!(f, " {}={}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Why a frame could not be decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The COBS framing of the frame is broken.
+    Cobs(CobsError),
+    /// No frame delimiter was found within the maximum length of a frame.
+    FrameTooLong,
+    UnsupportedVersion(u8),
+    InvalidLevel(u8),
+    /// The frame refers to a file that is not in the file table.
+    UnknownFile(u64),
+    InvalidTag(u8),
+    InvalidUtf8,
+    /// The frame ends in the middle of a field, or contains bytes after its end.
+    InvalidLength,
+}
+
+impl From<CobsError> for DecodeError {
+    fn from(error: CobsError) -> Self {
+        DecodeError::Cobs(error)
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Cobs(_) => write!(f, "malformed COBS frame"),
+            DecodeError::FrameTooLong => write!(f, "frame too long"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version {}", version)
+            }
+            DecodeError::InvalidLevel(level) => write!(f, "invalid level {}", level),
+            DecodeError::UnknownFile(index) => write!(f, "unknown file index {}", index),
+            DecodeError::InvalidTag(tag) => write!(f, "invalid tag {}", tag),
+            DecodeError::InvalidUtf8 => write!(f, "invalid UTF-8 string"),
+            DecodeError::InvalidLength => write!(f, "invalid frame length"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Reads the fields of a frame.
+struct FrameReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> FrameReader<'a> {
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let (&byte, rest) = self.bytes.split_first().ok_or(DecodeError::InvalidLength)?;
+        self.bytes = rest;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::InvalidLength)
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        u32::try_from(self.varint()?).map_err(|_| DecodeError::InvalidLength)
+    }
+
+    fn bytes(&mut self, len: u64) -> Result<&'a [u8], DecodeError> {
+        let len = usize::try_from(len).map_err(|_| DecodeError::InvalidLength)?;
+        if len > self.bytes.len() {
+            return Err(DecodeError::InvalidLength);
+        }
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    fn string(&mut self) -> Result<&'a str, DecodeError> {
+        let len = self.varint()?;
+        self.str_of_len(len)
+    }
+
+    fn str_of_len(&mut self, len: u64) -> Result<&'a str, DecodeError> {
+        std::str::from_utf8(self.bytes(len)?).map_err(|_| DecodeError::InvalidUtf8)
+    }
+}
+
+/// Decodes a frame of the binary format, after its COBS framing was removed.
+///
+/// `files` must be the file table given to the [`BinaryLogger`](crate::binary::BinaryLogger) that encoded the frame.
+pub fn decode_frame(frame: &[u8], files: &[&str]) -> Result<DecodedRecord, DecodeError> {
+    let mut reader = FrameReader { bytes: frame };
+
+    let version = reader.byte()?;
+    if version != FORMAT_VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+
+    let level = reader.byte()?;
+    let level = ULogLevel::try_from(level).map_err(|_| DecodeError::InvalidLevel(level))?;
+
+    let file = reader.varint()?;
+    let file = if file & 1 == 1 {
+        let index = file >> 1;
+        *usize::try_from(index)
+            .ok()
+            .and_then(|index| files.get(index))
+            .ok_or(DecodeError::UnknownFile(index))?
+    } else {
+        reader.str_of_len(file >> 1)?
+    };
+
+    let line = reader.u32()?;
+
+    let flags = reader.byte()?;
+    let has = |flag: u8| flags & flag != 0;
+    let column = has(FLAG_COLUMN).then(|| reader.u32()).transpose()?;
+    let target = has(FLAG_TARGET).then(|| reader.string()).transpose()?;
+    let module = has(FLAG_MODULE).then(|| reader.string()).transpose()?;
+    let timestamp = has(FLAG_TIMESTAMP)
+        .then(|| reader.varint().map(Duration::from_nanos))
+        .transpose()?;
+    let sequence = has(FLAG_SEQUENCE).then(|| reader.u32()).transpose()?;
+    let thread = has(FLAG_THREAD).then(|| reader.u32()).transpose()?;
+    let user = has(FLAG_USER).then(|| reader.u32()).transpose()?;
+    let level_name = match has(FLAG_LEVEL_NAME) {
+        true => reader.string()?,
+        false => level.as_str(),
+    };
+
+    let mut record = DecodedRecord {
+        level,
+        level_name: level_name.to_string(),
+        file: file.to_string(),
+        line,
+        column,
+        target: target.map(str::to_string),
+        module: module.map(str::to_string),
+        timestamp,
+        sequence,
+        thread,
+        user,
+        message: String::new(),
+        fields: Vec::new(),
+    };
+
+    loop {
+        match reader.byte()? {
+            TAG_END => break,
+            TAG_STR => {
+                if !record.message.is_empty() {
+                    record.message.push(' ');
+                }
+                record.message.push_str(reader.string()?);
+            }
+            TAG_STR_CONTINUED => record.message.push_str(reader.string()?),
+            tag @ (TAG_KV | TAG_I64 | TAG_U64 | TAG_F64 | TAG_BOOL | TAG_STR_VALUE | TAG_BYTES
+            | TAG_JSON) => {
+                let key = reader.string()?.to_string();
+                let value = match tag {
+                    TAG_KV => DecodedValue::Debug(reader.string()?.to_string()),
+                    TAG_I64 => {
+                        let value = reader.varint()?;
+                        DecodedValue::I64((value >> 1) as i64 ^ -((value & 1) as i64))
+                    }
+                    TAG_U64 => DecodedValue::U64(reader.varint()?),
+                    TAG_F64 => {
+                        DecodedValue::F64(f64::from_le_bytes(reader.bytes(8)?.try_into().unwrap()))
+                    }
+                    TAG_BOOL => DecodedValue::Bool(reader.byte()? != 0),
+                    TAG_STR_VALUE => DecodedValue::Str(reader.string()?.to_string()),
+                    TAG_JSON => DecodedValue::Json(reader.string()?.to_string()),
+                    _ => {
+                        let len = reader.varint()?;
+                        DecodedValue::Bytes(reader.bytes(len)?.to_vec())
+                    }
+                };
+                record.fields.push((key, value));
+            }
+            tag => return Err(DecodeError::InvalidTag(tag)),
+        }
+    }
+
+    if !reader.bytes.is_empty() {
+        return Err(DecodeError::InvalidLength);
+    }
+
+    Ok(record)
+}
+
+/// Incrementally decodes a stream of COBS-framed binary frames, as written by a
+/// [`CobsEncoder`](crate::binary::CobsEncoder), back into [`DecodedRecord`]s.
+///
+/// Bytes can be fed in chunks of any size; corrupted frames are reported as errors, and decoding carries on
+/// with the next frame.
+///
+/// ```
+/// use ulog::binary::{BinaryLogger, CobsEncoder};
+/// use ulog::decoder::StreamDecoder;
+/// use std::cell::RefCell;
+///
+/// let received = RefCell::new(Vec::new());
+/// let logger = BinaryLogger::new(CobsEncoder::new(|bytes: &[u8]| {
+///     received.borrow_mut().extend_from_slice(bytes)
+/// }));
+/// ulog::info!(logger, "Hello", "value" => 32);
+///
+/// let mut decoder = StreamDecoder::new(&[]);
+/// decoder.feed(&received.borrow());
+/// let record = decoder.next_record().unwrap().unwrap();
+/// assert_eq!(record.message, "Hello");
+/// ```
+pub struct StreamDecoder<'a> {
+    files: &'a [&'a str],
+    buffer: Vec<u8>,
+    max_frame_len: usize,
+    /// Set when a frame was too long, until the end of that frame
+    skipping: bool,
+}
+
+impl<'a> StreamDecoder<'a> {
+    /// Creates a decoder, with the file table given to the [`BinaryLogger`](crate::binary::BinaryLogger).
+    pub fn new(files: &'a [&'a str]) -> Self {
+        Self {
+            files,
+            buffer: Vec::new(),
+            max_frame_len: 64 * 1024,
+            skipping: false,
+        }
+    }
+
+    /// Sets the length beyond which a frame is considered corrupted, 64 KiB by default.
+    pub fn with_max_frame_len(mut self, max_frame_len: usize) -> Self {
+        self.max_frame_len = max_frame_len;
+        self
+    }
+
+    /// Adds received bytes to the stream.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Decodes the next complete frame of the stream, if any.
+    pub fn next_record(&mut self) -> Option<Result<DecodedRecord, DecodeError>> {
+        loop {
+            let Some(end) = self.buffer.iter().position(|&byte| byte == 0) else {
+                if self.buffer.len() > self.max_frame_len {
+                    self.buffer.clear();
+                    if !self.skipping {
+                        self.skipping = true;
+                        return Some(Err(DecodeError::FrameTooLong));
+                    }
+                }
+                return None;
+            };
+
+            let mut frame = self.buffer.drain(..=end).collect::<Vec<_>>();
+            frame.pop();
+
+            if std::mem::take(&mut self.skipping) || frame.is_empty() {
+                continue;
+            }
+
+            return Some(
+                cobs_decode(&mut frame)
+                    .map_err(DecodeError::from)
+                    .and_then(|len| decode_frame(&frame[..len], self.files)),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::binary::{BinaryLogger, CobsEncoder};
+    use crate::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_stream_decoder() {
+        let files = ["src/main.rs", file!()];
+        let output = RefCell::new(Vec::new());
+        let logger = BinaryLogger::new(CobsEncoder::new(|bytes: &[u8]| {
+            output.borrow_mut().extend_from_slice(bytes)
+        }))
+        .with_file_table(&files);
+
+        let line = line!() + 1;
+        warn!(logger, "Hello", "value" => 32, "name" => "world");
+        info!(logger, "Bye");
+
+        let mut bytes = output.into_inner();
+        let first_len = bytes.iter().position(|&byte| byte == 0).unwrap() + 1;
+        // Corrupts the second frame, and starts the stream in the middle of a frame
+        bytes[first_len + 2] = 0x55;
+        let mut stream = vec![0x12, 0x34, 0x00];
+        stream.extend_from_slice(&bytes);
+        stream.extend_from_slice(&bytes[..first_len]);
+
+        let mut decoder = StreamDecoder::new(&files);
+        let mut results = Vec::new();
+        for chunk in stream.chunks(5) {
+            decoder.feed(chunk);
+            while let Some(result) = decoder.next_record() {
+                results.push(result);
+            }
+        }
+
+        let decoded = results[1].clone().unwrap();
+        assert!(decoded.column.is_some());
+        assert_eq!(decoded.thread, thread::current_thread_id());
+        let expected = DecodedRecord {
+            level: ULogLevel::Warning,
+            level_name: String::from("WARN"),
+            file: String::from(file!()),
+            line,
+            column: decoded.column,
+            target: Some(String::from(module_path!())),
+            module: Some(String::from(module_path!())),
+            timestamp: None,
+            sequence: None,
+            thread: decoded.thread,
+            user: None,
+            message: String::from("Hello"),
+            fields: vec![
+                (
+                    String::from("value"),
+                    DecodedValue::Debug(String::from("32")),
+                ),
+                (
+                    String::from("name"),
+                    DecodedValue::Debug(String::from("\"world\"")),
+                ),
+            ],
+        };
+
+        assert_eq!(results.len(), 4);
+        assert!(results[0].is_err());
+        assert_eq!(results[1], Ok(expected.clone()));
+        assert!(results[2].is_err());
+        assert_eq!(results[3], Ok(expected.clone()));
+        assert_eq!(
+            expected.to_string(),
+            format!("WARN src/decoder.rs:{line} Hello value=32 name=\"world\"")
+        );
+    }
+
+    #[test]
+    fn test_continued_strings() {
+        use core::fmt::Write;
+
+        let output = RefCell::new(Vec::new());
+        let logger = BinaryLogger::new(|bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes));
+
+        let mut statement = logger.at(ULogLevel::Info).msg("read");
+        statement.write_str("\"de").unwrap();
+        statement.write_str("ad\"").unwrap();
+        statement.emit();
+
+        let record = decode_frame(&output.into_inner(), &[]).unwrap();
+        assert_eq!(record.message, "read \"dead\"");
+    }
+
+    #[test]
+    fn test_typed_values() {
+        let output = RefCell::new(Vec::new());
+        let logger = BinaryLogger::new(|bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes));
+
+        let values = [
+            Value::I64(-300),
+            Value::I64(i64::MIN),
+            Value::U64(u64::MAX),
+            Value::F64(-0.25),
+            Value::Bool(true),
+            Value::Str("world"),
+            Value::Bytes(&[0, 1, 2]),
+        ];
+        let log_data = ULogData::new(ULogLevel::Info, 1, "main.rs");
+        logger.log_begin(&log_data);
+        for value in values {
+            logger.log_value(&log_data, "key", value);
+        }
+        logger.log_end(&log_data);
+
+        let record = decode_frame(&output.into_inner(), &[]).unwrap();
+        assert_eq!(
+            record.fields,
+            [
+                DecodedValue::I64(-300),
+                DecodedValue::I64(i64::MIN),
+                DecodedValue::U64(u64::MAX),
+                DecodedValue::F64(-0.25),
+                DecodedValue::Bool(true),
+                DecodedValue::Str(String::from("world")),
+                DecodedValue::Bytes(vec![0, 1, 2]),
+            ]
+            .map(|value| (String::from("key"), value))
+        );
+        assert_eq!(
+            record.to_string(),
+            "INFO main.rs:1 key=-300 key=-9223372036854775808 key=18446744073709551615 key=-0.25 key=true key=\"world\" key=[0, 1, 2]"
+        );
+    }
+
+    #[test]
+    fn test_metadata() {
+        let output = RefCell::new(Vec::new());
+        let logger = BinaryLogger::new(|bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes));
+
+        let log_data = ULogData::new(ULogLevel::Notice, 7, "main.rs")
+            .with_level(ULogLevel::Notice)
+            .with_column(3)
+            .with_target("radio")
+            .with_module("app::radio")
+            .with_timestamp(Duration::from_millis(1_700_000_000_123))
+            .with_sequence(12)
+            .with_thread(1)
+            .with_user(42);
+        logger.log_begin(&log_data);
+        logger.log_str(&log_data, "Hello");
+        logger.log_end(&log_data);
+
+        let record = decode_frame(&output.into_inner(), &[]).unwrap();
+        assert_eq!(record.column, Some(3));
+        assert_eq!(record.target.as_deref(), Some("radio"));
+        assert_eq!(record.module.as_deref(), Some("app::radio"));
+        assert_eq!(record.timestamp, log_data.timestamp);
+        assert_eq!(record.sequence, Some(12));
+        assert_eq!(record.thread, Some(1));
+        assert_eq!(record.user, Some(42));
+        assert_eq!(
+            record.to_string(),
+            "2023-11-14T22:13:20.123Z NOTICE main.rs:7 Hello"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialized_values() {
+        let output = RefCell::new(Vec::new());
+        let logger = BinaryLogger::new(|bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes));
+
+        info!(logger, "Hello", "list" =># vec![(1, "a"), (2, "b")]);
+
+        let record = decode_frame(&output.into_inner(), &[]).unwrap();
+        assert_eq!(
+            record.fields,
+            [(
+                String::from("list"),
+                DecodedValue::Json(String::from(r#"[[1,"a"],[2,"b"]]"#))
+            )]
+        );
+    }
+}
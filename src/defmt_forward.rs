@@ -0,0 +1,90 @@
+use super::record::TextBuffer;
+use super::{ULog, ULogData, ULogLevel};
+use core::cell::RefCell;
+use core::fmt::Write;
+
+/// Forwards statements to the `defmt` macros, so that the defmt transports (RTT, semihosting, ...)
+/// and the host tools decoding their output keep working with ulog.
+///
+/// Each statement is formatted into a buffer of `SIZE` bytes, as `message key=value`, then logged as a single
+/// `{=str}` argument of the macro matching its level; [`Notice`](ULogLevel::Notice) is logged with `defmt::info!`,
+/// and [`Critical`](ULogLevel::Critical) with `defmt::error!`.
+/// Statements are filtered by `defmt` as usual, with the `DEFMT_LOG` environment variable, and the location
+/// reported by `defmt` is the one of this logger rather than the one of the statement.
+///
+/// A `defmt` global logger must be linked in, like `defmt-rtt`.
+///
+/// ```no_run
+/// use ulog::defmt_forward::DefmtLogger;
+///
+/// let logger = DefmtLogger::<128>::new();
+/// ulog::info!(logger, "Connected", "port" => 8080); // Logged with `defmt::info!`
+/// ```
+pub struct DefmtLogger<const SIZE: usize> {
+    buffer: RefCell<TextBuffer<SIZE>>,
+}
+
+impl<const SIZE: usize> DefmtLogger<SIZE> {
+    pub const fn new() -> Self {
+        Self {
+            buffer: RefCell::new(TextBuffer::new()),
+        }
+    }
+}
+
+impl<const SIZE: usize> Default for DefmtLogger<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SIZE: usize> ULog for DefmtLogger<SIZE> {
+    fn log_str(&self, _log_data: &ULogData, string: &str) {
+        let mut buffer = self.buffer.borrow_mut();
+        if !buffer.is_empty() {
+            let _ = buffer.write_char(' ');
+        }
+        let _ = buffer.write_str(string);
+    }
+
+    fn log_str_continued(&self, _log_data: &ULogData, string: &str) {
+        let _ = self.buffer.borrow_mut().write_str(string);
+    }
+
+    fn log_fmt(&self, _log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        let mut buffer = self.buffer.borrow_mut();
+        if !buffer.is_empty() {
+            let _ = buffer.write_char(' ');
+        }
+        let _ = buffer.write_fmt(args);
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+        let mut buffer = self.buffer.borrow_mut();
+        if !buffer.is_empty() {
+            let _ = buffer.write_char(' ');
+        }
+        let _ = write!(buffer, "{}={:?}", key, value);
+    }
+
+    fn log_begin(&self, _log_data: &ULogData) {
+        self.buffer.borrow_mut().clear();
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        let buffer = self.buffer.borrow();
+        let text = buffer.as_str();
+
+        match log_data.level {
+            ULogLevel::Trace => defmt::trace!("{=str}", text),
+            ULogLevel::Debug => defmt::debug!("{=str}", text),
+            ULogLevel::Info | ULogLevel::Notice => defmt::info!("{=str}", text),
+            ULogLevel::Warning => defmt::warn!("{=str}", text),
+            ULogLevel::Error | ULogLevel::Critical => defmt::error!("{=str}", text),
+        }
+    }
+
+    fn flush(&self) {
+        defmt::flush();
+    }
+}
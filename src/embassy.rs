@@ -0,0 +1,257 @@
+use super::asynchronous::ULogAsync;
+use super::buffer::OverflowPolicy;
+use super::record::Record;
+#[cfg(feature = "serde")]
+use super::serialize::SerializeJson;
+use super::time::ULogClock;
+use super::{ULog, ULogData, Value};
+use core::cell::{Cell, RefCell};
+use core::time::Duration;
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::channel::{Channel, TrySendError};
+
+/// Queues logging statements in an [`embassy_sync::channel::Channel`] without blocking, until a task forwards them
+/// to the actual logger with [`run`](EmbassyLogger::run), or [`run_sync`](EmbassyLogger::run_sync).
+///
+/// Statements can be made from any task or interrupt that the raw mutex `M` allows, like
+/// `CriticalSectionRawMutex` to log from several executors and from interrupts; a statement interrupted by
+/// another one is however lost. Up to `STATEMENTS` statements are kept, each of them taking up to `SIZE` bytes
+/// (see [`Record`]). When the channel is full, statements are dropped according to the [`OverflowPolicy`];
+/// since the caller can't wait for the task, [`OverflowPolicy::Block`] drops the new statement.
+///
+/// Embassy tasks can't be generic, so the logging task is written by the application:
+///
+/// ```
+/// use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+/// use ulog::asynchronous::ULogAsync;
+/// use ulog::buffer::OverflowPolicy;
+/// use ulog::embassy::EmbassyLogger;
+/// use ulog::record::Record;
+///
+/// struct Uart;
+///
+/// impl ULogAsync for Uart {
+///     async fn log_record<const SIZE: usize>(&self, record: &Record<SIZE>) {
+///         // uart.write(record.to_string().as_bytes()).await
+///     }
+/// }
+///
+/// static LOGGER: EmbassyLogger<CriticalSectionRawMutex, 8, 128> =
+///     EmbassyLogger::new(OverflowPolicy::DropOldest);
+///
+/// // #[embassy_executor::task]
+/// async fn logging_task() -> ! {
+///     LOGGER.run(&Uart).await
+/// }
+///
+/// ulog::info!(LOGGER, "Queued until the logging task runs");
+/// ```
+pub struct EmbassyLogger<M: RawMutex, const STATEMENTS: usize, const SIZE: usize> {
+    policy: OverflowPolicy,
+    current: Mutex<M, RefCell<Option<Record<SIZE>>>>,
+    channel: Channel<M, Record<SIZE>, STATEMENTS>,
+    dropped: Mutex<M, Cell<usize>>,
+}
+
+impl<M: RawMutex, const STATEMENTS: usize, const SIZE: usize> EmbassyLogger<M, STATEMENTS, SIZE> {
+    pub const fn new(policy: OverflowPolicy) -> Self {
+        Self {
+            policy,
+            current: Mutex::new(RefCell::new(None)),
+            channel: Channel::new(),
+            dropped: Mutex::new(Cell::new(0)),
+        }
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Returns the number of statements waiting in the channel.
+    pub fn len(&self) -> usize {
+        self.channel.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channel.is_empty()
+    }
+
+    /// Returns the number of statements dropped because of the [`OverflowPolicy`].
+    pub fn dropped(&self) -> usize {
+        self.dropped.lock(|dropped| dropped.get())
+    }
+
+    /// Waits for the oldest statement of the channel.
+    pub async fn receive(&self) -> Record<SIZE> {
+        self.channel.receive().await
+    }
+
+    /// Forwards the statements to `logger` as they get queued, forever; meant to be the body of a logging task.
+    pub async fn run(&self, logger: &impl ULogAsync) -> ! {
+        loop {
+            let record = self.receive().await;
+            logger.log_record(&record).await;
+        }
+    }
+
+    /// Like [`run`](EmbassyLogger::run), but [replays](Record::replay) the statements to a blocking logger,
+    /// like a UART written to without DMA.
+    pub async fn run_sync(&self, logger: &impl ULog) -> ! {
+        loop {
+            self.receive().await.replay(logger);
+        }
+    }
+
+    fn count_dropped(&self) {
+        self.dropped.lock(|dropped| dropped.set(dropped.get() + 1));
+    }
+}
+
+impl<M: RawMutex, const STATEMENTS: usize, const SIZE: usize> ULog
+    for EmbassyLogger<M, STATEMENTS, SIZE>
+{
+    fn log_str(&self, _log_data: &ULogData, string: &str) {
+        self.current.lock(|current| {
+            if let Some(record) = current.borrow_mut().as_mut() {
+                record.push_str(string);
+            }
+        });
+    }
+
+    fn log_str_continued(&self, _log_data: &ULogData, string: &str) {
+        self.current.lock(|current| {
+            if let Some(record) = current.borrow_mut().as_mut() {
+                record.push_str_continued(string);
+            }
+        });
+    }
+
+    fn log_fmt(&self, _log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.current.lock(|current| {
+            if let Some(record) = current.borrow_mut().as_mut() {
+                record.push_fmt(args);
+            }
+        });
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+        self.current.lock(|current| {
+            if let Some(record) = current.borrow_mut().as_mut() {
+                record.push_format(key, value);
+            }
+        });
+    }
+
+    fn log_value(&self, _log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.current.lock(|current| {
+            if let Some(record) = current.borrow_mut().as_mut() {
+                record.push_value(key, value);
+            }
+        });
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, _log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        self.current.lock(|current| {
+            if let Some(record) = current.borrow_mut().as_mut() {
+                record.push_serialize(key, value);
+            }
+        });
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        let record = Record::new(log_data.clone());
+        self.current
+            .lock(|current| *current.borrow_mut() = Some(record));
+    }
+
+    fn log_end(&self, _log_data: &ULogData) {
+        let Some(record) = self.current.lock(|current| current.borrow_mut().take()) else {
+            return;
+        };
+
+        let Err(TrySendError::Full(record)) = self.channel.try_send(record) else {
+            return;
+        };
+        match self.policy {
+            OverflowPolicy::Block | OverflowPolicy::DropNewest => {}
+            OverflowPolicy::DropOldest => {
+                let _ = self.channel.try_receive();
+                let _ = self.channel.try_send(record);
+            }
+            OverflowPolicy::Panic => panic!("ulog: the queue of statements is full"),
+        }
+        self.count_dropped();
+    }
+}
+
+/// A clock reading [`embassy_time::Instant::now`], the time elapsed since the device booted,
+/// to timestamp statements with the `global` feature, or to time spans.
+///
+/// ```no_run
+/// use ulog::common::StubLogger;
+/// use ulog::embassy::EmbassyClock;
+/// use ulog::time::SpanLogger;
+///
+/// let logger = SpanLogger::new(StubLogger, EmbassyClock);
+/// let _span = logger.span("Connecting");
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EmbassyClock;
+
+impl ULogClock for EmbassyClock {
+    fn now(&self) -> Duration {
+        Duration::from_micros(embassy_time::Instant::now().as_micros())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+    use core::future::Future;
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    use std::pin::pin;
+    use std::task::{Context, Waker};
+
+    #[derive(Default)]
+    struct VecLogger {
+        logs: RefCell<Vec<String>>,
+    }
+
+    impl ULogAsync for VecLogger {
+        async fn log_record<const SIZE: usize>(&self, record: &Record<SIZE>) {
+            self.logs.borrow_mut().push(record.to_string());
+        }
+    }
+
+    #[test]
+    fn test_embassy_logger() {
+        let logger = EmbassyLogger::<NoopRawMutex, 2, 64>::new(OverflowPolicy::DropOldest);
+        let sink = VecLogger::default();
+        let mut context = Context::from_waker(Waker::noop());
+
+        let mut task = pin!(logger.run(&sink));
+        assert!(task.as_mut().poll(&mut context).is_pending());
+
+        info!(logger, "Dropped");
+        warn!(logger, "Hello", "value" => 32);
+        error!(logger, "world");
+        assert_eq!(logger.len(), 2);
+        assert_eq!(logger.dropped(), 1);
+
+        assert!(task.as_mut().poll(&mut context).is_pending());
+        assert!(logger.is_empty());
+        assert_eq!(sink.logs.borrow().len(), 2);
+        assert!(sink.logs.borrow()[0].ends_with(" Hello value=32"));
+        assert!(sink.logs.borrow()[1].ends_with(" world"));
+    }
+
+    #[test]
+    fn test_embassy_clock() {
+        let start = EmbassyClock.now();
+        embassy_time::MockDriver::get().advance(embassy_time::Duration::from_millis(5));
+        assert_eq!(EmbassyClock.now() - start, Duration::from_millis(5));
+    }
+}
@@ -0,0 +1,136 @@
+use super::{Level, ULog, ULogData, ULogLevel};
+use core::fmt::{Debug, Display};
+use core::panic::Location;
+
+/// Logs the error of a [`Result`] and passes it through unchanged, for the "log and propagate" pattern.
+///
+/// The statements point to the location of the call, and hold the error as an `error` key-value pair.
+///
+/// ```
+/// use ulog::common::StubLogger;
+/// use ulog::ext::ULogResultExt;
+///
+/// fn save_config(logger: &StubLogger) -> Result<(), &'static str> {
+///     Err("disk full").log_err(logger, "saving config")?;
+///     Ok(())
+/// }
+///
+/// assert_eq!(save_config(&StubLogger), Err("disk full"));
+/// ```
+pub trait ULogResultExt<E>: Sized {
+    /// Logs the error at the [`Error`](ULogLevel::Error) level, with `message`.
+    #[track_caller]
+    fn log_err(self, logger: &impl ULog, message: &str) -> Self {
+        self.log_err_at(logger, ULogLevel::Error, message)
+    }
+
+    /// Logs the error at `level`, with `message`.
+    #[track_caller]
+    fn log_err_at(self, logger: &impl ULog, level: impl Level, message: &str) -> Self;
+
+    /// Logs the error at the [`Error`](ULogLevel::Error) level, with a message only built if there is an error.
+    #[track_caller]
+    fn log_err_with<M: Display>(self, logger: &impl ULog, message: impl FnOnce(&E) -> M) -> Self;
+}
+
+impl<T, E: Debug> ULogResultExt<E> for Result<T, E> {
+    #[track_caller]
+    fn log_err_at(self, logger: &impl ULog, level: impl Level, message: &str) -> Self {
+        if let Err(error) = &self {
+            let log_data = caller_data(level);
+
+            logger.log_begin(&log_data);
+            logger.log_str(&log_data, message);
+            logger.log_format(&log_data, "error", error);
+            logger.log_end(&log_data);
+        }
+        self
+    }
+
+    #[track_caller]
+    fn log_err_with<M: Display>(self, logger: &impl ULog, message: impl FnOnce(&E) -> M) -> Self {
+        if let Err(error) = &self {
+            let log_data = caller_data(ULogLevel::Error);
+
+            logger.log_begin(&log_data);
+            logger.log_fmt(&log_data, format_args!("{}", message(error)));
+            logger.log_format(&log_data, "error", error);
+            logger.log_end(&log_data);
+        }
+        self
+    }
+}
+
+/// Logs when an [`Option`] is `None` and passes it through unchanged.
+pub trait ULogOptionExt: Sized {
+    /// Logs `message` at the [`Warning`](ULogLevel::Warning) level if the option is `None`.
+    #[track_caller]
+    fn log_none(self, logger: &impl ULog, message: &str) -> Self {
+        self.log_none_at(logger, ULogLevel::Warning, message)
+    }
+
+    /// Logs `message` at `level` if the option is `None`.
+    #[track_caller]
+    fn log_none_at(self, logger: &impl ULog, level: impl Level, message: &str) -> Self;
+}
+
+impl<T> ULogOptionExt for Option<T> {
+    #[track_caller]
+    fn log_none_at(self, logger: &impl ULog, level: impl Level, message: &str) -> Self {
+        if self.is_none() {
+            let log_data = caller_data(level);
+
+            logger.log_begin(&log_data);
+            logger.log_str(&log_data, message);
+            logger.log_end(&log_data);
+        }
+        self
+    }
+}
+
+#[track_caller]
+fn caller_data(level: impl Level) -> ULogData {
+    let location = Location::caller();
+    ULogData::new(level, location.line(), location.file()).with_column(location.column())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::TestLogger;
+
+    #[test]
+    fn test_result_ext() {
+        let logger = TestLogger::default();
+
+        let ok: Result<u8, &str> = Ok(1);
+        assert_eq!(ok.log_err(&logger, "hidden"), Ok(1));
+        assert_eq!(
+            Err::<u8, _>("disk full").log_err(&logger, "saving config"),
+            Err("disk full")
+        );
+        let _ = Err::<(), _>(5).log_err_with(&logger, |code| format!("code {code}"));
+        assert_eq!(
+            None::<u8>.log_none_at(&logger, ULogLevel::Info, "no value"),
+            None
+        );
+        assert_eq!(Some(2).log_none(&logger, "hidden"), Some(2));
+
+        assert_eq!(
+            &logger.logs.into_inner()[..],
+            &[
+                (ULogLevel::Error, String::from("__BEGIN__")),
+                (ULogLevel::Error, String::from("saving config")),
+                (ULogLevel::Error, String::from("error => \"disk full\"")),
+                (ULogLevel::Error, String::from("__END__")),
+                (ULogLevel::Error, String::from("__BEGIN__")),
+                (ULogLevel::Error, String::from("code 5")),
+                (ULogLevel::Error, String::from("error => 5")),
+                (ULogLevel::Error, String::from("__END__")),
+                (ULogLevel::Info, String::from("__BEGIN__")),
+                (ULogLevel::Info, String::from("no value")),
+                (ULogLevel::Info, String::from("__END__")),
+            ]
+        );
+    }
+}
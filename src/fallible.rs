@@ -0,0 +1,612 @@
+use super::format::ULogFormatter;
+use super::record::TextBuffer;
+#[cfg(feature = "serde")]
+use super::serialize::{SerializeJson, Serialized};
+use super::{DisplayAsDebug, ULog, ULogData, ULogLevel, Value, FMT_BUFFER_SIZE};
+use core::cell::Cell;
+use core::fmt::{self, Debug, Write};
+use core::panic::Location;
+
+/// A destination for raw bytes that can report its failures, like a socket or some flash storage.
+pub trait TryByteSink {
+    type Error;
+
+    fn try_write_bytes(&self, bytes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Called by loggers once they have written a complete statement, for sinks that delimit them.
+    fn try_end_frame(&self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// See [`ByteSink::flush`](crate::sink::ByteSink::flush); defaults to doing nothing.
+    fn try_flush(&self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<Sink: TryByteSink + ?Sized> TryByteSink for &Sink {
+    type Error = Sink::Error;
+
+    fn try_write_bytes(&self, bytes: &[u8]) -> Result<(), Self::Error> {
+        (**self).try_write_bytes(bytes)
+    }
+
+    fn try_end_frame(&self) -> Result<(), Self::Error> {
+        (**self).try_end_frame()
+    }
+
+    fn try_flush(&self) -> Result<(), Self::Error> {
+        (**self).try_flush()
+    }
+}
+
+/// A logger that can report its failures, the fallible counterpart of [`ULog`].
+///
+/// It is turned into a [`ULog`] with [`on_error`](TryULog::on_error), which decides what to do with the errors.
+pub trait TryULog {
+    type Error;
+
+    fn try_log_str(&self, log_data: &ULogData, string: &str) -> Result<(), Self::Error>;
+
+    /// See [`ULog::log_str_continued`]; defaults to [`try_log_str`](TryULog::try_log_str).
+    fn try_log_str_continued(&self, log_data: &ULogData, string: &str) -> Result<(), Self::Error> {
+        self.try_log_str(log_data, string)
+    }
+
+    /// See [`ULog::log_fmt`]; defaults to [`try_log_str`](TryULog::try_log_str), rendering the message
+    /// into a stack buffer of [`FMT_BUFFER_SIZE`] bytes first.
+    fn try_log_fmt(
+        &self,
+        log_data: &ULogData,
+        args: fmt::Arguments<'_>,
+    ) -> Result<(), Self::Error> {
+        match args.as_str() {
+            Some(string) => self.try_log_str(log_data, string),
+            None => {
+                let mut buffer = TextBuffer::<FMT_BUFFER_SIZE>::new();
+                let _ = buffer.write_fmt(args);
+                self.try_log_str(log_data, buffer.as_str())
+            }
+        }
+    }
+
+    fn try_log_format<T: Debug>(
+        &self,
+        log_data: &ULogData,
+        key: &str,
+        value: &T,
+    ) -> Result<(), Self::Error>;
+
+    /// See [`ULog::log_value`]; defaults to [`try_log_format`](TryULog::try_log_format).
+    fn try_log_value(
+        &self,
+        log_data: &ULogData,
+        key: &str,
+        value: Value<'_>,
+    ) -> Result<(), Self::Error> {
+        self.try_log_format(log_data, key, &value)
+    }
+
+    /// See [`ULog::log_bytes`]; defaults to [`try_log_value`](TryULog::try_log_value) with a [`Value::Bytes`].
+    fn try_log_bytes(
+        &self,
+        log_data: &ULogData,
+        key: &str,
+        bytes: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.try_log_value(log_data, key, Value::Bytes(bytes))
+    }
+
+    /// See [`ULog::log_display`]; defaults to [`try_log_format`](TryULog::try_log_format),
+    /// with a value whose `Debug` implementation is the `Display` one of `value`.
+    fn try_log_display<T: fmt::Display>(
+        &self,
+        log_data: &ULogData,
+        key: &str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.try_log_format(log_data, key, &DisplayAsDebug(value))
+    }
+
+    /// See [`ULog::log_serialize`]; defaults to [`try_log_format`](TryULog::try_log_format),
+    /// with the value written as JSON.
+    #[cfg(feature = "serde")]
+    fn try_log_serialize(
+        &self,
+        log_data: &ULogData,
+        key: &str,
+        value: &dyn SerializeJson,
+    ) -> Result<(), Self::Error> {
+        self.try_log_format(log_data, key, &Serialized(value))
+    }
+
+    fn try_log_begin(&self, log_data: &ULogData) -> Result<(), Self::Error>;
+
+    fn try_log_end(&self, log_data: &ULogData) -> Result<(), Self::Error>;
+
+    /// See [`ULog::flush`]; defaults to doing nothing.
+    fn try_flush(&self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// See [`ULog::enabled`]; defaults to `true`.
+    fn enabled(&self, _log_data: &ULogData) -> bool {
+        true
+    }
+
+    /// A shortcut for [`OnErrorLogger::new(self, callback)`](OnErrorLogger::new).
+    fn on_error<F: Fn(&ULogData, Self::Error)>(self, callback: F) -> OnErrorLogger<Self, F>
+    where
+        Self: Sized,
+    {
+        OnErrorLogger::new(self, callback)
+    }
+}
+
+/// Turns a [`TryULog`] into a [`ULog`], calling `callback` with the first error of each failed statement.
+///
+/// The rest of a failed statement is skipped, and the logger reports itself as unavailable through
+/// [`ULog::is_available`] until [`retry`](OnErrorLogger::retry) is called; wrapped in a
+/// [`FailoverLogger`](crate::common::FailoverLogger), the following statements are thus sent to the secondary logger.
+///
+/// ```
+/// use ulog::fallible::{TryByteSink, TryFormattedLogger, TryULog};
+/// use ulog::format::{FormattedLogger, TextFormatter};
+/// use ulog::ULog;
+///
+/// struct Link;
+///
+/// impl TryByteSink for Link {
+///     type Error = &'static str;
+///
+///     fn try_write_bytes(&self, _bytes: &[u8]) -> Result<(), Self::Error> {
+///         Err("link down")
+///     }
+/// }
+///
+/// let logger = TryFormattedLogger::new(TextFormatter::new(), Link)
+///     .on_error(|_log_data, error| eprintln!("logging failed: {error}"))
+///     .failover(FormattedLogger::new(TextFormatter::new(), |bytes: &[u8]| {
+///         eprint!("{}", core::str::from_utf8(bytes).unwrap());
+///     }));
+///
+/// ulog::info!(logger, "Sent to the link, which fails");
+/// ulog::info!(logger, "Sent to the secondary logger");
+/// assert!(logger.is_using_secondary());
+/// ```
+pub struct OnErrorLogger<Logger, F> {
+    logger: Logger,
+    callback: F,
+    failed: Cell<bool>,
+    unavailable: Cell<bool>,
+}
+
+impl<Logger: TryULog, F: Fn(&ULogData, Logger::Error)> OnErrorLogger<Logger, F> {
+    pub fn new(logger: Logger, callback: F) -> Self {
+        Self {
+            logger,
+            callback,
+            failed: Cell::new(false),
+            unavailable: Cell::new(false),
+        }
+    }
+
+    /// Makes the logger available again after a failure, for instance once a link is back up.
+    pub fn retry(&self) {
+        self.unavailable.set(false);
+    }
+
+    pub fn into_inner(self) -> (Logger, F) {
+        (self.logger, self.callback)
+    }
+
+    fn handle(&self, log_data: &ULogData, result: Result<(), Logger::Error>) {
+        if let Err(error) = result {
+            self.failed.set(true);
+            self.unavailable.set(true);
+            (self.callback)(log_data, error);
+        }
+    }
+}
+
+impl<Logger: TryULog, F: Fn(&ULogData, Logger::Error)> ULog for OnErrorLogger<Logger, F> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        if !self.failed.get() {
+            self.handle(log_data, self.logger.try_log_str(log_data, string));
+        }
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        if !self.failed.get() {
+            self.handle(
+                log_data,
+                self.logger.try_log_str_continued(log_data, string),
+            );
+        }
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: fmt::Arguments<'_>) {
+        if !self.failed.get() {
+            self.handle(log_data, self.logger.try_log_fmt(log_data, args));
+        }
+    }
+
+    fn log_format<T: Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        if !self.failed.get() {
+            self.handle(log_data, self.logger.try_log_format(log_data, key, value));
+        }
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        if !self.failed.get() {
+            self.handle(log_data, self.logger.try_log_value(log_data, key, value));
+        }
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        if !self.failed.get() {
+            self.handle(log_data, self.logger.try_log_bytes(log_data, key, bytes));
+        }
+    }
+
+    fn log_display<T: fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        if !self.failed.get() {
+            self.handle(log_data, self.logger.try_log_display(log_data, key, value));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        if !self.failed.get() {
+            self.handle(
+                log_data,
+                self.logger.try_log_serialize(log_data, key, value),
+            );
+        }
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.failed.set(false);
+        self.handle(log_data, self.logger.try_log_begin(log_data));
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        if !self.failed.get() {
+            self.handle(log_data, self.logger.try_log_end(log_data));
+        }
+    }
+
+    fn is_available(&self) -> bool {
+        !self.unavailable.get()
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.logger.enabled(log_data)
+    }
+
+    /// A failed flush is reported to the callback as a statement made where `flush` was called.
+    #[track_caller]
+    fn flush(&self) {
+        let location = Location::caller();
+        let log_data = ULogData::new(ULogLevel::Info, location.line(), location.file())
+            .with_column(location.column());
+        self.handle(&log_data, self.logger.try_flush());
+    }
+}
+
+/// Formats logging statements with a [`ULogFormatter`] and writes the resulting text to a [`TryByteSink`],
+/// returning the errors of the sink; the fallible counterpart of [`FormattedLogger`](crate::format::FormattedLogger).
+pub struct TryFormattedLogger<Formatter, Sink> {
+    formatter: Formatter,
+    sink: Sink,
+}
+
+impl<Formatter: ULogFormatter, Sink: TryByteSink> TryFormattedLogger<Formatter, Sink> {
+    pub fn new(formatter: Formatter, sink: Sink) -> Self {
+        Self { formatter, sink }
+    }
+
+    pub fn formatter(&self) -> &Formatter {
+        &self.formatter
+    }
+
+    pub fn sink(&self) -> &Sink {
+        &self.sink
+    }
+
+    pub fn into_inner(self) -> (Formatter, Sink) {
+        (self.formatter, self.sink)
+    }
+
+    fn write(
+        &self,
+        format: impl FnOnce(&mut TrySinkWriter<'_, Sink>) -> fmt::Result,
+    ) -> Result<(), Sink::Error> {
+        let mut writer = TrySinkWriter {
+            sink: &self.sink,
+            error: None,
+        };
+        let _ = format(&mut writer);
+
+        match writer.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<Formatter: ULogFormatter, Sink: TryByteSink> TryULog for TryFormattedLogger<Formatter, Sink> {
+    type Error = Sink::Error;
+
+    fn try_log_str(&self, log_data: &ULogData, string: &str) -> Result<(), Self::Error> {
+        self.write(|out| self.formatter.format_str(out, log_data, string))
+    }
+
+    fn try_log_str_continued(&self, log_data: &ULogData, string: &str) -> Result<(), Self::Error> {
+        self.write(|out| self.formatter.format_str_continued(out, log_data, string))
+    }
+
+    fn try_log_fmt(
+        &self,
+        log_data: &ULogData,
+        args: fmt::Arguments<'_>,
+    ) -> Result<(), Self::Error> {
+        self.write(|out| self.formatter.format_fmt(out, log_data, args))
+    }
+
+    fn try_log_format<T: Debug>(
+        &self,
+        log_data: &ULogData,
+        key: &str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.write(|out| self.formatter.format_kv(out, log_data, key, value))
+    }
+
+    fn try_log_value(
+        &self,
+        log_data: &ULogData,
+        key: &str,
+        value: Value<'_>,
+    ) -> Result<(), Self::Error> {
+        self.write(|out| self.formatter.format_value(out, log_data, key, value))
+    }
+
+    #[cfg(feature = "serde")]
+    fn try_log_serialize(
+        &self,
+        log_data: &ULogData,
+        key: &str,
+        value: &dyn SerializeJson,
+    ) -> Result<(), Self::Error> {
+        self.write(|out| self.formatter.format_serialize(out, log_data, key, value))
+    }
+
+    fn try_log_begin(&self, log_data: &ULogData) -> Result<(), Self::Error> {
+        self.write(|out| self.formatter.format_begin(out, log_data))
+    }
+
+    fn try_log_end(&self, log_data: &ULogData) -> Result<(), Self::Error> {
+        self.write(|out| self.formatter.format_end(out, log_data))?;
+        self.sink.try_end_frame()
+    }
+
+    fn try_flush(&self) -> Result<(), Self::Error> {
+        self.sink.try_flush()
+    }
+}
+
+/// Adapts a [`TryByteSink`] to [`Write`], keeping the first error of the sink.
+struct TrySinkWriter<'a, Sink: TryByteSink> {
+    sink: &'a Sink,
+    error: Option<Sink::Error>,
+}
+
+impl<Sink: TryByteSink> Write for TrySinkWriter<'_, Sink> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        match self.sink.try_write_bytes(string.as_bytes()) {
+            Ok(()) => Ok(()),
+            Err(error) => {
+                self.error.get_or_insert(error);
+                Err(fmt::Error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::format::TextFormatter;
+    use crate::test::TestLogger;
+    use std::cell::RefCell;
+
+    #[derive(Default)]
+    struct FlakySink {
+        up: Cell<bool>,
+        output: RefCell<String>,
+    }
+
+    impl TryByteSink for FlakySink {
+        type Error = &'static str;
+
+        fn try_write_bytes(&self, bytes: &[u8]) -> Result<(), Self::Error> {
+            if !self.up.get() {
+                return Err("link down");
+            }
+            self.output
+                .borrow_mut()
+                .push_str(core::str::from_utf8(bytes).unwrap());
+            Ok(())
+        }
+
+        fn try_flush(&self) -> Result<(), Self::Error> {
+            if !self.up.get() {
+                return Err("flush failed");
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_on_error() {
+        let sink = FlakySink::default();
+        let errors = RefCell::new(Vec::new());
+        let secondary = TestLogger::default();
+        let logger = TryFormattedLogger::new(TextFormatter::new(), &sink)
+            .on_error(|log_data, error| errors.borrow_mut().push((log_data.line, error)))
+            .failover(&secondary);
+
+        let line = line!() + 1;
+        crate::info!(logger, "lost", "value" => 1);
+        crate::info!(logger, "rerouted");
+        sink.up.set(true);
+        logger.primary().retry();
+        crate::warn!(logger, "sent");
+
+        assert_eq!(&errors.into_inner()[..], &[(line, "link down")]);
+        assert_eq!(
+            &secondary.logs.into_inner()[..],
+            &[
+                (ULogLevel::Info, String::from("__BEGIN__")),
+                (ULogLevel::Info, String::from("rerouted")),
+                (ULogLevel::Info, String::from("__END__")),
+            ]
+        );
+        assert!(sink.output.into_inner().ends_with(" sent\n"));
+    }
+
+    #[test]
+    fn test_on_error_flush() {
+        let sink = FlakySink::default();
+        sink.up.set(true);
+        let errors = RefCell::new(Vec::new());
+        let logger = TryFormattedLogger::new(TextFormatter::new(), &sink)
+            .on_error(|log_data, error| errors.borrow_mut().push((log_data.line, error)));
+
+        logger.flush();
+        assert!(logger.is_available());
+
+        sink.up.set(false);
+        let line = line!() + 1;
+        logger.flush();
+        assert!(!logger.is_available());
+        assert_eq!(&errors.borrow()[..], &[(line, "flush failed")]);
+    }
+
+    #[test]
+    fn test_on_error_enabled() {
+        struct Muted;
+
+        impl TryULog for Muted {
+            type Error = &'static str;
+
+            fn try_log_str(&self, _log_data: &ULogData, _string: &str) -> Result<(), Self::Error> {
+                Err("muted")
+            }
+
+            fn try_log_format<T: Debug>(
+                &self,
+                _log_data: &ULogData,
+                _key: &str,
+                _value: &T,
+            ) -> Result<(), Self::Error> {
+                Err("muted")
+            }
+
+            fn try_log_begin(&self, _log_data: &ULogData) -> Result<(), Self::Error> {
+                Err("muted")
+            }
+
+            fn try_log_end(&self, _log_data: &ULogData) -> Result<(), Self::Error> {
+                Err("muted")
+            }
+
+            fn enabled(&self, log_data: &ULogData) -> bool {
+                log_data.level >= ULogLevel::Warning
+            }
+        }
+
+        let errors = Cell::new(0);
+        let logger = Muted.on_error(|_, _| errors.set(errors.get() + 1));
+
+        crate::info!(logger, "skipped");
+        assert_eq!(errors.get(), 0);
+        crate::warn!(logger, "failed");
+        assert_eq!(errors.get(), 1);
+    }
+
+    #[test]
+    fn test_on_error_display() {
+        #[derive(Default)]
+        struct DisplayRecorder(RefCell<Vec<String>>);
+
+        impl TryULog for DisplayRecorder {
+            type Error = ();
+
+            fn try_log_str(&self, _log_data: &ULogData, _string: &str) -> Result<(), Self::Error> {
+                Ok(())
+            }
+
+            fn try_log_format<T: Debug>(
+                &self,
+                _log_data: &ULogData,
+                key: &str,
+                value: &T,
+            ) -> Result<(), Self::Error> {
+                self.0.borrow_mut().push(format!("{key} => {:?}", value));
+                Ok(())
+            }
+
+            fn try_log_display<T: fmt::Display>(
+                &self,
+                _log_data: &ULogData,
+                key: &str,
+                value: &T,
+            ) -> Result<(), Self::Error> {
+                self.0.borrow_mut().push(format!("{key} =>% {}", value));
+                Ok(())
+            }
+
+            fn try_log_begin(&self, _log_data: &ULogData) -> Result<(), Self::Error> {
+                Ok(())
+            }
+
+            fn try_log_end(&self, _log_data: &ULogData) -> Result<(), Self::Error> {
+                Ok(())
+            }
+        }
+
+        let logger = DisplayRecorder::default().on_error(|_, _| {});
+        logger
+            .at(ULogLevel::Info)
+            .display("address", "10.0.0.1")
+            .emit();
+
+        assert_eq!(
+            &logger.into_inner().0 .0.into_inner()[..],
+            &[String::from("address =>% 10.0.0.1")]
+        );
+    }
+
+    #[test]
+    fn test_on_error_fmt() {
+        use crate::format::JsonFormatter;
+
+        let sink = FlakySink::default();
+        sink.up.set(true);
+        let logger = TryFormattedLogger::new(JsonFormatter::new(), &sink).on_error(|_, _| {});
+
+        let long = "x".repeat(2 * FMT_BUFFER_SIZE);
+        crate::info!(logger, "{}!", long);
+        logger
+            .at(ULogLevel::Info)
+            .msg("Sampled")
+            .value("count", 3)
+            .emit();
+
+        let output = sink.output.into_inner();
+        assert!(output.contains(&format!("\"{}!\"", long)));
+        assert!(output.contains("\"count\":3"));
+    }
+}
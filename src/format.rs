@@ -0,0 +1,3195 @@
+use super::common::LEVEL_COUNT;
+use super::record::{Record, Segment, TextBuffer, Verbatim};
+#[cfg(feature = "serde")]
+use super::serialize::{RawJson, SerializeJson, Serialized};
+use super::sink::ByteSink;
+use super::time::{Rfc3339, ULogClock};
+use super::{ULog, ULogData, ULogLevel, Value};
+use core::cell::{Cell, RefCell};
+use core::fmt::{self, Debug, Write};
+use core::time::Duration;
+
+/// Lays logging statements out as text, independently of where that text ends up.
+///
+/// Each method writes its part of the statement into `out`; a statement is made of a call to
+/// [`format_begin`](ULogFormatter::format_begin), calls to [`format_str`](ULogFormatter::format_str)
+/// and [`format_kv`](ULogFormatter::format_kv) in the order they appear, and a call to
+/// [`format_end`](ULogFormatter::format_end).
+pub trait ULogFormatter {
+    fn format_begin(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result;
+
+    fn format_str(&self, out: &mut dyn Write, log_data: &ULogData, string: &str) -> fmt::Result;
+
+    /// Formats a string continuing the previous one without any separator, see [`ULog::log_str_continued`];
+    /// defaults to [`format_str`](ULogFormatter::format_str).
+    fn format_str_continued(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        string: &str,
+    ) -> fmt::Result {
+        self.format_str(out, log_data, string)
+    }
+
+    /// Formats a message written with format arguments, see [`ULog::log_fmt`]; defaults to
+    /// [`format_str`](ULogFormatter::format_str) for its first piece, and to
+    /// [`format_str_continued`](ULogFormatter::format_str_continued) for the following ones.
+    fn format_fmt(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        args: fmt::Arguments<'_>,
+    ) -> fmt::Result {
+        if let Some(string) = args.as_str() {
+            return self.format_str(out, log_data, string);
+        }
+
+        let mut pieces = Pieces {
+            formatter: self,
+            out,
+            log_data,
+            started: false,
+        };
+        pieces.write_fmt(args)?;
+        if !pieces.started {
+            self.format_str(pieces.out, log_data, "")?;
+        }
+        Ok(())
+    }
+
+    fn format_kv(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        key: &str,
+        value: &dyn Debug,
+    ) -> fmt::Result;
+
+    /// Formats a key-value pair holding a typed [`Value`]; defaults to [`format_kv`](ULogFormatter::format_kv).
+    fn format_value(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        key: &str,
+        value: Value<'_>,
+    ) -> fmt::Result {
+        self.format_kv(out, log_data, key, &value)
+    }
+
+    /// Formats a key-value pair holding a [`Serialize`](serde::Serialize) value, requires the `serde` feature;
+    /// defaults to [`format_kv`](ULogFormatter::format_kv), with the value written as JSON.
+    #[cfg(feature = "serde")]
+    fn format_serialize(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        key: &str,
+        value: &dyn SerializeJson,
+    ) -> fmt::Result {
+        self.format_kv(out, log_data, key, &Serialized(value))
+    }
+
+    /// Finishes the statement, typically with a line feed.
+    fn format_end(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result;
+}
+
+/// Formats the pieces of a formatted message as a string followed by its continuations.
+struct Pieces<'a, Formatter: ?Sized> {
+    formatter: &'a Formatter,
+    out: &'a mut dyn Write,
+    log_data: &'a ULogData,
+    started: bool,
+}
+
+impl<Formatter: ULogFormatter + ?Sized> Write for Pieces<'_, Formatter> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        if self.started {
+            self.formatter
+                .format_str_continued(self.out, self.log_data, string)
+        } else {
+            self.started = true;
+            self.formatter.format_str(self.out, self.log_data, string)
+        }
+    }
+}
+
+impl<Formatter: ULogFormatter + ?Sized> ULogFormatter for &Formatter {
+    fn format_begin(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        (**self).format_begin(out, log_data)
+    }
+
+    fn format_str(&self, out: &mut dyn Write, log_data: &ULogData, string: &str) -> fmt::Result {
+        (**self).format_str(out, log_data, string)
+    }
+
+    fn format_str_continued(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        string: &str,
+    ) -> fmt::Result {
+        (**self).format_str_continued(out, log_data, string)
+    }
+
+    fn format_fmt(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        args: fmt::Arguments<'_>,
+    ) -> fmt::Result {
+        (**self).format_fmt(out, log_data, args)
+    }
+
+    fn format_kv(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        key: &str,
+        value: &dyn Debug,
+    ) -> fmt::Result {
+        (**self).format_kv(out, log_data, key, value)
+    }
+
+    fn format_value(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        key: &str,
+        value: Value<'_>,
+    ) -> fmt::Result {
+        (**self).format_value(out, log_data, key, value)
+    }
+
+    #[cfg(feature = "serde")]
+    fn format_serialize(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        key: &str,
+        value: &dyn SerializeJson,
+    ) -> fmt::Result {
+        (**self).format_serialize(out, log_data, key, value)
+    }
+
+    fn format_end(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        (**self).format_end(out, log_data)
+    }
+}
+
+/// Returns the [timestamp](ULogData::timestamp) captured with the statement, or asks `clock` for the time.
+fn timestamp(log_data: &ULogData, clock: &Option<impl ULogClock>) -> Option<Duration> {
+    log_data
+        .timestamp
+        .or_else(|| clock.as_ref().map(|clock| clock.now()))
+}
+
+/// Writes the name of the [thread](ULogData::thread) of the statement if it has one, or else its id.
+fn write_thread(out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+    let Some(thread) = log_data.thread else {
+        return Ok(());
+    };
+
+    #[cfg(feature = "std")]
+    if let Some(name) = super::thread::thread_name(thread) {
+        return out.write_str(&name);
+    }
+    write!(out, "{thread}")
+}
+
+/// Formats statements as lines of text, as `LEVEL file:line message key=value\n`.
+///
+/// With a clock returning the time elapsed since the Unix epoch, lines are prefixed with an [RFC 3339](Rfc3339)
+/// timestamp, as `2023-11-14T22:13:20.123Z LEVEL file:line message key=value\n`;
+/// the [timestamp](ULogData::timestamp) captured with the statement is preferred over the clock if there is one.
+/// With [`LevelSymbols`], the level is preceded, or replaced, by a symbol, as `⚠ WARN file:line message\n`.
+#[derive(Clone, Copy, Debug)]
+pub struct TextFormatter<Clock = fn() -> Duration> {
+    clock: Option<Clock>,
+    symbols: Option<LevelSymbols>,
+    level_text: bool,
+}
+
+impl TextFormatter {
+    pub const fn new() -> Self {
+        Self {
+            clock: None,
+            symbols: None,
+            level_text: true,
+        }
+    }
+}
+
+impl Default for TextFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Clock: ULogClock> TextFormatter<Clock> {
+    /// Sets the clock used to prefix lines with timestamps; it must return the time elapsed since the Unix epoch,
+    /// like [`SystemClock`](crate::time::SystemClock) does.
+    pub fn with_clock<NewClock: ULogClock>(self, clock: NewClock) -> TextFormatter<NewClock> {
+        TextFormatter {
+            clock: Some(clock),
+            symbols: self.symbols,
+            level_text: self.level_text,
+        }
+    }
+
+    /// Sets the symbols printed before the level.
+    pub fn with_symbols(mut self, symbols: LevelSymbols) -> Self {
+        self.symbols = Some(symbols);
+        self
+    }
+
+    /// Sets whether the name of the level is printed; it can be turned off to only print its symbol.
+    pub fn with_level_text(mut self, level_text: bool) -> Self {
+        self.level_text = level_text;
+        self
+    }
+}
+
+impl<Clock: ULogClock> ULogFormatter for TextFormatter<Clock> {
+    fn format_begin(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        if let Some(timestamp) = timestamp(log_data, &self.clock) {
+            write!(out, "{} ", Rfc3339(timestamp))?;
+        }
+        match (&self.symbols, self.level_text) {
+            (Some(symbols), true) => write!(
+                out,
+                "{} {}",
+                symbols.symbol(log_data.level),
+                log_data.level_name
+            )?,
+            (Some(symbols), false) => out.write_str(symbols.symbol(log_data.level))?,
+            (None, _) => out.write_str(log_data.level_name)?,
+        }
+        write!(out, " {}:{}", log_data.file, log_data.line)
+    }
+
+    fn format_str(&self, out: &mut dyn Write, _log_data: &ULogData, string: &str) -> fmt::Result {
+        write!(out, " {}", string)
+    }
+
+    fn format_str_continued(
+        &self,
+        out: &mut dyn Write,
+        _log_data: &ULogData,
+        string: &str,
+    ) -> fmt::Result {
+        out.write_str(string)
+    }
+
+    fn format_kv(
+        &self,
+        out: &mut dyn Write,
+        _log_data: &ULogData,
+        key: &str,
+        value: &dyn Debug,
+    ) -> fmt::Result {
+        write!(out, " {}={:?}", key, value)
+    }
+
+    /// Writes [`Value::Bytes`] as hexadecimal, and the other values like [`format_kv`](ULogFormatter::format_kv).
+    fn format_value(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        key: &str,
+        value: Value<'_>,
+    ) -> fmt::Result {
+        match value {
+            Value::Bytes(bytes) => self.format_kv(out, log_data, key, &Hex(bytes)),
+            value => self.format_kv(out, log_data, key, &value),
+        }
+    }
+
+    fn format_end(&self, out: &mut dyn Write, _log_data: &ULogData) -> fmt::Result {
+        out.write_str("\n")
+    }
+}
+
+/// Formats bytes as lowercase hexadecimal, without any separator, when printed with [`Debug`].
+struct Hex<'a>(&'a [u8]);
+
+impl Debug for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Maps each [`ULogLevel`] to a symbol, printed by [`TextFormatter::with_symbols`].
+///
+/// [`LevelSymbols::ASCII`] can be used as a fallback for terminals that can't display [`LevelSymbols::UNICODE`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LevelSymbols {
+    symbols: [&'static str; LEVEL_COUNT],
+}
+
+impl LevelSymbols {
+    /// `⋯`, `·`, `ℹ`, `✱`, `⚠`, `✖` and `‼`, from trace to critical.
+    pub const UNICODE: LevelSymbols = LevelSymbols::new(["⋯", "·", "ℹ", "✱", "⚠", "✖", "‼"]);
+
+    /// `,`, `.`, `i`, `*`, `!`, `x` and `X`, from trace to critical.
+    pub const ASCII: LevelSymbols = LevelSymbols::new([",", ".", "i", "*", "!", "x", "X"]);
+
+    /// Creates a table from the symbols of the levels, in ascending order.
+    pub const fn new(ascending: [&'static str; LEVEL_COUNT]) -> Self {
+        let levels = ULogLevel::all_levels();
+        let mut symbols = [""; LEVEL_COUNT];
+        let mut index = 0;
+        while index < LEVEL_COUNT {
+            symbols[levels[index] as usize] = ascending[index];
+            index += 1;
+        }
+        Self { symbols }
+    }
+
+    pub fn with_symbol(mut self, level: ULogLevel, symbol: &'static str) -> Self {
+        self.symbols[level as usize] = symbol;
+        self
+    }
+
+    pub fn symbol(&self, level: ULogLevel) -> &'static str {
+        self.symbols[level as usize]
+    }
+}
+
+impl Default for LevelSymbols {
+    fn default() -> Self {
+        Self::UNICODE
+    }
+}
+
+/// A text style, made of the parameters of an ANSI "Select Graphic Rendition" escape sequence, like `"1;31"`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnsiStyle(pub &'static str);
+
+impl AnsiStyle {
+    pub const NONE: AnsiStyle = AnsiStyle("");
+    pub const BOLD: AnsiStyle = AnsiStyle("1");
+    pub const DIM: AnsiStyle = AnsiStyle("2");
+    pub const RED: AnsiStyle = AnsiStyle("31");
+    pub const GREEN: AnsiStyle = AnsiStyle("32");
+    pub const YELLOW: AnsiStyle = AnsiStyle("33");
+    pub const BLUE: AnsiStyle = AnsiStyle("34");
+    pub const MAGENTA: AnsiStyle = AnsiStyle("35");
+    pub const CYAN: AnsiStyle = AnsiStyle("36");
+    pub const BOLD_RED: AnsiStyle = AnsiStyle("1;31");
+
+    pub fn is_none(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Maps each [`ULogLevel`] to the [`AnsiStyle`] used by [`AnsiFormatter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColorScheme {
+    styles: [AnsiStyle; LEVEL_COUNT],
+}
+
+impl ColorScheme {
+    /// A scheme where every level uses `style`.
+    pub const fn uniform(style: AnsiStyle) -> Self {
+        Self {
+            styles: [style; LEVEL_COUNT],
+        }
+    }
+
+    pub fn with_style(mut self, level: ULogLevel, style: AnsiStyle) -> Self {
+        self.styles[level as usize] = style;
+        self
+    }
+
+    pub fn style(&self, level: ULogLevel) -> AnsiStyle {
+        self.styles[level as usize]
+    }
+}
+
+impl Default for ColorScheme {
+    /// Dims trace and debug statements, and goes from green to cyan, then yellow to bold red for the other levels.
+    fn default() -> Self {
+        Self::uniform(AnsiStyle::NONE)
+            .with_style(ULogLevel::Trace, AnsiStyle::DIM)
+            .with_style(ULogLevel::Debug, AnsiStyle::DIM)
+            .with_style(ULogLevel::Info, AnsiStyle::GREEN)
+            .with_style(ULogLevel::Notice, AnsiStyle::CYAN)
+            .with_style(ULogLevel::Warning, AnsiStyle::YELLOW)
+            .with_style(ULogLevel::Error, AnsiStyle::RED)
+            .with_style(ULogLevel::Critical, AnsiStyle::BOLD_RED)
+    }
+}
+
+/// Formats statements like [`TextFormatter`], coloring their level with ANSI escape codes,
+/// or their whole line with [`with_whole_line`](AnsiFormatter::with_whole_line).
+///
+/// Colors can be disabled with [`with_colors`](AnsiFormatter::with_colors), for instance when the output
+/// is not a terminal.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AnsiFormatter {
+    scheme: ColorScheme,
+    whole_line: bool,
+    disabled: bool,
+}
+
+impl AnsiFormatter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_scheme(mut self, scheme: ColorScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Sets whether the whole line is colored, instead of only the level.
+    pub fn with_whole_line(mut self, whole_line: bool) -> Self {
+        self.whole_line = whole_line;
+        self
+    }
+
+    /// Enables or disables colors; when disabled, statements are formatted exactly like [`TextFormatter`] does.
+    pub fn with_colors(mut self, enabled: bool) -> Self {
+        self.disabled = !enabled;
+        self
+    }
+
+    pub fn scheme(&self) -> &ColorScheme {
+        &self.scheme
+    }
+
+    fn style(&self, level: ULogLevel) -> AnsiStyle {
+        if self.disabled {
+            AnsiStyle::NONE
+        } else {
+            self.scheme.style(level)
+        }
+    }
+}
+
+impl ULogFormatter for AnsiFormatter {
+    fn format_begin(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        let style = self.style(log_data.level);
+        if style.is_none() {
+            return TextFormatter::new().format_begin(out, log_data);
+        }
+
+        write!(out, "\x1b[{}m{}", style.0, log_data.level_name)?;
+        if !self.whole_line {
+            out.write_str("\x1b[0m")?;
+        }
+        write!(out, " {}:{}", log_data.file, log_data.line)
+    }
+
+    fn format_str(&self, out: &mut dyn Write, log_data: &ULogData, string: &str) -> fmt::Result {
+        TextFormatter::new().format_str(out, log_data, string)
+    }
+
+    fn format_str_continued(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        string: &str,
+    ) -> fmt::Result {
+        TextFormatter::new().format_str_continued(out, log_data, string)
+    }
+
+    fn format_kv(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        key: &str,
+        value: &dyn Debug,
+    ) -> fmt::Result {
+        TextFormatter::new().format_kv(out, log_data, key, value)
+    }
+
+    fn format_end(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        if self.whole_line && !self.style(log_data.level).is_none() {
+            out.write_str("\x1b[0m")?;
+        }
+        TextFormatter::new().format_end(out, log_data)
+    }
+}
+
+/// Formats statements as JSON objects, one per line (NDJSON), as
+/// `{"level":"ERROR","file":"src/main.rs","line":42,"target":"app","msg":"message","key":"value"}\n`,
+/// where the `target` field is left out for statements without a [target](ULogData::target), and a `seq` field
+/// follows it for statements with a [sequence number](ULogData::sequence), as does a `user` field for statements with
+/// [user metadata](ULogData::user).
+///
+/// Values are serialized as strings holding their [`Debug`] representation, except for [`Value`]s logged with
+/// [`log_value`](ULog::log_value), which are serialized as native JSON values.
+/// The strings of a statement are joined with spaces into its `msg` field, unless they are separated by a key-value pair.
+/// With a clock returning the time elapsed since the Unix epoch, objects start with an [RFC 3339](Rfc3339)
+/// `timestamp` field; the [timestamp](ULogData::timestamp) captured with the statement is preferred over the clock
+/// if there is one.
+///
+/// With [`with_nested_keys`](JsonFormatter::with_nested_keys), dotted keys like `net.rx.bytes` are written as nested
+/// objects, as `{"net":{"rx":{"bytes":42}}}`. Pairs are written as they are logged, so consecutive pairs sharing
+/// a prefix end up in the same object; as an object can't be reopened once closed without repeating its key,
+/// the rest of a later key is written as is in the closest open object instead, as `"net.up":true`.
+///
+/// Keys naming one of the fields written by the formatter, like `msg` or `level`, are prefixed with `fields.`,
+/// as `"fields.msg":"value"`, so that objects never hold the same key twice; with nested keys, so are the keys
+/// whose first part names one of them, like `level.max`.
+#[derive(Debug)]
+pub struct JsonFormatter<Clock = fn() -> Duration> {
+    clock: Option<Clock>,
+    nested_keys: bool,
+    msg_open: Cell<bool>,
+    /// The keys of the nested objects being written, joined with dots
+    path: RefCell<TextBuffer<JSON_PATH_SIZE>>,
+    /// The paths of the nested objects closed in the statement, one per line
+    closed: RefCell<TextBuffer<JSON_CLOSED_SIZE>>,
+    depth: Cell<usize>,
+    /// Whether no pair was written yet in the innermost object
+    first: Cell<bool>,
+}
+
+/// The longest prefix of a dotted key that [`JsonFormatter`] writes as nested objects;
+/// keys with longer prefixes are written as they are.
+const JSON_PATH_SIZE: usize = 64;
+
+/// The room [`JsonFormatter`] keeps to remember the nested objects closed in a statement; once it is full,
+/// no more objects are opened in the statement.
+const JSON_CLOSED_SIZE: usize = 128;
+
+/// The keys of the fields written by [`JsonFormatter`] itself, which the keys of pairs can't take.
+const JSON_RESERVED_KEYS: [&str; 8] = [
+    "timestamp",
+    "level",
+    "file",
+    "line",
+    "target",
+    "seq",
+    "user",
+    "msg",
+];
+
+impl JsonFormatter {
+    pub fn new() -> Self {
+        Self {
+            clock: None,
+            nested_keys: false,
+            msg_open: Cell::new(false),
+            path: RefCell::new(TextBuffer::new()),
+            closed: RefCell::new(TextBuffer::new()),
+            depth: Cell::new(0),
+            first: Cell::new(false),
+        }
+    }
+}
+
+impl Default for JsonFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Clock: ULogClock> JsonFormatter<Clock> {
+    /// Sets the clock used for the `timestamp` field; it must return the time elapsed since the Unix epoch,
+    /// like [`SystemClock`](crate::time::SystemClock) does.
+    pub fn with_clock<NewClock: ULogClock>(self, clock: NewClock) -> JsonFormatter<NewClock> {
+        JsonFormatter {
+            clock: Some(clock),
+            nested_keys: self.nested_keys,
+            msg_open: self.msg_open,
+            path: self.path,
+            closed: self.closed,
+            depth: self.depth,
+            first: self.first,
+        }
+    }
+
+    /// Sets whether keys containing dots are written as nested objects.
+    pub fn with_nested_keys(mut self, nested_keys: bool) -> Self {
+        self.nested_keys = nested_keys;
+        self
+    }
+
+    fn close_msg(&self, out: &mut dyn Write) -> fmt::Result {
+        if self.msg_open.replace(false) {
+            out.write_str("\"")?;
+        }
+        Ok(())
+    }
+
+    /// Closes the nested objects, keeping the first `keep` ones open.
+    fn close_objects(&self, out: &mut dyn Write, keep: usize) -> fmt::Result {
+        let path = self.path.borrow();
+        let mut closed = self.closed.borrow_mut();
+        for depth in (keep..self.depth.get()).rev() {
+            out.write_str("}")?;
+            self.first.set(false);
+            let _ = writeln!(closed, "{}", json_path_prefix(path.as_str(), depth + 1));
+        }
+        self.depth.set(keep.min(self.depth.get()));
+        Ok(())
+    }
+
+    /// Returns whether the nested object at `path` was already closed in the statement.
+    fn is_closed(&self, path: &str) -> bool {
+        let closed = self.closed.borrow();
+        closed.is_truncated() || closed.as_str().lines().any(|closed| closed == path)
+    }
+
+    /// Writes the key of a pair, preceded by a comma if needed; with nested keys, the objects named by the parts
+    /// of the key before its last dot are opened first.
+    fn write_key(&self, out: &mut dyn Write, key: &str) -> fmt::Result {
+        self.close_msg(out)?;
+
+        let top_level = match self.nested_keys {
+            true => key.split('.').next().unwrap_or(key),
+            false => key,
+        };
+        let reserved = JSON_RESERVED_KEYS.contains(&top_level);
+
+        let (parents, mut leaf) = match key.rsplit_once('.') {
+            Some((parents, leaf))
+                if self.nested_keys && !reserved && parents.len() <= JSON_PATH_SIZE =>
+            {
+                (Some(parents), leaf)
+            }
+            _ => (None, key),
+        };
+
+        let common = match parents {
+            Some(parents) => {
+                let path = self.path.borrow();
+                let open = path.as_str().split('.').take(self.depth.get());
+                open.zip(parents.split('.'))
+                    .take_while(|(open, parent)| open == parent)
+                    .count()
+            }
+            None => 0,
+        };
+        self.close_objects(out, common)?;
+
+        if let Some(parents) = parents {
+            let mut depth = common;
+            for parent in parents.split('.').skip(common) {
+                let prefix = json_path_prefix(parents, depth + 1);
+                if self.is_closed(prefix) {
+                    // Written as is from this part on, rather than reopening the object
+                    leaf = &key[prefix.len() - parent.len()..];
+                    break;
+                }
+                if !self.first.get() {
+                    out.write_str(",")?;
+                }
+                out.write_str("\"")?;
+                JsonEscaper(out).write_str(parent)?;
+                out.write_str("\":{")?;
+                self.first.set(true);
+                depth += 1;
+            }
+            self.depth.set(depth);
+        }
+        {
+            let mut path = self.path.borrow_mut();
+            path.clear();
+            if let Some(parents) = parents {
+                let _ = path.write_str(json_path_prefix(parents, self.depth.get()));
+            }
+        }
+
+        if !self.first.replace(false) {
+            out.write_str(",")?;
+        }
+        out.write_str("\"")?;
+        if reserved {
+            out.write_str("fields.")?;
+        }
+        JsonEscaper(out).write_str(leaf)?;
+        out.write_str("\":")
+    }
+}
+
+/// Returns the first `depth` parts of a dotted path.
+fn json_path_prefix(path: &str, depth: usize) -> &str {
+    if depth == 0 {
+        return "";
+    }
+    match path.match_indices('.').nth(depth - 1) {
+        Some((index, _)) => &path[..index],
+        None => path,
+    }
+}
+
+/// Escapes everything written through it as the inside of a JSON string.
+pub(crate) struct JsonEscaper<'a>(pub(crate) &'a mut dyn Write);
+
+impl Write for JsonEscaper<'_> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        let mut start = 0;
+        for (index, c) in string.char_indices() {
+            let escaped = match c {
+                '"' => "\\\"",
+                '\\' => "\\\\",
+                '\n' => "\\n",
+                '\r' => "\\r",
+                '\t' => "\\t",
+                c if (c as u32) < 0x20 => "",
+                _ => continue,
+            };
+
+            self.0.write_str(&string[start..index])?;
+            if escaped.is_empty() {
+                write!(self.0, "\\u{:04x}", c as u32)?;
+            } else {
+                self.0.write_str(escaped)?;
+            }
+            start = index + c.len_utf8();
+        }
+        self.0.write_str(&string[start..])
+    }
+}
+
+impl<Clock: ULogClock> ULogFormatter for JsonFormatter<Clock> {
+    fn format_begin(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        self.msg_open.set(false);
+        self.depth.set(0);
+        self.first.set(false);
+        self.closed.borrow_mut().clear();
+        out.write_str("{")?;
+        if let Some(timestamp) = timestamp(log_data, &self.clock) {
+            write!(out, "\"timestamp\":\"{}\",", Rfc3339(timestamp))?;
+        }
+        out.write_str("\"level\":\"")?;
+        JsonEscaper(out).write_str(log_data.level_name)?;
+        out.write_str("\",\"file\":\"")?;
+        JsonEscaper(out).write_str(log_data.file)?;
+        write!(out, "\",\"line\":{}", log_data.line)?;
+        if let Some(target) = log_data.target {
+            out.write_str(",\"target\":\"")?;
+            JsonEscaper(out).write_str(target)?;
+            out.write_str("\"")?;
+        }
+        if let Some(sequence) = log_data.sequence {
+            write!(out, ",\"seq\":{sequence}")?;
+        }
+        if let Some(user) = log_data.user {
+            write!(out, ",\"user\":{user}")?;
+        }
+        Ok(())
+    }
+
+    fn format_str(&self, out: &mut dyn Write, _log_data: &ULogData, string: &str) -> fmt::Result {
+        if self.msg_open.replace(true) {
+            out.write_str(" ")?;
+        } else {
+            self.close_objects(out, 0)?;
+            out.write_str(",\"msg\":\"")?;
+        }
+        JsonEscaper(out).write_str(string)
+    }
+
+    fn format_str_continued(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        string: &str,
+    ) -> fmt::Result {
+        if self.msg_open.get() {
+            JsonEscaper(out).write_str(string)
+        } else {
+            self.format_str(out, log_data, string)
+        }
+    }
+
+    fn format_kv(
+        &self,
+        out: &mut dyn Write,
+        _log_data: &ULogData,
+        key: &str,
+        value: &dyn Debug,
+    ) -> fmt::Result {
+        self.write_key(out, key)?;
+        out.write_str("\"")?;
+        write!(JsonEscaper(out), "{:?}", value)?;
+        out.write_str("\"")
+    }
+
+    fn format_value(
+        &self,
+        out: &mut dyn Write,
+        _log_data: &ULogData,
+        key: &str,
+        value: Value<'_>,
+    ) -> fmt::Result {
+        self.write_key(out, key)?;
+
+        match value {
+            Value::I64(value) => write!(out, "{}", value),
+            Value::U64(value) => write!(out, "{}", value),
+            Value::F64(value) if value.is_finite() => write!(out, "{}", value),
+            Value::F64(_) => out.write_str("null"),
+            Value::Bool(value) => write!(out, "{}", value),
+            Value::Str(value) => {
+                out.write_str("\"")?;
+                JsonEscaper(out).write_str(value)?;
+                out.write_str("\"")
+            }
+            Value::Bytes(bytes) => {
+                out.write_str("[")?;
+                for (index, byte) in bytes.iter().enumerate() {
+                    if index > 0 {
+                        out.write_str(",")?;
+                    }
+                    write!(out, "{}", byte)?;
+                }
+                out.write_str("]")
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn format_serialize(
+        &self,
+        out: &mut dyn Write,
+        _log_data: &ULogData,
+        key: &str,
+        value: &dyn SerializeJson,
+    ) -> fmt::Result {
+        self.write_key(out, key)?;
+        value.write_json(out)
+    }
+
+    fn format_end(&self, out: &mut dyn Write, _log_data: &ULogData) -> fmt::Result {
+        self.close_msg(out)?;
+        self.close_objects(out, 0)?;
+        out.write_str("}\n")
+    }
+}
+
+/// Formats statements in the logfmt style, as `level=error file=src/main.rs line=10 msg="boot failed" code=5\n`.
+///
+/// The message is always quoted; values are written as their [`Debug`] representation,
+/// which gets quoted if it is empty or contains spaces, `=`, `"` or control characters, and so do the file
+/// and the keys.
+/// The strings of a statement are joined with spaces into its `msg` field, unless they are separated by a key-value pair.
+#[derive(Debug, Default)]
+pub struct LogfmtFormatter {
+    msg_open: Cell<bool>,
+}
+
+impl LogfmtFormatter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn close_msg(&self, out: &mut dyn Write) -> fmt::Result {
+        if self.msg_open.replace(false) {
+            out.write_str("\"")?;
+        }
+        Ok(())
+    }
+}
+
+/// Escapes everything written through it as the inside of a logfmt quoted value.
+struct LogfmtEscaper<'a>(&'a mut dyn Write);
+
+impl Write for LogfmtEscaper<'_> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        let mut start = 0;
+        for (index, c) in string.char_indices() {
+            let escaped = match c {
+                '"' => "\\\"",
+                '\\' => "\\\\",
+                '\n' => "\\n",
+                '\r' => "\\r",
+                '\t' => "\\t",
+                _ => continue,
+            };
+
+            self.0.write_str(&string[start..index])?;
+            self.0.write_str(escaped)?;
+            start = index + c.len_utf8();
+        }
+        self.0.write_str(&string[start..])
+    }
+}
+
+/// Checks whether what is written through it needs to be quoted as a logfmt value.
+struct LogfmtQuoting {
+    empty: bool,
+    needs_quotes: bool,
+}
+
+impl Write for LogfmtQuoting {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        self.empty &= string.is_empty();
+        self.needs_quotes |= string
+            .chars()
+            .any(|c| c == ' ' || c == '=' || c == '"' || c.is_control());
+        Ok(())
+    }
+}
+
+/// Writes `args` as a logfmt key or value, quoted and escaped if needed.
+fn write_logfmt(out: &mut dyn Write, args: fmt::Arguments<'_>) -> fmt::Result {
+    let mut quoting = LogfmtQuoting {
+        empty: true,
+        needs_quotes: false,
+    };
+    quoting.write_fmt(args)?;
+
+    if quoting.empty || quoting.needs_quotes {
+        out.write_str("\"")?;
+        LogfmtEscaper(out).write_fmt(args)?;
+        out.write_str("\"")
+    } else {
+        out.write_fmt(args)
+    }
+}
+
+impl ULogFormatter for LogfmtFormatter {
+    fn format_begin(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        self.msg_open.set(false);
+        out.write_str("level=")?;
+        for c in log_data.level_name.chars() {
+            out.write_char(c.to_ascii_lowercase())?;
+        }
+        out.write_str(" file=")?;
+        write_logfmt(out, format_args!("{}", log_data.file))?;
+        write!(out, " line={}", log_data.line)
+    }
+
+    fn format_str(&self, out: &mut dyn Write, _log_data: &ULogData, string: &str) -> fmt::Result {
+        if self.msg_open.replace(true) {
+            out.write_str(" ")?;
+        } else {
+            out.write_str(" msg=\"")?;
+        }
+        LogfmtEscaper(out).write_str(string)
+    }
+
+    fn format_str_continued(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        string: &str,
+    ) -> fmt::Result {
+        if self.msg_open.get() {
+            LogfmtEscaper(out).write_str(string)
+        } else {
+            self.format_str(out, log_data, string)
+        }
+    }
+
+    fn format_kv(
+        &self,
+        out: &mut dyn Write,
+        _log_data: &ULogData,
+        key: &str,
+        value: &dyn Debug,
+    ) -> fmt::Result {
+        self.close_msg(out)?;
+        out.write_str(" ")?;
+        write_logfmt(out, format_args!("{}", key))?;
+        out.write_str("=")?;
+        write_logfmt(out, format_args!("{:?}", value))
+    }
+
+    /// Writes [`Value::Bytes`] as hexadecimal, and the other values like [`format_kv`](ULogFormatter::format_kv).
+    fn format_value(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        key: &str,
+        value: Value<'_>,
+    ) -> fmt::Result {
+        match value {
+            Value::Bytes(bytes) => self.format_kv(out, log_data, key, &Hex(bytes)),
+            value => self.format_kv(out, log_data, key, &value),
+        }
+    }
+
+    fn format_end(&self, out: &mut dyn Write, _log_data: &ULogData) -> fmt::Result {
+        self.close_msg(out)?;
+        out.write_str("\n")
+    }
+}
+
+/// A column of the rows written by [`CsvFormatter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsvColumn {
+    /// The [timestamp](ULogData::timestamp) of the statement, or else the time given by the clock of the formatter,
+    /// in seconds; empty if there is neither.
+    Timestamp,
+    Level,
+    File,
+    Line,
+    /// The [target](ULogData::target) of the statement, empty if it has none.
+    Target,
+    /// The id of the [thread](ULogData::thread) of the statement, empty if it is unknown.
+    Thread,
+    Message,
+}
+
+impl CsvColumn {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CsvColumn::Timestamp => "timestamp",
+            CsvColumn::Level => "level",
+            CsvColumn::File => "file",
+            CsvColumn::Line => "line",
+            CsvColumn::Target => "target",
+            CsvColumn::Thread => "thread",
+            CsvColumn::Message => "msg",
+        }
+    }
+}
+
+/// Which fields [`CsvFormatter`] puts between double quotes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsvQuoting {
+    /// Only the fields containing the separator, double quotes or line breaks, as well as the message.
+    Minimal,
+    /// All of the fields.
+    Always,
+}
+
+/// Formats statements as rows of comma-separated values, for data loggers writing to spreadsheet-friendly files.
+///
+/// Each row is made of the given [columns](CsvColumn), followed by the values of the statement in the order
+/// they were logged; keys are only written in the optional header row, written before the first statement.
+/// The strings of a statement are joined with spaces into its message, unless they are separated by a key-value pair,
+/// in which case they are written as values. If there is no [`Message`](CsvColumn::Message) column, strings are ignored.
+///
+/// ```
+/// use core::time::Duration;
+/// use ulog::format::{CsvColumn, CsvFormatter, FormattedLogger};
+///
+/// let formatter = CsvFormatter::new(&[CsvColumn::Timestamp, CsvColumn::Message])
+///     .with_clock(|| Duration::from_millis(1500))
+///     .with_header(&["temperature"]);
+/// let logger = FormattedLogger::new(formatter, |bytes: &[u8]| {
+///     print!("{}", core::str::from_utf8(bytes).unwrap());
+/// });
+///
+/// // Prints `timestamp,msg,temperature` and `1.500,"Sensor",21.5`
+/// ulog::info!(logger, "Sensor", "temperature" => 21.5);
+/// ```
+pub struct CsvFormatter<'a, Clock = fn() -> Duration> {
+    columns: &'a [CsvColumn],
+    clock: Option<Clock>,
+    header: Option<&'a [&'a str]>,
+    quoting: CsvQuoting,
+    separator: char,
+    header_written: Cell<bool>,
+    first_field: Cell<bool>,
+    next_column: Cell<usize>,
+    msg_open: Cell<bool>,
+}
+
+impl<'a> CsvFormatter<'a> {
+    pub fn new(columns: &'a [CsvColumn]) -> Self {
+        Self {
+            columns,
+            clock: None,
+            header: None,
+            quoting: CsvQuoting::Minimal,
+            separator: ',',
+            header_written: Cell::new(false),
+            first_field: Cell::new(true),
+            next_column: Cell::new(0),
+            msg_open: Cell::new(false),
+        }
+    }
+}
+
+impl<'a, Clock: ULogClock> CsvFormatter<'a, Clock> {
+    /// Sets the clock used for the [`Timestamp`](CsvColumn::Timestamp) column.
+    pub fn with_clock<NewClock: ULogClock>(self, clock: NewClock) -> CsvFormatter<'a, NewClock> {
+        CsvFormatter {
+            columns: self.columns,
+            clock: Some(clock),
+            header: self.header,
+            quoting: self.quoting,
+            separator: self.separator,
+            header_written: self.header_written,
+            first_field: self.first_field,
+            next_column: self.next_column,
+            msg_open: self.msg_open,
+        }
+    }
+
+    /// Writes a header row before the first statement, naming the columns followed by `keys`.
+    pub fn with_header(mut self, keys: &'a [&'a str]) -> Self {
+        self.header = Some(keys);
+        self
+    }
+
+    pub fn with_quoting(mut self, quoting: CsvQuoting) -> Self {
+        self.quoting = quoting;
+        self
+    }
+
+    /// Sets the character separating fields, `,` by default.
+    pub fn with_separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    fn write_separator(&self, out: &mut dyn Write) -> fmt::Result {
+        if !self.first_field.replace(false) {
+            out.write_char(self.separator)?;
+        }
+        Ok(())
+    }
+
+    fn write_field(&self, out: &mut dyn Write, field: fmt::Arguments<'_>) -> fmt::Result {
+        self.write_separator(out)?;
+
+        let quoted = match self.quoting {
+            CsvQuoting::Always => true,
+            CsvQuoting::Minimal => {
+                let mut quoting = CsvNeedsQuotes {
+                    separator: self.separator,
+                    needs_quotes: false,
+                };
+                quoting.write_fmt(field)?;
+                quoting.needs_quotes
+            }
+        };
+
+        if quoted {
+            out.write_str("\"")?;
+            CsvEscaper(out).write_fmt(field)?;
+            out.write_str("\"")
+        } else {
+            out.write_fmt(field)
+        }
+    }
+
+    fn write_column(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        column: CsvColumn,
+    ) -> fmt::Result {
+        match column {
+            CsvColumn::Timestamp => match timestamp(log_data, &self.clock) {
+                Some(now) => self.write_field(
+                    out,
+                    format_args!("{}.{:03}", now.as_secs(), now.subsec_millis()),
+                ),
+                None => self.write_field(out, format_args!("")),
+            },
+            CsvColumn::Level => self.write_field(out, format_args!("{}", log_data.level_name)),
+            CsvColumn::File => self.write_field(out, format_args!("{}", log_data.file)),
+            CsvColumn::Line => self.write_field(out, format_args!("{}", log_data.line)),
+            CsvColumn::Target => {
+                self.write_field(out, format_args!("{}", log_data.target.unwrap_or("")))
+            }
+            CsvColumn::Thread => match log_data.thread {
+                Some(thread) => self.write_field(out, format_args!("{thread}")),
+                None => self.write_field(out, format_args!("")),
+            },
+            CsvColumn::Message => {
+                self.write_separator(out)?;
+                self.msg_open.set(true);
+                out.write_str("\"")
+            }
+        }
+    }
+
+    /// Writes the columns up to the message column, or all of them if there is none.
+    fn write_columns_until_message(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        while let Some(&column) = self.columns.get(self.next_column.get()) {
+            if column == CsvColumn::Message {
+                break;
+            }
+            self.write_column(out, log_data, column)?;
+            self.next_column.set(self.next_column.get() + 1);
+        }
+        Ok(())
+    }
+
+    /// Closes the message column, and writes the following columns.
+    fn finish_columns(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        if self.columns.get(self.next_column.get()) == Some(&CsvColumn::Message) {
+            if !self.msg_open.get() {
+                self.write_column(out, log_data, CsvColumn::Message)?;
+            }
+            self.msg_open.set(false);
+            out.write_str("\"")?;
+            self.next_column.set(self.next_column.get() + 1);
+        }
+        self.write_columns_until_message(out, log_data)
+    }
+}
+
+/// Escapes everything written through it as the inside of a quoted CSV field.
+struct CsvEscaper<'a>(&'a mut dyn Write);
+
+impl Write for CsvEscaper<'_> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        for (index, part) in string.split('"').enumerate() {
+            if index > 0 {
+                self.0.write_str("\"\"")?;
+            }
+            self.0.write_str(part)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks whether what is written through it needs to be quoted as a CSV field.
+struct CsvNeedsQuotes {
+    separator: char,
+    needs_quotes: bool,
+}
+
+impl Write for CsvNeedsQuotes {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        self.needs_quotes |= string
+            .chars()
+            .any(|c| c == self.separator || c == '"' || c == '\n' || c == '\r');
+        Ok(())
+    }
+}
+
+impl<Clock: ULogClock> ULogFormatter for CsvFormatter<'_, Clock> {
+    fn format_begin(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        if let Some(keys) = self.header {
+            if !self.header_written.replace(true) {
+                self.first_field.set(true);
+                for column in self.columns {
+                    self.write_field(out, format_args!("{}", column.name()))?;
+                }
+                for key in keys {
+                    self.write_field(out, format_args!("{}", key))?;
+                }
+                out.write_str("\n")?;
+            }
+        }
+
+        self.first_field.set(true);
+        self.next_column.set(0);
+        self.msg_open.set(false);
+        self.write_columns_until_message(out, log_data)
+    }
+
+    fn format_str(&self, out: &mut dyn Write, log_data: &ULogData, string: &str) -> fmt::Result {
+        if self.msg_open.get() {
+            out.write_str(" ")?;
+            CsvEscaper(out).write_str(string)
+        } else if self.columns.get(self.next_column.get()) == Some(&CsvColumn::Message) {
+            self.write_column(out, log_data, CsvColumn::Message)?;
+            CsvEscaper(out).write_str(string)
+        } else if self.columns.contains(&CsvColumn::Message) {
+            self.write_field(out, format_args!("{}", string))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn format_str_continued(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        string: &str,
+    ) -> fmt::Result {
+        if self.msg_open.get() {
+            CsvEscaper(out).write_str(string)
+        } else {
+            self.format_str(out, log_data, string)
+        }
+    }
+
+    fn format_kv(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        _key: &str,
+        value: &dyn Debug,
+    ) -> fmt::Result {
+        self.finish_columns(out, log_data)?;
+        self.write_field(out, format_args!("{:?}", value))
+    }
+
+    fn format_end(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        self.finish_columns(out, log_data)?;
+        out.write_str("\n")
+    }
+}
+
+/// A placeholder of a [`Template`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Placeholder {
+    Level,
+    LevelShort,
+    File,
+    Line,
+    Target,
+    Module,
+    Thread,
+    Msg,
+    Kv,
+}
+
+impl Placeholder {
+    const fn parse(name: &[u8]) -> Option<Self> {
+        const NAMES: [(&[u8], Placeholder); 9] = [
+            (b"level", Placeholder::Level),
+            (b"level:short", Placeholder::LevelShort),
+            (b"file", Placeholder::File),
+            (b"line", Placeholder::Line),
+            (b"target", Placeholder::Target),
+            (b"module", Placeholder::Module),
+            (b"thread", Placeholder::Thread),
+            (b"msg", Placeholder::Msg),
+            (b"kv", Placeholder::Kv),
+        ];
+
+        let mut index = 0;
+        while index < NAMES.len() {
+            if bytes_eq(name, NAMES[index].0) {
+                return Some(NAMES[index].1);
+            }
+            index += 1;
+        }
+        None
+    }
+}
+
+const fn bytes_eq(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+    let mut index = 0;
+    while index < left.len() {
+        if left[index] != right[index] {
+            return false;
+        }
+        index += 1;
+    }
+    true
+}
+
+enum Piece<'a> {
+    Literal(&'a str),
+    Placeholder(Placeholder),
+}
+
+/// Why a layout string was rejected by [`Template::try_new`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A `{` or `}` that is neither a placeholder nor escaped as `{{` or `}}`.
+    UnmatchedBrace,
+    UnknownPlaceholder,
+    /// `{msg}` or `{kv}` appears more than once, or `{kv}` appears before `{msg}`.
+    MisplacedPlaceholder,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::UnmatchedBrace => write!(f, "unmatched brace in template"),
+            TemplateError::UnknownPlaceholder => write!(f, "unknown placeholder in template"),
+            TemplateError::MisplacedPlaceholder => {
+                write!(
+                    f,
+                    "{{msg}} and {{kv}} must appear at most once, in that order"
+                )
+            }
+        }
+    }
+}
+
+/// A layout string used by [`TemplateFormatter`], like `"{level:short} {file}:{line} | {msg} {kv}"`.
+///
+/// The following placeholders are supported, and braces are escaped as `{{` and `}}`:
+/// - `{level}` and `{level:short}`, the level as given by [`ULogLevel::as_str`] and [`ULogLevel::as_short_str`]
+/// - `{file}` and `{line}`
+/// - `{target}`, the [target](ULogData::target) of the statement, or nothing if it has none
+/// - `{module}`, the [module path](ULogData::module) of the statement, or nothing if it has none
+/// - `{thread}`, the name of the [thread](ULogData::thread) of the statement with the `std` feature if it has one,
+///   or else its id, or nothing if it is unknown
+/// - `{msg}`, the strings of the statement joined with spaces
+/// - `{kv}`, the key-value pairs of the statement as `key=value`, separated with spaces
+///
+/// As statements are formatted as they are logged, `{msg}` and `{kv}` may only appear once, in that order.
+/// Templates can be checked at compile time by creating them in a constant:
+///
+/// ```
+/// use ulog::format::{Template, TemplateFormatter};
+///
+/// const LAYOUT: Template = Template::new("[{level:short}] {msg} ({file}:{line}) {kv}");
+///
+/// let formatter = TemplateFormatter::new(LAYOUT);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Template<'a> {
+    template: &'a str,
+}
+
+impl<'a> Template<'a> {
+    /// Creates a template, panicking if it is invalid; see [`try_new`](Template::try_new).
+    pub const fn new(template: &'a str) -> Self {
+        match Self::try_new(template) {
+            Ok(template) => template,
+            Err(_) => panic!("ulog: invalid template"),
+        }
+    }
+
+    pub const fn try_new(template: &'a str) -> Result<Self, TemplateError> {
+        let bytes = template.as_bytes();
+        let mut seen_msg = false;
+        let mut seen_kv = false;
+        let mut index = 0;
+
+        while index < bytes.len() {
+            match bytes[index] {
+                b'{' if index + 1 < bytes.len() && bytes[index + 1] == b'{' => index += 2,
+                b'}' if index + 1 < bytes.len() && bytes[index + 1] == b'}' => index += 2,
+                b'}' => return Err(TemplateError::UnmatchedBrace),
+                b'{' => {
+                    let start = index + 1;
+                    let mut end = start;
+                    while end < bytes.len() && bytes[end] != b'}' {
+                        end += 1;
+                    }
+                    if end == bytes.len() {
+                        return Err(TemplateError::UnmatchedBrace);
+                    }
+
+                    let (name, _) = bytes.split_at(end);
+                    let (_, name) = name.split_at(start);
+                    match Placeholder::parse(name) {
+                        None => return Err(TemplateError::UnknownPlaceholder),
+                        Some(Placeholder::Msg) if seen_msg || seen_kv => {
+                            return Err(TemplateError::MisplacedPlaceholder)
+                        }
+                        Some(Placeholder::Msg) => seen_msg = true,
+                        Some(Placeholder::Kv) if seen_kv => {
+                            return Err(TemplateError::MisplacedPlaceholder)
+                        }
+                        Some(Placeholder::Kv) => seen_kv = true,
+                        Some(_) => {}
+                    }
+                    index = end + 1;
+                }
+                _ => index += 1,
+            }
+        }
+
+        Ok(Self { template })
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.template
+    }
+
+    /// Returns the piece of the template starting at `offset`, and the offset of the next one.
+    fn piece(&self, offset: usize) -> Option<(Piece<'a>, usize)> {
+        let rest = &self.template[offset..];
+        if rest.is_empty() {
+            return None;
+        }
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            return Some((Piece::Literal(&rest[..1]), offset + 2));
+        }
+        if rest.starts_with('{') {
+            let end = rest.find('}')?;
+            let placeholder = Placeholder::parse(&rest.as_bytes()[1..end])?;
+            return Some((Piece::Placeholder(placeholder), offset + end + 1));
+        }
+
+        let end = rest.find(['{', '}']).unwrap_or(rest.len());
+        Some((Piece::Literal(&rest[..end]), offset + end))
+    }
+}
+
+/// Formats statements following a [`Template`], each of them on its own line.
+#[derive(Debug)]
+pub struct TemplateFormatter<'a> {
+    template: Template<'a>,
+    /// The offset of the first piece of the template that has not been written yet
+    offset: Cell<usize>,
+    /// How many strings or key-value pairs were written in the current `{msg}` or `{kv}` placeholder
+    written: Cell<usize>,
+}
+
+impl<'a> TemplateFormatter<'a> {
+    pub fn new(template: Template<'a>) -> Self {
+        Self {
+            template,
+            offset: Cell::new(0),
+            written: Cell::new(0),
+        }
+    }
+
+    pub fn template(&self) -> Template<'a> {
+        self.template
+    }
+
+    /// Returns the placeholder at which the formatter stopped, if it is `{msg}` or `{kv}`.
+    fn current(&self) -> Option<Placeholder> {
+        match self.template.piece(self.offset.get()) {
+            Some((Piece::Placeholder(placeholder @ (Placeholder::Msg | Placeholder::Kv)), _)) => {
+                Some(placeholder)
+            }
+            _ => None,
+        }
+    }
+
+    /// Skips the current `{msg}` or `{kv}` placeholder, and writes the template until the next one.
+    fn advance(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        let mut offset = self.offset.get();
+        if self.current().is_some() {
+            offset = self.template.piece(offset).map_or(offset, |(_, next)| next);
+        }
+        self.written.set(0);
+
+        while let Some((piece, next)) = self.template.piece(offset) {
+            match piece {
+                Piece::Literal(literal) => out.write_str(literal)?,
+                Piece::Placeholder(Placeholder::Level) => out.write_str(log_data.level_name)?,
+                Piece::Placeholder(Placeholder::LevelShort) => {
+                    out.write_str(log_data.level_short_name)?
+                }
+                Piece::Placeholder(Placeholder::File) => out.write_str(log_data.file)?,
+                Piece::Placeholder(Placeholder::Line) => write!(out, "{}", log_data.line)?,
+                Piece::Placeholder(Placeholder::Target) => {
+                    out.write_str(log_data.target.unwrap_or(""))?
+                }
+                Piece::Placeholder(Placeholder::Module) => {
+                    out.write_str(log_data.module.unwrap_or(""))?
+                }
+                Piece::Placeholder(Placeholder::Thread) => write_thread(out, log_data)?,
+                Piece::Placeholder(Placeholder::Msg | Placeholder::Kv) => break,
+            }
+            offset = next;
+        }
+
+        self.offset.set(offset);
+        Ok(())
+    }
+
+    fn write_separator(&self, out: &mut dyn Write) -> fmt::Result {
+        if self.written.replace(self.written.get() + 1) > 0 {
+            out.write_str(" ")?;
+        }
+        Ok(())
+    }
+}
+
+impl ULogFormatter for TemplateFormatter<'_> {
+    fn format_begin(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        self.offset.set(0);
+        self.advance(out, log_data)
+    }
+
+    fn format_str(&self, out: &mut dyn Write, _log_data: &ULogData, string: &str) -> fmt::Result {
+        if self.current() != Some(Placeholder::Msg) {
+            return Ok(());
+        }
+        self.write_separator(out)?;
+        out.write_str(string)
+    }
+
+    fn format_str_continued(
+        &self,
+        out: &mut dyn Write,
+        _log_data: &ULogData,
+        string: &str,
+    ) -> fmt::Result {
+        if self.current() != Some(Placeholder::Msg) {
+            return Ok(());
+        }
+        // Continues the previous string of the placeholder, or starts it
+        self.written.set(self.written.get().max(1));
+        out.write_str(string)
+    }
+
+    fn format_kv(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        key: &str,
+        value: &dyn Debug,
+    ) -> fmt::Result {
+        if self.current() == Some(Placeholder::Msg) {
+            self.advance(out, log_data)?;
+        }
+        if self.current() != Some(Placeholder::Kv) {
+            return Ok(());
+        }
+        self.write_separator(out)?;
+        write!(out, "{}={:?}", key, value)
+    }
+
+    fn format_end(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        while self.current().is_some() {
+            self.advance(out, log_data)?;
+        }
+        out.write_str("\n")
+    }
+}
+
+/// Formats statements as lines of text like [`TextFormatter`], padding the level, the location (`file:line`)
+/// and the message into fixed-width columns, so that the output of many statements lines up;
+/// key-value pairs follow the message column.
+///
+/// With truncation enabled, which is the default, text longer than its column is cut and marked with a `…`:
+/// the message keeps its beginning, while the location keeps its end, where the file name and the line are.
+/// Otherwise, long text overflows its column and shifts the following ones.
+///
+/// Widths are counted in characters, or in terminal columns with the `unicode-width` feature,
+/// so that wide characters like CJK ideographs and emoji don't break the layout.
+#[derive(Debug)]
+pub struct ColumnFormatter {
+    level_width: usize,
+    location_width: usize,
+    message_width: usize,
+    truncate: bool,
+    message: Cell<Column>,
+    message_open: Cell<bool>,
+}
+
+impl ColumnFormatter {
+    /// Creates a formatter with columns of 8 characters for the level, 24 for the location and 40 for the message.
+    pub fn new() -> Self {
+        Self {
+            level_width: 8,
+            location_width: 24,
+            message_width: 40,
+            truncate: true,
+            message: Cell::new(Column::default()),
+            message_open: Cell::new(false),
+        }
+    }
+
+    pub fn with_level_width(mut self, width: usize) -> Self {
+        self.level_width = width;
+        self
+    }
+
+    pub fn with_location_width(mut self, width: usize) -> Self {
+        self.location_width = width;
+        self
+    }
+
+    pub fn with_message_width(mut self, width: usize) -> Self {
+        self.message_width = width;
+        self
+    }
+
+    /// Sets whether text longer than its column gets truncated, instead of overflowing it.
+    pub fn with_truncation(mut self, truncate: bool) -> Self {
+        self.truncate = truncate;
+        self
+    }
+
+    fn limit(&self, width: usize) -> Option<usize> {
+        self.truncate.then_some(width)
+    }
+
+    /// Writes a string of the message, preceded by a space if `separate` is set and the message isn't empty.
+    fn write_message(&self, out: &mut dyn Write, string: &str, separate: bool) -> fmt::Result {
+        if !self.message_open.get() {
+            if separate {
+                out.write_str(" ")?;
+            }
+            return out.write_str(string);
+        }
+
+        let mut column = self.message.get();
+        let mut writer = ColumnWriter {
+            out,
+            column: &mut column,
+            width: self.limit(self.message_width),
+        };
+        if separate && !writer.column.is_empty() {
+            writer.write_str(" ")?;
+        }
+        let result = writer.write_str(string);
+        self.message.set(column);
+        result
+    }
+
+    /// Finishes the message column, padding it if key-value pairs follow.
+    fn close_message(&self, out: &mut dyn Write, pad: bool) -> fmt::Result {
+        if !self.message_open.replace(false) {
+            return Ok(());
+        }
+        let mut column = self.message.get();
+        column.finish(out)?;
+        if pad {
+            column.pad(out, self.message_width)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for ColumnFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ULogFormatter for ColumnFormatter {
+    fn format_begin(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        let mut level = Column::default();
+        let mut writer = ColumnWriter {
+            out: &mut *out,
+            column: &mut level,
+            width: self.limit(self.level_width),
+        };
+        writer.write_str(log_data.level_name)?;
+        level.finish(out)?;
+        level.pad(out, self.level_width)?;
+        out.write_str(" ")?;
+
+        let mut length = WidthCount(0);
+        write!(length, "{}:{}", log_data.file, log_data.line)?;
+        let mut location = Column::default();
+        match self.limit(self.location_width) {
+            Some(width) if length.0 > width => {
+                if width > 0 {
+                    out.write_str("…")?;
+                }
+                let mut skipped = SkipWidth {
+                    out: &mut *out,
+                    skip: length.0 - width.saturating_sub(1),
+                    written: 0,
+                };
+                write!(skipped, "{}:{}", log_data.file, log_data.line)?;
+                location.len = width.min(1) + skipped.written;
+            }
+            _ => {
+                write!(out, "{}:{}", log_data.file, log_data.line)?;
+                location.len = length.0;
+            }
+        }
+        location.pad(out, self.location_width)?;
+
+        self.message.set(Column::default());
+        self.message_open.set(true);
+        out.write_str(" ")
+    }
+
+    fn format_str(&self, out: &mut dyn Write, _log_data: &ULogData, string: &str) -> fmt::Result {
+        self.write_message(out, string, true)
+    }
+
+    fn format_str_continued(
+        &self,
+        out: &mut dyn Write,
+        _log_data: &ULogData,
+        string: &str,
+    ) -> fmt::Result {
+        self.write_message(out, string, false)
+    }
+
+    fn format_kv(
+        &self,
+        out: &mut dyn Write,
+        _log_data: &ULogData,
+        key: &str,
+        value: &dyn Debug,
+    ) -> fmt::Result {
+        self.close_message(out, true)?;
+        write!(out, " {}={:?}", key, value)
+    }
+
+    fn format_end(&self, out: &mut dyn Write, _log_data: &ULogData) -> fmt::Result {
+        self.close_message(out, false)?;
+        out.write_str("\n")
+    }
+}
+
+/// The display width of `c`, in terminal columns with the `unicode-width` feature, or 1 otherwise.
+fn char_width(c: char) -> usize {
+    #[cfg(feature = "unicode-width")]
+    {
+        unicode_width::UnicodeWidthChar::width(c).unwrap_or(0)
+    }
+    #[cfg(not(feature = "unicode-width"))]
+    {
+        let _ = c;
+        1
+    }
+}
+
+/// The number of bytes of text that a [`Column`] can hold back.
+const COLUMN_HELD: usize = 16;
+
+/// The state of a column of [`ColumnFormatter`], as text gets written into it.
+#[derive(Clone, Copy, Debug, Default)]
+struct Column {
+    /// The width of the text written
+    len: usize,
+    /// The text that fits in the column but not before the room of the `…`, held back until it is known
+    /// whether the text needs to be cut
+    held: [u8; COLUMN_HELD],
+    held_len: usize,
+    held_width: usize,
+    cut: bool,
+}
+
+impl Column {
+    fn is_empty(&self) -> bool {
+        self.len == 0 && self.held_len == 0 && !self.cut
+    }
+
+    /// Writes the held text, if the text ended up fitting in the column.
+    fn finish(&mut self, out: &mut dyn Write) -> fmt::Result {
+        // Only whole characters are held
+        out.write_str(core::str::from_utf8(&self.held[..self.held_len]).unwrap_or_default())?;
+        self.len += self.held_width;
+        self.held_len = 0;
+        self.held_width = 0;
+        Ok(())
+    }
+
+    fn pad(&self, out: &mut dyn Write, width: usize) -> fmt::Result {
+        for _ in self.len..width {
+            out.write_char(' ')?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes text into a [`Column`], cutting it to a display width of `width` if set.
+struct ColumnWriter<'a> {
+    out: &'a mut dyn Write,
+    column: &'a mut Column,
+    width: Option<usize>,
+}
+
+impl Write for ColumnWriter<'_> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        let Some(width) = self.width else {
+            self.column.len += string.chars().map(char_width).sum::<usize>();
+            return self.out.write_str(string);
+        };
+
+        let column = &mut *self.column;
+        for c in string.chars() {
+            if column.cut || width == 0 {
+                column.cut = true;
+                break;
+            }
+
+            let c_width = char_width(c);
+            if column.held_len == 0 && column.len + c_width < width {
+                self.out.write_char(c)?;
+                column.len += c_width;
+            } else if column.len + column.held_width + c_width <= width
+                && column.held_len + c.len_utf8() <= COLUMN_HELD
+            {
+                c.encode_utf8(&mut column.held[column.held_len..]);
+                column.held_len += c.len_utf8();
+                column.held_width += c_width;
+            } else {
+                self.out.write_char('…')?;
+                column.len += 1;
+                column.held_len = 0;
+                column.held_width = 0;
+                column.cut = true;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Counts the display width of the text written through it.
+struct WidthCount(usize);
+
+impl Write for WidthCount {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        self.0 += string.chars().map(char_width).sum::<usize>();
+        Ok(())
+    }
+}
+
+/// Drops the first characters written through it, up to a display width of `skip`,
+/// and counts the width of the ones it writes.
+struct SkipWidth<'a> {
+    out: &'a mut dyn Write,
+    skip: usize,
+    written: usize,
+}
+
+impl Write for SkipWidth<'_> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        for c in string.chars() {
+            let c_width = char_width(c);
+            // Zero-width characters belong with the character before them
+            if self.skip > 0 || (self.written == 0 && c_width == 0) {
+                self.skip = self.skip.saturating_sub(c_width);
+            } else {
+                self.out.write_char(c)?;
+                self.written += c_width;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Wraps a formatter so that identical statements always produce identical, byte-stable output,
+/// for golden-file tests or for comparing the logs of reproducible builds.
+///
+/// Statements are captured into a [`Record`] of `SIZE` bytes, then formatted with the strings first and the key-value
+/// pairs sorted by key, whatever order they were logged in; pairs with the same key keep their relative order.
+/// Values are formatted with their [`Debug`] implementation, which does not depend on the locale.
+/// Timestamps are only written if the statements carry one or if the wrapped formatter was given a clock,
+/// which tests can replace with a fixed one.
+pub struct CanonicalFormatter<Formatter, const SIZE: usize> {
+    formatter: Formatter,
+    record: RefCell<Option<Record<SIZE>>>,
+}
+
+impl<Formatter: ULogFormatter, const SIZE: usize> CanonicalFormatter<Formatter, SIZE> {
+    pub fn new(formatter: Formatter) -> Self {
+        Self {
+            formatter,
+            record: RefCell::new(None),
+        }
+    }
+
+    pub fn formatter(&self) -> &Formatter {
+        &self.formatter
+    }
+
+    pub fn into_inner(self) -> Formatter {
+        self.formatter
+    }
+}
+
+impl<Formatter: ULogFormatter, const SIZE: usize> ULogFormatter
+    for CanonicalFormatter<Formatter, SIZE>
+{
+    fn format_begin(&self, _out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        *self.record.borrow_mut() = Some(Record::new(log_data.clone()));
+        Ok(())
+    }
+
+    fn format_str(&self, _out: &mut dyn Write, _log_data: &ULogData, string: &str) -> fmt::Result {
+        if let Some(record) = self.record.borrow_mut().as_mut() {
+            record.push_str(string);
+        }
+        Ok(())
+    }
+
+    fn format_str_continued(
+        &self,
+        _out: &mut dyn Write,
+        _log_data: &ULogData,
+        string: &str,
+    ) -> fmt::Result {
+        if let Some(record) = self.record.borrow_mut().as_mut() {
+            record.push_str_continued(string);
+        }
+        Ok(())
+    }
+
+    fn format_fmt(
+        &self,
+        _out: &mut dyn Write,
+        _log_data: &ULogData,
+        args: fmt::Arguments<'_>,
+    ) -> fmt::Result {
+        if let Some(record) = self.record.borrow_mut().as_mut() {
+            record.push_fmt(args);
+        }
+        Ok(())
+    }
+
+    fn format_kv(
+        &self,
+        _out: &mut dyn Write,
+        _log_data: &ULogData,
+        key: &str,
+        value: &dyn Debug,
+    ) -> fmt::Result {
+        if let Some(record) = self.record.borrow_mut().as_mut() {
+            record.push_format(key, &value);
+        }
+        Ok(())
+    }
+
+    fn format_value(
+        &self,
+        _out: &mut dyn Write,
+        _log_data: &ULogData,
+        key: &str,
+        value: Value<'_>,
+    ) -> fmt::Result {
+        if let Some(record) = self.record.borrow_mut().as_mut() {
+            record.push_value(key, value);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    fn format_serialize(
+        &self,
+        _out: &mut dyn Write,
+        _log_data: &ULogData,
+        key: &str,
+        value: &dyn SerializeJson,
+    ) -> fmt::Result {
+        if let Some(record) = self.record.borrow_mut().as_mut() {
+            record.push_serialize(key, value);
+        }
+        Ok(())
+    }
+
+    fn format_end(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        let Some(record) = self.record.borrow_mut().take() else {
+            return Ok(());
+        };
+
+        self.formatter.format_begin(out, log_data)?;
+        for segment in record.segments() {
+            if let Segment::Str(string) = segment {
+                self.formatter.format_str(out, log_data, string)?;
+            }
+        }
+
+        // Selects the fields by increasing `(key, index)`, as there is no room to sort them in place
+        let fields = || {
+            record
+                .segments()
+                .enumerate()
+                .filter_map(|(index, segment)| match segment {
+                    Segment::Field(key, _)
+                    | Segment::Value(key, _)
+                    | Segment::Serialized(key, _) => Some((key, index, segment)),
+                    Segment::Str(_) => None,
+                })
+        };
+        let mut previous = None;
+        while let Some((key, index, segment)) = fields()
+            .filter(|&(key, index, _)| previous < Some((key, index)))
+            .min_by_key(|&(key, index, _)| (key, index))
+        {
+            match segment {
+                Segment::Field(_, value) => {
+                    self.formatter
+                        .format_kv(out, log_data, key, &Verbatim(value))?
+                }
+                Segment::Value(_, value) => {
+                    self.formatter.format_value(out, log_data, key, value)?
+                }
+                #[cfg(feature = "serde")]
+                Segment::Serialized(_, json) => {
+                    self.formatter
+                        .format_serialize(out, log_data, key, &RawJson(json))?
+                }
+                #[cfg(not(feature = "serde"))]
+                Segment::Serialized(_, json) => {
+                    self.formatter
+                        .format_kv(out, log_data, key, &Verbatim(json))?
+                }
+                Segment::Str(_) => {}
+            }
+            previous = Some((key, index));
+        }
+
+        self.formatter.format_end(out, log_data)
+    }
+}
+
+/// How [`SanitizingFormatter`] handles control characters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SanitizeMode {
+    /// Replaces them with escape sequences, like `\n` or `\u{1b}`.
+    #[default]
+    Escape,
+    /// Removes them.
+    Strip,
+}
+
+/// Wraps a formatter so that control characters in messages and values, like line feeds or the escape character
+/// starting ANSI sequences, are escaped or stripped; untrusted input then can't forge fake lines or corrupt the state
+/// of a terminal.
+///
+/// Unicode bidirectional overrides, which can make text display differently than it reads, are handled the same way.
+/// The text written by the wrapped formatter itself, like the line feed ending statements, is left untouched.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SanitizingFormatter<Formatter> {
+    formatter: Formatter,
+    mode: SanitizeMode,
+}
+
+impl<Formatter: ULogFormatter> SanitizingFormatter<Formatter> {
+    pub fn new(formatter: Formatter) -> Self {
+        Self {
+            formatter,
+            mode: SanitizeMode::Escape,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: SanitizeMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn formatter(&self) -> &Formatter {
+        &self.formatter
+    }
+
+    pub fn into_inner(self) -> Formatter {
+        self.formatter
+    }
+}
+
+impl<Formatter: ULogFormatter> ULogFormatter for SanitizingFormatter<Formatter> {
+    fn format_begin(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        self.formatter.format_begin(out, log_data)
+    }
+
+    fn format_str(&self, out: &mut dyn Write, log_data: &ULogData, string: &str) -> fmt::Result {
+        let mut out = Sanitizer {
+            out,
+            mode: self.mode,
+        };
+        self.formatter.format_str(&mut out, log_data, string)
+    }
+
+    fn format_str_continued(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        string: &str,
+    ) -> fmt::Result {
+        let mut out = Sanitizer {
+            out,
+            mode: self.mode,
+        };
+        self.formatter
+            .format_str_continued(&mut out, log_data, string)
+    }
+
+    fn format_fmt(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        args: fmt::Arguments<'_>,
+    ) -> fmt::Result {
+        let mut out = Sanitizer {
+            out,
+            mode: self.mode,
+        };
+        self.formatter.format_fmt(&mut out, log_data, args)
+    }
+
+    fn format_kv(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        key: &str,
+        value: &dyn Debug,
+    ) -> fmt::Result {
+        let mut out = Sanitizer {
+            out,
+            mode: self.mode,
+        };
+        self.formatter.format_kv(&mut out, log_data, key, value)
+    }
+
+    fn format_value(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        key: &str,
+        value: Value<'_>,
+    ) -> fmt::Result {
+        let mut out = Sanitizer {
+            out,
+            mode: self.mode,
+        };
+        self.formatter.format_value(&mut out, log_data, key, value)
+    }
+
+    #[cfg(feature = "serde")]
+    fn format_serialize(
+        &self,
+        out: &mut dyn Write,
+        log_data: &ULogData,
+        key: &str,
+        value: &dyn SerializeJson,
+    ) -> fmt::Result {
+        let mut out = Sanitizer {
+            out,
+            mode: self.mode,
+        };
+        self.formatter
+            .format_serialize(&mut out, log_data, key, value)
+    }
+
+    fn format_end(&self, out: &mut dyn Write, log_data: &ULogData) -> fmt::Result {
+        self.formatter.format_end(out, log_data)
+    }
+}
+
+/// Escapes or strips the control characters written through it.
+struct Sanitizer<'a> {
+    out: &'a mut dyn Write,
+    mode: SanitizeMode,
+}
+
+impl Write for Sanitizer<'_> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        let mut start = 0;
+        for (index, c) in string.char_indices() {
+            if !c.is_control() && !matches!(c, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}') {
+                continue;
+            }
+
+            self.out.write_str(&string[start..index])?;
+            if self.mode == SanitizeMode::Escape {
+                match c {
+                    '\n' => self.out.write_str("\\n")?,
+                    '\r' => self.out.write_str("\\r")?,
+                    '\t' => self.out.write_str("\\t")?,
+                    c => write!(self.out, "\\u{{{:x}}}", c as u32)?,
+                }
+            }
+            start = index + c.len_utf8();
+        }
+        self.out.write_str(&string[start..])
+    }
+}
+
+/// Formats a byte slice as rows of 16 bytes, with their offset, their hexadecimal values and their ASCII characters,
+/// when printed with [`Debug`] or [`Display`](fmt::Display):
+///
+/// ```text
+/// 0000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 00 ff 0a  |Hello, world!...|
+/// 0010  01 02                                             |..|
+/// ```
+///
+/// ```
+/// use ulog::format::HexDump;
+///
+/// let packet = [0x48, 0x69, 0x00];
+/// ulog::debug!(ulog::common::StubLogger, "Received packet", "bytes" => HexDump(&packet));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct HexDump<'a>(pub &'a [u8]);
+
+impl HexDump<'_> {
+    const ROW: usize = 16;
+}
+
+impl fmt::Display for HexDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, row) in self.0.chunks(Self::ROW).enumerate() {
+            if index > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{:04x} ", index * Self::ROW)?;
+
+            for column in 0..Self::ROW {
+                if column == Self::ROW / 2 {
+                    f.write_str(" ")?;
+                }
+                match row.get(column) {
+                    Some(byte) => write!(f, " {:02x}", byte)?,
+                    None => f.write_str("   ")?,
+                }
+            }
+
+            f.write_str("  |")?;
+            for &byte in row {
+                let c = if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                };
+                f.write_char(c)?;
+            }
+            f.write_str("|")?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for HexDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Formats a fixed-point number, `Fixed(value, decimals)` standing for `value / 10^decimals`, using integer math only;
+/// unlike formatting an `f32` or an `f64`, this doesn't pull the float formatting code of `core::fmt` into the binary.
+///
+/// `Fixed(-1205, 2)` is formatted as `-12.05`; `decimals` is capped at 19, the most a `u64` can hold.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Fixed(pub i64, pub u8);
+
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let decimals = self.1.min(19) as usize;
+        let divisor = 10u64.pow(decimals as u32);
+        let magnitude = self.0.unsigned_abs();
+
+        if self.0 < 0 {
+            f.write_str("-")?;
+        }
+        write!(f, "{}", magnitude / divisor)?;
+        if decimals > 0 {
+            write!(f, ".{:0width$}", magnitude % divisor, width = decimals)?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Formats a voltage given in millivolts, as `3.300 V`, without float formatting; see [`Fixed`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Millivolts(pub i32);
+
+impl fmt::Display for Millivolts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} V", Fixed(i64::from(self.0), 3))
+    }
+}
+
+impl Debug for Millivolts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Formats a temperature given in thousandths of a degree Celsius, as `21.500 °C`, without float formatting;
+/// see [`Fixed`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Millicelsius(pub i32);
+
+impl fmt::Display for Millicelsius {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} °C", Fixed(i64::from(self.0), 3))
+    }
+}
+
+impl Debug for Millicelsius {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Formats a value with its pretty-printed [`Debug`] representation (`{:#?}`), indenting its continuation lines
+/// so that nested structures stay readable below the header of the statement:
+///
+/// ```text
+/// INFO src/main.rs:12 Loaded config config=Config {
+///         name: "sensor",
+///         period: 10,
+///     }
+/// ```
+///
+/// ```
+/// use ulog::format::Pretty;
+///
+/// #[derive(Debug)]
+/// struct Config {
+///     name: &'static str,
+///     period: u32,
+/// }
+///
+/// let config = Config { name: "sensor", period: 10 };
+/// ulog::info!(ulog::common::StubLogger, "Loaded config", "config" => Pretty(&config));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Pretty<T>(pub T);
+
+impl<T> Pretty<T> {
+    const INDENT: &'static str = "    ";
+}
+
+/// Writes everything written through it, with `indent` inserted after each line feed.
+struct Indenter<'a, W: Write + ?Sized> {
+    out: &'a mut W,
+    indent: &'a str,
+}
+
+impl<W: Write + ?Sized> Write for Indenter<'_, W> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        for (index, line) in string.split('\n').enumerate() {
+            if index > 0 {
+                self.out.write_str("\n")?;
+                self.out.write_str(self.indent)?;
+            }
+            self.out.write_str(line)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Debug> Debug for Pretty<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            Indenter {
+                out: f,
+                indent: Self::INDENT,
+            },
+            "{:#?}",
+            self.0
+        )
+    }
+}
+
+/// Adapts a [`ByteSink`] into a [`fmt::Write`].
+///
+/// With the `ufmt-write` feature, it also implements `ufmt_write::uWrite`, so that `ufmt::uwrite!` can write text
+/// to a sink without pulling in the `core::fmt` machinery; the statements themselves are still formatted with
+/// `core::fmt`.
+pub struct SinkWriter<'a, Sink: ?Sized>(pub &'a Sink);
+
+impl<Sink: ByteSink + ?Sized> Write for SinkWriter<'_, Sink> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        self.0.write_bytes(string.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ufmt-write")]
+impl<Sink: ByteSink + ?Sized> ufmt_write::uWrite for SinkWriter<'_, Sink> {
+    type Error = core::convert::Infallible;
+
+    fn write_str(&mut self, string: &str) -> Result<(), Self::Error> {
+        self.0.write_bytes(string.as_bytes());
+        Ok(())
+    }
+}
+
+/// Formats logging statements with a [`ULogFormatter`] and writes the resulting text to a [`ByteSink`],
+/// as it gets formatted.
+///
+/// ```
+/// use ulog::format::{FormattedLogger, TextFormatter};
+///
+/// let logger = FormattedLogger::new(TextFormatter::new(), |bytes: &[u8]| {
+///     print!("{}", core::str::from_utf8(bytes).unwrap());
+/// });
+///
+/// ulog::info!(logger, "Hello", "value" => 32);
+/// ```
+pub struct FormattedLogger<Formatter, Sink> {
+    formatter: Formatter,
+    sink: Sink,
+}
+
+impl<Formatter: ULogFormatter, Sink: ByteSink> FormattedLogger<Formatter, Sink> {
+    pub fn new(formatter: Formatter, sink: Sink) -> Self {
+        Self { formatter, sink }
+    }
+
+    pub fn formatter(&self) -> &Formatter {
+        &self.formatter
+    }
+
+    pub fn sink(&self) -> &Sink {
+        &self.sink
+    }
+
+    pub fn into_inner(self) -> (Formatter, Sink) {
+        (self.formatter, self.sink)
+    }
+}
+
+impl<Formatter: ULogFormatter, Sink: ByteSink> ULog for FormattedLogger<Formatter, Sink> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        let _ = self
+            .formatter
+            .format_str(&mut SinkWriter(&self.sink), log_data, string);
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        let _ = self
+            .formatter
+            .format_str_continued(&mut SinkWriter(&self.sink), log_data, string);
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: fmt::Arguments<'_>) {
+        let _ = self
+            .formatter
+            .format_fmt(&mut SinkWriter(&self.sink), log_data, args);
+    }
+
+    fn log_format<T: Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        let _ = self
+            .formatter
+            .format_kv(&mut SinkWriter(&self.sink), log_data, key, value);
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        let _ = self
+            .formatter
+            .format_value(&mut SinkWriter(&self.sink), log_data, key, value);
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        let _ = self
+            .formatter
+            .format_serialize(&mut SinkWriter(&self.sink), log_data, key, value);
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        let _ = self
+            .formatter
+            .format_begin(&mut SinkWriter(&self.sink), log_data);
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        let _ = self
+            .formatter
+            .format_end(&mut SinkWriter(&self.sink), log_data);
+        self.sink.end_frame();
+    }
+
+    fn flush(&self) {
+        self.sink.flush();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::buffer::BufferedLogger;
+    use crate::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_text_formatter() {
+        let output = RefCell::new(Vec::new());
+        let logger = FormattedLogger::new(TextFormatter::new(), |bytes: &[u8]| {
+            output.borrow_mut().extend_from_slice(bytes)
+        });
+
+        let line = line!() + 1;
+        warn!(logger, "Hello", "value" => 32, "name" => "world");
+
+        assert_eq!(
+            String::from_utf8(output.into_inner()).unwrap(),
+            format!("WARN src/format.rs:{line} Hello value=32 name=\"world\"\n")
+        );
+    }
+
+    #[test]
+    fn test_level_symbols() {
+        let output = RefCell::new(Vec::new());
+        let log = |formatter: TextFormatter| {
+            let logger = FormattedLogger::new(formatter, |bytes: &[u8]| {
+                output.borrow_mut().extend_from_slice(bytes)
+            });
+            let log_data = ULogData::new(ULogLevel::Warning, 1, "main.rs");
+            logger.log_begin(&log_data);
+            logger.log_str(&log_data, "Hello");
+            logger.log_end(&log_data);
+        };
+
+        log(TextFormatter::new().with_symbols(LevelSymbols::UNICODE));
+        log(TextFormatter::new()
+            .with_symbols(LevelSymbols::ASCII)
+            .with_level_text(false));
+        log(TextFormatter::new()
+            .with_symbols(LevelSymbols::ASCII.with_symbol(ULogLevel::Warning, "/!\\"))
+            .with_level_text(false));
+
+        assert_eq!(
+            String::from_utf8(output.into_inner()).unwrap(),
+            "⚠ WARN main.rs:1 Hello\n! main.rs:1 Hello\n/!\\ main.rs:1 Hello\n"
+        );
+    }
+
+    #[test]
+    fn test_json_formatter() {
+        let output = RefCell::new(Vec::new());
+        let logger = FormattedLogger::new(JsonFormatter::new(), |bytes: &[u8]| {
+            output.borrow_mut().extend_from_slice(bytes)
+        });
+
+        let line = line!() + 1;
+        error!(logger, "Hello\n\"world\"", "value" => 32, "name" => "a\tb");
+        info!(logger, "bye");
+
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            format!(
+                r#"{{"level":"ERROR","file":"src/format.rs","line":{line},"target":"ulog::format::test","msg":"Hello\n\"world\"","value":"32","name":"\"a\\tb\""}}"#
+            )
+        );
+        assert!(lines[1].ends_with(r#""msg":"bye"}"#));
+    }
+
+    #[test]
+    fn test_continued_strings() {
+        let output = RefCell::new(Vec::new());
+        let write = |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes);
+        let log = |logger: &dyn DynULog| {
+            let mut statement = logger.at(ULogLevel::Info).msg("read");
+            statement.write_str("\"de").unwrap();
+            statement.write_str("ad\"").unwrap();
+            statement.field("n", 2).emit();
+        };
+
+        log(&FormattedLogger::new(TextFormatter::new(), write));
+        log(&FormattedLogger::new(JsonFormatter::new(), write));
+        log(&FormattedLogger::new(LogfmtFormatter::new(), write));
+        log(&FormattedLogger::new(
+            CsvFormatter::new(&[CsvColumn::Message]),
+            write,
+        ));
+        log(&FormattedLogger::new(
+            TemplateFormatter::new(Template::new("> {msg} [{kv}]")),
+            write,
+        ));
+        log(&FormattedLogger::new(
+            ColumnFormatter::new().with_message_width(12),
+            write,
+        ));
+        log(&FormattedLogger::new(
+            CanonicalFormatter::<_, 128>::new(TextFormatter::new()),
+            write,
+        ));
+        let buffered =
+            BufferedLogger::<_, 1, 128>::new(FormattedLogger::new(TextFormatter::new(), write));
+        log(&buffered);
+        buffered.flush();
+
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert!(lines[0].ends_with(r#" read "dead" n=2"#));
+        assert!(lines[1].ends_with(r#""msg":"read \"dead\"","n":"2"}"#));
+        assert!(lines[2].ends_with(r#" msg="read \"dead\"" n=2"#));
+        assert_eq!(lines[3], r#""read ""dead""",2"#);
+        assert_eq!(lines[4], r#"> read "dead" [n=2]"#);
+        assert!(lines[5].ends_with(r#" read "dead"  n=2"#));
+        assert!(lines[6].ends_with(r#" read "dead" n=2"#));
+        assert!(lines[7].ends_with(r#" read "dead" n=2"#));
+    }
+
+    #[test]
+    fn test_format_args() {
+        let output = RefCell::new(Vec::new());
+        let write = |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes);
+        let name = "x".repeat(FMT_BUFFER_SIZE);
+        let log = |logger: &dyn DynULog| info!(logger, "\"{}\" is {} bytes long", name, name.len());
+
+        log(&FormattedLogger::new(TextFormatter::new(), write));
+        log(&FormattedLogger::new(JsonFormatter::new(), write));
+        log(&common::ChainLogger::new(
+            common::StubLogger,
+            FormattedLogger::new(LogfmtFormatter::new(), write),
+        ));
+
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert!(lines[0].ends_with(&format!(r#" "{name}" is 128 bytes long"#)));
+        assert!(lines[1].ends_with(&format!(r#""msg":"\"{name}\" is 128 bytes long"}}"#)));
+        assert!(lines[2].ends_with(&format!(r#" msg="\"{name}\" is 128 bytes long""#)));
+    }
+
+    #[test]
+    fn test_custom_level() {
+        use crate::test::Severity;
+
+        let output = RefCell::new(Vec::new());
+        let write = |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes);
+        let log = |logger: &dyn DynULog| ulog!(Severity::Audit, logger, "Badge read");
+
+        log(&FormattedLogger::new(TextFormatter::new(), write));
+        log(&FormattedLogger::new(JsonFormatter::new(), write));
+        log(&FormattedLogger::new(LogfmtFormatter::new(), write));
+        log(&FormattedLogger::new(
+            TemplateFormatter::new(Template::new("{level}/{level:short}: {msg}")),
+            write,
+        ));
+
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("AUDIT "));
+        assert!(lines[1].contains(r#""level":"AUDIT""#));
+        assert!(lines[2].starts_with("level=audit "));
+        assert_eq!(lines[3], "AUDIT/AUD: Badge read");
+    }
+
+    #[test]
+    fn test_logfmt_formatter() {
+        let output = RefCell::new(Vec::new());
+        let logger = FormattedLogger::new(LogfmtFormatter::new(), |bytes: &[u8]| {
+            output.borrow_mut().extend_from_slice(bytes)
+        });
+
+        let line = line!() + 1;
+        error!(logger, "boot failed", "code" => 5, "name" => "a b", "ratio" => 0.5);
+
+        assert_eq!(
+            String::from_utf8(output.into_inner()).unwrap(),
+            format!(
+                "level=error file=src/format.rs line={line} msg=\"boot failed\" code=5 name=\"\\\"a b\\\"\" ratio=0.5\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_logfmt_keys() {
+        let output = RefCell::new(Vec::new());
+        let logger = FormattedLogger::new(LogfmtFormatter::new(), |bytes: &[u8]| {
+            output.borrow_mut().extend_from_slice(bytes)
+        });
+
+        let log_data = ULogData::new(ULogLevel::Info, 1, "my src/main.rs");
+        logger.log_begin(&log_data);
+        logger.log_format(&log_data, "a=b", &1);
+        logger.log_format(&log_data, "two words", &2);
+        logger.log_format(&log_data, "", &3);
+        logger.log_end(&log_data);
+
+        assert_eq!(
+            String::from_utf8(output.into_inner()).unwrap(),
+            "level=info file=\"my src/main.rs\" line=1 \"a=b\"=1 \"two words\"=2 \"\"=3\n"
+        );
+    }
+
+    #[test]
+    fn test_bytes() {
+        let outputs = [(); 3].map(|_| RefCell::new(Vec::new()));
+        let sink = |index: usize| {
+            let output = &outputs[index];
+            move |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes)
+        };
+        let logger = FormattedLogger::new(TextFormatter::new(), sink(0))
+            .chain(FormattedLogger::new(LogfmtFormatter::new(), sink(1)))
+            .chain(FormattedLogger::new(JsonFormatter::new(), sink(2)));
+
+        logger
+            .at(ULogLevel::Info)
+            .msg("Received")
+            .bytes("payload", &[0xde, 0xad, 0x01])
+            .emit();
+
+        let [text, logfmt, json] =
+            outputs.map(|output| String::from_utf8(output.into_inner()).unwrap());
+        assert!(text.ends_with(" Received payload=dead01\n"));
+        assert!(logfmt.ends_with(" msg=\"Received\" payload=dead01\n"));
+        assert!(json
+            .trim_end()
+            .ends_with(r#""msg":"Received","payload":[222,173,1]}"#));
+    }
+
+    #[test]
+    fn test_csv_formatter() {
+        let output = RefCell::new(Vec::new());
+        let formatter =
+            CsvFormatter::new(&[CsvColumn::Level, CsvColumn::Message, CsvColumn::Timestamp])
+                .with_clock(|| Duration::from_millis(1250))
+                .with_header(&["value", "name"]);
+        let logger = FormattedLogger::new(formatter, |bytes: &[u8]| {
+            output.borrow_mut().extend_from_slice(bytes)
+        });
+
+        info!(logger, "Hello", "value" => 32, "name" => "a,b");
+        warn!(logger, "Say \"hi\"");
+        error!(logger, "Bye", "value" => 1);
+
+        assert_eq!(
+            String::from_utf8(output.into_inner()).unwrap(),
+            "level,msg,timestamp,value,name\n\
+            INFO,\"Hello\",1.250,32,\"\"\"a,b\"\"\"\n\
+            WARN,\"Say \"\"hi\"\"\",1.250\n\
+            ERROR,\"Bye\",1.250,1\n"
+        );
+    }
+
+    #[test]
+    fn test_ansi_formatter() {
+        let output = RefCell::new(Vec::new());
+        let sink = |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes);
+        let colored = FormattedLogger::new(AnsiFormatter::new(), &sink);
+        let whole_line = FormattedLogger::new(AnsiFormatter::new().with_whole_line(true), &sink);
+        let disabled = FormattedLogger::new(AnsiFormatter::new().with_colors(false), &sink);
+
+        let line = line!() + 1;
+        error!(colored, "Hello");
+        error!(whole_line, "Hello");
+        error!(disabled, "Hello");
+
+        assert_eq!(
+            String::from_utf8(output.into_inner()).unwrap(),
+            format!(
+                "\x1b[31mERROR\x1b[0m src/format.rs:{} Hello\n\
+                \x1b[31mERROR src/format.rs:{} Hello\x1b[0m\n\
+                ERROR src/format.rs:{} Hello\n",
+                line,
+                line + 1,
+                line + 2
+            )
+        );
+    }
+
+    #[test]
+    fn test_template_formatter() {
+        assert_eq!(
+            Template::try_new("{msg} {kv} {msg}"),
+            Err(TemplateError::MisplacedPlaceholder)
+        );
+        assert_eq!(
+            Template::try_new("{kv} {msg}"),
+            Err(TemplateError::MisplacedPlaceholder)
+        );
+        assert_eq!(
+            Template::try_new("{msg"),
+            Err(TemplateError::UnmatchedBrace)
+        );
+        assert_eq!(Template::try_new("}"), Err(TemplateError::UnmatchedBrace));
+        assert_eq!(
+            Template::try_new("{time}"),
+            Err(TemplateError::UnknownPlaceholder)
+        );
+
+        let output = RefCell::new(Vec::new());
+        let formatter = TemplateFormatter::new(Template::new(
+            "{{{level:short}}} {file}:{line} | {msg} [{kv}] {level} {target} {module}",
+        ));
+        let logger = FormattedLogger::new(formatter, |bytes: &[u8]| {
+            output.borrow_mut().extend_from_slice(bytes)
+        });
+
+        let line = line!() + 1;
+        warn!(logger, "Hello", "value" => 32, "name" => "world");
+        info!(target: "net", logger, "Bye");
+
+        assert_eq!(
+            String::from_utf8(output.into_inner()).unwrap(),
+            format!(
+                "{{WRN}} src/format.rs:{} | Hello [value=32 name=\"world\"] WARN ulog::format::test ulog::format::test\n\
+                {{INF}} src/format.rs:{} | Bye [] INFO net ulog::format::test\n",
+                line,
+                line + 1
+            )
+        );
+    }
+
+    #[test]
+    fn test_column_formatter() {
+        let output = RefCell::new(Vec::new());
+        let logger = FormattedLogger::new(
+            ColumnFormatter::new()
+                .with_level_width(5)
+                .with_location_width(12)
+                .with_message_width(8),
+            |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes),
+        );
+
+        let log_data = ULogData::new(ULogLevel::Warning, 7, "main.rs");
+        logger.log_begin(&log_data);
+        logger.log_str(&log_data, "Hello");
+        logger.log_format(&log_data, "value", &32);
+        logger.log_end(&log_data);
+
+        let log_data = ULogData::new(ULogLevel::Critical, 1234, "src/format.rs");
+        logger.log_begin(&log_data);
+        logger.log_str(&log_data, "Hello");
+        logger.log_str(&log_data, "world");
+        logger.log_end(&log_data);
+
+        let log_data = ULogData::new(ULogLevel::Info, 1, "a.rs");
+        logger.log_begin(&log_data);
+        logger.log_str(&log_data, "12345678");
+        logger.log_end(&log_data);
+
+        assert_eq!(
+            String::from_utf8(output.into_inner()).unwrap(),
+            "WARN  main.rs:7    Hello    value=32\n\
+             CRIT… …mat.rs:1234 Hello w…\n\
+             INFO  a.rs:1       12345678\n"
+        );
+
+        let output = RefCell::new(Vec::new());
+        let logger = FormattedLogger::new(
+            ColumnFormatter::new()
+                .with_level_width(5)
+                .with_location_width(4)
+                .with_message_width(4)
+                .with_truncation(false),
+            |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes),
+        );
+        error!(logger, "Overflow", "value" => 1);
+
+        assert!(String::from_utf8(output.into_inner())
+            .unwrap()
+            .ends_with(" Overflow value=1\n"));
+    }
+
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn test_column_formatter_wide_chars() {
+        let output = RefCell::new(Vec::new());
+        let logger = FormattedLogger::new(
+            ColumnFormatter::new()
+                .with_level_width(4)
+                .with_location_width(8)
+                .with_message_width(8),
+            |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes),
+        );
+
+        let log_data = ULogData::new(ULogLevel::Info, 1, "日本.rs");
+        for message in ["日本語テキスト", "日本語テ", "👋 hi"] {
+            logger.log_begin(&log_data);
+            logger.log_str(&log_data, message);
+            logger.log_format(&log_data, "v", &1);
+            logger.log_end(&log_data);
+        }
+
+        assert_eq!(
+            String::from_utf8(output.into_inner()).unwrap(),
+            "INFO …本.rs:1 日本語…  v=1\n\
+             INFO …本.rs:1 日本語テ v=1\n\
+             INFO …本.rs:1 👋 hi    v=1\n"
+        );
+    }
+
+    #[test]
+    fn test_canonical_formatter() {
+        let output = RefCell::new(Vec::new());
+        let logger = FormattedLogger::new(
+            CanonicalFormatter::<_, 128>::new(TextFormatter::new()),
+            |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes),
+        );
+
+        let log_data = ULogData::new(ULogLevel::Info, 1, "main.rs");
+        logger.log_begin(&log_data);
+        logger.log_str(&log_data, "Hello");
+        logger.log_format(&log_data, "b", &1.5);
+        logger.log_format(&log_data, "a", &"x");
+        logger.log_str(&log_data, "world");
+        logger.log_format(&log_data, "c", &-2);
+        logger.log_format(&log_data, "a", &Some(3));
+        logger.log_end(&log_data);
+
+        assert_eq!(
+            String::from_utf8(output.into_inner()).unwrap(),
+            "INFO main.rs:1 Hello world a=\"x\" a=Some(3) b=1.5 c=-2\n"
+        );
+    }
+
+    #[test]
+    fn test_sanitizing_formatter() {
+        let output = RefCell::new(Vec::new());
+        let logger = FormattedLogger::new(
+            SanitizingFormatter::new(TextFormatter::new()),
+            |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes),
+        );
+        let log_data = ULogData::new(ULogLevel::Info, 1, "main.rs");
+        logger.log_begin(&log_data);
+        logger.log_str(&log_data, "user\nERROR main.rs:1 forged");
+        logger.log_format(&log_data, "name", &Verbatim("\x1b[2J\u{202e}"));
+        logger.log_end(&log_data);
+
+        let logger = FormattedLogger::new(
+            SanitizingFormatter::new(TextFormatter::new()).with_mode(SanitizeMode::Strip),
+            |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes),
+        );
+        logger.log_begin(&log_data);
+        logger.log_str(&log_data, "a\r\tb");
+        logger.log_end(&log_data);
+
+        assert_eq!(
+            String::from_utf8(output.into_inner()).unwrap(),
+            "INFO main.rs:1 user\\nERROR main.rs:1 forged name=\\u{1b}[2J\\u{202e}\n\
+             INFO main.rs:1 ab\n"
+        );
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        assert_eq!(format!("{:?}", HexDump(&[])), "");
+        assert_eq!(
+            format!("{:?}", HexDump(b"Hello, world!\x00\xff\n\x01\x02")),
+            "0000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 00 ff 0a  |Hello, world!...|\n\
+            0010  01 02                                             |..|"
+        );
+    }
+
+    #[test]
+    fn test_fixed() {
+        assert_eq!(Fixed(-1205, 2).to_string(), "-12.05");
+        assert_eq!(Fixed(-5, 3).to_string(), "-0.005");
+        assert_eq!(Fixed(42, 0).to_string(), "42");
+        assert_eq!(
+            format!("{:?}", Fixed(i64::MIN, 30)),
+            "-0.9223372036854775808"
+        );
+        assert_eq!(Millivolts(3300).to_string(), "3.300 V");
+        assert_eq!(format!("{:?}", Millicelsius(-250)), "-0.250 °C");
+    }
+
+    #[test]
+    fn test_pretty() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct Config {
+            name: &'static str,
+            period: u32,
+        }
+
+        let output = RefCell::new(Vec::new());
+        let logger = FormattedLogger::new(TextFormatter::new(), |bytes: &[u8]| {
+            output.borrow_mut().extend_from_slice(bytes)
+        });
+
+        let config = Config {
+            name: "sensor",
+            period: 10,
+        };
+        info!(logger, "Loaded", "config" => Pretty(&config), "period" => Pretty(10));
+
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        assert!(output.ends_with(
+            " Loaded config=Config {\n        name: \"sensor\",\n        period: 10,\n    } period=10\n"
+        ));
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let output = RefCell::new(Vec::new());
+        let sink = |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes);
+        let clock = || Duration::from_millis(1_700_000_000_123);
+        let text = FormattedLogger::new(TextFormatter::new().with_clock(clock), &sink);
+        let json = FormattedLogger::new(JsonFormatter::new().with_clock(clock), &sink);
+
+        info!(text, "Hello");
+        info!(json, "Hello");
+
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("2023-11-14T22:13:20.123Z INFO src/format.rs:"));
+        assert!(lines[1].starts_with(r#"{"timestamp":"2023-11-14T22:13:20.123Z","level":"INFO","#));
+    }
+
+    #[test]
+    fn test_json_nested_keys() {
+        let output = RefCell::new(Vec::new());
+        let logger = FormattedLogger::new(
+            JsonFormatter::new().with_nested_keys(true),
+            |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes),
+        );
+
+        let log_data = ULogData::new(ULogLevel::Info, 1, "main.rs");
+        logger.log_begin(&log_data);
+        logger.log_str(&log_data, "Hello");
+        logger.log_value(&log_data, "net.rx.bytes", Value::from(1));
+        logger.log_value(&log_data, "net.rx.packets", Value::from(2));
+        logger.log_value(&log_data, "net.tx", Value::from(3));
+        logger.log_format(&log_data, "host", &"a");
+        logger.log_value(&log_data, "net.up", Value::from(true));
+        logger.log_end(&log_data);
+        logger.log_begin(&log_data);
+        logger.log_value(&log_data, "net.rx.a", Value::from(1));
+        logger.log_value(&log_data, "net.tx.b", Value::from(2));
+        logger.log_value(&log_data, "net.rx.c", Value::from(3));
+        logger.log_value(&log_data, "net.tx.d.e", Value::from(4));
+        logger.log_value(&log_data, "net.tx.f.g", Value::from(5));
+        logger.log_end(&log_data);
+
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            r#"{"level":"INFO","file":"main.rs","line":1,"msg":"Hello","net":{"rx":{"bytes":1,"packets":2},"tx":3},"host":"\"a\"","net.up":true}"#
+        );
+        // Objects closed earlier in the statement aren't reopened
+        assert_eq!(
+            lines[1],
+            r#"{"level":"INFO","file":"main.rs","line":1,"net":{"rx":{"a":1},"tx":{"b":2},"rx.c":3,"tx.d.e":4,"tx.f.g":5}}"#
+        );
+    }
+
+    #[test]
+    fn test_json_values() {
+        let output = RefCell::new(Vec::new());
+        let logger = FormattedLogger::new(JsonFormatter::new(), |bytes: &[u8]| {
+            output.borrow_mut().extend_from_slice(bytes)
+        });
+
+        let log_data = ULogData::new(ULogLevel::Info, 1, "main.rs");
+        logger.log_begin(&log_data);
+        logger.log_str(&log_data, "Hello");
+        logger.log_value(&log_data, "int", Value::from(-3));
+        logger.log_value(&log_data, "float", Value::from(0.5));
+        logger.log_value(&log_data, "nan", Value::from(f64::NAN));
+        logger.log_value(&log_data, "bool", Value::from(true));
+        logger.log_value(&log_data, "str", Value::from("a\"b"));
+        logger.log_value(&log_data, "bytes", Value::from(&[1u8, 2][..]));
+        logger.log_end(&log_data);
+
+        assert_eq!(
+            String::from_utf8(output.into_inner()).unwrap(),
+            r#"{"level":"INFO","file":"main.rs","line":1,"msg":"Hello","int":-3,"float":0.5,"nan":null,"bool":true,"str":"a\"b","bytes":[1,2]}"#
+                .to_string()
+                + "\n"
+        );
+    }
+
+    #[test]
+    fn test_json_reserved_keys() {
+        let output = RefCell::new(Vec::new());
+        let sink = |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes);
+        let flat = FormattedLogger::new(JsonFormatter::new(), &sink);
+        let nested = FormattedLogger::new(JsonFormatter::new().with_nested_keys(true), &sink);
+
+        let log_data = ULogData::new(ULogLevel::Info, 1, "main.rs");
+        for logger in [&flat, &nested] {
+            logger.log_begin(&log_data);
+            logger.log_str(&log_data, "Hello");
+            logger.log_value(&log_data, "msg", Value::from("world"));
+            logger.log_value(&log_data, "level.max", Value::from(3));
+            logger.log_value(&log_data, "net.msg", Value::from(4));
+            logger.log_end(&log_data);
+        }
+
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            r#"{"level":"INFO","file":"main.rs","line":1,"msg":"Hello","fields.msg":"world","level.max":3,"net.msg":4}"#
+        );
+        assert_eq!(
+            lines[1],
+            r#"{"level":"INFO","file":"main.rs","line":1,"msg":"Hello","fields.msg":"world","fields.level.max":3,"net":{"msg":4}}"#
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_serialize() {
+        let output = RefCell::new(Vec::new());
+        let logger = FormattedLogger::new(JsonFormatter::new(), |bytes: &[u8]| {
+            output.borrow_mut().extend_from_slice(bytes)
+        });
+
+        let readings = std::collections::BTreeMap::from([("a", vec![1, 2]), ("b", vec![])]);
+        info!(logger, "Hello", "readings" =># readings, "count" => 2);
+
+        let line = line!() - 2;
+        assert_eq!(
+            String::from_utf8(output.into_inner()).unwrap(),
+            format!(
+                r#"{{"level":"INFO","file":"src/format.rs","line":{line},"target":"ulog::format::test","msg":"Hello","readings":{{"a":[1,2],"b":[]}},"count":"2"}}"#
+            ) + "\n"
+        );
+    }
+}
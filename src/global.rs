@@ -0,0 +1,229 @@
+use super::atomic;
+use super::common::StubLogger;
+#[cfg(feature = "serde")]
+use super::serialize::SerializeJson;
+use super::time::ULogClock;
+use super::{DynULog, ULog, ULogData, Value};
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(not(any(target_has_atomic = "ptr", feature = "critical-section")))]
+compile_error!(
+    "the `global` feature requires the `critical-section` feature on targets without compare-and-swap"
+);
+
+const UNSET: usize = 0;
+const SETTING: usize = 1;
+const SET: usize = 2;
+
+static STATE: AtomicUsize = AtomicUsize::new(UNSET);
+
+struct Slot(UnsafeCell<&'static (dyn DynULog + Sync)>);
+
+// The slot is only written once, while `STATE` is `SETTING`, and only read once `STATE` is `SET`
+unsafe impl Sync for Slot {}
+
+static LOGGER: Slot = Slot(UnsafeCell::new(&StubLogger));
+
+/// The error returned by [`set_global_logger`] when a global logger was already set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GlobalLoggerAlreadySet;
+
+impl fmt::Display for GlobalLoggerAlreadySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a global logger was already set")
+    }
+}
+
+/// Sets the logger used by the macros when no logger is given, as in `info!("message")`.
+///
+/// The global logger can only be set once; until then, statements sent to it are discarded.
+/// On targets without atomic compare-and-swap, like `thumbv6m-none-eabi`, it is set in a critical section.
+pub fn set_global_logger(
+    logger: &'static (dyn DynULog + Sync),
+) -> Result<(), GlobalLoggerAlreadySet> {
+    atomic::compare_exchange(&STATE, UNSET, SETTING).map_err(|_| GlobalLoggerAlreadySet)?;
+
+    unsafe {
+        *LOGGER.0.get() = logger;
+    }
+    STATE.store(SET, Ordering::Release);
+
+    Ok(())
+}
+
+/// Returns the global logger, or a [`StubLogger`] if none was set yet.
+pub fn global_logger() -> &'static (dyn DynULog + Sync) {
+    if STATE.load(Ordering::Acquire) == SET {
+        unsafe { *LOGGER.0.get() }
+    } else {
+        &StubLogger
+    }
+}
+
+static CLOCK_STATE: AtomicUsize = AtomicUsize::new(UNSET);
+
+struct ClockSlot(UnsafeCell<Option<&'static (dyn ULogClock + Sync)>>);
+
+// Same as `Slot`, with `CLOCK_STATE`
+unsafe impl Sync for ClockSlot {}
+
+static CLOCK: ClockSlot = ClockSlot(UnsafeCell::new(None));
+
+/// The error returned by [`set_global_clock`] when a global clock was already set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GlobalClockAlreadySet;
+
+impl fmt::Display for GlobalClockAlreadySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a global clock was already set")
+    }
+}
+
+/// Sets the clock timestamping every statement when its [`ULogData`] is created, so that all of the sinks
+/// and formatters see the same [`timestamp`](ULogData::timestamp), taken when the statement was made.
+///
+/// The global clock can only be set once; formatters printing timestamps as dates expect it to return
+/// the time elapsed since the Unix epoch.
+///
+/// ```
+/// use core::time::Duration;
+/// use ulog::global::set_global_clock;
+/// use ulog::{ULogData, ULogLevel};
+///
+/// fn uptime() -> Duration {
+///     Duration::ZERO
+/// }
+///
+/// set_global_clock(&uptime).unwrap();
+/// assert!(ULogData::new(ULogLevel::Info, line!(), file!()).timestamp.is_some());
+/// ```
+pub fn set_global_clock(
+    clock: &'static (dyn ULogClock + Sync),
+) -> Result<(), GlobalClockAlreadySet> {
+    atomic::compare_exchange(&CLOCK_STATE, UNSET, SETTING).map_err(|_| GlobalClockAlreadySet)?;
+
+    unsafe {
+        *CLOCK.0.get() = Some(clock);
+    }
+    CLOCK_STATE.store(SET, Ordering::Release);
+
+    Ok(())
+}
+
+/// Returns the global clock, if one was set.
+pub fn global_clock() -> Option<&'static (dyn ULogClock + Sync)> {
+    if CLOCK_STATE.load(Ordering::Acquire) == SET {
+        unsafe { *CLOCK.0.get() }
+    } else {
+        None
+    }
+}
+
+/// A logger forwarding statements to the [global logger](global_logger), used by the macros when no logger is given.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GlobalLogger;
+
+impl ULog for GlobalLogger {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        global_logger().log_str_dyn(log_data, string)
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        global_logger().log_str_continued_dyn(log_data, string)
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: fmt::Arguments<'_>) {
+        global_logger().log_fmt_dyn(log_data, args)
+    }
+
+    fn log_format<T: fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        global_logger().log_format_dyn(log_data, key, value)
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        global_logger().log_value_dyn(log_data, key, value)
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        global_logger().log_bytes_dyn(log_data, key, bytes)
+    }
+
+    fn log_display<T: fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        global_logger().log_display_dyn(log_data, key, value)
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        global_logger().log_serialize_dyn(log_data, key, value)
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        global_logger().log_begin_dyn(log_data)
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        global_logger().log_end_dyn(log_data)
+    }
+
+    fn is_available(&self) -> bool {
+        global_logger().is_available_dyn()
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        global_logger().enabled_dyn(log_data)
+    }
+
+    fn flush(&self) {
+        global_logger().flush_dyn()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ULogLevel;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct SyncLogger {
+        logs: Mutex<Vec<String>>,
+    }
+
+    impl ULog for SyncLogger {
+        fn log_str(&self, _log_data: &ULogData, string: &str) {
+            self.logs.lock().unwrap().push(string.to_string());
+        }
+
+        fn log_format<T: fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+            self.logs
+                .lock()
+                .unwrap()
+                .push(format!("{key} => {value:?}"));
+        }
+
+        fn log_begin(&self, log_data: &ULogData) {
+            self.logs.lock().unwrap().push(log_data.level.to_string());
+        }
+
+        fn log_end(&self, _log_data: &ULogData) {}
+    }
+
+    #[test]
+    fn test_global_logger() {
+        crate::info!("Discarded");
+
+        let logger: &'static SyncLogger = Box::leak(Box::default());
+        assert_eq!(set_global_logger(logger), Ok(()));
+        assert_eq!(set_global_logger(&StubLogger), Err(GlobalLoggerAlreadySet));
+
+        crate::info!("Hello");
+        crate::ulog!(ULogLevel::Warning, "read {} bytes", 12; "port" => 1);
+
+        assert_eq!(
+            &logger.logs.lock().unwrap()[..],
+            &["INFO", "Hello", "WARN", "read 12 bytes", "port => 1"]
+        );
+    }
+}
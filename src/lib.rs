@@ -1,23 +1,340 @@
 #![doc = include_str!("../README.md")]
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
 /// Contains some common loggers.
 pub mod common;
 
-#[derive(Clone, Debug, PartialEq, Copy, PartialOrd, Eq, Ord)]
+/// Contains the identification of the threads and cores statements are made on.
+pub mod thread;
+
+/// Contains utilities to capture logging statements into fixed-size buffers.
+pub mod record;
+
+/// Contains loggers that buffer logging statements before forwarding them.
+pub mod buffer;
+
+/// Contains a lock-free queue of statements, which tasks and interrupts of any priority can log to,
+/// requires atomic compare-and-swap or the `critical-section` feature.
+#[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+pub mod lock_free;
+
+mod atomic;
+#[doc(hidden)]
+pub use atomic::{count_hit as __count_hit, first_hit as __first_hit};
+
+/// Contains byte-oriented sinks, and loggers serializing statements into them.
+pub mod sink;
+
+/// Contains formatters laying statements out as text, and a logger combining them with sinks.
+pub mod format;
+
+/// Contains loggers and sinks that can report their failures, and adapters handling them.
+pub mod fallible;
+
+/// Contains a trait for loggers taking `&mut self`, and adapters sharing them behind a lock.
+pub mod mutable;
+
+/// Contains a trait for asynchronous loggers, and a queue feeding them from synchronous code,
+/// requires the `async` feature.
+#[cfg(feature = "async")]
+pub mod asynchronous;
+
+/// Contains a compact binary encoding of statements, requires the `binary` feature.
+#[cfg(feature = "binary")]
+pub mod binary;
+
+/// Contains clocks and time-related loggers.
+pub mod time;
+
+/// Contains guards logging when a scope is entered and exited, and how long it took.
+pub mod scope;
+
+/// Contains a method-chaining alternative to the logging macros.
+pub mod builder;
+
+/// Contains extension traits logging the errors of results and the absence of options.
+pub mod ext;
+
+/// Contains conversions between levels and syslog severities, and syslog facilities and priorities.
+pub mod syslog;
+
+/// Contains a logger forwarding statements to a runtime list of loggers, requires the `alloc` feature.
+#[cfg(feature = "alloc")]
+pub mod broadcast;
+
+/// Contains a panic hook forwarding panics to a logger, requires the `std` feature.
+#[cfg(feature = "std")]
+pub mod panic;
+
+/// Contains a terminal logger cooperating with status lines, requires the `std` feature.
+#[cfg(feature = "std")]
+pub mod terminal;
+
+/// Contains a logger handing statements over to a worker thread, requires the `std` feature.
+#[cfg(feature = "std")]
+pub mod channel;
+
+/// Contains a decoder for the binary format, requires the `std` and `binary` features.
+#[cfg(all(feature = "std", feature = "binary"))]
+pub mod decoder;
+
+/// Contains the serialization of [`Serialize`](serde::Serialize) values to JSON, and the serde implementations
+/// of [`ULogLevel`] and [`ULogData`], requires the `serde` feature.
+#[cfg(feature = "serde")]
+pub mod serialize;
+
+/// Contains an optional global logger, used by the macros when no logger is given, requires the `global` feature.
+#[cfg(feature = "global")]
+pub mod global;
+
+/// Contains macros following the syntax of the `log` crate, sending statements to the global logger,
+/// requires the `global` feature.
+///
+/// Code written against `log` can be migrated by replacing `use log::{info, warn};` with
+/// `use ulog::compat::{info, warn};`: the message is always a format string, and the target defaults to the
+/// module path, as with `log`.
+///
+/// ```
+/// use ulog::compat::{info, log, warn, Level};
+/// use ulog::{ULog, ULogData};
+/// use std::sync::Mutex;
+///
+/// #[derive(Default)]
+/// struct Logs(Mutex<Vec<String>>);
+///
+/// impl ULog for Logs {
+///     fn log_str(&self, log_data: &ULogData, string: &str) {
+///         self.0.lock().unwrap().push(format!("{} {string}", log_data.level));
+///     }
+///
+///     fn log_format<T: std::fmt::Debug>(&self, _log_data: &ULogData, _key: &str, _value: &T) {}
+///
+///     fn log_begin(&self, _log_data: &ULogData) {}
+///
+///     fn log_end(&self, _log_data: &ULogData) {}
+/// }
+///
+/// let logger: &'static Logs = Box::leak(Box::default());
+/// ulog::global::set_global_logger(logger).unwrap();
+///
+/// let items = 3;
+/// info!("{} items", items);
+/// warn!(target: "storage", "{items} items left");
+/// log!(Level::Debug, "{{done}}");
+///
+/// assert_eq!(
+///     &logger.0.lock().unwrap()[..],
+///     ["INFO 3 items", "WARN 3 items left", "DEBUG {done}"]
+/// );
+/// ```
+#[cfg(feature = "global")]
+pub mod compat;
+
+/// Contains the bridges between ulog and the `log` crate, requires the `log` feature.
+#[cfg(feature = "log")]
+pub mod log_bridge;
+
+/// Contains the bridges between ulog and the `tracing` crate, requires the `tracing` feature.
+#[cfg(feature = "tracing")]
+pub mod tracing_bridge;
+
+/// Contains the bridge between ulog and the `slog` crate, requires the `slog` feature.
+#[cfg(feature = "slog")]
+pub mod slog_bridge;
+
+/// Contains a logger forwarding statements to the `defmt` macros, requires the `defmt-forward` feature.
+#[cfg(feature = "defmt-forward")]
+pub mod defmt_forward;
+
+/// Contains a logger queueing statements in an Embassy channel, and a clock reading the Embassy time,
+/// requires the `embassy` feature.
+#[cfg(feature = "embassy")]
+pub mod embassy;
+
+/// Contains the catalog of the call sites of the macros, stored in a linker section, requires the `catalog` feature.
+#[cfg(feature = "catalog")]
+pub mod catalog;
+
+/// Logs when a function is entered and when it returns, requires the `macros` feature.
+///
+/// The logger is given as `logger = <expr>`, evaluated for each statement; `level = <level>` defaults to `Debug`,
+/// `fields(a, b)` lists the arguments to log on entry and `ret` logs the returned value on exit.
+///
+/// ```
+/// use ulog::{instrument, ULog, ULogData};
+/// use std::cell::RefCell;
+///
+/// #[derive(Default)]
+/// struct Logs(RefCell<Vec<String>>);
+///
+/// impl ULog for Logs {
+///     fn log_str(&self, _log_data: &ULogData, string: &str) {
+///         self.0.borrow_mut().push(string.to_string());
+///     }
+///
+///     fn log_format<T: std::fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+///         self.0.borrow_mut().push(format!("{key} => {value:?}"));
+///     }
+///
+///     fn log_begin(&self, _log_data: &ULogData) {}
+///
+///     fn log_end(&self, _log_data: &ULogData) {}
+/// }
+///
+/// struct Sensor {
+///     logger: Logs,
+///     offset: i32,
+/// }
+///
+/// impl Sensor {
+///     #[instrument(logger = self.logger, level = Info, fields(raw), ret)]
+///     fn calibrate(&mut self, raw: i32) -> i32 {
+///         self.offset += 1;
+///         raw + self.offset
+///     }
+/// }
+///
+/// let mut sensor = Sensor { logger: Logs::default(), offset: 0 };
+/// assert_eq!(sensor.calibrate(41), 42);
+/// assert_eq!(
+///     sensor.logger.0.into_inner(),
+///     ["calibrate", "span => enter", "raw => 41", "calibrate", "span => exit", "return => 42"]
+/// );
+/// ```
+#[cfg(feature = "macros")]
+pub use ulog_macros::instrument;
+
+/// Implements [`ULog`] for a struct by forwarding every method to the field marked with `#[ulog(delegate)]`,
+/// requires the `macros` feature.
+///
+/// ```
+/// use ulog::common::StubLogger;
+///
+/// #[derive(ulog::ULog)]
+/// struct Device<Logger> {
+///     id: u32,
+///     #[ulog(delegate)]
+///     logger: Logger,
+/// }
+///
+/// let device = Device { id: 1, logger: StubLogger };
+/// ulog::info!(device, "Device ready", "id" => device.id);
+/// ```
+#[cfg(feature = "macros")]
+pub use ulog_macros::ULog;
+
+/// Removes the statements below a level from an item, such as an inline module, at compile time,
+/// requires the `macros` feature.
+///
+/// The level is a [`ULogLevel`] variant, or `Off` to remove every statement; removed statements cost nothing
+/// at runtime or in the binary, and their arguments are not evaluated.
+///
+/// ```
+/// use ulog::{module_level, ULog, ULogData};
+/// use std::cell::RefCell;
+///
+/// #[derive(Default)]
+/// struct Logs(RefCell<Vec<String>>);
+///
+/// impl ULog for Logs {
+///     fn log_str(&self, _log_data: &ULogData, string: &str) {
+///         self.0.borrow_mut().push(string.to_string());
+///     }
+///
+///     fn log_format<T: std::fmt::Debug>(&self, _log_data: &ULogData, _key: &str, _value: &T) {}
+///
+///     fn log_begin(&self, _log_data: &ULogData) {}
+///
+///     fn log_end(&self, _log_data: &ULogData) {}
+/// }
+///
+/// #[module_level(Warning)]
+/// mod radio {
+///     use ulog::ULogLevel;
+///
+///     pub fn poll(logger: &impl ulog::ULog) {
+///         ulog::debug!(logger, "polling");
+///         ulog::ulog!(ULogLevel::Info, logger, "idle");
+///         ulog::warn!(logger, "noisy channel");
+///     }
+/// }
+///
+/// let logger = Logs::default();
+/// radio::poll(&logger);
+/// assert_eq!(logger.0.into_inner(), ["noisy channel"]);
+/// ```
+#[cfg(feature = "macros")]
+pub use ulog_macros::module_level;
+
+/// The level of a logging statement.
+///
+/// Levels have a stable numerical representation, from 0 for [`Trace`](ULogLevel::Trace) to 5 for
+/// [`Critical`](ULogLevel::Critical), which can be obtained with [`as_u8`](ULogLevel::as_u8) and converted back with
+/// `TryFrom<u8>`; it can be carried over binary protocols or stored in flash, and will not change.
+/// [`Notice`](ULogLevel::Notice), which was added later, is 6, while being ordered between `Info` and `Warning`.
+#[derive(Clone, Debug, PartialEq, Copy, Eq)]
+#[repr(u8)]
 pub enum ULogLevel {
-    Debug,
-    Info,
-    Warning,
-    Error,
-    Critical,
+    /// Very verbose statements, like the ones logged on every iteration of a loop.
+    Trace = 0,
+    Debug = 1,
+    Info = 2,
+    Warning = 3,
+    Error = 4,
+    Critical = 5,
+    /// Normal but significant events, like operational changes; matches the notice severity of syslog.
+    Notice = 6,
+}
+
+impl PartialOrd for ULogLevel {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ULogLevel {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+/// The error returned when converting an invalid number into a [`ULogLevel`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidLevel(pub u8);
+
+impl core::fmt::Display for InvalidLevel {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "invalid level {}", self.0)
+    }
+}
+
+impl TryFrom<u8> for ULogLevel {
+    type Error = InvalidLevel;
+
+    fn try_from(value: u8) -> Result<Self, InvalidLevel> {
+        ULogLevel::all_levels()
+            .into_iter()
+            .find(|level| level.as_u8() == value)
+            .ok_or(InvalidLevel(value))
+    }
+}
+
+impl From<ULogLevel> for u8 {
+    fn from(level: ULogLevel) -> Self {
+        level.as_u8()
+    }
 }
 
 impl From<&ULogLevel> for &'static str {
     fn from(value: &ULogLevel) -> Self {
         match value {
+            ULogLevel::Trace => "TRACE",
             ULogLevel::Debug => "DEBUG",
             ULogLevel::Info => "INFO",
+            ULogLevel::Notice => "NOTICE",
             ULogLevel::Warning => "WARN",
             ULogLevel::Error => "ERROR",
             ULogLevel::Critical => "CRITICAL",
@@ -38,6 +355,24 @@ impl From<ULogLevel> for &'static str {
 }
 
 impl ULogLevel {
+    /// Returns the numerical representation of the level, see [`ULogLevel`].
+    pub const fn as_u8(&self) -> u8 {
+        *self as u8
+    }
+
+    /// The position of the level in ascending order.
+    const fn rank(&self) -> u8 {
+        match self {
+            ULogLevel::Trace => 0,
+            ULogLevel::Debug => 1,
+            ULogLevel::Info => 2,
+            ULogLevel::Notice => 3,
+            ULogLevel::Warning => 4,
+            ULogLevel::Error => 5,
+            ULogLevel::Critical => 6,
+        }
+    }
+
     /// Converts the level name to an uppercase string.
     pub fn as_str(&self) -> &'static str {
         self.into()
@@ -46,8 +381,10 @@ impl ULogLevel {
     /// Converts the level name to a short, 3-character uppercase string.
     pub fn as_short_str(&self) -> &'static str {
         match self {
+            ULogLevel::Trace => "TRC",
             ULogLevel::Debug => "DBG",
             ULogLevel::Info => "INF",
+            ULogLevel::Notice => "NTC",
             ULogLevel::Warning => "WRN",
             ULogLevel::Error => "ERR",
             ULogLevel::Critical => "CRT",
@@ -55,10 +392,12 @@ impl ULogLevel {
     }
 
     /// A list of all possible log levels, in ascending order; useful for testing.
-    pub fn all_levels() -> [ULogLevel; 5] {
+    pub const fn all_levels() -> [ULogLevel; 7] {
         [
+            ULogLevel::Trace,
             ULogLevel::Debug,
             ULogLevel::Info,
+            ULogLevel::Notice,
             ULogLevel::Warning,
             ULogLevel::Error,
             ULogLevel::Critical,
@@ -66,21 +405,288 @@ impl ULogLevel {
     }
 }
 
+/// A level type, which domains with their own severities can implement to log statements at these severities
+/// and to filter them, with [`ulog!`] and [`MinLevelLogger`](common::MinLevelLogger) for instance.
+///
+/// Statements are carried with the [`ULogLevel`] returned by [`to_ulog_level`](Level::to_ulog_level), which
+/// filters compare, and with the names of the level, which formatters print: the statement below is kept by the
+/// logger as [`Critical`](ULogLevel::Critical) is above [`Warning`](ULogLevel::Warning), and printed as `ALARM`.
+///
+/// ```
+/// use ulog::{Level, ULog, ULogLevel};
+///
+/// #[derive(Clone, Copy)]
+/// enum Severity {
+///     Notice,
+///     Audit,
+///     Alarm,
+/// }
+///
+/// impl Level for Severity {
+///     fn as_str(&self) -> &'static str {
+///         match self {
+///             Severity::Notice => "NOTICE",
+///             Severity::Audit => "AUDIT",
+///             Severity::Alarm => "ALARM",
+///         }
+///     }
+///
+///     fn to_ulog_level(&self) -> ULogLevel {
+///         match self {
+///             Severity::Notice => ULogLevel::Info,
+///             Severity::Audit => ULogLevel::Warning,
+///             Severity::Alarm => ULogLevel::Critical,
+///         }
+///     }
+/// }
+///
+/// let logger = ulog::common::StubLogger.min_level(Severity::Audit);
+/// ulog::ulog!(Severity::Alarm, logger, "Door opened");
+///
+/// let log_data = ulog::ULogData::new(Severity::Alarm, 42, "src/door.rs");
+/// assert_eq!((log_data.level, log_data.level_name), (ULogLevel::Critical, "ALARM"));
+/// ```
+pub trait Level: Copy {
+    /// The name of the level, in uppercase.
+    fn as_str(&self) -> &'static str;
+
+    /// A short name for the level, defaults to [`as_str`](Level::as_str).
+    fn as_short_str(&self) -> &'static str {
+        self.as_str()
+    }
+
+    /// The standard level that statements at this level are logged with.
+    fn to_ulog_level(&self) -> ULogLevel;
+}
+
+impl Level for ULogLevel {
+    fn as_str(&self) -> &'static str {
+        ULogLevel::as_str(self)
+    }
+
+    fn as_short_str(&self) -> &'static str {
+        ULogLevel::as_short_str(self)
+    }
+
+    fn to_ulog_level(&self) -> ULogLevel {
+        *self
+    }
+}
+
 /// Contains data to be used when logging.
+///
+/// As more fields may be added, it is created with [`new`](ULogData::new) and completed with the `with_*` methods,
+/// which lets custom macros and bridges from other logging crates fill in everything they know about:
+///
+/// ```
+/// use ulog::{ULogData, ULogLevel};
+///
+/// let log_data = ULogData::new(ULogLevel::Warning, 42, "src/radio.rs")
+///     .with_column(9)
+///     .with_module("app::radio")
+///     .with_target("radio");
+/// assert_eq!(log_data.target, Some("radio"));
+/// ```
 #[non_exhaustive]
 #[derive(Clone, Debug)]
 pub struct ULogData {
     pub level: ULogLevel,
+    /// The name of the level the statement was made at, as given by [`Level::as_str`], which formatters print
+    /// instead of the one of `level` so that the levels of the application show up in the logs.
+    pub level_name: &'static str,
+    /// The short name of the level, as given by [`Level::as_short_str`].
+    pub level_short_name: &'static str,
     pub line: u32,
     pub file: &'static str,
+    /// The column of the statement, as given by `column!()` in the macros, which tells apart statements
+    /// sharing a line, like the ones generated by other macros.
+    pub column: Option<u32>,
+    /// The subsystem the statement comes from, set with `target: "name"` in the macros, which otherwise use the
+    /// module path, as with `log`.
+    pub target: Option<&'static str>,
+    /// The path of the module the statement comes from, as given by `module_path!()` in the macros.
+    pub module: Option<&'static str>,
+    /// When the statement was made, captured once for all of the sinks by a
+    /// [`TimestampLogger`](time::TimestampLogger) or by the global clock of the `global` feature.
+    pub timestamp: Option<core::time::Duration>,
+    /// The position of the statement in the stream of statements numbered by a
+    /// [`SequenceLogger`](common::SequenceLogger), to detect dropped or reordered statements downstream.
+    pub sequence: Option<u32>,
+    /// The thread or core the statement was made on, as given by [`thread::current_thread_id`],
+    /// to tell apart the statements of programs running on several threads or cores.
+    pub thread: Option<u32>,
+    /// Metadata defined by the application, like a tenant id or the state of a state machine, set with
+    /// `user: value` in the macros (after the target, if any) for custom sinks to consume.
+    pub user: Option<u32>,
 }
 
 impl ULogData {
-    pub fn new(level: ULogLevel, line: u32, file: &'static str) -> Self {
-        Self { level, line, file }
+    /// Creates the log data of a statement, made on the [current thread](thread::current_thread_id).
+    ///
+    /// With the `global` feature, once a clock was set with `global::set_global_clock`, the statement is also
+    /// timestamped with it, here rather than in the loggers, so that all of them see the same timestamp. This
+    /// happens for every statement, including the ones later filtered out by [`ULog::enabled`]; until a clock is
+    /// set, it only costs an atomic load. The timestamp can be replaced with
+    /// [`with_timestamp`](ULogData::with_timestamp).
+    pub fn new(level: impl Level, line: u32, file: &'static str) -> Self {
+        Self {
+            level: level.to_ulog_level(),
+            level_name: level.as_str(),
+            level_short_name: level.as_short_str(),
+            line,
+            file,
+            column: None,
+            target: None,
+            module: None,
+            #[cfg(feature = "global")]
+            timestamp: global::global_clock().map(|clock| clock.now()),
+            #[cfg(not(feature = "global"))]
+            timestamp: None,
+            sequence: None,
+            thread: thread::current_thread_id(),
+            user: None,
+        }
+    }
+
+    /// Replaces the level of the statement, along with its names.
+    pub fn with_level(mut self, level: impl Level) -> Self {
+        self.level = level.to_ulog_level();
+        self.level_name = level.as_str();
+        self.level_short_name = level.as_short_str();
+        self
+    }
+
+    pub fn with_column(mut self, column: u32) -> Self {
+        self.column = Some(column);
+        self
+    }
+
+    pub fn with_target(mut self, target: &'static str) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    pub fn with_module(mut self, module: &'static str) -> Self {
+        self.module = Some(module);
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: core::time::Duration) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn with_sequence(mut self, sequence: u32) -> Self {
+        self.sequence = Some(sequence);
+        self
+    }
+
+    pub fn with_thread(mut self, thread: u32) -> Self {
+        self.thread = Some(thread);
+        self
+    }
+
+    pub fn with_user(mut self, user: u32) -> Self {
+        self.user = Some(user);
+        self
+    }
+}
+
+/// A typed value, which loggers can encode natively with [`log_value`](ULog::log_value)
+/// instead of going through its [`Debug`](core::fmt::Debug) representation.
+///
+/// Its `Debug` implementation is the one of the value it holds.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Value<'a> {
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Bool(bool),
+    Str(&'a str),
+    Bytes(&'a [u8]),
+}
+
+impl core::fmt::Debug for Value<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Value::I64(value) => core::fmt::Debug::fmt(value, f),
+            Value::U64(value) => core::fmt::Debug::fmt(value, f),
+            Value::F64(value) => core::fmt::Debug::fmt(value, f),
+            Value::Bool(value) => core::fmt::Debug::fmt(value, f),
+            Value::Str(value) => core::fmt::Debug::fmt(value, f),
+            Value::Bytes(value) => core::fmt::Debug::fmt(value, f),
+        }
+    }
+}
+
+/// Formats a value implementing [`Display`](core::fmt::Display) when printed with [`Debug`](core::fmt::Debug).
+struct DisplayAsDebug<'a, T: ?Sized>(&'a T);
+
+impl<T: core::fmt::Display + ?Sized> core::fmt::Debug for DisplayAsDebug<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(self.0, f)
+    }
+}
+
+/// A value computed by a closure only when it is formatted, so that expensive summaries are skipped when
+/// the statement is dropped before reaching a sink, like by a [`MinLevelLogger`](common::MinLevelLogger);
+/// written as `key =>? closure` in the macros.
+///
+/// The closure is called each time the value is formatted, so once per sink that formats it.
+///
+/// ```
+/// use ulog::ULog;
+/// # let logger = ulog::common::StubLogger.min_level(ulog::ULogLevel::Info);
+/// # let samples = [1, 2, 3];
+/// // The sum is never computed, as debug statements are dropped
+/// ulog::debug!(logger, "Sampled", "sum" =>? || samples.iter().sum::<i32>());
+/// ```
+#[derive(Clone, Copy)]
+pub struct Lazy<F>(pub F);
+
+impl<F: Fn() -> T, T: core::fmt::Debug> core::fmt::Debug for Lazy<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&(self.0)(), f)
+    }
+}
+
+macro_rules! impl_value_from {
+    ( $variant:ident($target:ty): $($source:ty),+ ) => {
+        $(
+            impl From<$source> for Value<'_> {
+                fn from(value: $source) -> Self {
+                    Value::$variant(value as $target)
+                }
+            }
+        )+
+    };
+}
+
+impl_value_from!(I64(i64): i8, i16, i32, i64, isize);
+impl_value_from!(U64(u64): u8, u16, u32, u64, usize);
+impl_value_from!(F64(f64): f32, f64);
+
+impl From<bool> for Value<'_> {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl<'a> From<&'a str> for Value<'a> {
+    fn from(value: &'a str) -> Self {
+        Value::Str(value)
     }
 }
 
+impl<'a> From<&'a [u8]> for Value<'a> {
+    fn from(value: &'a [u8]) -> Self {
+        Value::Bytes(value)
+    }
+}
+
+/// The size of the buffer in which [`ULog::log_fmt`] renders formatted messages by default.
+pub const FMT_BUFFER_SIZE: usize = 128;
+
 /// A trait that all loggers should implement; [`log_str`](ULog::log_str) and [`log_format`](ULog::log_format)
 /// will be called by the different macros
 /// to respectively log a static string or a value implementing [`Debug`](core::fmt::Debug).
@@ -94,15 +700,104 @@ pub trait ULog {
     /// Logs a string of characters, alongside the given `level`.
     fn log_str(&self, log_data: &ULogData, string: &str);
 
+    /// Logs a string of characters continuing the previous one, as a single piece of text;
+    /// called by [`StatementBuilder`](builder::StatementBuilder) when text is written to it in several parts.
+    ///
+    /// Defaults to [`log_str`](ULog::log_str), so loggers that don't implement it may separate both parts.
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.log_str(log_data, string)
+    }
+
+    /// Logs a formatted message, called by the macros when the message is followed by format arguments;
+    /// a message without arguments is passed to [`log_str`](ULog::log_str) as is, without interpreting its braces.
+    ///
+    /// Defaults to [`log_str`](ULog::log_str), rendering the message into a stack buffer of [`FMT_BUFFER_SIZE`] bytes first;
+    /// longer messages are truncated. The sinks of this crate override it to write the message straight to their
+    /// output, and the wrappers forward it.
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        match args.as_str() {
+            Some(string) => self.log_str(log_data, string),
+            None => {
+                let mut buffer = record::TextBuffer::<FMT_BUFFER_SIZE>::new();
+                let _ = core::fmt::Write::write_fmt(&mut buffer, args);
+                self.log_str(log_data, buffer.as_str());
+            }
+        }
+    }
+
     /// Optionally logs a key-value pair, where the value implements [`Debug`](core::fmt::Debug).
     fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T);
 
+    /// Optionally logs a key-value pair holding a typed [`Value`], which structured sinks can encode natively.
+    ///
+    /// Defaults to [`log_format`](ULog::log_format), so loggers that don't forward it fall back to the `Debug` representation.
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.log_format(log_data, key, &value)
+    }
+
+    /// Optionally logs a key-value pair holding a raw binary payload, which binary sinks can embed verbatim
+    /// and text sinks can write as hexadecimal.
+    ///
+    /// Defaults to [`log_value`](ULog::log_value) with a [`Value::Bytes`], which itself falls back to the `Debug`
+    /// representation of the byte slice.
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        self.log_value(log_data, key, Value::Bytes(bytes))
+    }
+
+    /// Optionally logs a key-value pair, where the value implements [`Display`](core::fmt::Display);
+    /// called by the macros for pairs written as `key =>% value`.
+    ///
+    /// Defaults to [`log_format`](ULog::log_format), with a value whose `Debug` implementation is the `Display` one of `value`.
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.log_format(log_data, key, &DisplayAsDebug(value))
+    }
+
+    /// Optionally logs a key-value pair, where the value implements [`Serialize`](serde::Serialize);
+    /// called by the macros for pairs written as `key =># value`, requires the `serde` feature.
+    ///
+    /// Defaults to [`log_format`](ULog::log_format), with a value whose `Debug` implementation writes it as JSON.
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn serialize::SerializeJson) {
+        self.log_format(log_data, key, &serialize::Serialized(value))
+    }
+
     /// Begins a logging statement, called once before a chain of `log_str` and `log_format`.
     fn log_begin(&self, log_data: &ULogData);
 
     /// Ends a logging statement, called once after a chain of `log_str` and `log_format`.
     fn log_end(&self, log_data: &ULogData);
 
+    /// Returns whether the logger is currently able to handle logging statements, defaults to `true`.
+    ///
+    /// Loggers writing to a medium that may fail (a network link, some storage) can return `false` while it is down,
+    /// letting combinators like [`FailoverLogger`](common::FailoverLogger) send statements elsewhere.
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    /// Returns whether a statement with the given log data would be logged, defaults to `true`.
+    ///
+    /// The macros call it once before evaluating the message and the values of a statement, and skip the statement
+    /// when it returns `false`; loggers filtering statements, like [`MinLevelLogger`](common::MinLevelLogger),
+    /// implement it so that the statements they drop cost no formatting.
+    fn enabled(&self, _log_data: &ULogData) -> bool {
+        true
+    }
+
+    /// Writes out the statements that the logger or its sink may still be holding, defaults to doing nothing.
+    ///
+    /// Loggers batching or queueing statements forward them, and loggers writing to a [`ByteSink`](sink::ByteSink)
+    /// [flush it](sink::ByteSink::flush); combinators flush the loggers they wrap. Applications call it to make sure
+    /// that statements are persisted before going to sleep, rebooting or panicking.
+    fn flush(&self) {}
+
+    /// Starts building a statement logged at `level` by chaining method calls, as an alternative to the macros;
+    /// see [`StatementBuilder`](builder::StatementBuilder).
+    #[track_caller]
+    fn at(&self, level: impl Level) -> builder::StatementBuilder<'_, Self> {
+        builder::StatementBuilder::new(self, level)
+    }
+
     /// A shortcut for [`ChainLogger::new(self, other)`](common::ChainLogger::new);
     /// constructs a logger that forwards statements to both `self` and `other`.
     fn chain<Other: ULog>(self, other: Other) -> common::ChainLogger<Self, Other>
@@ -112,27 +807,82 @@ pub trait ULog {
         common::ChainLogger::new(self, other)
     }
 
-    /// A shortcut for [`MinLevelLogger::new(self, min_level)`](common::MinLogger::new);
+    /// A shortcut for [`MinLevelLogger::new(self, min_level)`](common::MinLevelLogger::new);
     /// wraps the logger so that it only interprets logging statements with a level above `min_level`.
-    fn min_level(self, min_level: ULogLevel) -> common::MinLevelLogger<Self>
+    fn min_level(self, min_level: impl Level) -> common::MinLevelLogger<Self>
     where
         Self: Sized,
     {
         common::MinLevelLogger::new(self, min_level)
     }
+
+    /// A shortcut for [`FailoverLogger::new(self, secondary)`](common::FailoverLogger::new);
+    /// constructs a logger that forwards statements to `self`, or to `secondary` while `self` is unavailable.
+    fn failover<Secondary: ULog>(
+        self,
+        secondary: Secondary,
+    ) -> common::FailoverLogger<Self, Secondary>
+    where
+        Self: Sized,
+    {
+        common::FailoverLogger::new(self, secondary)
+    }
+
+    /// A shortcut for [`ContextLogger::new(self, context)`](common::ContextLogger::new);
+    /// constructs a logger that appends the key-value pairs of `context` to every statement.
+    fn with_context<Context: common::KeyValues>(
+        self,
+        context: Context,
+    ) -> common::ContextLogger<Self, Context>
+    where
+        Self: Sized,
+    {
+        common::ContextLogger::new(self, context)
+    }
 }
 
-impl<Logger: ULog> ULog for &Logger {
+impl<Logger: ULog + ?Sized> ULog for &Logger {
     #[inline(always)]
     fn log_str(&self, log_data: &ULogData, string: &str) {
         <Logger as ULog>::log_str(*self, log_data, string)
     }
 
+    #[inline(always)]
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        <Logger as ULog>::log_str_continued(*self, log_data, string)
+    }
+
+    #[inline(always)]
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        <Logger as ULog>::log_fmt(*self, log_data, args)
+    }
+
     #[inline(always)]
     fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
         <Logger as ULog>::log_format(*self, log_data, key, value)
     }
 
+    #[inline(always)]
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        <Logger as ULog>::log_value(*self, log_data, key, value)
+    }
+
+    #[inline(always)]
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        <Logger as ULog>::log_bytes(*self, log_data, key, bytes)
+    }
+
+    #[inline(always)]
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        <Logger as ULog>::log_display(*self, log_data, key, value)
+    }
+
+    #[cfg(feature = "serde")]
+    #[inline(always)]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn serialize::SerializeJson) {
+        <Logger as ULog>::log_serialize(*self, log_data, key, value)
+    }
+
     #[inline(always)]
     fn log_begin(&self, log_data: &ULogData) {
         <Logger as ULog>::log_begin(*self, log_data)
@@ -142,167 +892,2042 @@ impl<Logger: ULog> ULog for &Logger {
     fn log_end(&self, log_data: &ULogData) {
         <Logger as ULog>::log_end(*self, log_data)
     }
+
+    #[inline(always)]
+    fn is_available(&self) -> bool {
+        <Logger as ULog>::is_available(*self)
+    }
+
+    #[inline(always)]
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        <Logger as ULog>::enabled(*self, log_data)
+    }
+
+    #[inline(always)]
+    fn flush(&self) {
+        <Logger as ULog>::flush(*self)
+    }
 }
 
-#[macro_export]
-macro_rules! ulog {
-    ( $level:expr, $logger:expr, $str:expr $(,)? ) => {{
-        let log_data = $crate::ULogData::new($level, line!(), file!());
+/// An object-safe version of [`ULog`], implemented for every logger.
+///
+/// This allows storing loggers as trait objects (`&dyn DynULog`, `Box<dyn DynULog>`), which implement [`ULog`] in turn;
+/// values are then passed as `&dyn Debug`.
+pub trait DynULog {
+    fn log_str_dyn(&self, log_data: &ULogData, string: &str);
 
-        $crate::ULog::log_begin(&$logger, &log_data);
-        $crate::ULog::log_str(&$logger, &log_data, $str);
-        $crate::ULog::log_end(&$logger, &log_data);
-    }};
+    fn log_str_continued_dyn(&self, log_data: &ULogData, string: &str);
 
-    ( $level:expr, $logger:expr, $str:expr, $($name:tt => $value:expr),+ $(,)? ) => {{
-        let log_data = $crate::ULogData::new($level, line!(), file!());
+    fn log_fmt_dyn(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>);
 
-        $crate::ULog::log_begin(&$logger, &log_data);
-        $crate::ULog::log_str(&$logger, &log_data, $str);
-        $(
-            $crate::ULog::log_format(&$logger, &log_data, $name, &$value);
-        )+
-        $crate::ULog::log_end(&$logger, &log_data);
-    }}
-}
+    fn log_format_dyn(&self, log_data: &ULogData, key: &str, value: &dyn core::fmt::Debug);
 
-#[macro_export]
-macro_rules! debug {
-    ( $logger:expr, $str:expr $(, $($name:tt => $value:expr),* $(,)? )? ) => {
-        $crate::ulog!($crate::ULogLevel::Debug, $logger, $str, $( $( $name => $value ),* )?)
-    }
+    fn log_value_dyn(&self, log_data: &ULogData, key: &str, value: Value<'_>);
+
+    fn log_bytes_dyn(&self, log_data: &ULogData, key: &str, bytes: &[u8]);
+
+    fn log_display_dyn(&self, log_data: &ULogData, key: &str, value: &dyn core::fmt::Display);
+
+    #[cfg(feature = "serde")]
+    fn log_serialize_dyn(
+        &self,
+        log_data: &ULogData,
+        key: &str,
+        value: &dyn serialize::SerializeJson,
+    );
+
+    fn log_begin_dyn(&self, log_data: &ULogData);
+
+    fn log_end_dyn(&self, log_data: &ULogData);
+
+    fn is_available_dyn(&self) -> bool;
+
+    fn enabled_dyn(&self, log_data: &ULogData) -> bool;
+
+    fn flush_dyn(&self);
 }
 
-#[macro_export]
-macro_rules! info {
-    ( $logger:expr, $str:expr $(, $($name:tt => $value:expr),* $(,)? )? ) => {
-        $crate::ulog!($crate::ULogLevel::Info, $logger, $str, $( $( $name => $value ),* )?)
+impl<Logger: ULog> DynULog for Logger {
+    #[inline(always)]
+    fn log_str_dyn(&self, log_data: &ULogData, string: &str) {
+        self.log_str(log_data, string)
     }
-}
 
-#[macro_export]
-macro_rules! warn {
-    ( $logger:expr, $str:expr $(, $($name:tt => $value:expr),* $(,)? )? ) => {
-        $crate::ulog!($crate::ULogLevel::Warning, $logger, $str, $( $( $name => $value ),* )?)
+    #[inline(always)]
+    fn log_str_continued_dyn(&self, log_data: &ULogData, string: &str) {
+        self.log_str_continued(log_data, string)
     }
-}
 
-#[macro_export]
-macro_rules! error {
-    ( $logger:expr, $str:expr $(, $($name:tt => $value:expr),* $(,)? )? ) => {
-        $crate::ulog!($crate::ULogLevel::Error, $logger, $str, $( $( $name => $value ),* )?)
+    #[inline(always)]
+    fn log_fmt_dyn(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.log_fmt(log_data, args)
     }
-}
+
+    #[inline(always)]
+    fn log_format_dyn(&self, log_data: &ULogData, key: &str, value: &dyn core::fmt::Debug) {
+        self.log_format(log_data, key, &value)
+    }
+
+    #[inline(always)]
+    fn log_value_dyn(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.log_value(log_data, key, value)
+    }
+
+    #[inline(always)]
+    fn log_bytes_dyn(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        self.log_bytes(log_data, key, bytes)
+    }
+
+    #[inline(always)]
+    fn log_display_dyn(&self, log_data: &ULogData, key: &str, value: &dyn core::fmt::Display) {
+        self.log_display(log_data, key, &value)
+    }
+
+    #[cfg(feature = "serde")]
+    #[inline(always)]
+    fn log_serialize_dyn(
+        &self,
+        log_data: &ULogData,
+        key: &str,
+        value: &dyn serialize::SerializeJson,
+    ) {
+        self.log_serialize(log_data, key, value)
+    }
+
+    #[inline(always)]
+    fn log_begin_dyn(&self, log_data: &ULogData) {
+        self.log_begin(log_data)
+    }
+
+    #[inline(always)]
+    fn log_end_dyn(&self, log_data: &ULogData) {
+        self.log_end(log_data)
+    }
+
+    #[inline(always)]
+    fn is_available_dyn(&self) -> bool {
+        self.is_available()
+    }
+
+    #[inline(always)]
+    fn enabled_dyn(&self, log_data: &ULogData) -> bool {
+        self.enabled(log_data)
+    }
+
+    #[inline(always)]
+    fn flush_dyn(&self) {
+        self.flush()
+    }
+}
+
+impl ULog for dyn DynULog + '_ {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        self.log_str_dyn(log_data, string)
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.log_str_continued_dyn(log_data, string)
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.log_fmt_dyn(log_data, args)
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.log_format_dyn(log_data, key, value)
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.log_value_dyn(log_data, key, value)
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        self.log_bytes_dyn(log_data, key, bytes)
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.log_display_dyn(log_data, key, value)
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn serialize::SerializeJson) {
+        self.log_serialize_dyn(log_data, key, value)
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.log_begin_dyn(log_data)
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        self.log_end_dyn(log_data)
+    }
+
+    fn is_available(&self) -> bool {
+        self.is_available_dyn()
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.enabled_dyn(log_data)
+    }
+
+    fn flush(&self) {
+        self.flush_dyn()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<Logger: ULog + ?Sized> ULog for alloc::boxed::Box<Logger> {
+    #[inline(always)]
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        (**self).log_str(log_data, string)
+    }
+
+    #[inline(always)]
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        (**self).log_str_continued(log_data, string)
+    }
+
+    #[inline(always)]
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        (**self).log_fmt(log_data, args)
+    }
+
+    #[inline(always)]
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        (**self).log_format(log_data, key, value)
+    }
+
+    #[inline(always)]
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        (**self).log_value(log_data, key, value)
+    }
+
+    #[inline(always)]
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        (**self).log_bytes(log_data, key, bytes)
+    }
+
+    #[inline(always)]
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        (**self).log_display(log_data, key, value)
+    }
+
+    #[cfg(feature = "serde")]
+    #[inline(always)]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn serialize::SerializeJson) {
+        (**self).log_serialize(log_data, key, value)
+    }
+
+    #[inline(always)]
+    fn log_begin(&self, log_data: &ULogData) {
+        (**self).log_begin(log_data)
+    }
+
+    #[inline(always)]
+    fn log_end(&self, log_data: &ULogData) {
+        (**self).log_end(log_data)
+    }
+
+    #[inline(always)]
+    fn is_available(&self) -> bool {
+        (**self).is_available()
+    }
+
+    #[inline(always)]
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        (**self).enabled(log_data)
+    }
+
+    #[inline(always)]
+    fn flush(&self) {
+        (**self).flush()
+    }
+}
+
+#[macro_export]
+macro_rules! ulog {
+    ( @user [$($user:expr)?] target: $target:expr, $level:expr, $($rest:tt)* ) => {{
+        $crate::__ulog_call_site!(::core::option::Option::None, $($rest)*);
+        $crate::__ulog_statement!(
+            $crate::ULogData::new($level, line!(), file!())
+                .with_column(column!())
+                .with_module(module_path!())
+                .with_target($target)
+                $(.with_user($user))?,
+            $($rest)*
+        )
+    }};
+
+    ( target: $target:expr, user: $user:expr, $level:expr, $($rest:tt)* ) => {
+        $crate::ulog!(@user [$user] target: $target, $level, $($rest)*)
+    };
+
+    ( target: $target:expr, $level:expr, $($rest:tt)* ) => {
+        $crate::ulog!(@user [] target: $target, $level, $($rest)*)
+    };
+
+    ( user: $user:expr, $level:expr, $($rest:tt)* ) => {
+        $crate::ulog!(@user [$user] target: module_path!(), $level, $($rest)*)
+    };
+
+    ( $level:expr, $($rest:tt)* ) => {
+        $crate::ulog!(@user [] target: module_path!(), $level, $($rest)*)
+    };
+}
+
+/// Logs a statement at the [`ULogLevel`] variant `$level`, for the level macros like [`info!`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ulog_level {
+    ( $level:ident, @user [$($user:expr)?] target: $target:expr, $($rest:tt)* ) => {{
+        $crate::__ulog_call_site!(::core::option::Option::Some($crate::ULogLevel::$level), $($rest)*);
+        $crate::__ulog_statement!(
+            $crate::ULogData::new($crate::ULogLevel::$level, line!(), file!())
+                .with_column(column!())
+                .with_module(module_path!())
+                .with_target($target)
+                $(.with_user($user))?,
+            $($rest)*
+        )
+    }};
+
+    ( $level:ident, target: $target:expr, user: $user:expr, $($rest:tt)* ) => {
+        $crate::__ulog_level!($level, @user [$user] target: $target, $($rest)*)
+    };
+
+    ( $level:ident, target: $target:expr, $($rest:tt)* ) => {
+        $crate::__ulog_level!($level, @user [] target: $target, $($rest)*)
+    };
+
+    ( $level:ident, user: $user:expr, $($rest:tt)* ) => {
+        $crate::__ulog_level!($level, @user [$user] target: module_path!(), $($rest)*)
+    };
+
+    ( $level:ident, $($rest:tt)* ) => {
+        $crate::__ulog_level!($level, @user [] target: module_path!(), $($rest)*)
+    };
+}
+
+/// Registers the call site of a statement in the [catalog](crate::catalog), when its message is a literal.
+#[cfg(feature = "catalog")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ulog_call_site {
+    ( $level:expr, $name:literal => $($rest:tt)* ) => {};
+
+    ( $level:expr, $logger:expr, $name:tt => $($rest:tt)* ) => {};
+
+    ( $level:expr, $str:literal $($rest:tt)* ) => {
+        #[used]
+        #[link_section = "ulog_call_sites"]
+        static __ULOG_CALL_SITE: $crate::catalog::CallSite =
+            $crate::catalog::CallSite::new($level, file!(), line!(), column!(), $str);
+    };
+
+    ( $level:expr, $logger:expr, $str:literal $($rest:tt)* ) => {
+        $crate::__ulog_call_site!($level, $str)
+    };
+
+    ( $level:expr, $($rest:tt)* ) => {};
+}
+
+#[cfg(not(feature = "catalog"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ulog_call_site {
+    ( $($rest:tt)* ) => {};
+}
+
+/// Logs a statement with the given [`ULogData`], for [`ulog!`]; the logger defaults to the global one
+/// when the message comes first.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ulog_statement {
+    ( $log_data:expr, $str:literal $($rest:tt)* ) => {
+        $crate::__ulog_statement!($log_data, $crate::global::GlobalLogger, $str $($rest)*)
+    };
+
+    // Statements made only of key-value pairs, without a message
+    ( $log_data:expr, $logger:expr, $name:tt => $($fields:tt)* ) => {
+        $crate::__ulog_statement!($log_data, $logger; $name => $($fields)*)
+    };
+
+    ( $log_data:expr, $logger:expr, .. $($fields:tt)+ ) => {
+        $crate::__ulog_statement!($log_data, $logger; .. $($fields)+)
+    };
+
+    ( $log_data:expr, $logger:expr; $($fields:tt)* ) => {{
+        let log_data = $log_data;
+
+        if $crate::ULog::enabled(&$logger, &log_data) {
+            $crate::ULog::log_begin(&$logger, &log_data);
+            $crate::__ulog_fields!($logger, log_data, $($fields)*);
+            $crate::ULog::log_end(&$logger, &log_data);
+        }
+    }};
+
+    ( $log_data:expr, $logger:expr, $str:expr $(,)? ) => {{
+        let log_data = $log_data;
+
+        if $crate::ULog::enabled(&$logger, &log_data) {
+            $crate::ULog::log_begin(&$logger, &log_data);
+            $crate::ULog::log_str(&$logger, &log_data, $str);
+            $crate::ULog::log_end(&$logger, &log_data);
+        }
+    }};
+
+    // `..pairs` would otherwise be taken as a format argument
+    ( $log_data:expr, $logger:expr, $str:expr, .. $($fields:tt)+ ) => {
+        $crate::__ulog_statement!($log_data, $logger, $str; .. $($fields)+)
+    };
+
+    ( $log_data:expr, $logger:expr, $fmt:literal, $($arg:expr),+ $(,)? $(; $($fields:tt)*)? ) => {{
+        let log_data = $log_data;
+
+        if $crate::ULog::enabled(&$logger, &log_data) {
+            $crate::ULog::log_begin(&$logger, &log_data);
+            $crate::ULog::log_fmt(&$logger, &log_data, format_args!($fmt, $($arg),+));
+            $($crate::__ulog_fields!($logger, log_data, $($fields)*);)?
+            $crate::ULog::log_end(&$logger, &log_data);
+        }
+    }};
+
+    ( $log_data:expr, $logger:expr, $str:expr, $($fields:tt)+ ) => {{
+        let log_data = $log_data;
+
+        if $crate::ULog::enabled(&$logger, &log_data) {
+            $crate::ULog::log_begin(&$logger, &log_data);
+            $crate::ULog::log_str(&$logger, &log_data, $str);
+            $crate::__ulog_fields!($logger, log_data, $($fields)+);
+            $crate::ULog::log_end(&$logger, &log_data);
+        }
+    }};
+
+    ( $log_data:expr, $logger:expr, $str:expr; $($fields:tt)* ) => {{
+        let log_data = $log_data;
+
+        if $crate::ULog::enabled(&$logger, &log_data) {
+            $crate::ULog::log_begin(&$logger, &log_data);
+            $crate::ULog::log_str(&$logger, &log_data, $str);
+            $crate::__ulog_fields!($logger, log_data, $($fields)*);
+            $crate::ULog::log_end(&$logger, &log_data);
+        }
+    }};
+}
+
+/// Implements the methods of [`ULog`] by forwarding them to the field `$field`, for `#[derive(ULog)]`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ulog_delegate {
+    ( $field:tt ) => {
+        fn log_str(&self, log_data: &$crate::ULogData, string: &str) {
+            $crate::ULog::log_str(&self.$field, log_data, string)
+        }
+
+        fn log_str_continued(&self, log_data: &$crate::ULogData, string: &str) {
+            $crate::ULog::log_str_continued(&self.$field, log_data, string)
+        }
+
+        fn log_fmt(&self, log_data: &$crate::ULogData, args: ::core::fmt::Arguments<'_>) {
+            $crate::ULog::log_fmt(&self.$field, log_data, args)
+        }
+
+        fn log_format<T: ::core::fmt::Debug>(
+            &self,
+            log_data: &$crate::ULogData,
+            key: &str,
+            value: &T,
+        ) {
+            $crate::ULog::log_format(&self.$field, log_data, key, value)
+        }
+
+        fn log_value(&self, log_data: &$crate::ULogData, key: &str, value: $crate::Value<'_>) {
+            $crate::ULog::log_value(&self.$field, log_data, key, value)
+        }
+
+        fn log_bytes(&self, log_data: &$crate::ULogData, key: &str, bytes: &[u8]) {
+            $crate::ULog::log_bytes(&self.$field, log_data, key, bytes)
+        }
+
+        fn log_display<T: ::core::fmt::Display>(
+            &self,
+            log_data: &$crate::ULogData,
+            key: &str,
+            value: &T,
+        ) {
+            $crate::ULog::log_display(&self.$field, log_data, key, value)
+        }
+
+        $crate::__ulog_delegate_serialize!($field);
+
+        fn log_begin(&self, log_data: &$crate::ULogData) {
+            $crate::ULog::log_begin(&self.$field, log_data)
+        }
+
+        fn log_end(&self, log_data: &$crate::ULogData) {
+            $crate::ULog::log_end(&self.$field, log_data)
+        }
+
+        fn is_available(&self) -> bool {
+            $crate::ULog::is_available(&self.$field)
+        }
+
+        fn enabled(&self, log_data: &$crate::ULogData) -> bool {
+            $crate::ULog::enabled(&self.$field, log_data)
+        }
+
+        fn flush(&self) {
+            $crate::ULog::flush(&self.$field)
+        }
+    };
+}
+
+/// Forwards [`ULog::log_serialize`] for [`__ulog_delegate!`], if the `serde` feature is enabled;
+/// the `cfg` has to be checked here, as it would otherwise refer to the features of the calling crate.
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ulog_delegate_serialize {
+    ( $field:tt ) => {
+        fn log_serialize(
+            &self,
+            log_data: &$crate::ULogData,
+            key: &str,
+            value: &dyn $crate::serialize::SerializeJson,
+        ) {
+            $crate::ULog::log_serialize(&self.$field, log_data, key, value)
+        }
+    };
+}
+
+#[cfg(not(feature = "serde"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ulog_delegate_serialize {
+    ( $field:tt ) => {};
+}
+
+/// Logs the key-value pairs of [`ulog!`], as `key => value` (using [`Debug`](core::fmt::Debug)),
+/// `key =>% value` (using [`Display`](core::fmt::Display)), `key =># value` (using `Serialize`, with the `serde` feature)
+/// or `key =>? closure` (computing the value only when it is formatted, see [`Lazy`]);
+/// a bare identifier `value` is a shorthand for `"value" => value` (the pairs must then follow the message after a
+/// semicolon, as identifiers right after it are format arguments), and `..pairs` logs every `(key, value)` pair
+/// of an iterator.
+///
+/// Keys can be any `&str`: a literal, a variable, or a parenthesized expression like `(name.as_str()) => value`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ulog_fields {
+    ( $logger:expr, $log_data:ident $(,)? ) => {};
+
+    ( $logger:expr, $log_data:ident, $name:tt =>% $value:expr $(, $($rest:tt)*)? ) => {
+        $crate::ULog::log_display(&$logger, &$log_data, $name, &$value);
+        $crate::__ulog_fields!($logger, $log_data $(, $($rest)*)?);
+    };
+
+    ( $logger:expr, $log_data:ident, .. $pairs:expr $(, $($rest:tt)*)? ) => {
+        for (key, value) in $pairs {
+            $crate::ULog::log_format(&$logger, &$log_data, ::core::convert::AsRef::<str>::as_ref(&key), &value);
+        }
+        $crate::__ulog_fields!($logger, $log_data $(, $($rest)*)?);
+    };
+
+    ( $logger:expr, $log_data:ident, $name:tt =>? $value:expr $(, $($rest:tt)*)? ) => {
+        $crate::ULog::log_format(&$logger, &$log_data, $name, &$crate::Lazy($value));
+        $crate::__ulog_fields!($logger, $log_data $(, $($rest)*)?);
+    };
+
+    ( $logger:expr, $log_data:ident, $name:tt =># $value:expr $(, $($rest:tt)*)? ) => {
+        $crate::ULog::log_serialize(&$logger, &$log_data, $name, &$value);
+        $crate::__ulog_fields!($logger, $log_data $(, $($rest)*)?);
+    };
+
+    ( $logger:expr, $log_data:ident, $name:tt => $value:expr $(, $($rest:tt)*)? ) => {
+        $crate::ULog::log_format(&$logger, &$log_data, $name, &$value);
+        $crate::__ulog_fields!($logger, $log_data $(, $($rest)*)?);
+    };
+
+    ( $logger:expr, $log_data:ident, $name:ident $(, $($rest:tt)*)? ) => {
+        $crate::ULog::log_format(&$logger, &$log_data, stringify!($name), &$name);
+        $crate::__ulog_fields!($logger, $log_data $(, $($rest)*)?);
+    };
+}
+
+#[macro_export]
+macro_rules! trace {
+    ( $($rest:tt)* ) => {
+        $crate::__ulog_level!(Trace, $($rest)*)
+    };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ( $($rest:tt)* ) => {
+        $crate::__ulog_level!(Debug, $($rest)*)
+    };
+}
+
+#[macro_export]
+macro_rules! info {
+    ( $($rest:tt)* ) => {
+        $crate::__ulog_level!(Info, $($rest)*)
+    };
+}
+
+#[macro_export]
+macro_rules! notice {
+    ( $($rest:tt)* ) => {
+        $crate::__ulog_level!(Notice, $($rest)*)
+    };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ( $($rest:tt)* ) => {
+        $crate::__ulog_level!(Warning, $($rest)*)
+    };
+}
+
+#[macro_export]
+macro_rules! error {
+    ( $($rest:tt)* ) => {
+        $crate::__ulog_level!(Error, $($rest)*)
+    };
+}
 
 #[macro_export]
 macro_rules! critical {
-    ( $logger:expr, $str:expr $(, $($name:tt => $value:expr),* $(,)? )? ) => {
-        $crate::ulog!($crate::ULogLevel::Critical, $logger, $str, $( $( $name => $value ),* )?)
+    ( $($rest:tt)* ) => {
+        $crate::__ulog_level!(Critical, $($rest)*)
+    };
+}
+
+/// Asserts that a condition holds, like [`assert!`]; if it does not, a [`Critical`](ULogLevel::Critical) statement
+/// with the condition and the given key-value pairs is logged before panicking.
+///
+/// ```should_panic
+/// # let logger = ulog::common::StubLogger;
+/// let (read, expected) = (3, 4);
+/// ulog::ulog_assert!(logger, read == expected, "read" => read, "expected" => expected);
+/// ```
+#[macro_export]
+macro_rules! ulog_assert {
+    ( $logger:expr, $condition:expr $(, $($fields:tt)*)? ) => {
+        if !$condition {
+            $crate::ulog!(
+                $crate::ULogLevel::Critical,
+                $logger,
+                concat!("assertion failed: ", stringify!($condition))
+                $(, $($fields)*)?
+            );
+            panic!("{}", concat!("assertion failed: ", stringify!($condition)));
+        }
+    };
+}
+
+/// Ensures that a condition holds; if it does not, a [`Critical`](ULogLevel::Critical) statement with the condition
+/// and the given key-value pairs is logged, and the enclosing function returns `Err(error.into())`.
+///
+/// ```
+/// # let logger = ulog::common::StubLogger;
+/// fn check(logger: &impl ulog::ULog, length: usize) -> Result<(), &'static str> {
+///     ulog::ulog_ensure!(logger, length <= 64, "frame too long", "length" => length);
+///     Ok(())
+/// }
+///
+/// assert_eq!(check(&logger, 100), Err("frame too long"));
+/// ```
+#[macro_export]
+macro_rules! ulog_ensure {
+    ( $logger:expr, $condition:expr, $error:expr $(, $($fields:tt)*)? ) => {
+        if !$condition {
+            $crate::ulog!(
+                $crate::ULogLevel::Critical,
+                $logger,
+                concat!("condition failed: ", stringify!($condition))
+                $(, $($fields)*)?
+            );
+            return Err(::core::convert::Into::into($error));
+        }
+    };
+}
+
+/// Runs a block inside of a [`Span`](time::Span) of a [`SpanLogger`](time::SpanLogger), logging when it is entered
+/// and when it exits, with the time it took according to the logger's clock; returns the value of the block.
+///
+/// ```
+/// use ulog::common::StubLogger;
+/// use ulog::time::SpanLogger;
+/// use core::time::Duration;
+///
+/// let logger = SpanLogger::new(StubLogger, || Duration::from_millis(0));
+///
+/// let sum = ulog::ulog_span!(logger, "sum", {
+///     (1..=10).sum::<u32>()
+/// });
+/// assert_eq!(sum, 55);
+/// ```
+#[macro_export]
+macro_rules! ulog_span {
+    ( $logger:expr, $name:expr, $body:block $(,)? ) => {{
+        let _span = $logger.span($name);
+        $body
+    }};
+
+    ( $level:expr, $logger:expr, $name:expr, $body:block $(,)? ) => {{
+        let _span = $logger.span_at($level, $name);
+        $body
+    }};
+}
+
+/// Logs the value of an expression at the debug level and returns it, like `std::dbg!`; the message is the location
+/// of the call, and the expression is logged as a key-value pair, with its [`Debug`](core::fmt::Debug) representation.
+///
+/// ```
+/// # let logger = ulog::common::StubLogger;
+/// let reading = ulog::udbg!(logger, 21 * 2); // Logs `src/main.rs:2`, with `21 * 2 => 42`
+/// assert_eq!(reading, 42);
+/// ```
+#[macro_export]
+macro_rules! udbg {
+    ( $logger:expr, $value:expr $(,)? ) => {
+        match $value {
+            value => {
+                let log_data = $crate::ULogData::new($crate::ULogLevel::Debug, line!(), file!())
+                    .with_column(column!())
+                    .with_module(module_path!())
+                    .with_target(module_path!());
+
+                if $crate::ULog::enabled(&$logger, &log_data) {
+                    $crate::ULog::log_begin(&$logger, &log_data);
+                    $crate::ULog::log_str(&$logger, &log_data, concat!(file!(), ":", line!()));
+                    $crate::ULog::log_format(&$logger, &log_data, stringify!($value), &value);
+                    $crate::ULog::log_end(&$logger, &log_data);
+                }
+
+                value
+            }
+        }
+    };
+
+    ( $logger:expr, $($value:expr),+ $(,)? ) => {
+        ($($crate::udbg!($logger, $value)),+,)
+    };
+}
+
+/// Evaluates an expression and logs it with its value and the time its evaluation took, then returns the value;
+/// a heavier sibling of [`udbg!`] to profile suspicious expressions.
+///
+/// The message is the expression, followed by the `value` and `elapsed` key-value pairs. The time is measured with
+/// a [`StdClock`](time::StdClock), which requires the `std` feature, or with the [`ULogClock`](time::ULogClock)
+/// given as `clock = <expr>`.
+///
+/// ```
+/// use ulog::ULogLevel;
+/// use core::time::Duration;
+/// # let logger = ulog::common::StubLogger;
+///
+/// // Logs `(1..=10).sum::<u32>()`, with `value => 55` and `elapsed => 0ns`
+/// let sum = ulog::log_expr!(ULogLevel::Debug, logger, clock = || Duration::ZERO, (1..=10).sum::<u32>());
+/// assert_eq!(sum, 55);
+/// ```
+#[macro_export]
+macro_rules! log_expr {
+    ( $level:expr, $logger:expr, clock = $clock:expr, $value:expr $(,)? ) => {{
+        let clock = $clock;
+        let start = $crate::time::ULogClock::now(&clock);
+
+        match $value {
+            value => {
+                let elapsed = $crate::time::ULogClock::now(&clock).saturating_sub(start);
+                let log_data = $crate::ULogData::new($level, line!(), file!())
+                    .with_column(column!())
+                    .with_module(module_path!())
+                    .with_target(module_path!());
+
+                if $crate::ULog::enabled(&$logger, &log_data) {
+                    $crate::ULog::log_begin(&$logger, &log_data);
+                    $crate::ULog::log_str(&$logger, &log_data, stringify!($value));
+                    $crate::ULog::log_format(&$logger, &log_data, "value", &value);
+                    $crate::ULog::log_format(&$logger, &log_data, "elapsed", &elapsed);
+                    $crate::ULog::log_end(&$logger, &log_data);
+                }
+
+                value
+            }
+        }
+    }};
+
+    ( $level:expr, $logger:expr, $value:expr $(,)? ) => {
+        $crate::log_expr!(
+            $level,
+            $logger,
+            clock = $crate::time::StdClock::new(),
+            $value
+        )
+    };
+}
+
+/// Logs a statement like [`ulog!`] if `condition` is true; the level, the data of the statement and its values
+/// are only evaluated when it is.
+///
+/// ```
+/// # let logger = ulog::common::StubLogger;
+/// let retries = 3;
+/// ulog::log_if!(retries > 2, ulog::ULogLevel::Warning, logger, "Link is unstable", "retries" => retries);
+/// ```
+#[macro_export]
+macro_rules! log_if {
+    ( $condition:expr, $($args:tt)* ) => {
+        if $condition {
+            $crate::ulog!($($args)*)
+        }
+    };
+}
+
+/// Logs a statement like [`ulog!`], but only the first time this call site is reached;
+/// useful for warnings inside hot loops.
+///
+/// On targets without atomic compare-and-swap, like `thumbv6m-none-eabi`, the flag is checked and set in a critical
+/// section with the `critical-section` feature; without it, two threads reaching the call site at the same time
+/// might both log the statement.
+#[macro_export]
+macro_rules! log_once {
+    ( $($args:tt)* ) => {
+        $crate::__once!({ $crate::ulog!($($args)*) })
+    };
+}
+
+/// Logs a statement like [`ulog!`], but only every `n`th time this call site is reached, starting with the first;
+/// the number of times it was reached is appended as a `count` key-value pair. With `n` being 0, the statement
+/// is never logged.
+///
+/// As with [`log_once!`], without atomic compare-and-swap nor the `critical-section` feature,
+/// concurrent calls might occasionally be counted once.
+///
+/// ```
+/// # let logger = ulog::common::StubLogger;
+/// for sample in 0..1000 {
+///     ulog::log_every_n!(100, ulog::ULogLevel::Debug, logger, "Sampling", "sample" => sample);
+/// }
+/// ```
+#[macro_export]
+macro_rules! log_every_n {
+    ( $n:expr, $level:expr, $($rest:tt)* ) => {{
+        static HITS: ::core::sync::atomic::AtomicUsize = ::core::sync::atomic::AtomicUsize::new(0);
+
+        let hits = $crate::__count_hit(&HITS);
+        let n: usize = $n;
+        if hits.checked_rem(n) == ::core::option::Option::Some(0) {
+            let count = hits.wrapping_add(1);
+            $crate::__with_count!(count [$level,] [] [] $($rest)*)
+        }
+    }};
+}
+
+/// Appends the `count` key-value pair of [`log_every_n!`] to the arguments of a statement, after its fields
+/// if it has some, or as its only field otherwise.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __with_count {
+    ( $count:ident [$($head:tt)*] [$($args:tt)*] [] $(,)? ) => {
+        $crate::ulog!($($head)* $($args)*; "count" => $count)
+    };
+
+    ( $count:ident [$($head:tt)*] [$($args:tt)*] [fields] $(,)? ) => {
+        $crate::ulog!($($head)* $($args)*, "count" => $count)
+    };
+
+    ( $count:ident [$($head:tt)*] [$($args:tt)*] [$($fields:tt)?] ; $($rest:tt)* ) => {
+        $crate::__with_count!($count [$($head)*] [$($args)* ;] [fields] $($rest)*)
+    };
+
+    ( $count:ident [$($head:tt)*] [$($args:tt)*] [$($fields:tt)?] => $($rest:tt)* ) => {
+        $crate::__with_count!($count [$($head)*] [$($args)* =>] [fields] $($rest)*)
+    };
+
+    ( $count:ident [$($head:tt)*] [$($args:tt)*] [$($fields:tt)?] , .. $($rest:tt)* ) => {
+        $crate::__with_count!($count [$($head)*] [$($args)* , ..] [fields] $($rest)*)
+    };
+
+    ( $count:ident [$($head:tt)*] [$($args:tt)*] [$($fields:tt)?] $token:tt $($rest:tt)* ) => {
+        $crate::__with_count!($count [$($head)*] [$($args)* $token] [$($fields)?] $($rest)*)
+    };
+}
+
+/// Runs the given block only the first time this call site is reached, for [`log_once!`] and its variants.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __once {
+    ( $body:block ) => {{
+        static HIT: ::core::sync::atomic::AtomicUsize = ::core::sync::atomic::AtomicUsize::new(0);
+
+        if $crate::__first_hit(&HIT) {
+            $body
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! trace_once {
+    ( $($args:tt)* ) => {
+        $crate::__once!({ $crate::trace!($($args)*) })
+    };
+}
+
+#[macro_export]
+macro_rules! debug_once {
+    ( $($args:tt)* ) => {
+        $crate::__once!({ $crate::debug!($($args)*) })
+    };
+}
+
+#[macro_export]
+macro_rules! info_once {
+    ( $($args:tt)* ) => {
+        $crate::__once!({ $crate::info!($($args)*) })
+    };
+}
+
+#[macro_export]
+macro_rules! notice_once {
+    ( $($args:tt)* ) => {
+        $crate::__once!({ $crate::notice!($($args)*) })
+    };
+}
+
+#[macro_export]
+macro_rules! warn_once {
+    ( $($args:tt)* ) => {
+        $crate::__once!({ $crate::warn!($($args)*) })
+    };
+}
+
+#[macro_export]
+macro_rules! error_once {
+    ( $($args:tt)* ) => {
+        $crate::__once!({ $crate::error!($($args)*) })
+    };
+}
+
+#[macro_export]
+macro_rules! critical_once {
+    ( $($args:tt)* ) => {
+        $crate::__once!({ $crate::critical!($($args)*) })
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    #[test]
+    fn test_level_order() {
+        let levels = ULogLevel::all_levels();
+
+        for i in 0..(levels.len() - 1) {
+            for j in (i + 1)..levels.len() {
+                assert!(levels[i] < levels[j]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_level_numbers() {
+        for level in ULogLevel::all_levels() {
+            assert_eq!(ULogLevel::try_from(level.as_u8()), Ok(level));
+        }
+        assert_eq!(u8::from(ULogLevel::Critical), 5);
+        assert_eq!(u8::from(ULogLevel::Notice), 6);
+        assert_eq!(ULogLevel::try_from(7), Err(InvalidLevel(7)));
+        assert!(ULogLevel::Info < ULogLevel::Notice && ULogLevel::Notice < ULogLevel::Warning);
+    }
+
+    #[derive(Default)]
+    pub(crate) struct TestLogger {
+        pub(crate) logs: RefCell<Vec<(ULogLevel, String)>>,
+        pub(crate) unavailable: Cell<bool>,
+    }
+
+    impl ULog for TestLogger {
+        fn log_str(&self, log_data: &ULogData, data: &str) {
+            self.logs
+                .borrow_mut()
+                .push((log_data.level, data.to_string()));
+        }
+
+        fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, name: &str, value: &T) {
+            self.logs
+                .borrow_mut()
+                .push((log_data.level, format!("{name} => {:?}", value)));
+        }
+
+        fn log_begin(&self, log_data: &ULogData) {
+            self.logs
+                .borrow_mut()
+                .push((log_data.level, String::from("__BEGIN__")));
+        }
+
+        fn log_end(&self, log_data: &ULogData) {
+            self.logs
+                .borrow_mut()
+                .push((log_data.level, String::from("__END__")));
+        }
+
+        fn is_available(&self) -> bool {
+            !self.unavailable.get()
+        }
+    }
+
+    #[test]
+    fn test_ulog_macro() {
+        let logger = TestLogger::default();
+
+        ulog!(ULogLevel::Debug, logger, "Hello");
+        ulog!(ULogLevel::Error, logger, "world", "value" => 32);
+
+        assert_eq!(
+            &logger.logs.into_inner()[..],
+            &[
+                (ULogLevel::Debug, String::from("__BEGIN__")),
+                (ULogLevel::Debug, String::from("Hello")),
+                (ULogLevel::Debug, String::from("__END__")),
+                (ULogLevel::Error, String::from("__BEGIN__")),
+                (ULogLevel::Error, String::from("world")),
+                (ULogLevel::Error, String::from("value => 32")),
+                (ULogLevel::Error, String::from("__END__")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_info_macro() {
+        let logger = TestLogger::default();
+
+        info!(logger, "Hello");
+        info!(logger, "world", "value" => 32);
+
+        assert_eq!(
+            &logger.logs.into_inner()[..],
+            &[
+                (ULogLevel::Info, String::from("__BEGIN__")),
+                (ULogLevel::Info, String::from("Hello")),
+                (ULogLevel::Info, String::from("__END__")),
+                (ULogLevel::Info, String::from("__BEGIN__")),
+                (ULogLevel::Info, String::from("world")),
+                (ULogLevel::Info, String::from("value => 32")),
+                (ULogLevel::Info, String::from("__END__")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trace_macro() {
+        let logger = TestLogger::default().min_level(ULogLevel::Debug);
+
+        trace!(logger, "Hidden", "value" => 1);
+        debug!(logger, "Shown");
+
+        let logger = logger.into_inner();
+        assert_eq!(
+            logger.logs.borrow()[1],
+            (ULogLevel::Debug, String::from("Shown"))
+        );
+        assert_eq!(logger.logs.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_format_args() {
+        let logger = TestLogger::default();
+        let address = "uart0";
+
+        info!(logger, "read {} bytes from {}", 12, address);
+        error!(logger, "{address} failed {} times", 3; "code" => 5, "fatal" =>% false);
+        debug!(logger, "no {} arguments");
+
+        assert_eq!(
+            &logger.logs.into_inner()[..],
+            &[
+                (ULogLevel::Info, String::from("__BEGIN__")),
+                (ULogLevel::Info, String::from("read 12 bytes from uart0")),
+                (ULogLevel::Info, String::from("__END__")),
+                (ULogLevel::Error, String::from("__BEGIN__")),
+                (ULogLevel::Error, String::from("uart0 failed 3 times")),
+                (ULogLevel::Error, String::from("code => 5")),
+                (ULogLevel::Error, String::from("fatal => false")),
+                (ULogLevel::Error, String::from("__END__")),
+                (ULogLevel::Debug, String::from("__BEGIN__")),
+                (ULogLevel::Debug, String::from("no {} arguments")),
+                (ULogLevel::Debug, String::from("__END__")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_field_shorthand() {
+        let logger = TestLogger::default();
+        let (value, count) = (1.5, 3);
+
+        debug!(logger, "state"; value, count);
+        info!(logger, "state", "step" => 2, count,);
+        warn!(logger, "{} items", count; value);
+
+        assert_eq!(
+            &logger.logs.into_inner()[..],
+            &[
+                (ULogLevel::Debug, String::from("__BEGIN__")),
+                (ULogLevel::Debug, String::from("state")),
+                (ULogLevel::Debug, String::from("value => 1.5")),
+                (ULogLevel::Debug, String::from("count => 3")),
+                (ULogLevel::Debug, String::from("__END__")),
+                (ULogLevel::Info, String::from("__BEGIN__")),
+                (ULogLevel::Info, String::from("state")),
+                (ULogLevel::Info, String::from("step => 2")),
+                (ULogLevel::Info, String::from("count => 3")),
+                (ULogLevel::Info, String::from("__END__")),
+                (ULogLevel::Warning, String::from("__BEGIN__")),
+                (ULogLevel::Warning, String::from("3 items")),
+                (ULogLevel::Warning, String::from("value => 1.5")),
+                (ULogLevel::Warning, String::from("__END__")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_target_level() {
+        let targets = [
+            ("wifi", ULogLevel::Debug),
+            ("wifi::scan", ULogLevel::Warning),
+        ];
+        let logger =
+            common::TargetLevelLogger::new(TestLogger::default(), ULogLevel::Info, &targets);
+
+        debug!(target: "wifi", logger, "connected", "rssi" => -60);
+        info!(target: "wifi::scan", logger, "found {} networks", 3);
+        debug!(target: "wifi2", logger, "hidden");
+        ulog!(target: "wifi::link", ULogLevel::Debug, logger, "link up");
+        debug!(logger, "hidden");
+
+        assert_eq!(
+            logger.level_for(Some("wifi::scan::passive")),
+            ULogLevel::Warning
+        );
+        assert_eq!(logger.level_for(None), ULogLevel::Info);
+        assert_eq!(
+            &logger.into_inner().logs.into_inner()[..],
+            &[
+                (ULogLevel::Debug, String::from("__BEGIN__")),
+                (ULogLevel::Debug, String::from("connected")),
+                (ULogLevel::Debug, String::from("rssi => -60")),
+                (ULogLevel::Debug, String::from("__END__")),
+                (ULogLevel::Debug, String::from("__BEGIN__")),
+                (ULogLevel::Debug, String::from("link up")),
+                (ULogLevel::Debug, String::from("__END__")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_user() {
+        let statements = RefCell::new(Vec::new());
+        let logger = common::CallbackLogger::<_, 16>::new(|log_data: &ULogData, _text: &str| {
+            statements
+                .borrow_mut()
+                .push((log_data.target, log_data.user))
+        });
+
+        info!(user: 3, logger, "tenant");
+        warn!(target: "net", user: 4, logger, "tenant {}", 4);
+        ulog!(user: 5, ULogLevel::Info, logger, "tenant");
+        ulog!(target: "net", ULogLevel::Info, logger, "none");
+
+        assert_eq!(
+            statements.into_inner(),
+            [
+                (Some("ulog::test"), Some(3)),
+                (Some("net"), Some(4)),
+                (Some("ulog::test"), Some(5)),
+                (Some("net"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_module_level() {
+        let targets = [("ulog::test", ULogLevel::Error), ("wifi", ULogLevel::Trace)];
+        let logger =
+            common::TargetLevelLogger::new(TestLogger::default(), ULogLevel::Info, &targets);
+
+        warn!(logger, "hidden");
+        debug!(target: "wifi", logger, "shown");
+
+        assert_eq!(logger.into_inner().logs.into_inner().len(), 3);
+    }
+
+    #[test]
+    fn test_ulog_ensure() {
+        fn check(logger: &TestLogger, value: u8) -> Result<u8, String> {
+            ulog_ensure!(logger, value < 10, "value too large", "value" => value);
+            Ok(value)
+        }
+
+        let logger = TestLogger::default();
+        assert_eq!(check(&logger, 3), Ok(3));
+        assert_eq!(check(&logger, 12), Err(String::from("value too large")));
+
+        assert_eq!(
+            &logger.logs.into_inner()[..],
+            &[
+                (ULogLevel::Critical, String::from("__BEGIN__")),
+                (
+                    ULogLevel::Critical,
+                    String::from("condition failed: value < 10")
+                ),
+                (ULogLevel::Critical, String::from("value => 12")),
+                (ULogLevel::Critical, String::from("__END__")),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion failed: logs.len() == 2")]
+    fn test_ulog_assert() {
+        let logger = TestLogger::default();
+        let logs = [1];
+        ulog_assert!(logger, logs.len() == 1);
+        ulog_assert!(logger, logs.len() == 2, "logs" => logs);
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion failed: s != \"{}\"")]
+    fn test_ulog_assert_braces() {
+        let logger = TestLogger::default();
+        let s = "{}";
+        ulog_assert!(logger, s != "{}");
+    }
+
+    #[test]
+    fn test_udbg() {
+        let logger = TestLogger::default();
+
+        let value = udbg!(logger, 6 * 7);
+        let pair = udbg!(logger, value + 1, "two");
+
+        assert_eq!(value, 42);
+        assert_eq!(pair, (43, "two"));
+
+        let logs = logger.logs.into_inner();
+        assert_eq!(logs.len(), 12);
+        assert_eq!(logs[0].0, ULogLevel::Debug);
+        assert!(logs[1].1.starts_with("src/lib.rs:"));
+        assert_eq!(logs[2].1, "6 * 7 => 42");
+        assert_eq!(logs[6].1, "value + 1 => 43");
+        assert_eq!(logs[10].1, "\"two\" => \"two\"");
+    }
+
+    #[test]
+    fn test_log_if() {
+        let logger = TestLogger::default();
+        let evaluated = Cell::new(0);
+        let value = || {
+            evaluated.set(evaluated.get() + 1);
+            evaluated.get()
+        };
+
+        log_if!(false, ULogLevel::Error, logger, "Hidden", "value" => value());
+        log_if!(true, ULogLevel::Error, logger, "Shown", "value" => value());
+
+        assert_eq!(evaluated.get(), 1);
+        assert_eq!(
+            &logger.logs.into_inner()[..],
+            &[
+                (ULogLevel::Error, String::from("__BEGIN__")),
+                (ULogLevel::Error, String::from("Shown")),
+                (ULogLevel::Error, String::from("value => 1")),
+                (ULogLevel::Error, String::from("__END__")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_log_every_n() {
+        let logger = TestLogger::default();
+
+        for i in 0..7 {
+            log_every_n!(3, ULogLevel::Info, logger, "Sampling", "i" => i);
+        }
+
+        let logs = logger.logs.into_inner();
+        let logs = logs.iter().map(|(_, log)| log.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            logs,
+            [
+                "__BEGIN__",
+                "Sampling",
+                "i => 0",
+                "count => 1",
+                "__END__",
+                "__BEGIN__",
+                "Sampling",
+                "i => 3",
+                "count => 4",
+                "__END__",
+                "__BEGIN__",
+                "Sampling",
+                "i => 6",
+                "count => 7",
+                "__END__",
+            ]
+        );
+
+        let logger = TestLogger::default();
+        for i in 0..4 {
+            log_every_n!(0, ULogLevel::Info, logger, "Never");
+            log_every_n!(4, ULogLevel::Info, logger, "Message",);
+            log_every_n!(4, ULogLevel::Info, logger, "Sample {}", i);
+            log_every_n!(4, ULogLevel::Info, logger, "Sample {}", i; "i" => i,);
+            log_every_n!(4, ULogLevel::Info, logger, "i" => i);
+        }
+
+        let logs = logger.logs.into_inner();
+        let logs = logs.iter().map(|(_, log)| log.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            logs,
+            [
+                "__BEGIN__",
+                "Message",
+                "count => 1",
+                "__END__",
+                "__BEGIN__",
+                "Sample 0",
+                "count => 1",
+                "__END__",
+                "__BEGIN__",
+                "Sample 0",
+                "i => 0",
+                "count => 1",
+                "__END__",
+                "__BEGIN__",
+                "i => 0",
+                "count => 1",
+                "__END__",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_log_once() {
+        let logger = TestLogger::default();
+
+        for i in 0..3 {
+            warn_once!(logger, "Sensor not calibrated", "i" => i);
+            log_once!(ULogLevel::Info, logger, "Loop {}", i);
+        }
+        warn_once!(logger, "Other call site");
+
+        assert_eq!(
+            &logger.logs.into_inner()[..],
+            &[
+                (ULogLevel::Warning, String::from("__BEGIN__")),
+                (ULogLevel::Warning, String::from("Sensor not calibrated")),
+                (ULogLevel::Warning, String::from("i => 0")),
+                (ULogLevel::Warning, String::from("__END__")),
+                (ULogLevel::Info, String::from("__BEGIN__")),
+                (ULogLevel::Info, String::from("Loop 0")),
+                (ULogLevel::Info, String::from("__END__")),
+                (ULogLevel::Warning, String::from("__BEGIN__")),
+                (ULogLevel::Warning, String::from("Other call site")),
+                (ULogLevel::Warning, String::from("__END__")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lazy_fields() {
+        let logger = TestLogger::default().min_level(ULogLevel::Info);
+        let calls = Cell::new(0);
+        let summary = || {
+            calls.set(calls.get() + 1);
+            "summary"
+        };
+
+        debug!(logger, "Hidden", "summary" =>? summary);
+        info!(logger, "Shown", "summary" =>? summary);
+
+        assert_eq!(calls.get(), 1);
+        assert_eq!(
+            logger.into_inner().logs.into_inner()[2],
+            (ULogLevel::Info, String::from("summary => \"summary\""))
+        );
+    }
+
+    #[test]
+    fn test_runtime_keys() {
+        let logger = TestLogger::default();
+        let config = std::collections::BTreeMap::from([
+            (String::from("baud"), 9600),
+            (String::from("parity"), 0),
+        ]);
+        let name = String::from("port");
+
+        info!(logger, "Config", (name.as_str()) => 2, ..config.iter(), "done" => true);
+
+        assert_eq!(
+            &logger.logs.into_inner()[1..6],
+            &[
+                (ULogLevel::Info, String::from("Config")),
+                (ULogLevel::Info, String::from("port => 2")),
+                (ULogLevel::Info, String::from("baud => 9600")),
+                (ULogLevel::Info, String::from("parity => 0")),
+                (ULogLevel::Info, String::from("done => true")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fields_only() {
+        let logger = TestLogger::default();
+        let (temp, rh) = (21.5, 40);
+        let registers = [("status", 0x80)];
+
+        info!(logger, "temp" => temp, "rh" =>% rh);
+        debug!(logger; temp);
+        trace!(logger, ..registers);
+
+        assert_eq!(
+            &logger.logs.into_inner()[..],
+            &[
+                (ULogLevel::Info, String::from("__BEGIN__")),
+                (ULogLevel::Info, String::from("temp => 21.5")),
+                (ULogLevel::Info, String::from("rh => 40")),
+                (ULogLevel::Info, String::from("__END__")),
+                (ULogLevel::Debug, String::from("__BEGIN__")),
+                (ULogLevel::Debug, String::from("temp => 21.5")),
+                (ULogLevel::Debug, String::from("__END__")),
+                (ULogLevel::Trace, String::from("__BEGIN__")),
+                (ULogLevel::Trace, String::from("status => 128")),
+                (ULogLevel::Trace, String::from("__END__")),
+            ]
+        );
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use std::cell::RefCell;
+    #[test]
+    fn test_enabled() {
+        let logger = TestLogger::default();
+        let evaluated = Cell::new(0);
+        let value = || {
+            evaluated.set(evaluated.get() + 1);
+            evaluated.get()
+        };
+
+        let filtered = (&logger).min_level(ULogLevel::Info);
+        debug!(filtered, "hidden {}", value(); "value" => value());
+        info!(filtered, "shown", "value" => value());
+        info!(common::StubLogger, "discarded", "value" => value());
+
+        assert_eq!(evaluated.get(), 1);
+        assert!(!filtered.enabled(&ULogData::new(ULogLevel::Debug, 0, "")));
+        assert!(filtered.enabled(&ULogData::new(ULogLevel::Warning, 0, "")));
+        assert_eq!(
+            &logger.logs.into_inner()[..],
+            &[
+                (ULogLevel::Info, String::from("__BEGIN__")),
+                (ULogLevel::Info, String::from("shown")),
+                (ULogLevel::Info, String::from("value => 1")),
+                (ULogLevel::Info, String::from("__END__")),
+            ]
+        );
+    }
 
     #[test]
-    fn test_level_order() {
-        let levels = ULogLevel::all_levels();
+    fn test_display_fields() {
+        let logger = TestLogger::default();
 
-        for i in 0..(levels.len() - 1) {
-            for j in (i + 1)..levels.len() {
-                assert!(levels[i] < levels[j]);
-            }
-        }
+        warn!(logger, "Hello", "name" =>% "world", "value" => "32", "ratio" =>% 0.5,);
+
+        assert_eq!(
+            &logger.logs.into_inner()[2..5],
+            &[
+                (ULogLevel::Warning, String::from("name => world")),
+                (ULogLevel::Warning, String::from("value => \"32\"")),
+                (ULogLevel::Warning, String::from("ratio => 0.5")),
+            ]
+        );
     }
 
-    #[derive(Default)]
-    struct TestLogger {
-        logs: RefCell<Vec<(ULogLevel, String)>>,
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_fields() {
+        let logger = TestLogger::default();
+
+        warn!(logger, "Hello", "list" =># [1, 2], "pair" =># ("a", None::<u8>));
+
+        assert_eq!(
+            &logger.logs.into_inner()[2..4],
+            &[
+                (ULogLevel::Warning, String::from("list => [1,2]")),
+                (ULogLevel::Warning, String::from("pair => [\"a\",null]")),
+            ]
+        );
     }
 
-    impl ULog for TestLogger {
-        fn log_str(&self, log_data: &ULogData, data: &str) {
-            self.logs
-                .borrow_mut()
-                .push((log_data.level, data.to_string()));
+    #[test]
+    fn test_min_level() {
+        let logger = TestLogger::default().min_level(ULogLevel::Warning);
+
+        for level in ULogLevel::all_levels() {
+            ulog!(level, logger, "Hello");
         }
 
-        fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, name: &str, value: &T) {
-            self.logs
-                .borrow_mut()
-                .push((log_data.level, format!("{name} => {:?}", value)));
+        assert!(logger
+            .into_inner()
+            .logs
+            .into_inner()
+            .iter()
+            .all(|log| log.0 >= ULogLevel::Warning));
+    }
+
+    #[derive(Clone, Copy)]
+    pub(crate) enum Severity {
+        Audit,
+        Alarm,
+    }
+
+    impl Level for Severity {
+        fn as_str(&self) -> &'static str {
+            match self {
+                Severity::Audit => "AUDIT",
+                Severity::Alarm => "ALARM",
+            }
         }
 
-        fn log_begin(&self, log_data: &ULogData) {
-            self.logs
-                .borrow_mut()
-                .push((log_data.level, String::from("__BEGIN__")));
+        fn as_short_str(&self) -> &'static str {
+            match self {
+                Severity::Audit => "AUD",
+                Severity::Alarm => "ALM",
+            }
         }
 
-        fn log_end(&self, log_data: &ULogData) {
-            self.logs
-                .borrow_mut()
-                .push((log_data.level, String::from("__END__")));
+        fn to_ulog_level(&self) -> ULogLevel {
+            match self {
+                Severity::Audit => ULogLevel::Info,
+                Severity::Alarm => ULogLevel::Critical,
+            }
         }
     }
 
     #[test]
-    fn test_ulog_macro() {
-        let logger = TestLogger::default();
+    fn test_custom_level() {
+        let log_data = ULogData::new(Severity::Alarm, 1, "main.rs");
+        assert_eq!(log_data.level, ULogLevel::Critical);
+        assert_eq!(
+            (log_data.level_name, log_data.level_short_name),
+            ("ALARM", "ALM")
+        );
 
-        ulog!(ULogLevel::Debug, logger, "Hello");
-        ulog!(ULogLevel::Error, logger, "world", "value" => 32);
+        let log_data = log_data.with_level(ULogLevel::Debug);
+        assert_eq!(
+            (log_data.level_name, log_data.level_short_name),
+            ("DEBUG", "DBG")
+        );
 
+        // Filtered by the level they map to
+        let logger = TestLogger::default().min_level(Severity::Alarm);
+        ulog!(Severity::Audit, logger, "Badge read");
+        ulog!(Severity::Alarm, logger, "Door forced");
+        ulog!(ULogLevel::Error, logger, "Dropped");
         assert_eq!(
-            &logger.logs.into_inner()[..],
-            &[
-                (ULogLevel::Debug, String::from("__BEGIN__")),
-                (ULogLevel::Debug, String::from("Hello")),
-                (ULogLevel::Debug, String::from("__END__")),
-                (ULogLevel::Error, String::from("__BEGIN__")),
-                (ULogLevel::Error, String::from("world")),
-                (ULogLevel::Error, String::from("value => 32")),
-                (ULogLevel::Error, String::from("__END__")),
+            logger.into_inner().logs.into_inner(),
+            [
+                (ULogLevel::Critical, String::from("__BEGIN__")),
+                (ULogLevel::Critical, String::from("Door forced")),
+                (ULogLevel::Critical, String::from("__END__")),
             ]
         );
     }
 
     #[test]
-    fn test_info_macro() {
-        let logger = TestLogger::default();
+    fn test_panic_on_level() {
+        let panics = Cell::new(0);
+        let logger = common::PanicOnLevelLogger::new(TestLogger::default(), ULogLevel::Error)
+            .with_hook(|log_data: &ULogData| {
+                assert_eq!(log_data.level, ULogLevel::Critical);
+                panics.set(panics.get() + 1);
+            });
+
+        warn!(logger, "Fine");
+        critical!(logger, "Not fine");
+
+        assert_eq!(panics.get(), 1);
+        assert_eq!(logger.into_inner().logs.into_inner().len(), 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "ulog: a statement was logged at the ERROR level")]
+    fn test_panic_on_level_panics() {
+        let logger = common::PanicOnLevelLogger::new(TestLogger::default(), ULogLevel::Error);
+
+        error!(logger, "Whoops");
+    }
+
+    #[test]
+    fn test_failover() {
+        let logger = TestLogger::default().failover(TestLogger::default());
 
         info!(logger, "Hello");
-        info!(logger, "world", "value" => 32);
+        logger.primary().unavailable.set(true);
+        info!(logger, "world");
+        logger.primary().unavailable.set(false);
+        info!(logger, "!");
+
+        let (primary, secondary) = logger.into_inner();
+        let messages = |logger: TestLogger| {
+            logger
+                .logs
+                .into_inner()
+                .into_iter()
+                .map(|log| log.1)
+                .filter(|message| !message.starts_with("__"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(messages(primary), ["Hello", "!"]);
+        assert_eq!(messages(secondary), ["world"]);
+    }
+
+    #[test]
+    fn test_context() {
+        let context = [(String::from("device"), 12), (String::from("firmware"), 3)];
+        let logger = TestLogger::default().with_context(context);
+
+        let key = String::from("value");
+        warn!(logger, "Hello", (key.as_str()) => 32);
 
         assert_eq!(
-            &logger.logs.into_inner()[..],
+            &logger.into_inner().0.logs.into_inner()[..],
+            &[
+                (ULogLevel::Warning, String::from("__BEGIN__")),
+                (ULogLevel::Warning, String::from("Hello")),
+                (ULogLevel::Warning, String::from("value => 32")),
+                (ULogLevel::Warning, String::from("device => 12")),
+                (ULogLevel::Warning, String::from("firmware => 3")),
+                (ULogLevel::Warning, String::from("__END__")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scoped_context() {
+        let logger = common::ScopedContextLogger::<_, 1, 16>::new(TestLogger::default());
+
+        {
+            let _scope = {
+                let key = String::from("request");
+                logger.scope(&key, &1)
+            };
+            let _inner_scope = logger.scope("ignored", &2);
+            info!(logger, "Hello");
+        }
+        info!(logger, "world");
+
+        assert_eq!(
+            &logger.into_inner().logs.into_inner()[..],
             &[
                 (ULogLevel::Info, String::from("__BEGIN__")),
                 (ULogLevel::Info, String::from("Hello")),
+                (ULogLevel::Info, String::from("request => 1")),
                 (ULogLevel::Info, String::from("__END__")),
                 (ULogLevel::Info, String::from("__BEGIN__")),
                 (ULogLevel::Info, String::from("world")),
-                (ULogLevel::Info, String::from("value => 32")),
                 (ULogLevel::Info, String::from("__END__")),
             ]
         );
     }
 
     #[test]
-    fn test_min_level() {
-        let logger = TestLogger::default().min_level(ULogLevel::Warning);
+    fn test_correlation_id() {
+        let id = common::CorrelationId::from(0x1234u64);
+        assert_eq!(id.to_string(), "00000000000000000000000000001234");
+        assert_eq!(id.child(1), id.child(1));
+        assert_ne!(id.child(1), id.child(2));
+        assert_ne!(id.child(1), id.child(1).child(1));
 
-        for level in ULogLevel::all_levels() {
-            ulog!(level, logger, "Hello");
+        let logger = common::CorrelationLogger::new(TestLogger::default(), id);
+        info!(logger.child(1), "Hello");
+
+        assert_eq!(
+            logger.into_inner().logs.into_inner()[2],
+            (ULogLevel::Info, format!("trace_id => {}", id.child(1)))
+        );
+    }
+
+    #[test]
+    fn test_counter() {
+        let logger = common::CounterLogger::new(TestLogger::default());
+
+        info!(logger, "Hello");
+        error!(logger, "world");
+        error!(logger, "!");
+
+        assert_eq!(logger.count(ULogLevel::Info), 1);
+        assert_eq!(logger.count(ULogLevel::Error), 2);
+        assert_eq!(logger.total(), 3);
+
+        logger.reset();
+        assert!(logger.counts().all(|(_, count)| count == 0));
+    }
+
+    #[test]
+    fn test_sequence() {
+        let sequences = RefCell::new(Vec::new());
+        let logger = common::SequenceLogger::new(common::CallbackLogger::<_, 16>::new(
+            |log_data: &ULogData, _text: &str| sequences.borrow_mut().push(log_data.sequence),
+        ));
+
+        info!(logger, "Hello");
+        warn!(logger, "world");
+        info!(logger, "!");
+        logger.log_begin(&ULogData::new(ULogLevel::Info, 1, "main.rs").with_sequence(42));
+        logger.log_end(&ULogData::new(ULogLevel::Info, 1, "main.rs").with_sequence(42));
+
+        assert_eq!(logger.next_sequence(), 4);
+        assert_eq!(
+            sequences.into_inner(),
+            [Some(0), Some(1), Some(2), Some(42)]
+        );
+    }
+
+    #[test]
+    fn test_sequence_threads() {
+        struct BeginLogger(std::sync::Mutex<Vec<Option<u32>>>);
+
+        impl ULog for BeginLogger {
+            fn log_str(&self, _log_data: &ULogData, _string: &str) {}
+
+            fn log_format<T: core::fmt::Debug>(
+                &self,
+                _log_data: &ULogData,
+                _key: &str,
+                _value: &T,
+            ) {
+            }
+
+            fn log_begin(&self, log_data: &ULogData) {
+                self.0.lock().unwrap().push(log_data.sequence);
+            }
+
+            fn log_end(&self, _log_data: &ULogData) {}
         }
 
-        assert!(logger
-            .into_inner()
-            .logs
-            .into_inner()
+        let logger = common::SequenceLogger::new(BeginLogger(Default::default()));
+        std::thread::scope(|scope| {
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        info!(logger, "Hello");
+                    }
+                });
+            }
+        });
+
+        assert_eq!(logger.next_sequence(), 200);
+        let mut sequences = logger.into_inner().0.into_inner().unwrap();
+        sequences.sort();
+        assert_eq!(sequences, (0..200).map(Some).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_callback() {
+        let statements = RefCell::new(Vec::new());
+        let logger = common::CallbackLogger::<_, 64>::new(|log_data, text| {
+            statements
+                .borrow_mut()
+                .push((log_data.level, text.to_string()))
+        });
+
+        info!(logger, "Hello");
+        error!(logger, "world", "value" => 32, "name" => "ulog");
+
+        assert_eq!(
+            statements.into_inner(),
+            [
+                (ULogLevel::Info, String::from("Hello")),
+                (
+                    ULogLevel::Error,
+                    String::from(r#"world value=32 name="ulog""#)
+                ),
+            ]
+        );
+
+        // Statements made from the callback
+        let statements = RefCell::new(Vec::new());
+        let reporter = std::cell::OnceCell::<&dyn DynULog>::new();
+        let logger = common::CallbackLogger::<_, 64>::new(|log_data, text| {
+            statements.borrow_mut().push(text.to_string());
+            if log_data.level >= ULogLevel::Error {
+                info!(reporter.get().unwrap(), "Reported", "error" => text);
+            }
+        });
+        let _ = reporter.set(&logger);
+
+        error!(logger, "Overheating");
+        assert_eq!(
+            statements.into_inner(),
+            ["Overheating", r#"Reported error="Overheating""#]
+        );
+    }
+
+    #[test]
+    fn test_indent() {
+        let logger = common::IndentLogger::<_, 64>::new(TestLogger::default());
+
+        info!(logger, "Hello");
+        {
+            let _guard = logger.enter();
+            let _guard = logger.enter();
+            info!(logger, "world", "value" => 32);
+        }
+        info!(logger, "!");
+
+        let logs = logger.into_inner().logs.into_inner();
+        let messages = logs
             .iter()
-            .all(|log| log.0 >= ULogLevel::Warning));
+            .map(|log| log.1.as_str())
+            .filter(|message| !message.starts_with("__"))
+            .collect::<Vec<_>>();
+        assert_eq!(messages, ["Hello", "    world", "value => 32", "!"]);
+    }
+
+    #[test]
+    fn test_key_filter() {
+        let filter = common::KeyFilter::Allow(&["size"]);
+        assert!(filter.allows("size"));
+        assert!(!filter.allows("payload"));
+
+        let logger = common::KeyFilterLogger::new(
+            TestLogger::default(),
+            common::KeyFilter::Deny(&["payload"]),
+        );
+        info!(logger, "Received", "size" => 3, "payload" => [1, 2, 3]);
+
+        assert_eq!(
+            &logger.into_inner().logs.into_inner()[..],
+            &[
+                (ULogLevel::Info, String::from("__BEGIN__")),
+                (ULogLevel::Info, String::from("Received")),
+                (ULogLevel::Info, String::from("size => 3")),
+                (ULogLevel::Info, String::from("__END__")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncate() {
+        let logger = common::TruncateLogger::<_, 16>::new(TestLogger::default(), 8);
+
+        info!(logger, "Hello", "short" => [1, 2], "long" => [1; 100]);
+
+        let logs = logger.into_inner().logs.into_inner();
+        assert_eq!(logs[2].1, "short => [1, 2]");
+        assert_eq!(logs[3].1, "long => [1, 1, 1… (300 bytes)");
+    }
+
+    #[test]
+    fn test_truncate_reentrant() {
+        // Statements made by the wrapped logger while it is given a value
+        let statements = TestLogger::default();
+        let truncate = std::cell::OnceCell::<&dyn DynULog>::new();
+        let sink = common::TransformLogger::new(&statements, |_, entry, emit| {
+            if let common::Entry::Field("error", _) = entry {
+                info!(truncate.get().unwrap(), "Reported", "code" => [7; 10]);
+            }
+            emit(entry)
+        });
+        let logger = common::TruncateLogger::<_, 16>::new(&sink, 8);
+        let _ = truncate.set(&logger);
+
+        error!(logger, "Overheating", "error" => [1; 100]);
+
+        let logs = statements.logs.borrow();
+        assert_eq!(logs[4].1, "code => [7, 7, 7… (30 bytes)");
+        assert_eq!(logs[6].1, "error => [1, 1, 1… (300 bytes)");
+    }
+
+    #[test]
+    fn test_truncate_json() {
+        use format::{FormattedLogger, JsonFormatter};
+
+        let output = RefCell::new(Vec::new());
+        let logger = common::TruncateLogger::<_, 16>::new(
+            FormattedLogger::new(JsonFormatter::new(), |bytes: &[u8]| {
+                output.borrow_mut().extend_from_slice(bytes)
+            }),
+            8,
+        );
+
+        logger
+            .at(ULogLevel::Info)
+            .msg("Hello")
+            .value("name", "abc")
+            .value("long", "abcdefghijkl")
+            .emit();
+
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        assert!(output.contains("\"name\":\"abc\""));
+        assert!(output.contains("\"long\":\"\\\"abcdefg… (14 bytes)\""));
+    }
+
+    #[test]
+    fn test_transform() {
+        // Records the metadata of the entries reaching the wrapped logger
+        let metadata = RefCell::new(Vec::new());
+        let sink = common::TransformLogger::new(TestLogger::default(), |log_data, entry, emit| {
+            metadata
+                .borrow_mut()
+                .push((log_data.level, log_data.line, log_data.file));
+            emit(entry)
+        });
+        let logger = common::TransformLogger::new(&sink, |_, entry, emit| match entry {
+            common::Entry::Str(string) => {
+                emit(common::Entry::Str(&string.to_uppercase()));
+                emit(common::Entry::Field("len", &string.len()));
+            }
+            common::Entry::Field("password", _) => emit(common::Entry::Field("password", &"***")),
+            common::Entry::Field("debug", _) => {}
+            entry => emit(entry),
+        });
+
+        let line = line!() + 1;
+        warn!(logger, "Hello", "password" => "hunter2", "debug" => true, "value" => 32);
+
+        assert_eq!(
+            &sink.into_inner().logs.into_inner()[..],
+            &[
+                (ULogLevel::Warning, String::from("__BEGIN__")),
+                (ULogLevel::Warning, String::from("HELLO")),
+                (ULogLevel::Warning, String::from("len => 5")),
+                (ULogLevel::Warning, String::from("password => \"***\"")),
+                (ULogLevel::Warning, String::from("value => 32")),
+                (ULogLevel::Warning, String::from("__END__")),
+            ]
+        );
+        assert_eq!(metadata.borrow().len(), 4);
+        assert!(metadata
+            .borrow()
+            .iter()
+            .all(|metadata| *metadata == (ULogLevel::Warning, line, file!())));
+    }
+
+    /// Records the payloads logged with `log_bytes` and the values logged with `log_display`,
+    /// which it tells apart from other values.
+    #[derive(Default)]
+    struct BytesLogger {
+        payloads: RefCell<Vec<String>>,
+    }
+
+    impl ULog for BytesLogger {
+        fn log_str(&self, _log_data: &ULogData, _string: &str) {}
+
+        fn log_format<T: core::fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+            self.payloads
+                .borrow_mut()
+                .push(format!("{key} => {:?}", value));
+        }
+
+        fn log_bytes(&self, _log_data: &ULogData, key: &str, bytes: &[u8]) {
+            self.payloads
+                .borrow_mut()
+                .push(format!("{key} => {} bytes", bytes.len()));
+        }
+
+        fn log_display<T: core::fmt::Display>(&self, _log_data: &ULogData, key: &str, value: &T) {
+            self.payloads
+                .borrow_mut()
+                .push(format!("{key} =>% {}", value));
+        }
+
+        fn log_begin(&self, _log_data: &ULogData) {}
+
+        fn log_end(&self, _log_data: &ULogData) {}
+    }
+
+    #[test]
+    fn test_forward_bytes() {
+        let sink = BytesLogger::default();
+        let log = |logger: &dyn DynULog| {
+            logger
+                .at(ULogLevel::Info)
+                .bytes("payload", &[1, 2, 3])
+                .emit()
+        };
+
+        log(&common::ContextLogger::new(&sink, ("id", 1)));
+        log(&common::ScopedContextLogger::<_, 1, 8>::new(&sink));
+        log(&common::CorrelationLogger::new(&sink, 1u64));
+        log(&common::SequenceLogger::new(&sink));
+        log(&common::IndentLogger::<_, 8>::new(&sink));
+        log(&common::TruncateLogger::<_, 16>::new(&sink, 16));
+        log(&common::TransformLogger::new(&sink, |_, entry, emit| {
+            emit(entry)
+        }));
+        log(&time::TimestampLogger::new(&sink, || {
+            core::time::Duration::ZERO
+        }));
+        log(&common::TruncateLogger::<_, 16>::new(&sink, 4));
+
+        let payloads = sink.payloads.into_inner();
+        let payloads = payloads
+            .iter()
+            .filter(|payload| payload.starts_with("payload"))
+            .collect::<Vec<_>>();
+        assert_eq!(payloads.len(), 9);
+        assert!(payloads[..8]
+            .iter()
+            .all(|payload| *payload == "payload => 3 bytes"));
+        assert_eq!(payloads[8], "payload => [1, … (9 bytes)");
+    }
+
+    #[test]
+    fn test_forward_display() {
+        let sink = BytesLogger::default();
+        let log = |logger: &dyn DynULog| {
+            logger
+                .at(ULogLevel::Info)
+                .display("address", "10.0.0.1")
+                .emit()
+        };
+        let clock = || core::time::Duration::ZERO;
+
+        log(&common::ContextLogger::new(&sink, ("id", 1)));
+        log(&common::ScopedContextLogger::<_, 1, 8>::new(&sink));
+        log(&common::CorrelationLogger::new(&sink, 1u64));
+        log(&common::CounterLogger::new(&sink));
+        log(&common::SequenceLogger::new(&sink));
+        log(&common::IndentLogger::<_, 8>::new(&sink));
+        log(&common::KeyFilterLogger::new(
+            &sink,
+            common::KeyFilter::Deny(&["password"]),
+        ));
+        log(&common::TruncateLogger::<_, 16>::new(&sink, 16));
+        log(&time::SpanLogger::new(&sink, clock));
+        log(&time::TimestampLogger::new(&sink, clock));
+        log(&time::DeltaTimeLogger::<_, _, 16>::new(
+            &sink,
+            clock,
+            time::DeltaMode::SinceStart,
+        ));
+        log(&buffer::LastErrorLogger::<_, 1, 64>::new(&sink));
+        log(&common::TruncateLogger::<_, 16>::new(&sink, 4));
+
+        let payloads = sink.payloads.into_inner();
+        let payloads = payloads
+            .iter()
+            .filter(|payload| payload.starts_with("address"))
+            .collect::<Vec<_>>();
+        assert_eq!(payloads.len(), 13);
+        assert!(payloads[..12]
+            .iter()
+            .all(|payload| *payload == "address =>% 10.0.0.1"));
+        assert_eq!(payloads[12], "address =>% 10.0… (8 bytes)");
     }
 }
@@ -0,0 +1,331 @@
+use super::atomic;
+use super::record::Record;
+#[cfg(feature = "serde")]
+use super::serialize::SerializeJson;
+use super::{ULog, ULogData, Value};
+use core::cell::{Cell, UnsafeCell};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+const FREE: usize = 0;
+const WRITING: usize = 1;
+const READY: usize = 2;
+const READING: usize = 3;
+
+struct Slot<const SIZE: usize> {
+    state: AtomicUsize,
+    /// The order in which the statements were completed
+    ticket: AtomicUsize,
+    record: UnsafeCell<Option<Record<SIZE>>>,
+}
+
+impl<const SIZE: usize> Slot<SIZE> {
+    const fn new() -> Self {
+        Self {
+            state: AtomicUsize::new(FREE),
+            ticket: AtomicUsize::new(0),
+            record: UnsafeCell::new(None),
+        }
+    }
+}
+
+/// Queues logging statements without locks, so that tasks and interrupts of any priority, on any core,
+/// can log without ever waiting for one another, until [`drain`](LockFreeQueue::drain) forwards them to the
+/// actual logger, typically from the idle task of an RTIC application.
+///
+/// Statements are made through [`LockFreeLogger`]s, the producers of the queue, with one of them for each task or
+/// interrupt handler logging to it, like a local resource of an RTIC task. Since a statement is only written by
+/// the logger that made it, sharing a UART between priorities with a resource lock, and the priority inversion
+/// that comes with it, is not needed anymore.
+///
+/// Up to `STATEMENTS` statements are kept, each of them taking up to `SIZE` bytes (see [`Record`]);
+/// when all of them are taken, new statements are dropped and counted in [`dropped`](LockFreeQueue::dropped).
+///
+/// On targets without atomic compare-and-swap, like `thumbv6m-none-eabi`, the queue requires the
+/// `critical-section` feature, and the few atomic operations of a statement are then made in critical sections.
+///
+/// ```
+/// use ulog::common::StubLogger;
+/// use ulog::lock_free::LockFreeQueue;
+///
+/// static QUEUE: LockFreeQueue<8, 128> = LockFreeQueue::new();
+///
+/// // In a task or an interrupt handler, at any priority, with a logger of its own
+/// let logger = QUEUE.logger();
+/// ulog::info!(logger, "Button pressed");
+///
+/// // In the idle task, with the actual logger
+/// QUEUE.drain(&StubLogger);
+/// ```
+pub struct LockFreeQueue<const STATEMENTS: usize, const SIZE: usize> {
+    slots: [Slot<SIZE>; STATEMENTS],
+    next_ticket: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+// A slot is only written by the logger that moved it from `FREE` to `WRITING`, and only read by the context
+// that moved it from `READY` to `READING`
+unsafe impl<const STATEMENTS: usize, const SIZE: usize> Sync for LockFreeQueue<STATEMENTS, SIZE> {}
+
+impl<const STATEMENTS: usize, const SIZE: usize> LockFreeQueue<STATEMENTS, SIZE> {
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { Slot::new() }; STATEMENTS],
+            next_ticket: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns a new producer of the queue, for a task or an interrupt handler.
+    pub const fn logger(&self) -> LockFreeLogger<'_, STATEMENTS, SIZE> {
+        LockFreeLogger {
+            queue: self,
+            slot: Cell::new(None),
+            depth: Cell::new(0),
+        }
+    }
+
+    /// Returns the number of complete statements waiting to be drained.
+    pub fn len(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.state.load(Ordering::Acquire) == READY)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of statements dropped since the creation of the queue or the last call to
+    /// [`reset_dropped`](LockFreeQueue::reset_dropped).
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn reset_dropped(&self) {
+        self.dropped.store(0, Ordering::Relaxed);
+    }
+
+    /// Forwards all of the complete statements to `logger`, from the oldest to the newest.
+    ///
+    /// Meant to be called from the lowest priority, like the idle task, where the time taken by `logger`
+    /// doesn't delay the other tasks.
+    pub fn drain(&self, logger: &impl ULog) {
+        while let Some(slot) = self.oldest() {
+            if atomic::compare_exchange(&slot.state, READY, READING).is_err() {
+                continue;
+            }
+
+            if let Some(record) = unsafe { &*slot.record.get() } {
+                record.replay(logger);
+            }
+            slot.state.store(FREE, Ordering::Release);
+        }
+    }
+
+    /// Returns the complete statement with the oldest ticket.
+    fn oldest(&self) -> Option<&Slot<SIZE>> {
+        let next_ticket = self.next_ticket.load(Ordering::Relaxed);
+
+        self.slots
+            .iter()
+            .filter(|slot| slot.state.load(Ordering::Acquire) == READY)
+            .max_by_key(|slot| next_ticket.wrapping_sub(slot.ticket.load(Ordering::Relaxed)))
+    }
+
+    /// Takes a free slot for a new statement, or counts it as dropped.
+    fn claim(&self, log_data: &ULogData) -> Option<usize> {
+        let index = self
+            .slots
+            .iter()
+            .position(|slot| atomic::compare_exchange(&slot.state, FREE, WRITING).is_ok());
+
+        match index {
+            // The slot is `WRITING`, and only known by the caller
+            Some(index) => unsafe {
+                *self.slots[index].record.get() = Some(Record::new(log_data.clone()));
+            },
+            None => self.count_dropped(),
+        }
+        index
+    }
+
+    /// Hands a complete statement over to [`drain`](LockFreeQueue::drain).
+    fn publish(&self, index: usize) {
+        let slot = &self.slots[index];
+        slot.ticket.store(
+            atomic::fetch_add(&self.next_ticket, 1, Ordering::Relaxed),
+            Ordering::Relaxed,
+        );
+        slot.state.store(READY, Ordering::Release);
+    }
+
+    fn count_dropped(&self) {
+        atomic::fetch_add(&self.dropped, 1, Ordering::Relaxed);
+    }
+}
+
+impl<const STATEMENTS: usize, const SIZE: usize> Default for LockFreeQueue<STATEMENTS, SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A producer of a [`LockFreeQueue`], given by [`LockFreeQueue::logger`].
+///
+/// Each task or interrupt handler is meant to log with a logger of its own, which is why it can't be shared
+/// between threads; a logger only keeps track of the statement it is making, so that the statements of the
+/// other loggers can freely interrupt it. A statement made while another one of the same logger is in progress,
+/// like from the `Debug` implementation of one of its values, is dropped.
+pub struct LockFreeLogger<'a, const STATEMENTS: usize, const SIZE: usize> {
+    queue: &'a LockFreeQueue<STATEMENTS, SIZE>,
+    /// The slot of the statement in progress, if it got one
+    slot: Cell<Option<usize>>,
+    /// The number of statements in progress, the outermost of which is the only one kept
+    depth: Cell<usize>,
+}
+
+impl<const STATEMENTS: usize, const SIZE: usize> LockFreeLogger<'_, STATEMENTS, SIZE> {
+    pub fn queue(&self) -> &LockFreeQueue<STATEMENTS, SIZE> {
+        self.queue
+    }
+
+    fn with_record(&self, f: impl FnOnce(&mut Record<SIZE>)) {
+        if self.depth.get() != 1 {
+            return;
+        }
+        if let Some(index) = self.slot.get() {
+            // The slot is `WRITING`, and was claimed by this logger
+            if let Some(record) = unsafe { &mut *self.queue.slots[index].record.get() } {
+                f(record);
+            }
+        }
+    }
+}
+
+impl<const STATEMENTS: usize, const SIZE: usize> ULog for LockFreeLogger<'_, STATEMENTS, SIZE> {
+    fn log_str(&self, _log_data: &ULogData, string: &str) {
+        self.with_record(|record| record.push_str(string));
+    }
+
+    fn log_str_continued(&self, _log_data: &ULogData, string: &str) {
+        self.with_record(|record| record.push_str_continued(string));
+    }
+
+    fn log_fmt(&self, _log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.with_record(|record| record.push_fmt(args));
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+        self.with_record(|record| record.push_format(key, value));
+    }
+
+    fn log_value(&self, _log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.with_record(|record| record.push_value(key, value));
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, _log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        self.with_record(|record| record.push_serialize(key, value));
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        let depth = self.depth.get();
+        self.depth.set(depth + 1);
+
+        if depth == 0 {
+            self.slot.set(self.queue.claim(log_data));
+        } else {
+            self.queue.count_dropped();
+        }
+    }
+
+    fn log_end(&self, _log_data: &ULogData) {
+        let depth = self.depth.get().saturating_sub(1);
+        self.depth.set(depth);
+
+        if depth == 0 {
+            if let Some(index) = self.slot.take() {
+                self.queue.publish(index);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+    use core::cell::RefCell;
+
+    #[derive(Default)]
+    struct VecLogger {
+        logs: RefCell<Vec<String>>,
+    }
+
+    impl ULog for VecLogger {
+        fn log_str(&self, _log_data: &ULogData, string: &str) {
+            self.logs.borrow_mut().push(string.to_string());
+        }
+
+        fn log_format<T: core::fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+            self.logs.borrow_mut().push(format!("{key}={value:?}"));
+        }
+
+        fn log_begin(&self, log_data: &ULogData) {
+            self.logs.borrow_mut().push(log_data.level.to_string());
+        }
+
+        fn log_end(&self, _log_data: &ULogData) {}
+    }
+
+    struct Nested<'a>(&'a LockFreeLogger<'a, 2, 64>);
+
+    impl core::fmt::Debug for Nested<'_> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            error!(self.0, "Nested");
+            write!(f, "nested")
+        }
+    }
+
+    #[test]
+    fn test_lock_free_queue() {
+        let queue = LockFreeQueue::<2, 64>::new();
+        let task = queue.logger();
+        let interrupt = queue.logger();
+        let sink = VecLogger::default();
+
+        // A statement interrupted by two others, the last of which doesn't fit
+        let log_data = ULogData::new(ULogLevel::Info, line!(), file!());
+        task.log_begin(&log_data);
+        task.log_str(&log_data, "Interrupted");
+        warn!(interrupt, "Interrupt", "value" => 32);
+        error!(interrupt, "Dropped");
+        task.log_format(&log_data, "done", &true);
+        task.log_end(&log_data);
+
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.dropped(), 1);
+
+        queue.drain(&sink);
+        assert!(queue.is_empty());
+        debug!(task, "After", "value" => Nested(&task));
+        queue.drain(&sink);
+        assert_eq!(queue.dropped(), 2);
+
+        assert_eq!(
+            sink.logs.borrow()[..],
+            [
+                "WARN",
+                "Interrupt",
+                "value=32",
+                "INFO",
+                "Interrupted",
+                "done=true",
+                "DEBUG",
+                "After",
+                "value=nested"
+            ]
+        );
+    }
+}
@@ -0,0 +1,418 @@
+use super::record::{Record, Segment};
+#[cfg(feature = "serde")]
+use super::serialize::SerializeJson;
+use super::{Level, ULog, ULogData, ULogLevel, Value};
+use core::cell::RefCell;
+use core::fmt;
+
+/// The levels of the `log` crate can be used with the macros, as `ulog!(log::Level::Warn, logger, "message")`.
+///
+/// As with `log`, they are ordered from the least to the most verbose; loggers compare the converted levels.
+impl Level for log::Level {
+    fn as_str(&self) -> &'static str {
+        log::Level::as_str(self)
+    }
+
+    fn to_ulog_level(&self) -> ULogLevel {
+        match self {
+            log::Level::Error => ULogLevel::Error,
+            log::Level::Warn => ULogLevel::Warning,
+            log::Level::Info => ULogLevel::Info,
+            log::Level::Debug => ULogLevel::Debug,
+            log::Level::Trace => ULogLevel::Trace,
+        }
+    }
+}
+
+/// Exposes a [`ULog`] as a [`log::Log`], so that the statements of the crates using the `log` facade
+/// go through the same loggers as the ones of the application.
+///
+/// The message is logged with [`log_fmt`](ULog::log_fmt), followed by the key-value pairs of the record.
+/// The data of the statement can only hold `'static` strings: the file and the module path of the record are kept
+/// when they are, as they are for the `log` macros, and so is the target when it is the module path, as it is by
+/// default; other targets are logged as a `target` key-value pair instead.
+///
+/// ```
+/// use ulog::log_bridge::LogBridge;
+/// use ulog::common::StubLogger;
+///
+/// static BRIDGE: LogBridge<StubLogger> = LogBridge::new(StubLogger);
+///
+/// BRIDGE.install().unwrap();
+/// log::info!("Sent to the StubLogger");
+/// ```
+#[derive(Debug, Default)]
+pub struct LogBridge<Logger> {
+    logger: Logger,
+}
+
+impl<Logger: ULog> LogBridge<Logger> {
+    pub const fn new(logger: Logger) -> Self {
+        Self { logger }
+    }
+
+    pub fn logger(&self) -> &Logger {
+        &self.logger
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+}
+
+impl<Logger: ULog + Send + Sync> LogBridge<Logger> {
+    /// Registers the bridge as the logger of the `log` facade with [`log::set_logger`], and lets every level
+    /// through with [`log::set_max_level`], so that statements are only filtered by the wrapped logger.
+    pub fn install(&'static self) -> Result<(), log::SetLoggerError> {
+        log::set_logger(self)?;
+        log::set_max_level(log::LevelFilter::Trace);
+        Ok(())
+    }
+}
+
+impl<Logger: ULog + Send + Sync> log::Log for LogBridge<Logger> {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        let log_data = ULogData::new(metadata.level().to_ulog_level(), 0, "");
+        self.logger.enabled(&log_data)
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        let mut log_data = ULogData::new(
+            record.level().to_ulog_level(),
+            record.line().unwrap_or(0),
+            record.file_static().unwrap_or("<unknown>"),
+        );
+        let mut target = Some(record.target());
+        if let Some(module) = record.module_path_static() {
+            log_data = log_data.with_module(module);
+            if target == Some(module) {
+                log_data = log_data.with_target(module);
+                target = None;
+            }
+        }
+
+        if !self.logger.enabled(&log_data) {
+            return;
+        }
+
+        self.logger.log_begin(&log_data);
+        self.logger.log_fmt(&log_data, *record.args());
+        let _ = record.key_values().visit(&mut KeyValueVisitor {
+            logger: &self.logger,
+            log_data: &log_data,
+        });
+        if let Some(target) = target {
+            self.logger.log_format(&log_data, "target", &target);
+        }
+        self.logger.log_end(&log_data);
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+}
+
+/// Logs the key-value pairs of a [`log::Record`].
+struct KeyValueVisitor<'a, Logger> {
+    logger: &'a Logger,
+    log_data: &'a ULogData,
+}
+
+impl<'kvs, Logger: ULog> log::kv::VisitSource<'kvs> for KeyValueVisitor<'_, Logger> {
+    fn visit_pair(
+        &mut self,
+        key: log::kv::Key<'kvs>,
+        value: log::kv::Value<'kvs>,
+    ) -> Result<(), log::kv::Error> {
+        self.logger
+            .log_format(self.log_data, key.as_str(), &DebugValue(value));
+        Ok(())
+    }
+}
+
+/// Formats a [`log::kv::Value`] as the value it holds.
+struct DebugValue<'v>(log::kv::Value<'v>);
+
+impl fmt::Debug for DebugValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+/// Returns the level of the `log` crate matching `level`.
+fn to_log_level(level: ULogLevel) -> log::Level {
+    match level {
+        ULogLevel::Trace => log::Level::Trace,
+        ULogLevel::Debug => log::Level::Debug,
+        ULogLevel::Info | ULogLevel::Notice => log::Level::Info,
+        ULogLevel::Warning => log::Level::Warn,
+        ULogLevel::Error | ULogLevel::Critical => log::Level::Error,
+    }
+}
+
+/// Forwards statements to the logger of the `log` facade, so that libraries written against ulog can be used
+/// in applications set up with `log` loggers, like `env_logger`.
+///
+/// Each statement is captured into a [`Record`] of `SIZE` bytes, then logged when it ends: its strings, joined
+/// with spaces, make up the message, and its key-value pairs are passed as the key-values of the `log` record.
+/// [`Notice`](ULogLevel::Notice) is logged as `Info`, and [`Critical`](ULogLevel::Critical) as `Error`.
+///
+/// ```
+/// use ulog::log_bridge::LogCrateLogger;
+///
+/// let logger = LogCrateLogger::<128>::new();
+/// ulog::info!(logger, "Connected", "port" => 8080); // Logged with `log::info!`
+/// ```
+pub struct LogCrateLogger<const SIZE: usize> {
+    current: RefCell<Option<Record<SIZE>>>,
+}
+
+impl<const SIZE: usize> LogCrateLogger<SIZE> {
+    pub const fn new() -> Self {
+        Self {
+            current: RefCell::new(None),
+        }
+    }
+}
+
+impl<const SIZE: usize> Default for LogCrateLogger<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SIZE: usize> ULog for LogCrateLogger<SIZE> {
+    fn log_str(&self, _log_data: &ULogData, string: &str) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_str(string);
+        }
+    }
+
+    fn log_str_continued(&self, _log_data: &ULogData, string: &str) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_str_continued(string);
+        }
+    }
+
+    fn log_fmt(&self, _log_data: &ULogData, args: fmt::Arguments<'_>) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_fmt(args);
+        }
+    }
+
+    fn log_format<T: fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_format(key, value);
+        }
+    }
+
+    fn log_value(&self, _log_data: &ULogData, key: &str, value: Value<'_>) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            match value {
+                // `log` has no byte slices, they are passed with their `Debug` representation
+                Value::Bytes(_) => record.push_format(key, &value),
+                _ => record.push_value(key, value),
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, _log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_serialize(key, value);
+        }
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        *self.current.borrow_mut() = Some(Record::new(log_data.clone()));
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        let Some(record) = self.current.borrow_mut().take() else {
+            return;
+        };
+
+        log::logger().log(
+            &log::Record::builder()
+                .level(to_log_level(log_data.level))
+                .target(log_data.target.or(log_data.module).unwrap_or_default())
+                .module_path_static(log_data.module)
+                .file_static(Some(log_data.file))
+                .line(Some(log_data.line))
+                .args(format_args!("{}", Message(&record)))
+                .key_values(&Fields(&record))
+                .build(),
+        );
+    }
+
+    fn flush(&self) {
+        log::logger().flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        let level = to_log_level(log_data.level);
+
+        level <= log::max_level()
+            && log::logger().enabled(
+                &log::Metadata::builder()
+                    .level(level)
+                    .target(log_data.target.or(log_data.module).unwrap_or_default())
+                    .build(),
+            )
+    }
+}
+
+/// The strings of a [`Record`], joined with spaces.
+struct Message<'a, const SIZE: usize>(&'a Record<SIZE>);
+
+impl<const SIZE: usize> fmt::Display for Message<'_, SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let strings = self.0.segments().filter_map(|segment| match segment {
+            Segment::Str(string) => Some(string),
+            Segment::Field(..) | Segment::Value(..) | Segment::Serialized(..) => None,
+        });
+        for (index, string) in strings.enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(string)?;
+        }
+        Ok(())
+    }
+}
+
+/// The key-value pairs of a [`Record`], as a [`log::kv::Source`].
+struct Fields<'a, const SIZE: usize>(&'a Record<SIZE>);
+
+impl<const SIZE: usize> log::kv::Source for Fields<'_, SIZE> {
+    fn visit<'kvs>(
+        &'kvs self,
+        visitor: &mut dyn log::kv::VisitSource<'kvs>,
+    ) -> Result<(), log::kv::Error> {
+        for segment in self.0.segments() {
+            let (key, value) = match segment {
+                Segment::Str(_) => continue,
+                Segment::Field(key, value) | Segment::Serialized(key, value) => {
+                    (key, log::kv::Value::from(value))
+                }
+                Segment::Value(key, value) => (
+                    key,
+                    match value {
+                        Value::I64(value) => log::kv::Value::from(value),
+                        Value::U64(value) => log::kv::Value::from(value),
+                        Value::F64(value) => log::kv::Value::from(value),
+                        Value::Bool(value) => log::kv::Value::from(value),
+                        Value::Str(value) => log::kv::Value::from(value),
+                        // Pushed with their `Debug` representation
+                        Value::Bytes(_) => continue,
+                    },
+                ),
+            };
+            visitor.visit_pair(log::kv::Key::from_str(key), value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use log::Log;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct SyncLogger {
+        logs: Mutex<Vec<String>>,
+    }
+
+    impl ULog for SyncLogger {
+        fn log_str(&self, _log_data: &ULogData, string: &str) {
+            self.logs.lock().unwrap().push(string.to_string());
+        }
+
+        fn log_format<T: fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+            self.logs
+                .lock()
+                .unwrap()
+                .push(format!("{key} => {value:?}"));
+        }
+
+        fn log_begin(&self, log_data: &ULogData) {
+            self.logs
+                .lock()
+                .unwrap()
+                .push(format!("{} {:?}", log_data.level, log_data.target));
+        }
+
+        fn log_end(&self, _log_data: &ULogData) {}
+    }
+
+    #[test]
+    fn test_log_crate_logger() {
+        // Statements make a round trip through the `log` facade
+        static BRIDGE: LogBridge<SyncLogger> = LogBridge::new(SyncLogger {
+            logs: Mutex::new(Vec::new()),
+        });
+        BRIDGE.install().unwrap();
+
+        let logger = LogCrateLogger::<64>::new();
+        let log_data = ULogData::new(ULogLevel::Info, 1, "main.rs").with_target("app");
+        logger.log_begin(&log_data);
+        logger.log_str(&log_data, "Connected to");
+        logger.log_str(&log_data, "server");
+        logger.log_format(&log_data, "port", &8080);
+        logger.log_end(&log_data);
+        crate::debug!(logger, "Hello");
+        crate::ulog!(target: "net", ULogLevel::Critical, logger, "read {} bytes", 12);
+
+        assert_eq!(
+            BRIDGE.logger().logs.lock().unwrap()[..],
+            [
+                "INFO None",
+                "Connected to server",
+                "port => \"8080\"",
+                "target => \"app\"",
+                "DEBUG Some(\"ulog::log_bridge::test\")",
+                "Hello",
+                "ERROR None",
+                "read 12 bytes",
+                "target => \"net\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_log_bridge() {
+        let bridge = LogBridge::new(SyncLogger::default());
+        let key_values = [("port", 8080)];
+
+        bridge.log(
+            &log::Record::builder()
+                .level(log::Level::Warn)
+                .target(module_path!())
+                .module_path_static(Some(module_path!()))
+                .args(format_args!("read {} bytes", 12))
+                .key_values(&key_values)
+                .build(),
+        );
+        bridge.log(
+            &log::Record::builder()
+                .level(log::Level::Debug)
+                .target("net")
+                .args(format_args!("Hello"))
+                .build(),
+        );
+
+        assert_eq!(
+            bridge.into_inner().logs.into_inner().unwrap(),
+            [
+                "WARN Some(\"ulog::log_bridge::test\")",
+                "read 12 bytes",
+                "port => 8080",
+                "DEBUG None",
+                "Hello",
+                "target => \"net\"",
+            ]
+        );
+    }
+}
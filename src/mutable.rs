@@ -0,0 +1,286 @@
+use super::record::TextBuffer;
+use super::{ULog, ULogData, Value, FMT_BUFFER_SIZE};
+use core::cell::RefCell;
+use core::fmt::{self, Debug, Write};
+
+/// A logger needing exclusive access to log, like a driver for a peripheral taking `&mut self`;
+/// the counterpart of [`ULog`] for loggers that would otherwise need interior mutability.
+///
+/// It is turned into a [`ULog`] with [`LockedLogger`], which takes a [`ULogLock`] deciding how the logger is shared.
+/// Conversely, a reference to any [`ULog`] implements it.
+pub trait ULogMut {
+    fn log_str(&mut self, log_data: &ULogData, string: &str);
+
+    /// See [`ULog::log_str_continued`]; defaults to [`log_str`](ULogMut::log_str).
+    fn log_str_continued(&mut self, log_data: &ULogData, string: &str) {
+        self.log_str(log_data, string)
+    }
+
+    /// See [`ULog::log_fmt`]; defaults to [`log_str`](ULogMut::log_str), rendering the message
+    /// into a stack buffer of [`FMT_BUFFER_SIZE`] bytes first.
+    fn log_fmt(&mut self, log_data: &ULogData, args: fmt::Arguments<'_>) {
+        match args.as_str() {
+            Some(string) => self.log_str(log_data, string),
+            None => {
+                let mut buffer = TextBuffer::<FMT_BUFFER_SIZE>::new();
+                let _ = buffer.write_fmt(args);
+                self.log_str(log_data, buffer.as_str());
+            }
+        }
+    }
+
+    fn log_format<T: Debug>(&mut self, log_data: &ULogData, key: &str, value: &T);
+
+    /// See [`ULog::log_value`]; defaults to [`log_format`](ULogMut::log_format).
+    fn log_value(&mut self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.log_format(log_data, key, &value)
+    }
+
+    fn log_begin(&mut self, log_data: &ULogData);
+
+    fn log_end(&mut self, log_data: &ULogData);
+
+    /// See [`ULog::flush`]; defaults to doing nothing.
+    fn flush(&mut self) {}
+}
+
+impl<Logger: ULog + ?Sized> ULogMut for &Logger {
+    fn log_str(&mut self, log_data: &ULogData, string: &str) {
+        ULog::log_str(*self, log_data, string)
+    }
+
+    fn log_str_continued(&mut self, log_data: &ULogData, string: &str) {
+        ULog::log_str_continued(*self, log_data, string)
+    }
+
+    fn log_fmt(&mut self, log_data: &ULogData, args: fmt::Arguments<'_>) {
+        ULog::log_fmt(*self, log_data, args)
+    }
+
+    fn log_format<T: Debug>(&mut self, log_data: &ULogData, key: &str, value: &T) {
+        ULog::log_format(*self, log_data, key, value)
+    }
+
+    fn log_value(&mut self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        ULog::log_value(*self, log_data, key, value)
+    }
+
+    fn log_begin(&mut self, log_data: &ULogData) {
+        ULog::log_begin(*self, log_data)
+    }
+
+    fn log_end(&mut self, log_data: &ULogData) {
+        ULog::log_end(*self, log_data)
+    }
+
+    fn flush(&mut self) {
+        ULog::flush(*self)
+    }
+}
+
+/// Gives exclusive access to a [`ULogMut`] from a shared reference, using some kind of synchronization.
+///
+/// Implemented for [`RefCell`], and for `critical_section::Mutex<RefCell<_>>` with the `critical-section` feature;
+/// other locks can implement it as well.
+pub trait ULogLock {
+    type Logger: ULogMut;
+
+    /// Calls `f` with exclusive access to the logger.
+    fn with_logger<R>(&self, f: impl FnOnce(&mut Self::Logger) -> R) -> R;
+}
+
+impl<Logger: ULogMut> ULogLock for RefCell<Logger> {
+    type Logger = Logger;
+
+    /// Panics if the logger is already borrowed, as when logging to it from within one of its methods.
+    fn with_logger<R>(&self, f: impl FnOnce(&mut Logger) -> R) -> R {
+        f(&mut self.borrow_mut())
+    }
+}
+
+#[cfg(feature = "critical-section")]
+impl<Logger: ULogMut> ULogLock for critical_section::Mutex<RefCell<Logger>> {
+    type Logger = Logger;
+
+    fn with_logger<R>(&self, f: impl FnOnce(&mut Logger) -> R) -> R {
+        critical_section::with(|cs| f(&mut self.borrow_ref_mut(cs)))
+    }
+}
+
+/// Turns a [`ULogMut`] behind a [`ULogLock`] into a [`ULog`].
+///
+/// The lock is taken once for each call made to the logger, not for whole statements: a statement made from an
+/// interrupt handler may thus get interleaved with the one it interrupted. Contexts preempting each other should
+/// log through a [`LockFreeQueue`](crate::lock_free::LockFreeQueue) instead, and threads through a
+/// `ChannelLogger` (with the `std` feature), which both capture whole statements before forwarding them.
+///
+/// ```
+/// use core::cell::RefCell;
+/// use ulog::mutable::{LockedLogger, ULogMut};
+/// use ulog::ULogData;
+///
+/// struct Uart {
+///     sent: usize,
+/// }
+///
+/// impl Uart {
+///     fn write(&mut self, bytes: &[u8]) {
+///         self.sent += bytes.len();
+///     }
+/// }
+///
+/// impl ULogMut for Uart {
+///     fn log_str(&mut self, _log_data: &ULogData, string: &str) {
+///         self.write(string.as_bytes());
+///     }
+///
+///     fn log_format<T: core::fmt::Debug>(&mut self, _log_data: &ULogData, key: &str, _value: &T) {
+///         self.write(key.as_bytes());
+///     }
+///
+///     fn log_begin(&mut self, _log_data: &ULogData) {}
+///
+///     fn log_end(&mut self, _log_data: &ULogData) {
+///         self.write(b"\n");
+///     }
+/// }
+///
+/// let logger = LockedLogger::new(RefCell::new(Uart { sent: 0 }));
+/// ulog::info!(logger, "Hello");
+/// assert_eq!(logger.into_inner().into_inner().sent, 6);
+/// ```
+pub struct LockedLogger<Lock> {
+    lock: Lock,
+}
+
+impl<Lock: ULogLock> LockedLogger<Lock> {
+    pub fn new(lock: Lock) -> Self {
+        Self { lock }
+    }
+
+    pub fn lock(&self) -> &Lock {
+        &self.lock
+    }
+
+    pub fn into_inner(self) -> Lock {
+        self.lock
+    }
+}
+
+impl<Lock: ULogLock> ULog for LockedLogger<Lock> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        self.lock
+            .with_logger(|logger| logger.log_str(log_data, string));
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.lock
+            .with_logger(|logger| logger.log_str_continued(log_data, string));
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: fmt::Arguments<'_>) {
+        self.lock
+            .with_logger(|logger| logger.log_fmt(log_data, args));
+    }
+
+    fn log_format<T: Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.lock
+            .with_logger(|logger| logger.log_format(log_data, key, value));
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.lock
+            .with_logger(|logger| logger.log_value(log_data, key, value));
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.lock.with_logger(|logger| logger.log_begin(log_data));
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        self.lock.with_logger(|logger| logger.log_end(log_data));
+    }
+
+    fn flush(&self) {
+        self.lock.with_logger(|logger| logger.flush());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::TestLogger;
+    use crate::*;
+
+    /// Records statements, like [`TestLogger`], but through `&mut self`
+    #[derive(Default)]
+    struct VecLogger {
+        logs: Vec<String>,
+    }
+
+    impl ULogMut for VecLogger {
+        fn log_str(&mut self, _log_data: &ULogData, string: &str) {
+            self.logs.push(string.to_string());
+        }
+
+        fn log_format<T: Debug>(&mut self, _log_data: &ULogData, key: &str, value: &T) {
+            self.logs.push(format!("{key} => {value:?}"));
+        }
+
+        fn log_begin(&mut self, log_data: &ULogData) {
+            self.logs.push(log_data.level.to_string());
+        }
+
+        fn log_end(&mut self, _log_data: &ULogData) {}
+    }
+
+    #[test]
+    fn test_locked_logger() {
+        let logger = LockedLogger::new(RefCell::new(VecLogger::default()));
+        info!(logger, "Hello", "value" => 32);
+        assert_eq!(
+            logger.into_inner().into_inner().logs,
+            ["INFO", "Hello", "value => 32"]
+        );
+
+        let test_logger = TestLogger::default();
+        let mut logger_mut = &test_logger;
+        let log_data = ULogData::new(ULogLevel::Debug, 1, "main.rs");
+        ULogMut::log_begin(&mut logger_mut, &log_data);
+        ULogMut::log_str(&mut logger_mut, &log_data, "Hi");
+        ULogMut::log_end(&mut logger_mut, &log_data);
+        assert_eq!(test_logger.logs.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_locked_logger_forwarding() {
+        use crate::format::{FormattedLogger, JsonFormatter};
+
+        let output = RefCell::new(Vec::new());
+        let json = FormattedLogger::new(JsonFormatter::new(), |bytes: &[u8]| {
+            output.borrow_mut().extend_from_slice(bytes)
+        });
+        let logger = LockedLogger::new(RefCell::new(&json));
+        let long = "x".repeat(2 * FMT_BUFFER_SIZE);
+        warn!(logger, "read {} bytes", long);
+        logger.at(ULogLevel::Info).value("ok", true).emit();
+
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        assert!(output.contains(&format!("\"read {} bytes\"", long)));
+        assert!(output.contains("\"ok\":true"));
+    }
+
+    #[cfg(feature = "critical-section")]
+    #[test]
+    fn test_critical_section_logger() {
+        let logger = LockedLogger::new(critical_section::Mutex::new(RefCell::new(
+            VecLogger::default(),
+        )));
+        error!(logger, "Bye");
+
+        assert_eq!(
+            logger.into_inner().into_inner().into_inner().logs,
+            ["ERROR", "Bye"]
+        );
+    }
+}
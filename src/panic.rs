@@ -0,0 +1,155 @@
+use super::{ULog, ULogData, ULogLevel};
+use std::panic::PanicHookInfo;
+
+/// Logs panics as [`Critical`](ULogLevel::Critical) statements, attributing them to the crate they originated from.
+///
+/// The statement contains the panic payload, its location and, when it can be derived from the location,
+/// the name of the crate that panicked. Use [`install_panic_hook`] to register it as the panic hook.
+#[derive(Debug, Clone)]
+pub struct PanicCapture<Logger> {
+    logger: Logger,
+    dependencies_only: bool,
+}
+
+impl<Logger: ULog> PanicCapture<Logger> {
+    pub fn new(logger: Logger) -> Self {
+        Self {
+            logger,
+            dependencies_only: false,
+        }
+    }
+
+    /// When set, only panics originating from dependencies (including the standard library) get logged.
+    pub fn dependencies_only(mut self, dependencies_only: bool) -> Self {
+        self.dependencies_only = dependencies_only;
+        self
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+
+    /// Logs the panic described by `info`; meant to be called from within a panic hook.
+    pub fn log_panic(&self, info: &PanicHookInfo<'_>) {
+        let location = info.location();
+        let crate_name = location.and_then(|location| crate_name(location.file()));
+
+        if self.dependencies_only && crate_name.is_none() {
+            return;
+        }
+
+        let payload = info
+            .payload()
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("<non-string payload>");
+
+        // Locations don't give out `'static` file names, so the location is logged as a field instead.
+        let mut log_data = ULogData::new(
+            ULogLevel::Critical,
+            location.map(|location| location.line()).unwrap_or(0),
+            "<panic>",
+        );
+        log_data.column = location.map(|location| location.column());
+
+        self.logger.log_begin(&log_data);
+        self.logger.log_str(&log_data, payload);
+        if let Some(crate_name) = crate_name {
+            self.logger.log_format(&log_data, "crate", &crate_name);
+        }
+        if let Some(location) = location {
+            self.logger
+                .log_format(&log_data, "location", &format_args!("{location}"));
+        }
+        self.logger.log_end(&log_data);
+    }
+}
+
+/// Registers `capture` as the panic hook; the previously registered hook keeps being called afterwards.
+pub fn install_panic_hook<Logger: ULog + Send + Sync + 'static>(capture: PanicCapture<Logger>) {
+    let previous_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        capture.log_panic(info);
+        previous_hook(info);
+    }));
+}
+
+/// Guesses the name of the crate that a source file belongs to, based on the layout of cargo's source directories.
+///
+/// Returns `None` for files that do not belong to a dependency, like the ones of the crate being built.
+pub fn crate_name(file: &str) -> Option<&str> {
+    let mut components = file.split(['/', '\\']);
+
+    while let Some(component) = components.next() {
+        match component {
+            // ~/.cargo/registry/src/<registry>/<name>-<version>/...
+            "registry" => {
+                if components.next() != Some("src") {
+                    continue;
+                }
+                components.next()?;
+                let package = components.next()?;
+
+                return Some(strip_suffix(package, |suffix| {
+                    suffix.starts_with(|c: char| c.is_ascii_digit())
+                }));
+            }
+            // ~/.cargo/git/checkouts/<name>-<hash>/<revision>/...
+            "git" => {
+                if components.next() != Some("checkouts") {
+                    continue;
+                }
+                let checkout = components.next()?;
+
+                return Some(strip_suffix(checkout, |suffix| {
+                    suffix.chars().all(|c| c.is_ascii_hexdigit())
+                }));
+            }
+            // /rustc/<hash>/library/<name>/...
+            "rustc" => {
+                components.next()?;
+                if components.next() != Some("library") {
+                    continue;
+                }
+
+                return components.next();
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn strip_suffix(package: &str, is_suffix: impl Fn(&str) -> bool) -> &str {
+    match package.rsplit_once('-') {
+        Some((name, suffix)) if is_suffix(suffix) => name,
+        _ => package,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_crate_name() {
+        assert_eq!(
+            crate_name("/home/user/.cargo/registry/src/index.crates.io-6f17d22bba15001f/serde-json-1.0.99/src/de.rs"),
+            Some("serde-json")
+        );
+        assert_eq!(
+            crate_name("/home/user/.cargo/git/checkouts/ulog-3f2a1b9c0d/8a1b2c3/src/lib.rs"),
+            Some("ulog")
+        );
+        assert_eq!(
+            crate_name(
+                "/rustc/90b35a6239c3d8bdabc530a6a0816f7ff89a0aaf/library/core/src/option.rs"
+            ),
+            Some("core")
+        );
+        assert_eq!(crate_name("src/main.rs"), None);
+    }
+}
@@ -0,0 +1,651 @@
+#[cfg(feature = "serde")]
+use super::serialize::{RawJson, SerializeJson};
+use super::{ULog, ULogData, Value};
+use core::fmt::{self, Write};
+
+/// A fixed-capacity string buffer implementing [`core::fmt::Write`], and `ufmt_write::uWrite` with the `ufmt-write` feature.
+///
+/// Text that does not fit in the buffer is cut at the last character boundary and the buffer is marked as truncated,
+/// instead of returning an error.
+#[derive(Clone)]
+pub struct TextBuffer<const SIZE: usize> {
+    bytes: [u8; SIZE],
+    len: usize,
+    truncated: bool,
+}
+
+impl<const SIZE: usize> TextBuffer<SIZE> {
+    pub const fn new() -> Self {
+        Self {
+            bytes: [0; SIZE],
+            len: 0,
+            truncated: false,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Only whole characters get written, so this never fails
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        SIZE
+    }
+
+    /// Returns whether some text had to be dropped because the buffer was full.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.truncated = false;
+    }
+
+    /// Gives mutable access to the written bytes, for loggers transforming them in place before clearing the buffer.
+    pub(crate) fn as_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.bytes[..self.len]
+    }
+}
+
+impl<const SIZE: usize> Default for TextBuffer<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SIZE: usize> Write for TextBuffer<SIZE> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        if !self.truncated && !write_truncated(&mut self.bytes, &mut self.len, string) {
+            self.truncated = true;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ufmt-write")]
+impl<const SIZE: usize> ufmt_write::uWrite for TextBuffer<SIZE> {
+    type Error = core::convert::Infallible;
+
+    fn write_str(&mut self, string: &str) -> Result<(), Self::Error> {
+        let _ = Write::write_str(self, string);
+        Ok(())
+    }
+}
+
+impl<const SIZE: usize> fmt::Debug for TextBuffer<SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<const SIZE: usize> fmt::Display for TextBuffer<SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Copies as much of `string` as possible into `buffer[*len..]`, without splitting characters.
+/// Returns `false` if the string had to be truncated.
+fn write_truncated(buffer: &mut [u8], len: &mut usize, string: &str) -> bool {
+    let available = buffer.len() - *len;
+    let mut written = string.len().min(available);
+    while !string.is_char_boundary(written) {
+        written -= 1;
+    }
+
+    write_bytes_truncated(buffer, len, &string.as_bytes()[..written]) && written == string.len()
+}
+
+/// Copies as much of `bytes` as possible into `buffer[*len..]`.
+/// Returns `false` if the bytes had to be truncated.
+fn write_bytes_truncated(buffer: &mut [u8], len: &mut usize, bytes: &[u8]) -> bool {
+    let written = bytes.len().min(buffer.len() - *len);
+
+    buffer[*len..*len + written].copy_from_slice(&bytes[..written]);
+    *len += written;
+
+    written == bytes.len()
+}
+
+/// Formats a string without quotes when printed with [`Debug`](core::fmt::Debug);
+/// used to forward values that have already been formatted.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Verbatim<'a>(pub &'a str);
+
+impl fmt::Debug for Verbatim<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+const SEGMENT_STR: u8 = 0;
+const SEGMENT_FIELD: u8 = 1;
+const SEGMENT_VALUE: u8 = 2;
+const SEGMENT_SERIALIZED: u8 = 3;
+
+const VALUE_I64: u8 = 0;
+const VALUE_U64: u8 = 1;
+const VALUE_F64: u8 = 2;
+const VALUE_BOOL: u8 = 3;
+const VALUE_STR: u8 = 4;
+const VALUE_BYTES: u8 = 5;
+
+/// A complete logging statement, captured into a fixed-size buffer so that it can be [replayed](Record::replay) later.
+///
+/// Values are formatted using their [`Debug`](core::fmt::Debug) implementation when they are pushed,
+/// except for typed [`Value`]s, which are kept as they are. Once the buffer is full, the last string or value is truncated and subsequent ones are dropped.
+#[derive(Clone)]
+pub struct Record<const SIZE: usize> {
+    data: ULogData,
+    bytes: [u8; SIZE],
+    len: usize,
+    /// The start of the text segment being written, as segments are limited to `u16::MAX` bytes
+    text_start: usize,
+    /// Whether the last segment is a string, which `text_start` points to, that can be continued
+    last_str: bool,
+    truncated: bool,
+}
+
+/// A part of a [`Record`], as returned by [`Record::segments`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Segment<'a> {
+    Str(&'a str),
+    /// A key-value pair, with the value formatted using its [`Debug`](core::fmt::Debug) implementation.
+    Field(&'a str, &'a str),
+    /// A key-value pair holding a typed value, pushed with [`push_value`](Record::push_value).
+    Value(&'a str, Value<'a>),
+    /// A key-value pair with the value written as JSON, pushed with `push_serialize` with the `serde` feature.
+    Serialized(&'a str, &'a str),
+}
+
+impl<const SIZE: usize> Record<SIZE> {
+    pub fn new(log_data: ULogData) -> Self {
+        Self {
+            data: log_data,
+            bytes: [0; SIZE],
+            len: 0,
+            text_start: 0,
+            last_str: false,
+            truncated: false,
+        }
+    }
+
+    pub fn data(&self) -> &ULogData {
+        &self.data
+    }
+
+    /// Returns whether some strings or values had to be shortened or dropped because the buffer was full.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    pub fn push_str(&mut self, string: &str) {
+        if self.truncated || !self.push_tag(SEGMENT_STR) {
+            return;
+        }
+
+        let start = self.begin_text();
+        self.write_text(string);
+        self.end_text(start);
+        self.last_str = true;
+    }
+
+    /// Appends `string` to the previous string without any separator, if it was the last thing pushed,
+    /// see [`ULog::log_str_continued`]; otherwise pushes it like [`push_str`](Record::push_str).
+    pub fn push_str_continued(&mut self, string: &str) {
+        if !self.last_str {
+            return self.push_str(string);
+        }
+
+        self.write_text(string);
+        self.end_text(self.text_start);
+    }
+
+    /// Pushes a string formatted from `args`, like [`push_str`](Record::push_str).
+    pub fn push_fmt(&mut self, args: fmt::Arguments<'_>) {
+        if self.truncated || !self.push_tag(SEGMENT_STR) {
+            return;
+        }
+
+        let start = self.begin_text();
+        // Writing to the record never fails
+        let _ = RecordWriter(self).write_fmt(args);
+        self.end_text(start);
+        self.last_str = true;
+    }
+
+    pub fn push_format<T: fmt::Debug>(&mut self, key: &str, value: &T) {
+        if self.truncated {
+            return;
+        }
+        if SIZE - self.len < 1 + 2 + key.len() + 2 {
+            self.truncated = true;
+            return;
+        }
+        self.push_tag(SEGMENT_FIELD);
+
+        let start = self.begin_text();
+        self.write_text(key);
+        self.end_text(start);
+
+        let start = self.begin_text();
+        // Writing to the record never fails
+        let _ = write!(RecordWriter(self), "{:?}", value);
+        self.end_text(start);
+    }
+
+    /// Pushes a key-value pair holding a typed [`Value`], which is [replayed](Record::replay) with
+    /// [`ULog::log_value`]; strings and byte slices are truncated like other values.
+    pub fn push_value(&mut self, key: &str, value: Value<'_>) {
+        let (kind, fixed): (u8, &[u8]) = match value {
+            Value::I64(value) => (VALUE_I64, &value.to_le_bytes()),
+            Value::U64(value) => (VALUE_U64, &value.to_le_bytes()),
+            Value::F64(value) => (VALUE_F64, &value.to_le_bytes()),
+            Value::Bool(value) => (VALUE_BOOL, &[value as u8]),
+            Value::Str(_) => (VALUE_STR, &[]),
+            Value::Bytes(_) => (VALUE_BYTES, &[]),
+        };
+
+        if self.truncated {
+            return;
+        }
+        if SIZE - self.len < 1 + 2 + key.len() + 1 + 2 + fixed.len() {
+            self.truncated = true;
+            return;
+        }
+        self.push_tag(SEGMENT_VALUE);
+
+        let start = self.begin_text();
+        self.write_text(key);
+        self.end_text(start);
+
+        self.bytes[self.len] = kind;
+        self.len += 1;
+
+        let start = self.begin_text();
+        match value {
+            Value::Str(string) => self.write_text(string),
+            Value::Bytes(bytes) => self.write_bytes(bytes),
+            _ => self.write_bytes(fixed),
+        }
+        self.end_text(start);
+    }
+
+    /// Pushes a key-value pair holding a value written as JSON, which is [replayed](Record::replay) with
+    /// [`ULog::log_serialize`]; requires the `serde` feature.
+    ///
+    /// Unlike other values, JSON that doesn't fit is dropped rather than truncated, so that it stays valid.
+    #[cfg(feature = "serde")]
+    pub fn push_serialize(&mut self, key: &str, value: &dyn SerializeJson) {
+        if self.truncated {
+            return;
+        }
+        if SIZE - self.len < 1 + 2 + key.len() + 2 {
+            self.truncated = true;
+            return;
+        }
+        let segment_start = self.len;
+        self.push_tag(SEGMENT_SERIALIZED);
+
+        let start = self.begin_text();
+        self.write_text(key);
+        self.end_text(start);
+
+        let start = self.begin_text();
+        if value.write_json(&mut RecordWriter(self)).is_err() || self.truncated {
+            self.len = segment_start;
+            return;
+        }
+        self.end_text(start);
+    }
+
+    pub fn segments(&self) -> Segments<'_> {
+        Segments {
+            bytes: &self.bytes[..self.len],
+        }
+    }
+
+    /// Forwards the statement to `logger`, with the values formatted as [`Verbatim`] strings.
+    pub fn replay<Logger: ULog>(&self, logger: &Logger) {
+        logger.log_begin(&self.data);
+        for segment in self.segments() {
+            match segment {
+                Segment::Str(string) => logger.log_str(&self.data, string),
+                Segment::Field(key, value) => logger.log_format(&self.data, key, &Verbatim(value)),
+                Segment::Value(key, value) => logger.log_value(&self.data, key, value),
+                #[cfg(feature = "serde")]
+                Segment::Serialized(key, json) => {
+                    logger.log_serialize(&self.data, key, &RawJson(json))
+                }
+                #[cfg(not(feature = "serde"))]
+                Segment::Serialized(key, json) => {
+                    logger.log_format(&self.data, key, &Verbatim(json))
+                }
+            }
+        }
+        logger.log_end(&self.data);
+    }
+
+    fn push_tag(&mut self, tag: u8) -> bool {
+        if SIZE - self.len < 3 {
+            self.truncated = true;
+            return false;
+        }
+
+        self.bytes[self.len] = tag;
+        self.len += 1;
+        self.last_str = false;
+        true
+    }
+
+    /// Reserves the space for the length of a text segment, which must fit.
+    fn begin_text(&mut self) -> usize {
+        let start = self.len;
+        self.len += 2;
+        self.text_start = start;
+        start
+    }
+
+    /// Returns the end of the text segment being written, which holds up to `u16::MAX` bytes.
+    fn text_end(&self) -> usize {
+        (self.text_start + 2 + u16::MAX as usize).min(SIZE)
+    }
+
+    fn write_text(&mut self, string: &str) {
+        if self.truncated {
+            return;
+        }
+
+        let mut len = self.len;
+        let end = self.text_end();
+
+        if !write_truncated(&mut self.bytes[..end], &mut len, string) {
+            self.truncated = true;
+        }
+        self.len = len;
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        if self.truncated {
+            return;
+        }
+
+        let mut len = self.len;
+        let end = self.text_end();
+
+        if !write_bytes_truncated(&mut self.bytes[..end], &mut len, bytes) {
+            self.truncated = true;
+        }
+        self.len = len;
+    }
+
+    fn end_text(&mut self, start: usize) {
+        let text_len = (self.len - start - 2) as u16;
+        self.bytes[start..start + 2].copy_from_slice(&text_len.to_le_bytes());
+    }
+}
+
+impl<const SIZE: usize> fmt::Debug for Record<SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Record")
+            .field("data", &self.data)
+            .field("segments", &self.segments())
+            .field("truncated", &self.truncated)
+            .finish()
+    }
+}
+
+/// Formats the record on a single line, as `LEVEL file:line message key=value`.
+impl<const SIZE: usize> fmt::Display for Record<SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}:{}",
+            self.data.level_name, self.data.file, self.data.line
+        )?;
+        for segment in self.segments() {
+            match segment {
+                Segment::Str(string) => write!(f, " {}", string)?,
+                Segment::Field(key, value) => write!(f, " {}={}", key, value)?,
+                Segment::Value(key, value) => write!(f, " {}={:?}", key, value)?,
+                Segment::Serialized(key, json) => write!(f, " {}={}", key, json)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+struct RecordWriter<'a, const SIZE: usize>(&'a mut Record<SIZE>);
+
+impl<const SIZE: usize> Write for RecordWriter<'_, SIZE> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        self.0.write_text(string);
+        Ok(())
+    }
+}
+
+/// An iterator over the [`Segment`]s of a [`Record`].
+#[derive(Clone)]
+pub struct Segments<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Segments<'a> {
+    fn next_bytes(&mut self) -> &'a [u8] {
+        let len = u16::from_le_bytes([self.bytes[0], self.bytes[1]]) as usize;
+        let bytes = &self.bytes[2..2 + len];
+        self.bytes = &self.bytes[2 + len..];
+        bytes
+    }
+
+    fn next_text(&mut self) -> &'a str {
+        core::str::from_utf8(self.next_bytes()).unwrap_or_default()
+    }
+
+    fn next_value(&mut self) -> Value<'a> {
+        let (&kind, rest) = self.bytes.split_first().unwrap_or((&VALUE_BYTES, &[]));
+        self.bytes = rest;
+
+        if kind == VALUE_STR {
+            return Value::Str(self.next_text());
+        }
+        let bytes = self.next_bytes();
+        let fixed = bytes.try_into().unwrap_or_default();
+        match kind {
+            VALUE_I64 => Value::I64(i64::from_le_bytes(fixed)),
+            VALUE_U64 => Value::U64(u64::from_le_bytes(fixed)),
+            VALUE_F64 => Value::F64(f64::from_le_bytes(fixed)),
+            VALUE_BOOL => Value::Bool(bytes.first() == Some(&1)),
+            _ => Value::Bytes(bytes),
+        }
+    }
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = Segment<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&tag, rest) = self.bytes.split_first()?;
+        self.bytes = rest;
+
+        match tag {
+            SEGMENT_STR => Some(Segment::Str(self.next_text())),
+            SEGMENT_FIELD => {
+                let key = self.next_text();
+                Some(Segment::Field(key, self.next_text()))
+            }
+            SEGMENT_SERIALIZED => {
+                let key = self.next_text();
+                Some(Segment::Serialized(key, self.next_text()))
+            }
+            _ => {
+                let key = self.next_text();
+                Some(Segment::Value(key, self.next_value()))
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Segments<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A fixed-capacity first-in, first-out queue of [`Record`]s.
+pub struct RecordQueue<const STATEMENTS: usize, const SIZE: usize> {
+    records: [Option<Record<SIZE>>; STATEMENTS],
+    start: usize,
+    len: usize,
+}
+
+impl<const STATEMENTS: usize, const SIZE: usize> RecordQueue<STATEMENTS, SIZE> {
+    pub fn new() -> Self {
+        Self {
+            records: core::array::from_fn(|_| None),
+            start: 0,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == STATEMENTS
+    }
+
+    /// Appends `record` at the end of the queue, or gives it back if the queue is full.
+    // Like `Vec::push_within_capacity`, the record is given back as is, for the caller to drop or retry
+    #[allow(clippy::result_large_err)]
+    pub fn push(&mut self, record: Record<SIZE>) -> Result<(), Record<SIZE>> {
+        if self.is_full() {
+            return Err(record);
+        }
+
+        self.records[(self.start + self.len) % STATEMENTS] = Some(record);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Appends `record` at the end of the queue, removing and returning the oldest record if the queue is full.
+    pub fn push_overwrite(&mut self, record: Record<SIZE>) -> Option<Record<SIZE>> {
+        let oldest = if self.is_full() { self.pop() } else { None };
+        // There is now room for the new record
+        let _ = self.push(record);
+        oldest
+    }
+
+    /// Removes the oldest record from the queue.
+    pub fn pop(&mut self) -> Option<Record<SIZE>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let record = self.records[self.start].take();
+        self.start = (self.start + 1) % STATEMENTS;
+        self.len -= 1;
+        record
+    }
+
+    /// Iterates over the records, from the oldest to the newest.
+    pub fn iter(&self) -> impl Iterator<Item = &Record<SIZE>> + '_ {
+        (0..self.len)
+            .filter_map(move |index| self.records[(self.start + index) % STATEMENTS].as_ref())
+    }
+}
+
+impl<const STATEMENTS: usize, const SIZE: usize> Default for RecordQueue<STATEMENTS, SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ULogLevel;
+
+    #[test]
+    fn test_record_truncation() {
+        let mut record = Record::<20>::new(ULogData::new(ULogLevel::Info, 1, "test.rs"));
+        record.push_str("Hello");
+        record.push_format("value", &"é");
+        record.push_str("world");
+
+        assert!(record.is_truncated());
+        assert_eq!(
+            record.segments().collect::<Vec<_>>(),
+            [Segment::Str("Hello"), Segment::Field("value", "\"")]
+        );
+    }
+
+    #[test]
+    fn test_record_values() {
+        let mut record = Record::<128>::new(ULogData::new(ULogLevel::Info, 1, "test.rs"));
+        record.push_value("count", Value::U64(3));
+        record.push_value("delta", Value::I64(-2));
+        record.push_value("ratio", Value::F64(0.5));
+        record.push_value("ok", Value::Bool(true));
+        record.push_value("name", Value::Str("eth0"));
+        record.push_value("raw", Value::Bytes(&[1, 2]));
+
+        assert!(!record.is_truncated());
+        assert_eq!(
+            record.segments().collect::<Vec<_>>(),
+            [
+                Segment::Value("count", Value::U64(3)),
+                Segment::Value("delta", Value::I64(-2)),
+                Segment::Value("ratio", Value::F64(0.5)),
+                Segment::Value("ok", Value::Bool(true)),
+                Segment::Value("name", Value::Str("eth0")),
+                Segment::Value("raw", Value::Bytes(&[1, 2])),
+            ]
+        );
+        assert_eq!(
+            record.to_string(),
+            "INFO test.rs:1 count=3 delta=-2 ratio=0.5 ok=true name=\"eth0\" raw=[1, 2]"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_record_serialize() {
+        let mut record = Record::<32>::new(ULogData::new(ULogLevel::Info, 1, "test.rs"));
+        record.push_serialize("list", &[1, 2]);
+        record.push_serialize("long", &"does not fit in the record");
+
+        assert!(record.is_truncated());
+        assert_eq!(
+            record.segments().collect::<Vec<_>>(),
+            [Segment::Serialized("list", "[1,2]")]
+        );
+    }
+
+    #[cfg(feature = "ufmt-write")]
+    #[test]
+    fn test_ufmt_text_buffer() {
+        use ufmt_write::uWrite;
+
+        let mut buffer = TextBuffer::<8>::new();
+        let _ = uWrite::write_str(&mut buffer, "Hello, ");
+        let _ = uWrite::write_char(&mut buffer, 'é');
+
+        assert!(buffer.is_truncated());
+        assert_eq!(buffer.as_str(), "Hello, ");
+    }
+}
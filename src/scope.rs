@@ -0,0 +1,238 @@
+use super::record::Verbatim;
+use super::time::ULogClock;
+use super::{Level, ULog, ULogData};
+use core::panic::Location;
+use core::time::Duration;
+
+/// A guard logging when a scope is entered, as soon as it is created, and when it is exited, as it is dropped;
+/// so that early returns are traced without logging before each of them.
+///
+/// Both statements have the name of the scope as message, with `span => enter` or `span => exit`, like the spans of
+/// [`SpanLogger`](crate::time::SpanLogger). With the `std` feature, the exit statement also holds `panicking => true`
+/// when the scope is left by unwinding.
+///
+/// ```
+/// use ulog::scope::ScopeLog;
+/// use ulog::ULogLevel;
+/// # let logger = ulog::common::StubLogger;
+///
+/// fn parse(logger: &impl ulog::ULog, input: &str) -> Option<u32> {
+///     let _scope = ScopeLog::new(logger, ULogLevel::Trace, "parse");
+///     if input.is_empty() {
+///         return None; // Logs `parse span=exit`
+///     }
+///     input.parse().ok()
+/// }
+/// # parse(&logger, "");
+/// ```
+#[must_use = "the scope is exited as soon as the guard is dropped"]
+pub struct ScopeLog<'a, Logger: ULog + ?Sized> {
+    logger: &'a Logger,
+    name: &'a str,
+    log_data: ULogData,
+}
+
+impl<'a, Logger: ULog + ?Sized> ScopeLog<'a, Logger> {
+    /// Logs that the scope `name` is entered, located where this is called, and returns the guard exiting it.
+    #[track_caller]
+    pub fn new(logger: &'a Logger, level: impl Level, name: &'a str) -> Self {
+        let location = Location::caller();
+        let log_data =
+            ULogData::new(level, location.line(), location.file()).with_column(location.column());
+
+        logger.log_begin(&log_data);
+        logger.log_str(&log_data, name);
+        logger.log_format(&log_data, "span", &Verbatim("enter"));
+        logger.log_end(&log_data);
+
+        Self {
+            logger,
+            name,
+            log_data,
+        }
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+}
+
+impl<Logger: ULog + ?Sized> Drop for ScopeLog<'_, Logger> {
+    fn drop(&mut self) {
+        self.logger.log_begin(&self.log_data);
+        self.logger.log_str(&self.log_data, self.name);
+        self.logger
+            .log_format(&self.log_data, "span", &Verbatim("exit"));
+        #[cfg(feature = "std")]
+        if std::thread::panicking() {
+            self.logger.log_format(&self.log_data, "panicking", &true);
+        }
+        self.logger.log_end(&self.log_data);
+    }
+}
+
+/// A guard measuring the time spent in a scope with a [`ULogClock`], and logging it when dropped.
+///
+/// The statement has the label as message, followed by `elapsed => <duration>`; it is logged at the level given to
+/// [`new`](ScopeTimer::new), or at a more severe level when the duration exceeds the threshold set with
+/// [`with_threshold`](ScopeTimer::with_threshold).
+///
+/// ```
+/// use ulog::scope::ScopeTimer;
+/// use ulog::ULogLevel;
+/// use core::time::Duration;
+/// # let logger = ulog::common::StubLogger;
+/// # let clock = || Duration::ZERO;
+///
+/// {
+///     let _timer = ScopeTimer::new(&logger, ULogLevel::Debug, "flush", clock)
+///         .with_threshold(Duration::from_millis(100), ULogLevel::Warning);
+///     // Logs `flush elapsed=...` when leaving the scope, as a warning if it took more than 100ms
+/// }
+/// ```
+#[must_use = "the timer is stopped as soon as the guard is dropped"]
+pub struct ScopeTimer<'a, Logger: ULog + ?Sized, Clock: ULogClock> {
+    logger: &'a Logger,
+    label: &'a str,
+    clock: Clock,
+    log_data: ULogData,
+    threshold: Option<(Duration, ULogData)>,
+    start: Duration,
+}
+
+impl<'a, Logger: ULog + ?Sized, Clock: ULogClock> ScopeTimer<'a, Logger, Clock> {
+    /// Starts measuring the time until the guard is dropped, which is logged at `level`, located where this is called.
+    #[track_caller]
+    pub fn new(logger: &'a Logger, level: impl Level, label: &'a str, clock: Clock) -> Self {
+        let location = Location::caller();
+        let start = clock.now();
+
+        Self {
+            logger,
+            label,
+            clock,
+            log_data: ULogData::new(level, location.line(), location.file())
+                .with_column(location.column()),
+            threshold: None,
+            start,
+        }
+    }
+
+    /// Logs the statement at `level` instead when the elapsed time is over `threshold`.
+    pub fn with_threshold(mut self, threshold: Duration, level: impl Level) -> Self {
+        self.threshold = Some((threshold, self.log_data.clone().with_level(level)));
+        self
+    }
+
+    /// Returns the time elapsed since the timer was started.
+    pub fn elapsed(&self) -> Duration {
+        self.clock.now().saturating_sub(self.start)
+    }
+}
+
+impl<Logger: ULog + ?Sized, Clock: ULogClock> Drop for ScopeTimer<'_, Logger, Clock> {
+    fn drop(&mut self) {
+        let elapsed = self.elapsed();
+        if let Some((threshold, log_data)) = self.threshold.take() {
+            if elapsed > threshold {
+                self.log_data = log_data;
+            }
+        }
+
+        self.logger.log_begin(&self.log_data);
+        self.logger.log_str(&self.log_data, self.label);
+        self.logger.log_format(&self.log_data, "elapsed", &elapsed);
+        self.logger.log_end(&self.log_data);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::TestLogger;
+    use crate::ULogLevel;
+    use core::cell::Cell;
+
+    #[test]
+    fn test_scope_log() {
+        let logger = TestLogger::default();
+
+        let find = |needle: u8| {
+            let _scope = ScopeLog::new(&logger, ULogLevel::Debug, "find");
+            for byte in [1, 2, 3] {
+                if byte == needle {
+                    return true;
+                }
+            }
+            false
+        };
+        assert!(find(2));
+
+        #[cfg(feature = "std")]
+        {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let _scope = ScopeLog::new(&logger, ULogLevel::Debug, "unwind");
+                panic!("expected");
+            }));
+            assert!(result.is_err());
+        }
+
+        let logs = logger.logs.into_inner();
+        let logs = logs.iter().map(|(_, log)| log.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            logs[..8],
+            [
+                "__BEGIN__",
+                "find",
+                "span => enter",
+                "__END__",
+                "__BEGIN__",
+                "find",
+                "span => exit",
+                "__END__",
+            ]
+        );
+        #[cfg(feature = "std")]
+        assert_eq!(
+            logs[8..],
+            [
+                "__BEGIN__",
+                "unwind",
+                "span => enter",
+                "__END__",
+                "__BEGIN__",
+                "unwind",
+                "span => exit",
+                "panicking => true",
+                "__END__",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scope_timer() {
+        let logger = TestLogger::default();
+        let time = Cell::new(Duration::from_millis(10));
+        let clock = || time.get();
+
+        for duration in [50, 150] {
+            let _timer = ScopeTimer::new(&logger, ULogLevel::Debug, "flush", &clock)
+                .with_threshold(Duration::from_millis(100), ULogLevel::Warning);
+            time.set(time.get() + Duration::from_millis(duration));
+        }
+
+        assert_eq!(
+            &logger.logs.into_inner()[..],
+            &[
+                (ULogLevel::Debug, String::from("__BEGIN__")),
+                (ULogLevel::Debug, String::from("flush")),
+                (ULogLevel::Debug, String::from("elapsed => 50ms")),
+                (ULogLevel::Debug, String::from("__END__")),
+                (ULogLevel::Warning, String::from("__BEGIN__")),
+                (ULogLevel::Warning, String::from("flush")),
+                (ULogLevel::Warning, String::from("elapsed => 150ms")),
+                (ULogLevel::Warning, String::from("__END__")),
+            ]
+        );
+    }
+}
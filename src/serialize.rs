@@ -0,0 +1,835 @@
+use super::format::JsonEscaper;
+use super::{ULogData, ULogLevel};
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::fmt::{self, Write};
+#[cfg(feature = "alloc")]
+use core::time::Duration;
+use serde::de::{self, Deserialize};
+use serde::ser::{self, Serialize};
+
+/// A value implementing [`Serialize`], logged with [`log_serialize`](crate::ULog::log_serialize);
+/// implemented for every `Serialize` type.
+///
+/// As `Serialize` cannot be used as a trait object, this trait exposes the serialization of the value to JSON,
+/// which every sink can make use of.
+pub trait SerializeJson {
+    /// Writes the value as compact JSON; maps with keys that are not strings, numbers or booleans fail to serialize.
+    fn write_json(&self, out: &mut dyn Write) -> fmt::Result;
+}
+
+impl<T: Serialize + ?Sized> SerializeJson for T {
+    fn write_json(&self, out: &mut dyn Write) -> fmt::Result {
+        self.serialize(&mut JsonSerializer { out, key: false })
+            .map_err(|_| fmt::Error)
+    }
+}
+
+/// Formats a [`SerializeJson`] value as JSON when printed with [`Debug`](core::fmt::Debug) or [`Display`](fmt::Display).
+#[derive(Clone, Copy)]
+pub struct Serialized<'a>(pub &'a dyn SerializeJson);
+
+impl fmt::Display for Serialized<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.write_json(f)
+    }
+}
+
+impl fmt::Debug for Serialized<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.write_json(f)
+    }
+}
+
+/// JSON that was already serialized, like the values of a [`Record`](crate::record::Record), written as is.
+pub(crate) struct RawJson<'a>(pub &'a str);
+
+impl SerializeJson for RawJson<'_> {
+    fn write_json(&self, out: &mut dyn Write) -> fmt::Result {
+        out.write_str(self.0)
+    }
+}
+
+/// The error of serializing a value to JSON, see [`SerializeJson`].
+#[derive(Debug)]
+pub struct SerializeError;
+
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to serialize value")
+    }
+}
+
+impl ser::StdError for SerializeError {}
+
+impl ser::Error for SerializeError {
+    fn custom<T: fmt::Display>(_message: T) -> Self {
+        SerializeError
+    }
+}
+
+impl From<fmt::Error> for SerializeError {
+    fn from(_error: fmt::Error) -> Self {
+        SerializeError
+    }
+}
+
+/// Serializes values as compact JSON into a [`fmt::Write`].
+struct JsonSerializer<'a> {
+    out: &'a mut dyn Write,
+    /// Set while serializing the key of a map, which gets written inside of a JSON string
+    key: bool,
+}
+
+impl JsonSerializer<'_> {
+    fn write_display(&mut self, value: impl fmt::Display) -> Result<(), SerializeError> {
+        Ok(write!(self.out, "{}", value)?)
+    }
+
+    fn write_string(&mut self, string: &str) -> Result<(), SerializeError> {
+        if !self.key {
+            self.out.write_str("\"")?;
+        }
+        JsonEscaper(self.out).write_str(string)?;
+        if !self.key {
+            self.out.write_str("\"")?;
+        }
+        Ok(())
+    }
+
+    fn not_a_key(&self) -> Result<(), SerializeError> {
+        if self.key {
+            Err(SerializeError)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn begin_variant(&mut self, variant: &str) -> Result<(), SerializeError> {
+        self.not_a_key()?;
+        self.out.write_str("{")?;
+        self.write_string(variant)?;
+        Ok(self.out.write_str(":")?)
+    }
+}
+
+/// Serializes the elements of sequences, maps and structs.
+struct Compound<'a, 'b> {
+    serializer: &'b mut JsonSerializer<'a>,
+    first: bool,
+    /// Closes the compound, like `]` or `}}` for a variant holding a struct
+    end: &'static str,
+}
+
+impl Compound<'_, '_> {
+    fn separator(&mut self) -> Result<(), SerializeError> {
+        if !core::mem::replace(&mut self.first, false) {
+            self.serializer.out.write_str(",")?;
+        }
+        Ok(())
+    }
+
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerializeError> {
+        self.separator()?;
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn field<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), SerializeError> {
+        self.separator()?;
+        self.serializer.write_string(key)?;
+        self.serializer.out.write_str(":")?;
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn finish(self) -> Result<(), SerializeError> {
+        Ok(self.serializer.out.write_str(self.end)?)
+    }
+}
+
+impl<'a, 'b> ser::Serializer for &'b mut JsonSerializer<'a> {
+    type Ok = ();
+    type Error = SerializeError;
+    type SerializeSeq = Compound<'a, 'b>;
+    type SerializeTuple = Compound<'a, 'b>;
+    type SerializeTupleStruct = Compound<'a, 'b>;
+    type SerializeTupleVariant = Compound<'a, 'b>;
+    type SerializeMap = Compound<'a, 'b>;
+    type SerializeStruct = Compound<'a, 'b>;
+    type SerializeStructVariant = Compound<'a, 'b>;
+
+    fn serialize_bool(self, value: bool) -> Result<(), SerializeError> {
+        self.write_display(value)
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), SerializeError> {
+        self.write_display(value)
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<(), SerializeError> {
+        self.write_display(value)
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<(), SerializeError> {
+        self.write_display(value)
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<(), SerializeError> {
+        self.write_display(value)
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<(), SerializeError> {
+        self.write_display(value)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<(), SerializeError> {
+        self.write_display(value)
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<(), SerializeError> {
+        self.write_display(value)
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), SerializeError> {
+        self.write_display(value)
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<(), SerializeError> {
+        self.write_display(value)
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<(), SerializeError> {
+        self.write_display(value)
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<(), SerializeError> {
+        self.serialize_f64(f64::from(value))
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<(), SerializeError> {
+        if value.is_finite() {
+            self.write_display(value)
+        } else {
+            self.serialize_unit()
+        }
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), SerializeError> {
+        self.write_string(value.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<(), SerializeError> {
+        self.write_string(value)
+    }
+
+    fn collect_str<T: fmt::Display + ?Sized>(self, value: &T) -> Result<(), SerializeError> {
+        if !self.key {
+            self.out.write_str("\"")?;
+        }
+        write!(JsonEscaper(self.out), "{}", value)?;
+        if !self.key {
+            self.out.write_str("\"")?;
+        }
+        Ok(())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), SerializeError> {
+        use ser::SerializeSeq;
+
+        let mut seq = self.serialize_seq(Some(value.len()))?;
+        for byte in value {
+            seq.serialize_element(byte)?;
+        }
+        seq.end()
+    }
+
+    fn serialize_none(self) -> Result<(), SerializeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), SerializeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), SerializeError> {
+        self.not_a_key()?;
+        Ok(self.out.write_str("null")?)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), SerializeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), SerializeError> {
+        self.write_string(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        self.begin_variant(variant)?;
+        value.serialize(&mut *self)?;
+        Ok(self.out.write_str("}")?)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a, 'b>, SerializeError> {
+        self.not_a_key()?;
+        self.out.write_str("[")?;
+        Ok(Compound {
+            serializer: self,
+            first: true,
+            end: "]",
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a, 'b>, SerializeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, 'b>, SerializeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a, 'b>, SerializeError> {
+        self.begin_variant(variant)?;
+        self.out.write_str("[")?;
+        Ok(Compound {
+            serializer: self,
+            first: true,
+            end: "]}",
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a, 'b>, SerializeError> {
+        self.not_a_key()?;
+        self.out.write_str("{")?;
+        Ok(Compound {
+            serializer: self,
+            first: true,
+            end: "}",
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, 'b>, SerializeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a, 'b>, SerializeError> {
+        self.begin_variant(variant)?;
+        self.out.write_str("{")?;
+        Ok(Compound {
+            serializer: self,
+            first: true,
+            end: "}}",
+        })
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_, '_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for Compound<'_, '_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_, '_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerializeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_, '_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerializeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeMap for Compound<'_, '_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerializeError> {
+        self.separator()?;
+        self.serializer.out.write_str("\"")?;
+        self.serializer.key = true;
+        let result = key.serialize(&mut *self.serializer);
+        self.serializer.key = false;
+        result?;
+        Ok(self.serializer.out.write_str("\":")?)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerializeError> {
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_, '_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_, '_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        self.finish()
+    }
+}
+
+/// Serialized as its name, like `"WARN"`, in human-readable formats, and as its number otherwise;
+/// deserialized from either, with names being case-insensitive.
+impl Serialize for ULogLevel {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(self.as_str())
+        } else {
+            serializer.serialize_u8(self.as_u8())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ULogLevel {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct LevelVisitor;
+
+        impl de::Visitor<'_> for LevelVisitor {
+            type Value = ULogLevel;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a level name or number")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<ULogLevel, E> {
+                u8::try_from(value)
+                    .ok()
+                    .and_then(|value| ULogLevel::try_from(value).ok())
+                    .ok_or_else(|| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<ULogLevel, E> {
+                ULogLevel::all_levels()
+                    .into_iter()
+                    .find(|level| {
+                        value.eq_ignore_ascii_case(level.as_str())
+                            || (*level == ULogLevel::Warning
+                                && value.eq_ignore_ascii_case("warning"))
+                    })
+                    .ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(LevelVisitor)
+        } else {
+            deserializer.deserialize_u8(LevelVisitor)
+        }
+    }
+}
+
+const DATA_FIELDS: &[&str] = &[
+    "level",
+    "file",
+    "line",
+    "column",
+    "target",
+    "module",
+    "timestamp",
+    "sequence",
+    "thread",
+    "user",
+];
+
+impl Serialize for ULogData {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use ser::SerializeStruct;
+
+        let mut data = serializer.serialize_struct("ULogData", DATA_FIELDS.len())?;
+        data.serialize_field("level", &self.level)?;
+        data.serialize_field("file", self.file)?;
+        data.serialize_field("line", &self.line)?;
+        data.serialize_field("column", &self.column)?;
+        data.serialize_field("target", &self.target)?;
+        data.serialize_field("module", &self.module)?;
+        data.serialize_field("timestamp", &self.timestamp)?;
+        data.serialize_field("sequence", &self.sequence)?;
+        data.serialize_field("thread", &self.thread)?;
+        data.serialize_field("user", &self.user)?;
+        data.end()
+    }
+}
+
+/// The [`ULogData`] of a statement, with owned strings, to read it back from configuration files, network
+/// transports or host tools; as the file name, the target and the module of [`ULogData`] are `&'static str`,
+/// it can't be deserialized itself. Requires the `alloc` feature.
+///
+/// ```
+/// use ulog::serialize::OwnedULogData;
+/// use ulog::{ULogData, ULogLevel};
+///
+/// let data = ULogData::new(ULogLevel::Warning, 7, "src/main.rs").with_target("radio");
+/// let owned = OwnedULogData::from(&data);
+/// assert_eq!(owned.target.as_deref(), Some("radio"));
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnedULogData {
+    pub level: ULogLevel,
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    pub target: Option<String>,
+    pub module: Option<String>,
+    pub timestamp: Option<Duration>,
+    pub sequence: Option<u32>,
+    pub thread: Option<u32>,
+    pub user: Option<u32>,
+}
+
+#[cfg(feature = "alloc")]
+impl OwnedULogData {
+    /// Converts it back into a [`ULogData`], leaking its strings to make them `'static`; meant for tools
+    /// replaying a bounded amount of statements to loggers.
+    pub fn leak(self) -> ULogData {
+        ULogData {
+            column: self.column,
+            target: self.target.map(|target| &*target.leak()),
+            module: self.module.map(|module| &*module.leak()),
+            timestamp: self.timestamp,
+            sequence: self.sequence,
+            thread: self.thread,
+            user: self.user,
+            ..ULogData::new(self.level, self.line, self.file.leak())
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<&ULogData> for OwnedULogData {
+    fn from(data: &ULogData) -> Self {
+        Self {
+            level: data.level,
+            file: String::from(data.file),
+            line: data.line,
+            column: data.column,
+            target: data.target.map(String::from),
+            module: data.module.map(String::from),
+            timestamp: data.timestamp,
+            sequence: data.sequence,
+            thread: data.thread,
+            user: data.user,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Serialize for OwnedULogData {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use ser::SerializeStruct;
+
+        let mut data = serializer.serialize_struct("ULogData", DATA_FIELDS.len())?;
+        data.serialize_field("level", &self.level)?;
+        data.serialize_field("file", &self.file)?;
+        data.serialize_field("line", &self.line)?;
+        data.serialize_field("column", &self.column)?;
+        data.serialize_field("target", &self.target)?;
+        data.serialize_field("module", &self.module)?;
+        data.serialize_field("timestamp", &self.timestamp)?;
+        data.serialize_field("sequence", &self.sequence)?;
+        data.serialize_field("thread", &self.thread)?;
+        data.serialize_field("user", &self.user)?;
+        data.end()
+    }
+}
+
+/// Reads the fields written by the [`Serialize`] implementation of [`ULogData`], in any order for
+/// self-describing formats; only the level, the file and the line are required.
+#[cfg(feature = "alloc")]
+impl<'de> Deserialize<'de> for OwnedULogData {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DataVisitor;
+
+        impl<'de> de::Visitor<'de> for DataVisitor {
+            type Value = OwnedULogData;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(
+                    "a struct with a level, a file, a line, a column, a target, a module, a timestamp, a sequence number, a thread and user metadata",
+                )
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<OwnedULogData, A::Error> {
+                Ok(OwnedULogData {
+                    level: seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(0, &self))?,
+                    file: seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(1, &self))?,
+                    line: seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(2, &self))?,
+                    column: seq.next_element()?.flatten(),
+                    target: seq.next_element()?.flatten(),
+                    module: seq.next_element()?.flatten(),
+                    timestamp: seq.next_element()?.flatten(),
+                    sequence: seq.next_element()?.flatten(),
+                    thread: seq.next_element()?.flatten(),
+                    user: seq.next_element()?.flatten(),
+                })
+            }
+
+            fn visit_map<A: de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<OwnedULogData, A::Error> {
+                let (mut level, mut file, mut line, mut column) = (None, None, None, None);
+                let (mut target, mut module, mut timestamp) = (None, None, None);
+                let (mut sequence, mut thread, mut user) = (None, None, None);
+                // Keys are owned too, as they may be escaped in the input
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "level" => level = Some(map.next_value()?),
+                        "file" => file = Some(map.next_value()?),
+                        "line" => line = Some(map.next_value()?),
+                        "column" => column = map.next_value()?,
+                        "target" => target = map.next_value()?,
+                        "module" => module = map.next_value()?,
+                        "timestamp" => timestamp = map.next_value()?,
+                        "sequence" => sequence = map.next_value()?,
+                        "thread" => thread = map.next_value()?,
+                        "user" => user = map.next_value()?,
+                        _ => {
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(OwnedULogData {
+                    level: level.ok_or_else(|| de::Error::missing_field("level"))?,
+                    file: file.ok_or_else(|| de::Error::missing_field("file"))?,
+                    line: line.ok_or_else(|| de::Error::missing_field("line"))?,
+                    column,
+                    target,
+                    module,
+                    timestamp,
+                    sequence,
+                    thread,
+                    user,
+                })
+            }
+        }
+
+        deserializer.deserialize_struct("ULogData", DATA_FIELDS, DataVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    enum Mode {
+        Idle,
+        Sampling { period: u32 },
+        Error(u8, &'static str),
+    }
+
+    #[derive(Serialize)]
+    struct Sensor {
+        name: &'static str,
+        values: Vec<f32>,
+        mode: Mode,
+        calibration: Option<(i8, i8)>,
+        limits: BTreeMap<u8, bool>,
+    }
+
+    #[test]
+    fn test_serialize_json() {
+        let sensor = Sensor {
+            name: "temp\"1\"",
+            values: vec![21.5, -3.0],
+            mode: Mode::Sampling { period: 10 },
+            calibration: None,
+            limits: BTreeMap::from([(1, true), (2, false)]),
+        };
+
+        assert_eq!(
+            Serialized(&sensor).to_string(),
+            r#"{"name":"temp\"1\"","values":[21.5,-3],"mode":{"Sampling":{"period":10}},"calibration":null,"limits":{"1":true,"2":false}}"#
+        );
+        assert_eq!(Serialized(&Mode::Idle).to_string(), r#""Idle""#);
+        assert_eq!(
+            Serialized(&Mode::Error(4, "bus")).to_string(),
+            r#"{"Error":[4,"bus"]}"#
+        );
+
+        let invalid_key = BTreeMap::from([((1, 2), 3)]);
+        let mut output = String::new();
+        assert!(invalid_key.write_json(&mut output).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_level_and_data() {
+        let mut data = ULogData::new(ULogLevel::Warning, 7, "main.rs");
+        data.thread = None;
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(
+            json,
+            r#"{"level":"WARN","file":"main.rs","line":7,"column":null,"target":null,"module":null,"timestamp":null,"sequence":null,"thread":null,"user":null}"#
+        );
+
+        let parsed: OwnedULogData =
+            serde_json::from_str(r#"{"line":7,"file":"main.rs","level":"warning"}"#).unwrap();
+        assert_eq!(
+            (parsed.level, parsed.file.as_str(), parsed.line),
+            (data.level, data.file, data.line)
+        );
+
+        // Escaped strings can't be borrowed from the input
+        let parsed: OwnedULogData = serde_json::from_str(
+            r#"{"level":"INFO","file":"C:\\src\\main.rs","line":1,"target":"a\"b","\u006dodule":"m"}"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.file, r"C:\src\main.rs");
+        assert_eq!(parsed.target.as_deref(), Some("a\"b"));
+        assert_eq!(parsed.module.as_deref(), Some("m"));
+
+        let bytes = postcard::to_allocvec(&data).unwrap();
+        assert_eq!(bytes[0], ULogLevel::Warning.as_u8());
+        let parsed: OwnedULogData = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, OwnedULogData::from(&data));
+
+        let data = data
+            .with_column(12)
+            .with_target("wifi")
+            .with_module("app::net")
+            .with_timestamp(Duration::from_millis(1500))
+            .with_sequence(7)
+            .with_thread(2)
+            .with_user(5);
+        let bytes = postcard::to_allocvec(&data).unwrap();
+        let parsed: OwnedULogData = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, OwnedULogData::from(&data));
+        assert_eq!(postcard::to_allocvec(&parsed).unwrap(), bytes);
+
+        let parsed = parsed.leak();
+        assert_eq!(parsed.column, Some(12));
+        assert_eq!(parsed.target, Some("wifi"));
+        assert_eq!(parsed.module, Some("app::net"));
+        assert_eq!(parsed.timestamp, Some(Duration::from_millis(1500)));
+        assert_eq!(parsed.sequence, Some(7));
+        assert_eq!(parsed.thread, Some(2));
+        assert_eq!(parsed.user, Some(5));
+
+        assert_eq!(
+            serde_json::from_str::<ULogLevel>("5").unwrap(),
+            ULogLevel::Critical
+        );
+        assert!(serde_json::from_str::<ULogLevel>(r#""loud""#).is_err());
+    }
+}
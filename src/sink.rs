@@ -0,0 +1,1242 @@
+use super::format::{TextFormatter, ULogFormatter};
+use super::record::TextBuffer;
+use super::{ULog, ULogData};
+use core::cell::{Cell, RefCell};
+use core::fmt::Write;
+#[cfg(feature = "std")]
+use std::{fs, io, path::PathBuf, sync::Arc, thread::JoinHandle};
+
+/// A destination for raw bytes, like a serial port, a socket or some flash storage.
+///
+/// Implemented for closures taking a byte slice.
+pub trait ByteSink {
+    fn write_bytes(&self, bytes: &[u8]);
+
+    /// Called by loggers once they have written a complete frame or statement, for sinks that delimit them.
+    fn end_frame(&self) {}
+
+    /// Writes out the bytes the sink may still be buffering, called by [`ULog::flush`]; defaults to doing nothing.
+    fn flush(&self) {}
+}
+
+impl<F: Fn(&[u8])> ByteSink for F {
+    fn write_bytes(&self, bytes: &[u8]) {
+        self(bytes)
+    }
+}
+
+/// A cipher used by [`EncryptLogger`] to encrypt statements, like a stream cipher or an AEAD.
+pub trait ULogCipher {
+    /// Data produced alongside the ciphertext, like an authentication tag; use `[u8; 0]` if there is none.
+    type Tag: AsRef<[u8]>;
+
+    /// Encrypts `buffer` in place, using `nonce`, which is never reused by a given [`EncryptLogger`].
+    fn encrypt(&self, nonce: u64, buffer: &mut [u8]) -> Self::Tag;
+}
+
+/// Formats each logging statement as a line of text, encrypts it and writes the resulting frame to a [`ByteSink`],
+/// so that logs sent over untrusted links stay confidential.
+///
+/// Each frame is made of the following, with integers encoded in little-endian:
+/// - the length of the rest of the frame, as a `u16`
+/// - the nonce passed to the cipher, as a `u64`
+/// - the encrypted statement, formatted as `LEVEL file:line message key=value\n` and truncated to `SIZE` bytes
+/// - the [tag](ULogCipher::Tag) returned by the cipher
+///
+/// Nonces start at the one given to [`new`](EncryptLogger::new) and get incremented for every statement.
+/// Reusing a nonce with the same key breaks the confidentiality of most ciphers, so the initial nonce must
+/// never have been used with that key: after a reboot, start from a nonce persisted to storage beforehand,
+/// or from a boot counter in the upper bits, and read [`nonce`](EncryptLogger::nonce) to persist it.
+///
+/// So that the length of a frame always fits in its `u16`, `SIZE` can't be larger than `65527` bytes minus the size
+/// of the tag, which is checked at compile time; frames whose tag turns out larger than its type are dropped.
+///
+/// ```compile_fail
+/// use ulog::sink::{EncryptLogger, ULogCipher};
+///
+/// struct Plain;
+///
+/// impl ULogCipher for Plain {
+///     type Tag = [u8; 0];
+///
+///     fn encrypt(&self, _nonce: u64, _buffer: &mut [u8]) -> [u8; 0] {
+///         []
+///     }
+/// }
+///
+/// let logger = EncryptLogger::<_, _, 65536>::new(|_: &[u8]| {}, Plain, 0);
+/// ```
+pub struct EncryptLogger<Sink, Cipher, const SIZE: usize> {
+    sink: Sink,
+    cipher: Cipher,
+    nonce: Cell<u64>,
+    buffer: RefCell<TextBuffer<SIZE>>,
+}
+
+impl<Sink: ByteSink, Cipher: ULogCipher, const SIZE: usize> EncryptLogger<Sink, Cipher, SIZE> {
+    /// Creates a logger encrypting its first statement with `nonce`, see [`EncryptLogger`] for how to pick it.
+    pub fn new(sink: Sink, cipher: Cipher, nonce: u64) -> Self {
+        const {
+            assert!(
+                8 + SIZE + size_of::<Cipher::Tag>() <= u16::MAX as usize,
+                "ulog: the frames of an EncryptLogger can't hold statements of SIZE bytes"
+            )
+        };
+
+        Self {
+            sink,
+            cipher,
+            nonce: Cell::new(nonce),
+            buffer: RefCell::new(TextBuffer::new()),
+        }
+    }
+
+    /// Returns the nonce that will be used for the next statement.
+    pub fn nonce(&self) -> u64 {
+        self.nonce.get()
+    }
+
+    pub fn into_inner(self) -> (Sink, Cipher) {
+        (self.sink, self.cipher)
+    }
+}
+
+impl<Sink: ByteSink, Cipher: ULogCipher, const SIZE: usize> ULog
+    for EncryptLogger<Sink, Cipher, SIZE>
+{
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        let _ = TextFormatter::new().format_str(&mut *self.buffer.borrow_mut(), log_data, string);
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        let _ = TextFormatter::new().format_str_continued(
+            &mut *self.buffer.borrow_mut(),
+            log_data,
+            string,
+        );
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        let _ = TextFormatter::new().format_fmt(&mut *self.buffer.borrow_mut(), log_data, args);
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        let _ =
+            TextFormatter::new().format_kv(&mut *self.buffer.borrow_mut(), log_data, key, value);
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        let mut buffer = self.buffer.borrow_mut();
+        buffer.clear();
+        let _ = TextFormatter::new().format_begin(&mut *buffer, log_data);
+    }
+
+    fn log_end(&self, _log_data: &ULogData) {
+        let mut buffer = self.buffer.borrow_mut();
+        let _ = buffer.write_str("\n");
+
+        let nonce = self.nonce.get();
+        self.nonce.set(nonce.wrapping_add(1));
+
+        let ciphertext = buffer.as_bytes_mut();
+        let tag = self.cipher.encrypt(nonce, ciphertext);
+        let Ok(len) = u16::try_from(8 + ciphertext.len() + tag.as_ref().len()) else {
+            buffer.clear();
+            return;
+        };
+
+        self.sink.write_bytes(&len.to_le_bytes());
+        self.sink.write_bytes(&nonce.to_le_bytes());
+        self.sink.write_bytes(ciphertext);
+        self.sink.write_bytes(tag.as_ref());
+        self.sink.end_frame();
+
+        buffer.clear();
+    }
+
+    fn flush(&self) {
+        self.sink.flush();
+    }
+}
+
+/// A message authentication code used by [`HmacChainLogger`], typically HMAC-SHA256 with a device-specific key.
+pub trait ULogMac {
+    /// The authentication code, like `[u8; 32]` for HMAC-SHA256.
+    type Output: AsRef<[u8]> + Clone + Default;
+
+    /// Computes the authentication code of `previous ‖ record`.
+    fn mac(&self, previous: &[u8], record: &[u8]) -> Self::Output;
+}
+
+/// Formats each logging statement as a line of text and appends an authentication code chained over all
+/// of the previous statements, before writing the line to a [`ByteSink`].
+///
+/// Lines are formatted as `LEVEL file:line message key=value mac=<hex>\n`, where the code covers the previous
+/// code (all zeroes for the first line) and the line up to, but excluding, ` mac=`.
+/// Line breaks and backslashes within a statement are escaped as `\n`, `\r` and `\\`, so that each statement
+/// stays on a single line.
+/// Modifying, removing or reordering lines thus breaks the chain, which [`HmacChainVerifier`] checks.
+/// Statements are truncated to `SIZE` bytes.
+pub struct HmacChainLogger<Sink, Mac: ULogMac, const SIZE: usize> {
+    sink: Sink,
+    mac: Mac,
+    previous: RefCell<Mac::Output>,
+    buffer: RefCell<TextBuffer<SIZE>>,
+}
+
+impl<Sink: ByteSink, Mac: ULogMac, const SIZE: usize> HmacChainLogger<Sink, Mac, SIZE> {
+    pub fn new(sink: Sink, mac: Mac) -> Self {
+        Self {
+            sink,
+            mac,
+            previous: RefCell::new(Mac::Output::default()),
+            buffer: RefCell::new(TextBuffer::new()),
+        }
+    }
+
+    /// Continues a chain whose last code was `previous`, for instance after a reboot.
+    pub fn with_previous(self, previous: Mac::Output) -> Self {
+        *self.previous.borrow_mut() = previous;
+        self
+    }
+
+    /// Returns the code of the last statement; keeping it somewhere safe allows detecting truncated logs.
+    pub fn last_mac(&self) -> Mac::Output {
+        self.previous.borrow().clone()
+    }
+
+    pub fn into_inner(self) -> (Sink, Mac) {
+        (self.sink, self.mac)
+    }
+}
+
+impl<Sink, Mac: ULogMac, const SIZE: usize> HmacChainLogger<Sink, Mac, SIZE> {
+    fn write(&self, f: impl FnOnce(&mut dyn Write) -> core::fmt::Result) {
+        let _ = f(&mut LineEscaper(&mut *self.buffer.borrow_mut()));
+    }
+}
+
+impl<Sink: ByteSink, Mac: ULogMac, const SIZE: usize> ULog for HmacChainLogger<Sink, Mac, SIZE> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        self.write(|out| TextFormatter::new().format_str(out, log_data, string));
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.write(|out| TextFormatter::new().format_str_continued(out, log_data, string));
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.write(|out| TextFormatter::new().format_fmt(out, log_data, args));
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.write(|out| TextFormatter::new().format_kv(out, log_data, key, value));
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.buffer.borrow_mut().clear();
+        self.write(|out| TextFormatter::new().format_begin(out, log_data));
+    }
+
+    fn log_end(&self, _log_data: &ULogData) {
+        let mut buffer = self.buffer.borrow_mut();
+        let mut previous = self.previous.borrow_mut();
+
+        let mac = self.mac.mac(previous.as_ref(), buffer.as_str().as_bytes());
+
+        self.sink.write_bytes(buffer.as_str().as_bytes());
+        self.sink.write_bytes(b" mac=");
+        for byte in mac.as_ref() {
+            self.sink.write_bytes(&hex_byte(*byte));
+        }
+        self.sink.write_bytes(b"\n");
+        self.sink.end_frame();
+
+        *previous = mac;
+        buffer.clear();
+    }
+
+    fn flush(&self) {
+        self.sink.flush();
+    }
+}
+
+/// Escapes the line breaks and backslashes written through it, for [`HmacChainLogger`].
+struct LineEscaper<'a>(&'a mut dyn Write);
+
+impl Write for LineEscaper<'_> {
+    fn write_str(&mut self, string: &str) -> core::fmt::Result {
+        let mut start = 0;
+        for (index, c) in string.char_indices() {
+            let escaped = match c {
+                '\\' => "\\\\",
+                '\n' => "\\n",
+                '\r' => "\\r",
+                _ => continue,
+            };
+
+            self.0.write_str(&string[start..index])?;
+            self.0.write_str(escaped)?;
+            start = index + c.len_utf8();
+        }
+        self.0.write_str(&string[start..])
+    }
+}
+
+fn hex_byte(byte: u8) -> [u8; 2] {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    [DIGITS[(byte >> 4) as usize], DIGITS[(byte & 0xf) as usize]]
+}
+
+fn hex_digit(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|digit| digit as u8)
+}
+
+/// An error returned by [`HmacChainVerifier::verify_line`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MacError {
+    /// The line does not end with a well-formed ` mac=<hex>` suffix.
+    Malformed,
+    /// The code does not match: the line or one of the previous ones was modified, removed or reordered.
+    Mismatch,
+}
+
+/// Checks the lines written by a [`HmacChainLogger`], one after the other.
+///
+/// Lines removed from the end of the logs can be detected by comparing [`last_mac`](HmacChainVerifier::last_mac)
+/// with the [code kept by the logger](HmacChainLogger::last_mac).
+pub struct HmacChainVerifier<Mac: ULogMac> {
+    mac: Mac,
+    previous: Mac::Output,
+}
+
+impl<Mac: ULogMac> HmacChainVerifier<Mac> {
+    pub fn new(mac: Mac) -> Self {
+        Self {
+            mac,
+            previous: Mac::Output::default(),
+        }
+    }
+
+    /// Starts verifying from the middle of a chain, whose last code was `previous`.
+    pub fn with_previous(mut self, previous: Mac::Output) -> Self {
+        self.previous = previous;
+        self
+    }
+
+    /// Returns the code of the last successfully verified line.
+    pub fn last_mac(&self) -> &Mac::Output {
+        &self.previous
+    }
+
+    /// Verifies the next line of the logs, with or without its trailing newline.
+    pub fn verify_line(&mut self, line: &[u8]) -> Result<(), MacError> {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let separator = line
+            .windows(5)
+            .rposition(|window| window == b" mac=")
+            .ok_or(MacError::Malformed)?;
+        let (record, hex) = (&line[..separator], &line[separator + 5..]);
+
+        let expected = self.mac.mac(self.previous.as_ref(), record);
+        let expected_bytes = expected.as_ref();
+        if hex.len() != expected_bytes.len() * 2 {
+            return Err(MacError::Malformed);
+        }
+
+        // Every byte is compared, so that timing doesn't reveal the position of the first difference
+        let mut difference = 0;
+        for (digits, expected_byte) in hex.chunks(2).zip(expected_bytes) {
+            let high = hex_digit(digits[0]).ok_or(MacError::Malformed)?;
+            let low = hex_digit(digits[1]).ok_or(MacError::Malformed)?;
+            difference |= ((high << 4) | low) ^ expected_byte;
+        }
+
+        if difference != 0 {
+            return Err(MacError::Mismatch);
+        }
+        self.previous = expected;
+        Ok(())
+    }
+}
+
+/// A compression algorithm used by [`CompressLogger`], like heatshrink or deflate.
+pub trait ULogCompressor {
+    /// Compresses `input` into `output`, returning the length of the compressed data,
+    /// or `None` if it does not fit in `output`.
+    fn compress(&self, input: &[u8], output: &mut [u8]) -> Option<usize>;
+}
+
+/// Frame kind of a [`CompressLogger`] frame holding uncompressed data.
+pub const FRAME_RAW: u8 = 0;
+/// Frame kind of a [`CompressLogger`] frame holding compressed data.
+pub const FRAME_COMPRESSED: u8 = 1;
+
+/// Formats logging statements as lines of text and batches them, then compresses each batch before writing it
+/// to a [`ByteSink`], trading latency for bandwidth on slow links.
+///
+/// A batch is sent once the next statement would not fit in its `SIZE` bytes, or when
+/// [`flush`](CompressLogger::flush) is called. Lines are formatted as `LEVEL file:line message key=value\n`,
+/// and truncated if a single one, newline included, doesn't fit in `SIZE` bytes.
+///
+/// Each frame is made of the length of the rest of the frame (as a little-endian `u16`),
+/// the frame kind ([`FRAME_COMPRESSED`], or [`FRAME_RAW`] if the batch was not compressible) and the data.
+/// So that the length always fits, `SIZE` can't be larger than `65534` bytes, which is checked at compile time:
+///
+/// ```compile_fail
+/// use ulog::sink::{CompressLogger, ULogCompressor};
+///
+/// struct Store;
+///
+/// impl ULogCompressor for Store {
+///     fn compress(&self, _input: &[u8], _output: &mut [u8]) -> Option<usize> {
+///         None
+///     }
+/// }
+///
+/// let logger = CompressLogger::<_, _, 65535>::new(|_: &[u8]| {}, Store);
+/// ```
+pub struct CompressLogger<Sink, Compressor, const SIZE: usize> {
+    sink: Sink,
+    compressor: Compressor,
+    batch: RefCell<Batch<SIZE>>,
+}
+
+struct Batch<const SIZE: usize> {
+    bytes: [u8; SIZE],
+    len: usize,
+    /// The start of the statement being written, which is only sent once complete
+    statement_start: usize,
+    truncated: bool,
+    output: [u8; SIZE],
+}
+
+impl<Sink: ByteSink, Compressor: ULogCompressor, const SIZE: usize>
+    CompressLogger<Sink, Compressor, SIZE>
+{
+    pub fn new(sink: Sink, compressor: Compressor) -> Self {
+        const {
+            assert!(
+                SIZE > 0 && SIZE < u16::MAX as usize,
+                "ulog: the frames of a CompressLogger can't hold batches of SIZE bytes"
+            )
+        };
+
+        Self {
+            sink,
+            compressor,
+            batch: RefCell::new(Batch {
+                bytes: [0; SIZE],
+                len: 0,
+                statement_start: 0,
+                truncated: false,
+                output: [0; SIZE],
+            }),
+        }
+    }
+
+    /// Compresses and sends the statements batched so far, then [flushes](ByteSink::flush) the sink.
+    pub fn flush(&self) {
+        self.send(&mut self.batch.borrow_mut());
+        self.sink.flush();
+    }
+
+    pub fn into_inner(self) -> (Sink, Compressor) {
+        self.flush();
+        (self.sink, self.compressor)
+    }
+
+    /// Sends the complete statements of the batch, and moves the statement being written to the start of the batch.
+    fn send(&self, batch: &mut Batch<SIZE>) {
+        let Batch {
+            bytes,
+            len,
+            statement_start,
+            output,
+            ..
+        } = batch;
+        if *statement_start == 0 {
+            return;
+        }
+
+        let input = &bytes[..*statement_start];
+        match self.compressor.compress(input, output) {
+            Some(compressed_len) if compressed_len < input.len() => {
+                self.write_frame(FRAME_COMPRESSED, &output[..compressed_len]);
+            }
+            _ => self.write_frame(FRAME_RAW, input),
+        }
+
+        bytes.copy_within(*statement_start..*len, 0);
+        *len -= *statement_start;
+        *statement_start = 0;
+    }
+
+    fn write_frame(&self, kind: u8, data: &[u8]) {
+        // Data never exceeds `SIZE` bytes, checked in `new`
+        let Ok(len) = u16::try_from(data.len() + 1) else {
+            return;
+        };
+        self.sink.write_bytes(&len.to_le_bytes());
+        self.sink.write_bytes(&[kind]);
+        self.sink.write_bytes(data);
+        self.sink.end_frame();
+    }
+}
+
+struct BatchWriter<'a, Sink, Compressor, const SIZE: usize> {
+    logger: &'a CompressLogger<Sink, Compressor, SIZE>,
+    batch: &'a mut Batch<SIZE>,
+}
+
+impl<Sink: ByteSink, Compressor: ULogCompressor, const SIZE: usize> Write
+    for BatchWriter<'_, Sink, Compressor, SIZE>
+{
+    fn write_str(&mut self, string: &str) -> core::fmt::Result {
+        if self.batch.truncated {
+            return Ok(());
+        }
+        // One byte is always kept for the newline ending the statement
+        if self.batch.len + string.len() > SIZE - 1 {
+            self.logger.send(self.batch);
+        }
+
+        let available = SIZE - 1 - self.batch.len;
+        let mut written = string.len().min(available);
+        while !string.is_char_boundary(written) {
+            written -= 1;
+        }
+
+        let len = self.batch.len;
+        self.batch.bytes[len..len + written].copy_from_slice(&string.as_bytes()[..written]);
+        self.batch.len += written;
+        self.batch.truncated = written < string.len();
+
+        Ok(())
+    }
+}
+
+impl<Sink: ByteSink, Compressor: ULogCompressor, const SIZE: usize> ULog
+    for CompressLogger<Sink, Compressor, SIZE>
+{
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        let batch = &mut *self.batch.borrow_mut();
+        let _ = TextFormatter::new().format_str(
+            &mut BatchWriter {
+                logger: self,
+                batch,
+            },
+            log_data,
+            string,
+        );
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        let batch = &mut *self.batch.borrow_mut();
+        let _ = TextFormatter::new().format_str_continued(
+            &mut BatchWriter {
+                logger: self,
+                batch,
+            },
+            log_data,
+            string,
+        );
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        let batch = &mut *self.batch.borrow_mut();
+        let _ = TextFormatter::new().format_fmt(
+            &mut BatchWriter {
+                logger: self,
+                batch,
+            },
+            log_data,
+            args,
+        );
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        let batch = &mut *self.batch.borrow_mut();
+        let _ = TextFormatter::new().format_kv(
+            &mut BatchWriter {
+                logger: self,
+                batch,
+            },
+            log_data,
+            key,
+            value,
+        );
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        let batch = &mut *self.batch.borrow_mut();
+        batch.len = batch.statement_start;
+        batch.truncated = false;
+        let _ = TextFormatter::new().format_begin(
+            &mut BatchWriter {
+                logger: self,
+                batch,
+            },
+            log_data,
+        );
+    }
+
+    fn log_end(&self, _log_data: &ULogData) {
+        let batch = &mut *self.batch.borrow_mut();
+        // The statement leaves room for its newline, see `BatchWriter`
+        batch.bytes[batch.len] = b'\n';
+        batch.len += 1;
+        batch.truncated = false;
+        batch.statement_start = batch.len;
+    }
+
+    fn flush(&self) {
+        CompressLogger::flush(self);
+    }
+}
+
+/// Writes bytes to an [`io::Write`](std::io::Write), like a file or a socket, ignoring its errors; requires the `std` feature.
+///
+/// [`ULog::flush`] flushes the writer, so that statements are persisted once it returns:
+///
+/// ```no_run
+/// use ulog::format::{FormattedLogger, TextFormatter};
+/// use ulog::sink::IoSink;
+/// use ulog::ULog;
+///
+/// let file = std::fs::File::create("device.log").unwrap();
+/// let logger = FormattedLogger::new(TextFormatter::new(), IoSink::new(std::io::BufWriter::new(file)));
+///
+/// ulog::info!(logger, "Going to sleep");
+/// logger.flush();
+/// ```
+#[cfg(feature = "std")]
+pub struct IoSink<W> {
+    writer: RefCell<W>,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> IoSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: RefCell::new(writer),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> ByteSink for IoSink<W> {
+    fn write_bytes(&self, bytes: &[u8]) {
+        let _ = self.writer.borrow_mut().write_all(bytes);
+    }
+
+    fn flush(&self) {
+        let _ = self.writer.borrow_mut().flush();
+    }
+}
+
+/// Compresses the files rotated by a [`RotatingFileSink`], for instance with gzip; requires the `std` feature.
+///
+/// With [`CompressionMode::Background`], it is called from another thread.
+#[cfg(feature = "std")]
+pub trait FileCompressor: Send + Sync + 'static {
+    /// The extension given to compressed files, like `"gz"`.
+    fn extension(&self) -> &str;
+
+    /// Compresses the whole of `input` into `output`.
+    fn compress(&self, input: &mut dyn io::Read, output: &mut dyn io::Write) -> io::Result<()>;
+}
+
+/// When a [`RotatingFileSink`] compresses the files it rotates.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompressionMode {
+    /// Compresses them while rotating, delaying the statement that filled the file.
+    Blocking,
+    /// Compresses them on a background thread; the next rotation, and dropping the sink, wait for it to be done.
+    Background,
+}
+
+/// Writes bytes to a file through an [`IoSink`], and rotates the file once it grows past `max_size` bytes;
+/// requires the `std` feature.
+///
+/// Files are rotated between statements: the file at `path` is renamed to `<path>.1`, after `<path>.1` was renamed
+/// to `<path>.2` and so on, keeping up to [`max_files`](RotatingFileSink::with_max_files) rotated files (5 by default).
+/// With a [`FileCompressor`], rotated files are compressed, as `<path>.1.gz` for gzip, and
+/// [`with_max_total_size`](RotatingFileSink::with_max_total_size) deletes the oldest ones once they take up more
+/// than a given number of bytes together, so that long-running devices don't fill their storage.
+///
+/// Errors are ignored, as with [`IoSink`]; a rotated file that can't be compressed is kept as it is.
+///
+/// ```no_run
+/// use std::io;
+/// use ulog::format::{FormattedLogger, TextFormatter};
+/// use ulog::sink::{CompressionMode, FileCompressor, RotatingFileSink};
+///
+/// struct Gzip;
+///
+/// impl FileCompressor for Gzip {
+///     fn extension(&self) -> &str {
+///         "gz"
+///     }
+///
+///     fn compress(&self, input: &mut dyn io::Read, output: &mut dyn io::Write) -> io::Result<()> {
+///         // With flate2: `io::copy(input, &mut GzEncoder::new(output, Compression::default()))`
+///         io::copy(input, output).map(|_| ())
+///     }
+/// }
+///
+/// let sink = RotatingFileSink::new("device.log", 1024 * 1024)
+///     .unwrap()
+///     .with_max_files(10)
+///     .with_compressor(Gzip, CompressionMode::Background)
+///     .with_max_total_size(4 * 1024 * 1024);
+/// let logger = FormattedLogger::new(TextFormatter::new(), sink);
+///
+/// ulog::info!(logger, "Booting");
+/// ```
+#[cfg(feature = "std")]
+pub struct RotatingFileSink {
+    rotation: Rotation,
+    max_size: u64,
+    compressor: Option<(Arc<dyn FileCompressor>, CompressionMode)>,
+    sink: RefCell<Option<IoSink<io::BufWriter<fs::File>>>>,
+    size: Cell<u64>,
+    /// The background compression of the last rotated file
+    pending: RefCell<Option<JoinHandle<()>>>,
+}
+
+/// Where a [`RotatingFileSink`] puts its rotated files, and how many of them it keeps.
+#[cfg(feature = "std")]
+#[derive(Clone)]
+struct Rotation {
+    path: PathBuf,
+    extension: Option<String>,
+    max_files: usize,
+    max_total_size: Option<u64>,
+}
+
+#[cfg(feature = "std")]
+impl Rotation {
+    /// The path of the `index`th rotated file, before it gets compressed.
+    fn uncompressed(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        path.into()
+    }
+
+    /// The path of the `index`th rotated file.
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut path = self.uncompressed(index).into_os_string();
+        if let Some(extension) = &self.extension {
+            path.push(format!(".{extension}"));
+        }
+        path.into()
+    }
+
+    /// Makes room for a new rotated file, deleting the oldest one.
+    fn shift(&self) {
+        let _ = fs::remove_file(self.rotated(self.max_files));
+        for index in (1..self.max_files).rev() {
+            let _ = fs::rename(self.rotated(index), self.rotated(index + 1));
+        }
+    }
+
+    fn compress(&self, compressor: &dyn FileCompressor) {
+        let (uncompressed, rotated) = (self.uncompressed(1), self.rotated(1));
+        let result = fs::File::open(&uncompressed).and_then(|mut input| {
+            let mut output = io::BufWriter::new(fs::File::create(&rotated)?);
+            compressor.compress(&mut input, &mut output)?;
+            io::Write::flush(&mut output)
+        });
+
+        match result {
+            Ok(()) => {
+                let _ = fs::remove_file(uncompressed);
+            }
+            Err(_) => {
+                let _ = fs::rename(uncompressed, rotated);
+            }
+        }
+    }
+
+    /// Deletes the oldest rotated files, until the rest fits in `max_total_size` bytes.
+    fn prune(&self) {
+        let Some(max_total_size) = self.max_total_size else {
+            return;
+        };
+
+        let mut total_size = 0;
+        for index in 1..=self.max_files {
+            let path = self.rotated(index);
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            total_size += metadata.len();
+            if total_size > max_total_size {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl RotatingFileSink {
+    /// Opens the file at `path`, appending to it if it exists, and rotates it once it holds `max_size` bytes.
+    pub fn new(path: impl Into<PathBuf>, max_size: u64) -> io::Result<Self> {
+        let path = path.into();
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            rotation: Rotation {
+                path,
+                extension: None,
+                max_files: 5,
+                max_total_size: None,
+            },
+            max_size,
+            compressor: None,
+            sink: RefCell::new(Some(IoSink::new(io::BufWriter::new(file)))),
+            size: Cell::new(size),
+            pending: RefCell::new(None),
+        })
+    }
+
+    /// Sets the number of rotated files to keep, at least one.
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.rotation.max_files = max_files.max(1);
+        self
+    }
+
+    /// Compresses the rotated files with `compressor`, adding its extension to their names.
+    pub fn with_compressor(
+        mut self,
+        compressor: impl FileCompressor,
+        mode: CompressionMode,
+    ) -> Self {
+        self.rotation.extension = Some(compressor.extension().to_string());
+        self.compressor = Some((Arc::new(compressor), mode));
+        self
+    }
+
+    /// Deletes the oldest rotated files while they take up more than `max_total_size` bytes together,
+    /// once they are compressed.
+    pub fn with_max_total_size(mut self, max_total_size: u64) -> Self {
+        self.rotation.max_total_size = Some(max_total_size);
+        self
+    }
+
+    /// Waits for the compression of the last rotated file, if it is done in the background.
+    pub fn wait(&self) {
+        if let Some(pending) = self.pending.borrow_mut().take() {
+            let _ = pending.join();
+        }
+    }
+
+    fn rotate(&self) {
+        self.wait();
+
+        // Closes the file before renaming it, which some platforms require
+        let mut sink = self.sink.borrow_mut();
+        if let Some(sink) = sink.take() {
+            let _ = io::Write::flush(&mut sink.into_inner());
+        }
+
+        let rotation = &self.rotation;
+        rotation.shift();
+        let target = match self.compressor {
+            Some(_) => rotation.uncompressed(1),
+            None => rotation.rotated(1),
+        };
+        let _ = fs::rename(&rotation.path, target);
+
+        *sink = fs::File::create(&rotation.path)
+            .ok()
+            .map(|file| IoSink::new(io::BufWriter::new(file)));
+        self.size.set(0);
+
+        match &self.compressor {
+            Some((compressor, CompressionMode::Blocking)) => {
+                rotation.compress(&**compressor);
+                rotation.prune();
+            }
+            Some((compressor, CompressionMode::Background)) => {
+                let (rotation, compressor) = (rotation.clone(), compressor.clone());
+                *self.pending.borrow_mut() = Some(std::thread::spawn(move || {
+                    rotation.compress(&*compressor);
+                    rotation.prune();
+                }));
+            }
+            None => rotation.prune(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ByteSink for RotatingFileSink {
+    fn write_bytes(&self, bytes: &[u8]) {
+        if let Some(sink) = &*self.sink.borrow() {
+            sink.write_bytes(bytes);
+            self.size.set(self.size.get() + bytes.len() as u64);
+        }
+    }
+
+    /// Rotates the file if it is full, so that statements are never split across files.
+    fn end_frame(&self) {
+        if self.size.get() >= self.max_size {
+            self.rotate();
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(sink) = &*self.sink.borrow() {
+            sink.flush();
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for RotatingFileSink {
+    fn drop(&mut self) {
+        self.wait();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+
+    struct XorCipher(u8);
+
+    impl ULogCipher for XorCipher {
+        type Tag = [u8; 1];
+
+        fn encrypt(&self, nonce: u64, buffer: &mut [u8]) -> Self::Tag {
+            let mut checksum = 0;
+            for byte in buffer.iter_mut() {
+                checksum ^= *byte;
+                *byte ^= self.0 ^ nonce as u8;
+            }
+            [checksum]
+        }
+    }
+
+    #[test]
+    fn test_encrypt() {
+        let output = RefCell::new(Vec::new());
+        let logger = EncryptLogger::<_, _, 64>::new(
+            |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes),
+            XorCipher(0x55),
+            3,
+        );
+
+        info!(logger, "Hello", "value" => 32);
+        assert_eq!(logger.nonce(), 4);
+
+        let output = output.into_inner();
+        let len = u16::from_le_bytes([output[0], output[1]]) as usize;
+        assert_eq!(len, output.len() - 2);
+        assert_eq!(&output[2..10], &3u64.to_le_bytes());
+
+        let plaintext = output[10..output.len() - 1]
+            .iter()
+            .map(|byte| byte ^ 0x55 ^ 3)
+            .collect::<Vec<_>>();
+        let plaintext = String::from_utf8(plaintext).unwrap();
+        assert!(plaintext.starts_with("INFO src/sink.rs:"));
+        assert!(plaintext.ends_with(" Hello value=32\n"));
+        assert_eq!(
+            output[output.len() - 1],
+            plaintext.bytes().fold(0, |checksum, byte| checksum ^ byte)
+        );
+    }
+
+    /// Not an actual MAC, but good enough to check the chaining
+    struct SumMac;
+
+    impl ULogMac for SumMac {
+        type Output = [u8; 2];
+
+        fn mac(&self, previous: &[u8], record: &[u8]) -> Self::Output {
+            let sum = previous.iter().chain(record).fold(7u16, |sum, byte| {
+                sum.wrapping_mul(31).wrapping_add(*byte as u16)
+            });
+            sum.to_le_bytes()
+        }
+    }
+
+    #[test]
+    fn test_hmac_chain() {
+        let output = RefCell::new(Vec::new());
+        let logger = HmacChainLogger::<_, _, 64>::new(
+            |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes),
+            SumMac,
+        );
+
+        info!(logger, "Hello");
+        warn!(logger, "world", "value" => 32);
+        error!(logger, "!");
+        let last_mac = logger.last_mac();
+
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+
+        let mut verifier = HmacChainVerifier::new(SumMac);
+        for line in &lines {
+            assert_eq!(verifier.verify_line(line.as_bytes()), Ok(()));
+        }
+        assert_eq!(verifier.last_mac(), &last_mac);
+
+        let mut verifier = HmacChainVerifier::new(SumMac);
+        assert_eq!(verifier.verify_line(lines[0].as_bytes()), Ok(()));
+        assert_eq!(
+            verifier.verify_line(lines[2].as_bytes()),
+            Err(MacError::Mismatch)
+        );
+
+        let tampered = lines[0].replace("Hello", "Hallo");
+        assert_eq!(
+            HmacChainVerifier::new(SumMac).verify_line(tampered.as_bytes()),
+            Err(MacError::Mismatch)
+        );
+    }
+
+    #[test]
+    fn test_hmac_chain_line_breaks() {
+        let output = RefCell::new(Vec::new());
+        let logger = HmacChainLogger::<_, _, 128>::new(
+            |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes),
+            SumMac,
+        );
+
+        info!(logger, "Hello\nworld mac=0000", "path" => "a\\b\r\n");
+        info!(logger, "!");
+
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains(r#" Hello\nworld mac=0000 path="a\\\\b\\r\\n" mac="#));
+
+        let mut verifier = HmacChainVerifier::new(SumMac);
+        for line in &lines {
+            assert_eq!(verifier.verify_line(line.as_bytes()), Ok(()));
+        }
+    }
+
+    /// Run-length encoding, which doesn't make much sense for text but is trivial to decompress
+    struct RunLength;
+
+    impl ULogCompressor for RunLength {
+        fn compress(&self, input: &[u8], output: &mut [u8]) -> Option<usize> {
+            let mut len = 0;
+            for chunk in input.chunk_by(|a, b| a == b) {
+                for run in chunk.chunks(255) {
+                    output
+                        .get_mut(len..len + 2)?
+                        .copy_from_slice(&[run.len() as u8, run[0]]);
+                    len += 2;
+                }
+            }
+            Some(len)
+        }
+    }
+
+    #[test]
+    fn test_compress() {
+        let output = RefCell::new(Vec::new());
+        let logger = CompressLogger::<_, _, 96>::new(
+            |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes),
+            RunLength,
+        );
+
+        info!(logger, "Hello", "value" => "....................");
+        info!(logger, "world");
+        assert!(output.borrow().is_empty());
+
+        info!(logger, "!");
+        assert!(!output.borrow().is_empty());
+        logger.flush();
+
+        let text = decompress(&output.into_inner());
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with(r#" Hello value="....................""#));
+        assert!(lines[1].ends_with(" world"));
+        assert!(lines[2].ends_with(" !"));
+    }
+
+    #[test]
+    fn test_compress_full_batch() {
+        const SIZE: usize = 48;
+
+        let output = RefCell::new(Vec::new());
+        let logger = CompressLogger::<_, _, SIZE>::new(
+            |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes),
+            RunLength,
+        );
+
+        let log_data = ULogData::new(ULogLevel::Info, 1, "a.rs");
+        let mut prefix = String::new();
+        TextFormatter::new()
+            .format_begin(&mut prefix, &log_data)
+            .unwrap();
+        TextFormatter::new()
+            .format_str(&mut prefix, &log_data, "")
+            .unwrap();
+
+        for len in [SIZE - 1, SIZE] {
+            let message = "x".repeat(len - prefix.len());
+            logger.log_begin(&log_data);
+            logger.log_str(&log_data, &message);
+            logger.log_end(&log_data);
+            info!(logger, "next");
+        }
+        logger.flush();
+
+        let text = decompress(&output.into_inner());
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0].len(), SIZE - 1);
+        assert_eq!(lines[2].len(), SIZE - 1);
+        assert!(lines[1].ends_with(" next"));
+        assert!(lines[3].ends_with(" next"));
+    }
+
+    /// Decodes the frames written by a [`CompressLogger`] using [`RunLength`]
+    fn decompress(mut frames: &[u8]) -> String {
+        let mut text = Vec::new();
+        while !frames.is_empty() {
+            let len = u16::from_le_bytes([frames[0], frames[1]]) as usize;
+            let (frame, rest) = frames[2..].split_at(len);
+            match frame[0] {
+                FRAME_RAW => text.extend_from_slice(&frame[1..]),
+                _ => {
+                    for run in frame[1..].chunks(2) {
+                        text.extend(std::iter::repeat_n(run[1], run[0] as usize));
+                    }
+                }
+            }
+            frames = rest;
+        }
+        String::from_utf8(text).unwrap()
+    }
+
+    /// Keeps the bytes written to it until it is flushed
+    struct FlushSink<'a> {
+        pending: RefCell<Vec<u8>>,
+        flushed: &'a RefCell<Vec<u8>>,
+    }
+
+    impl ByteSink for FlushSink<'_> {
+        fn write_bytes(&self, bytes: &[u8]) {
+            self.pending.borrow_mut().extend_from_slice(bytes);
+        }
+
+        fn flush(&self) {
+            self.flushed
+                .borrow_mut()
+                .append(&mut self.pending.borrow_mut());
+        }
+    }
+
+    #[test]
+    fn test_flush() {
+        use crate::buffer::BufferedLogger;
+        use crate::format::FormattedLogger;
+
+        let flushed = RefCell::new(Vec::new());
+        let sink = FlushSink {
+            pending: RefCell::new(Vec::new()),
+            flushed: &flushed,
+        };
+        let logger =
+            BufferedLogger::<_, 4, 64>::new(FormattedLogger::new(TextFormatter::new(), sink))
+                .min_level(ULogLevel::Info)
+                .chain(common::StubLogger);
+
+        info!(logger, "Hello");
+        assert!(flushed.borrow().is_empty());
+
+        ULog::flush(&logger);
+        let output = String::from_utf8(flushed.take()).unwrap();
+        assert!(output.starts_with("INFO src/sink.rs:"));
+        assert!(output.ends_with(" Hello\n"));
+    }
+
+    /// Prefixes files with a marker instead of actually compressing them
+    #[cfg(feature = "std")]
+    struct Marker;
+
+    #[cfg(feature = "std")]
+    impl FileCompressor for Marker {
+        fn extension(&self) -> &str {
+            "z"
+        }
+
+        fn compress(&self, input: &mut dyn io::Read, output: &mut dyn io::Write) -> io::Result<()> {
+            output.write_all(b"Z")?;
+            io::copy(input, output).map(|_| ())
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_rotating_file_sink() {
+        use crate::format::FormattedLogger;
+
+        let dir = std::env::temp_dir().join(format!("ulog-rotate-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("device.log");
+        let rotated = |name: &str| dir.join(format!("device.log.{name}"));
+
+        for mode in [CompressionMode::Blocking, CompressionMode::Background] {
+            let sink = RotatingFileSink::new(&path, 48)
+                .unwrap()
+                .with_max_files(3)
+                .with_compressor(Marker, mode);
+            let logger = FormattedLogger::new(TextFormatter::new(), sink);
+            for index in 0..5 {
+                info!(logger, "Statement filling the file", "index" => index);
+            }
+            drop(logger);
+
+            assert!(fs::read_to_string(&path).unwrap().is_empty());
+            for index in 1..=3 {
+                let text = fs::read_to_string(rotated(&format!("{index}.z"))).unwrap();
+                assert!(text.starts_with("ZINFO src/sink.rs:"));
+                assert!(text.ends_with(&format!(" index={}\n", 5 - index)));
+            }
+            assert!(!rotated("4.z").exists());
+            assert!(!rotated("1").exists());
+        }
+
+        // Keeps the newest rotated file only, as two of them take up more than 100 bytes
+        let sink = RotatingFileSink::new(&path, 48)
+            .unwrap()
+            .with_max_files(3)
+            .with_compressor(Marker, CompressionMode::Blocking)
+            .with_max_total_size(100);
+        let logger = FormattedLogger::new(TextFormatter::new(), sink);
+        info!(logger, "Statement filling the file", "index" => 5);
+        drop(logger);
+
+        assert!(fs::read_to_string(rotated("1.z"))
+            .unwrap()
+            .ends_with(" index=5\n"));
+        assert!(!rotated("2.z").exists());
+        assert!(!rotated("3.z").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+use super::{Level, ULog, ULogData, ULogLevel};
+use core::fmt;
+
+/// The levels of `slog` can be used with the macros, as `ulog!(slog::Level::Warning, logger, "message")`.
+///
+/// As with `slog`, they are ordered from the least to the most verbose; loggers compare the converted levels.
+impl Level for slog::Level {
+    fn as_str(&self) -> &'static str {
+        slog::Level::as_str(self)
+    }
+
+    fn to_ulog_level(&self) -> ULogLevel {
+        match self {
+            slog::Level::Critical => ULogLevel::Critical,
+            slog::Level::Error => ULogLevel::Error,
+            slog::Level::Warning => ULogLevel::Warning,
+            slog::Level::Info => ULogLevel::Info,
+            slog::Level::Debug => ULogLevel::Debug,
+            slog::Level::Trace => ULogLevel::Trace,
+        }
+    }
+}
+
+/// Exposes a [`ULog`] as a [`slog::Drain`], so that code written against `slog` can be moved to ulog
+/// one module at a time, with both of them logging to the same loggers.
+///
+/// The message is logged with [`log_fmt`](ULog::log_fmt), followed by the key-value pairs of the record,
+/// then the ones of the `slog::Logger`, each with [`log_format`](ULog::log_format).
+/// The module path of the record is used as its target, as it is by the macros; the tag of the record,
+/// if it has one, is logged as a `tag` key-value pair.
+///
+/// ```
+/// use slog::{info, o, Logger};
+/// use ulog::common::StubLogger;
+/// use ulog::slog_bridge::ULogDrain;
+///
+/// let logger = Logger::root(ULogDrain::new(StubLogger), o!("version" => "1.0"));
+/// info!(logger, "Sent to the StubLogger"; "port" => 8080);
+/// ```
+#[derive(Debug, Default)]
+pub struct ULogDrain<Logger> {
+    logger: Logger,
+}
+
+impl<Logger: ULog> ULogDrain<Logger> {
+    pub const fn new(logger: Logger) -> Self {
+        Self { logger }
+    }
+
+    pub fn logger(&self) -> &Logger {
+        &self.logger
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+}
+
+impl<Logger: ULog> slog::Drain for ULogDrain<Logger> {
+    type Ok = ();
+    type Err = slog::Never;
+
+    fn log(
+        &self,
+        record: &slog::Record<'_>,
+        values: &slog::OwnedKVList,
+    ) -> Result<Self::Ok, Self::Err> {
+        let log_data = ULogData::new(record.level().to_ulog_level(), record.line(), record.file())
+            .with_column(record.column())
+            .with_module(record.module())
+            .with_target(record.module());
+
+        if !self.logger.enabled(&log_data) {
+            return Ok(());
+        }
+
+        self.logger.log_begin(&log_data);
+        self.logger.log_fmt(&log_data, *record.msg());
+        let mut serializer = KeyValueSerializer {
+            logger: &self.logger,
+            log_data: &log_data,
+        };
+        let _ = slog::KV::serialize(&record.kv(), record, &mut serializer);
+        let _ = slog::KV::serialize(values, record, &mut serializer);
+        if !record.tag().is_empty() {
+            self.logger.log_format(&log_data, "tag", &record.tag());
+        }
+        self.logger.log_end(&log_data);
+
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), slog::FlushError> {
+        self.logger.flush();
+        Ok(())
+    }
+
+    fn is_enabled(&self, level: slog::Level) -> bool {
+        let log_data = ULogData::new(level.to_ulog_level(), 0, "");
+        self.logger.enabled(&log_data)
+    }
+}
+
+/// Logs the key-value pairs of a [`slog::Record`] or a [`slog::OwnedKVList`].
+struct KeyValueSerializer<'a, Logger> {
+    logger: &'a Logger,
+    log_data: &'a ULogData,
+}
+
+impl<Logger: ULog> slog::Serializer for KeyValueSerializer<'_, Logger> {
+    fn emit_str(&mut self, key: slog::Key, value: &str) -> slog::Result {
+        self.logger.log_format(self.log_data, key.as_ref(), &value);
+        Ok(())
+    }
+
+    fn emit_arguments(&mut self, key: slog::Key, value: &fmt::Arguments<'_>) -> slog::Result {
+        self.logger.log_format(self.log_data, key.as_ref(), value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct SyncLogger {
+        logs: Mutex<Vec<String>>,
+    }
+
+    impl ULog for SyncLogger {
+        fn log_str(&self, _log_data: &ULogData, string: &str) {
+            self.logs.lock().unwrap().push(string.to_string());
+        }
+
+        fn log_format<T: fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+            self.logs
+                .lock()
+                .unwrap()
+                .push(format!("{key} => {value:?}"));
+        }
+
+        fn log_begin(&self, log_data: &ULogData) {
+            self.logs
+                .lock()
+                .unwrap()
+                .push(format!("{} {:?}", log_data.level, log_data.target));
+        }
+
+        fn log_end(&self, _log_data: &ULogData) {}
+    }
+
+    #[test]
+    fn test_ulog_drain() {
+        let logger: &'static SyncLogger = Box::leak(Box::default());
+        let root = slog::Logger::root(ULogDrain::new(logger), slog::o!("version" => "1.0"));
+
+        slog::warn!(root, "read {} bytes", 12; "port" => 8080, "name" => "eth0");
+        slog::crit!(root, #"net", "Hello");
+        slog::debug!(root.new(slog::o!("task" => 3)), "Bye");
+
+        assert_eq!(
+            logger.logs.lock().unwrap()[..],
+            [
+                "WARN Some(\"ulog::slog_bridge::test\")",
+                "read 12 bytes",
+                "name => \"eth0\"",
+                "port => 8080",
+                "version => \"1.0\"",
+                "CRITICAL Some(\"ulog::slog_bridge::test\")",
+                "Hello",
+                "version => \"1.0\"",
+                "tag => \"net\"",
+                "DEBUG Some(\"ulog::slog_bridge::test\")",
+                "Bye",
+                "task => 3",
+                "version => \"1.0\"",
+            ]
+        );
+    }
+}
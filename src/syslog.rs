@@ -0,0 +1,191 @@
+use super::ULogLevel;
+use core::fmt;
+
+/// The severity of a syslog message, as defined by RFC 5424.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Emergency = 0,
+    Alert = 1,
+    Critical = 2,
+    Error = 3,
+    Warning = 4,
+    Notice = 5,
+    Informational = 6,
+    Debug = 7,
+}
+
+impl Severity {
+    /// Returns the severity with the given numerical code, between 0 and 7.
+    pub fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            0 => Severity::Emergency,
+            1 => Severity::Alert,
+            2 => Severity::Critical,
+            3 => Severity::Error,
+            4 => Severity::Warning,
+            5 => Severity::Notice,
+            6 => Severity::Informational,
+            7 => Severity::Debug,
+            _ => return None,
+        })
+    }
+
+    pub fn code(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Returns the closest [`ULogLevel`]; the severities above critical map to [`ULogLevel::Critical`].
+    pub fn to_level(&self) -> ULogLevel {
+        match self {
+            Severity::Emergency | Severity::Alert | Severity::Critical => ULogLevel::Critical,
+            Severity::Error => ULogLevel::Error,
+            Severity::Warning => ULogLevel::Warning,
+            Severity::Notice => ULogLevel::Notice,
+            Severity::Informational => ULogLevel::Info,
+            Severity::Debug => ULogLevel::Debug,
+        }
+    }
+}
+
+impl From<ULogLevel> for Severity {
+    fn from(level: ULogLevel) -> Self {
+        match level {
+            ULogLevel::Trace | ULogLevel::Debug => Severity::Debug,
+            ULogLevel::Info => Severity::Informational,
+            ULogLevel::Notice => Severity::Notice,
+            ULogLevel::Warning => Severity::Warning,
+            ULogLevel::Error => Severity::Error,
+            ULogLevel::Critical => Severity::Critical,
+        }
+    }
+}
+
+impl From<Severity> for ULogLevel {
+    fn from(severity: Severity) -> Self {
+        severity.to_level()
+    }
+}
+
+/// The facility of a syslog message, telling which part of the system it comes from, as defined by RFC 5424.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Facility {
+    Kernel = 0,
+    User = 1,
+    Mail = 2,
+    Daemon = 3,
+    Auth = 4,
+    Syslog = 5,
+    Printer = 6,
+    News = 7,
+    Uucp = 8,
+    Cron = 9,
+    AuthPriv = 10,
+    Ftp = 11,
+    Ntp = 12,
+    Audit = 13,
+    Alert = 14,
+    Clock = 15,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+impl Facility {
+    const ALL: [Facility; 24] = [
+        Facility::Kernel,
+        Facility::User,
+        Facility::Mail,
+        Facility::Daemon,
+        Facility::Auth,
+        Facility::Syslog,
+        Facility::Printer,
+        Facility::News,
+        Facility::Uucp,
+        Facility::Cron,
+        Facility::AuthPriv,
+        Facility::Ftp,
+        Facility::Ntp,
+        Facility::Audit,
+        Facility::Alert,
+        Facility::Clock,
+        Facility::Local0,
+        Facility::Local1,
+        Facility::Local2,
+        Facility::Local3,
+        Facility::Local4,
+        Facility::Local5,
+        Facility::Local6,
+        Facility::Local7,
+    ];
+
+    /// Returns the facility with the given numerical code, between 0 and 23.
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.get(code as usize).copied()
+    }
+
+    pub fn code(&self) -> u8 {
+        *self as u8
+    }
+}
+
+/// The priority of a syslog message, combining its [`Facility`] and its [`Severity`];
+/// formatted as the `<PRI>` header starting RFC 3164 and RFC 5424 messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Priority {
+    pub facility: Facility,
+    pub severity: Severity,
+}
+
+impl Priority {
+    pub fn new(facility: Facility, severity: impl Into<Severity>) -> Self {
+        Self {
+            facility,
+            severity: severity.into(),
+        }
+    }
+
+    /// Returns the numerical value of the priority, `facility * 8 + severity`.
+    pub fn value(&self) -> u8 {
+        self.facility.code() * 8 + self.severity.code()
+    }
+
+    /// Splits the numerical value of a priority into its facility and severity.
+    pub fn from_value(value: u8) -> Option<Self> {
+        Some(Self {
+            facility: Facility::from_code(value / 8)?,
+            severity: Severity::from_code(value % 8)?,
+        })
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}>", self.value())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_priority() {
+        let priority = Priority::new(Facility::Local4, ULogLevel::Warning);
+        assert_eq!(priority.value(), 164);
+        assert_eq!(priority.to_string(), "<164>");
+        assert_eq!(Priority::from_value(164), Some(priority));
+        assert_eq!(Priority::from_value(192), None);
+
+        for level in &ULogLevel::all_levels()[1..] {
+            assert_eq!(Severity::from(*level).to_level(), *level);
+        }
+        assert_eq!(Severity::from(ULogLevel::Trace), Severity::Debug);
+        assert_eq!(ULogLevel::from(Severity::Alert), ULogLevel::Critical);
+        assert_eq!(Severity::from_code(8), None);
+    }
+}
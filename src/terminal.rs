@@ -0,0 +1,139 @@
+use super::{ULog, ULogData};
+use std::cell::RefCell;
+use std::io::{self, Write};
+
+/// Clears the current line and moves the cursor back to its start.
+const CLEAR_LINE: &str = "\r\x1b[2K";
+
+/// Writes logging statements to a terminal (by default stderr) as `LEVEL file:line message key=value` lines,
+/// while cooperating with an in-place status line, like a progress bar.
+///
+/// The status line is cleared before each statement and drawn again after it, so that statements don't
+/// get mixed up with it.
+///
+/// ```no_run
+/// use ulog::terminal::TerminalLogger;
+///
+/// let logger = TerminalLogger::new();
+///
+/// for file in 0..10 {
+///     logger.set_status(&format!("Processing files: {file}/10"));
+///     ulog::info!(logger, "Processing file", "file" => file);
+/// }
+/// logger.clear_status();
+/// ```
+pub struct TerminalLogger<W: Write = io::Stderr> {
+    writer: RefCell<W>,
+    status: RefCell<String>,
+}
+
+impl TerminalLogger {
+    pub fn new() -> Self {
+        Self::with_writer(io::stderr())
+    }
+}
+
+impl Default for TerminalLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Write> TerminalLogger<W> {
+    pub fn with_writer(writer: W) -> Self {
+        Self {
+            writer: RefCell::new(writer),
+            status: RefCell::new(String::new()),
+        }
+    }
+
+    /// Replaces the status line, which gets drawn below the statements.
+    pub fn set_status(&self, status: &str) {
+        let mut current_status = self.status.borrow_mut();
+        current_status.clear();
+        current_status.push_str(status);
+
+        let mut writer = self.writer.borrow_mut();
+        let _ = write!(writer, "{}{}", CLEAR_LINE, current_status);
+        let _ = writer.flush();
+    }
+
+    /// Removes the status line.
+    pub fn clear_status(&self) {
+        self.status.borrow_mut().clear();
+
+        let mut writer = self.writer.borrow_mut();
+        let _ = write!(writer, "{}", CLEAR_LINE);
+        let _ = writer.flush();
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+impl<W: Write> ULog for TerminalLogger<W> {
+    fn log_str(&self, _log_data: &ULogData, string: &str) {
+        let _ = write!(self.writer.borrow_mut(), " {}", string);
+    }
+
+    fn log_str_continued(&self, _log_data: &ULogData, string: &str) {
+        let _ = write!(self.writer.borrow_mut(), "{}", string);
+    }
+
+    fn log_fmt(&self, _log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        let _ = write!(self.writer.borrow_mut(), " {}", args);
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+        let _ = write!(self.writer.borrow_mut(), " {}={:?}", key, value);
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        let mut writer = self.writer.borrow_mut();
+        if !self.status.borrow().is_empty() {
+            let _ = write!(writer, "{}", CLEAR_LINE);
+        }
+        let _ = write!(
+            writer,
+            "{} {}:{}",
+            log_data.level_name, log_data.file, log_data.line
+        );
+    }
+
+    fn log_end(&self, _log_data: &ULogData) {
+        let mut writer = self.writer.borrow_mut();
+        let _ = writeln!(writer);
+        let _ = write!(writer, "{}", self.status.borrow());
+        let _ = writer.flush();
+    }
+
+    fn flush(&self) {
+        let _ = self.writer.borrow_mut().flush();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn test_status_line() {
+        let logger = TerminalLogger::with_writer(Vec::new());
+
+        logger.set_status("1/2");
+        info!(logger, "Hello");
+        logger.clear_status();
+        info!(logger, "world");
+
+        let output = String::from_utf8(logger.into_inner()).unwrap();
+        let lines = output.split('\n').collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("\r\x1b[2K1/2\r\x1b[2KINFO src/terminal.rs:"));
+        assert!(lines[0].ends_with(" Hello"));
+        assert!(lines[1].starts_with("1/2\r\x1b[2KINFO src/terminal.rs:"));
+        assert!(lines[1].ends_with(" world"));
+        assert_eq!(lines[2], "");
+    }
+}
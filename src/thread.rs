@@ -0,0 +1,152 @@
+#[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+use super::atomic;
+use core::cell::UnsafeCell;
+#[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+const UNSET: usize = 0;
+#[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+const SETTING: usize = 1;
+const SET: usize = 2;
+
+static HOOK_STATE: AtomicUsize = AtomicUsize::new(UNSET);
+
+struct HookSlot(UnsafeCell<Option<fn() -> u32>>);
+
+// The slot is only written once, while `HOOK_STATE` is `SETTING`, and only read once `HOOK_STATE` is `SET`
+unsafe impl Sync for HookSlot {}
+
+static HOOK: HookSlot = HookSlot(UnsafeCell::new(None));
+
+/// The error returned by [`set_core_id_hook`] when a hook was already set.
+#[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CoreIdHookAlreadySet;
+
+#[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+impl fmt::Display for CoreIdHookAlreadySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a core id hook was already set")
+    }
+}
+
+/// Sets the function returning the id of the core running the caller, like the hart id on RISC-V or the
+/// `SIO.CPUID` register on the RP2040, which then fills the [`thread`](crate::ULogData::thread) of every statement.
+///
+/// The hook can only be set once, and takes precedence over the thread ids given with the `std` feature.
+/// On targets without atomic compare-and-swap, like `thumbv6m-none-eabi`, it requires the `critical-section` feature.
+///
+/// ```
+/// use ulog::thread::set_core_id_hook;
+/// use ulog::{ULogData, ULogLevel};
+///
+/// fn core_id() -> u32 {
+///     1 // read from the hardware
+/// }
+///
+/// set_core_id_hook(core_id).unwrap();
+/// assert_eq!(ULogData::new(ULogLevel::Info, line!(), file!()).thread, Some(1));
+/// ```
+#[cfg(any(target_has_atomic = "ptr", feature = "critical-section"))]
+pub fn set_core_id_hook(hook: fn() -> u32) -> Result<(), CoreIdHookAlreadySet> {
+    atomic::compare_exchange(&HOOK_STATE, UNSET, SETTING).map_err(|_| CoreIdHookAlreadySet)?;
+
+    unsafe {
+        *HOOK.0.get() = Some(hook);
+    }
+    HOOK_STATE.store(SET, Ordering::Release);
+
+    Ok(())
+}
+
+/// Returns the id of the thread or core running the caller: the one given by the [core id hook](set_core_id_hook)
+/// if it was set, or else with the `std` feature, a number given to each thread the first time it asks for it,
+/// starting from 1.
+pub fn current_thread_id() -> Option<u32> {
+    if HOOK_STATE.load(Ordering::Acquire) == SET {
+        if let Some(hook) = unsafe { *HOOK.0.get() } {
+            return Some(hook());
+        }
+    }
+
+    #[cfg(feature = "std")]
+    let id = Some(std_threads::current());
+    #[cfg(not(feature = "std"))]
+    let id = None;
+    id
+}
+
+/// Returns the name of the thread with the given [id](current_thread_id), if it had one when it got its id,
+/// requires the `std` feature.
+///
+/// Names are looked up by id, so that statements formatted on another thread, as with a
+/// [`NonBlockingLogger`](crate::buffer::NonBlockingLogger), still show the name of the thread that made them.
+#[cfg(feature = "std")]
+pub fn thread_name(id: u32) -> Option<std::string::String> {
+    std_threads::NAMES
+        .lock()
+        .unwrap_or_else(|error| error.into_inner())
+        .iter()
+        .find(|(thread, _)| *thread == id)
+        .map(|(_, name)| name.clone())
+}
+
+#[cfg(feature = "std")]
+mod std_threads {
+    use std::string::String;
+    use std::sync::Mutex;
+    use std::vec::Vec;
+
+    // A lock rather than an atomic counter, which targets without compare-and-swap wouldn't have
+    static NEXT_ID: Mutex<u32> = Mutex::new(1);
+
+    /// The names of the threads which had one when they got their id
+    pub(super) static NAMES: Mutex<Vec<(u32, String)>> = Mutex::new(Vec::new());
+
+    std::thread_local! {
+        static ID: u32 = {
+            let id = {
+                let mut next = NEXT_ID.lock().unwrap_or_else(|error| error.into_inner());
+                *next += 1;
+                *next - 1
+            };
+            if let Some(name) = std::thread::current().name() {
+                NAMES
+                    .lock()
+                    .unwrap_or_else(|error| error.into_inner())
+                    .push((id, String::from(name)));
+            }
+            id
+        };
+    }
+
+    pub(super) fn current() -> u32 {
+        ID.with(|id| *id)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_thread_ids() {
+        let id = current_thread_id().unwrap();
+        assert_eq!(current_thread_id(), Some(id));
+
+        let (other, name) = std::thread::Builder::new()
+            .name(String::from("worker"))
+            .spawn(|| {
+                let other = current_thread_id().unwrap();
+                (other, thread_name(other))
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert_ne!(other, id);
+        assert_eq!(name.as_deref(), Some("worker"));
+        assert_eq!(thread_name(id).as_deref(), std::thread::current().name());
+    }
+}
@@ -0,0 +1,693 @@
+use super::record::{TextBuffer, Verbatim};
+#[cfg(feature = "serde")]
+use super::serialize::SerializeJson;
+use super::{Level, ULog, ULogData, ULogLevel, Value};
+use core::cell::{Cell, RefCell};
+use core::fmt::Write;
+use core::panic::Location;
+use core::time::Duration;
+
+/// A source of time for the loggers that need one, like [`SpanLogger`].
+///
+/// Implemented for closures returning a [`Duration`], so that any hardware timer can be used.
+pub trait ULogClock {
+    /// Returns the time elapsed since an arbitrary, but fixed point in time.
+    fn now(&self) -> Duration;
+}
+
+impl<F: Fn() -> Duration> ULogClock for F {
+    fn now(&self) -> Duration {
+        self()
+    }
+}
+
+/// A monotonic clock measuring the time elapsed since its creation, requires the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct StdClock {
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl StdClock {
+    pub fn new() -> Self {
+        Self {
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for StdClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl ULogClock for StdClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// A clock measuring the time elapsed since the Unix epoch, requires the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl ULogClock for SystemClock {
+    fn now(&self) -> Duration {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+}
+
+/// Formats a Unix timestamp, given as the time elapsed since the Unix epoch, as RFC 3339 text in UTC
+/// with millisecond precision, like `2023-11-14T22:13:20.123Z`.
+///
+/// ```
+/// use core::time::Duration;
+/// use ulog::time::Rfc3339;
+///
+/// let timestamp = Rfc3339(Duration::from_millis(1_700_000_000_123));
+/// assert_eq!(timestamp.to_string(), "2023-11-14T22:13:20.123Z");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Rfc3339(pub Duration);
+
+impl Rfc3339 {
+    /// Converts a number of days since the Unix epoch into a `(year, month, day)` date of the proleptic Gregorian calendar.
+    fn civil_from_days(days: u64) -> (u64, u64, u64) {
+        // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let days = days + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+        (year, month, day)
+    }
+}
+
+impl core::fmt::Display for Rfc3339 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let seconds = self.0.as_secs();
+        let (year, month, day) = Self::civil_from_days(seconds / 86_400);
+        let seconds_of_day = seconds % 86_400;
+
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            year,
+            month,
+            day,
+            seconds_of_day / 3600,
+            seconds_of_day / 60 % 60,
+            seconds_of_day % 60,
+            self.0.subsec_millis()
+        )
+    }
+}
+
+impl core::fmt::Debug for Rfc3339 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(self, f)
+    }
+}
+
+/// Forwards logging statements to the wrapped logger, and lets you open [`Span`]s which log how long they lasted.
+///
+/// ```
+/// use ulog::common::StubLogger;
+/// use ulog::time::SpanLogger;
+/// use core::time::Duration;
+///
+/// let logger = SpanLogger::new(StubLogger, || Duration::from_millis(0));
+///
+/// {
+///     let _span = logger.span("boot"); // Logs `boot` with `span => enter`
+///     ulog::info!(logger, "Booting...");
+/// } // Logs `boot` with `span => exit` and `elapsed => 0ns`
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpanLogger<Logger, Clock> {
+    logger: Logger,
+    clock: Clock,
+    level: ULogLevel,
+}
+
+impl<Logger: ULog, Clock: ULogClock> SpanLogger<Logger, Clock> {
+    /// Creates a `SpanLogger`, with spans logged at the [`Debug`](ULogLevel::Debug) level by default.
+    pub fn new(logger: Logger, clock: Clock) -> Self {
+        Self {
+            logger,
+            clock,
+            level: ULogLevel::Debug,
+        }
+    }
+
+    /// Sets the level at which [`span`](SpanLogger::span) logs spans.
+    pub fn with_level(mut self, level: ULogLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Opens a span, which lasts until the returned guard is dropped.
+    #[track_caller]
+    pub fn span<'a>(&'a self, name: &'a str) -> Span<'a, Logger, Clock> {
+        self.span_at(self.level, name)
+    }
+
+    /// Opens a span logged at `level`, which lasts until the returned guard is dropped.
+    #[track_caller]
+    pub fn span_at<'a>(&'a self, level: impl Level, name: &'a str) -> Span<'a, Logger, Clock> {
+        let location = Location::caller();
+        let log_data =
+            ULogData::new(level, location.line(), location.file()).with_column(location.column());
+
+        self.logger.log_begin(&log_data);
+        self.logger.log_str(&log_data, name);
+        self.logger
+            .log_format(&log_data, "span", &Verbatim("enter"));
+        self.logger.log_end(&log_data);
+
+        Span {
+            logger: self,
+            name,
+            log_data,
+            start: self.clock.now(),
+        }
+    }
+
+    pub fn into_inner(self) -> (Logger, Clock) {
+        (self.logger, self.clock)
+    }
+}
+
+impl<Logger: ULog, Clock: ULogClock> ULog for SpanLogger<Logger, Clock> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str(log_data, string);
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str_continued(log_data, string);
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.logger.log_fmt(log_data, args);
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_format(log_data, key, value);
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.logger.log_value(log_data, key, value);
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        self.logger.log_bytes(log_data, key, bytes);
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_display(log_data, key, value);
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        self.logger.log_serialize(log_data, key, value);
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.logger.log_begin(log_data);
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        self.logger.log_end(log_data);
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.logger.enabled(log_data)
+    }
+}
+
+/// A guard returned by [`SpanLogger::span`], which logs the span's duration when dropped.
+#[must_use = "the span is closed as soon as the guard is dropped"]
+pub struct Span<'a, Logger: ULog, Clock: ULogClock> {
+    logger: &'a SpanLogger<Logger, Clock>,
+    name: &'a str,
+    log_data: ULogData,
+    start: Duration,
+}
+
+impl<Logger: ULog, Clock: ULogClock> Span<'_, Logger, Clock> {
+    /// Returns the time elapsed since the span was opened.
+    pub fn elapsed(&self) -> Duration {
+        self.logger.clock.now().saturating_sub(self.start)
+    }
+}
+
+impl<Logger: ULog, Clock: ULogClock> Drop for Span<'_, Logger, Clock> {
+    fn drop(&mut self) {
+        let elapsed = self.elapsed();
+        let logger = &self.logger.logger;
+
+        logger.log_begin(&self.log_data);
+        logger.log_str(&self.log_data, self.name);
+        logger.log_format(&self.log_data, "span", &Verbatim("exit"));
+        logger.log_format(&self.log_data, "elapsed", &elapsed);
+        logger.log_end(&self.log_data);
+    }
+}
+
+/// Stamps each logging statement with the time given by a [`ULogClock`] when it begins, in its
+/// [`timestamp`](ULogData::timestamp), before forwarding it to the wrapped logger.
+///
+/// All of the sinks and formatters behind it thus see the same timestamp, taken when the statement was made
+/// rather than when it got written. Statements that already carry a timestamp, as given by the
+/// global clock of the `global` feature, keep it.
+pub struct TimestampLogger<Logger, Clock> {
+    logger: Logger,
+    clock: Clock,
+    /// The timestamp of the current statement
+    current: Cell<Option<Duration>>,
+}
+
+impl<Logger: ULog, Clock: ULogClock> TimestampLogger<Logger, Clock> {
+    pub fn new(logger: Logger, clock: Clock) -> Self {
+        Self {
+            logger,
+            clock,
+            current: Cell::new(None),
+        }
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    pub fn into_inner(self) -> (Logger, Clock) {
+        (self.logger, self.clock)
+    }
+
+    fn stamp(&self, log_data: &ULogData) -> ULogData {
+        let mut log_data = log_data.clone();
+        if log_data.timestamp.is_none() {
+            log_data.timestamp = self.current.get();
+        }
+        log_data
+    }
+}
+
+impl<Logger: ULog, Clock: ULogClock> ULog for TimestampLogger<Logger, Clock> {
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str(&self.stamp(log_data), string);
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str_continued(&self.stamp(log_data), string);
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        self.logger.log_fmt(&self.stamp(log_data), args);
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_format(&self.stamp(log_data), key, value);
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.logger.log_value(&self.stamp(log_data), key, value);
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        self.logger.log_bytes(&self.stamp(log_data), key, bytes);
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_display(&self.stamp(log_data), key, value);
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        self.logger.log_serialize(&self.stamp(log_data), key, value);
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        self.current.set(Some(self.clock.now()));
+        self.logger.log_begin(&self.stamp(log_data));
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        self.logger.log_end(&self.stamp(log_data));
+        self.current.set(None);
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.logger.enabled(log_data)
+    }
+}
+
+/// Which elapsed times a [`DeltaTimeLogger`] prefixes messages with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeltaMode {
+    /// The time elapsed since the previous statement, as `+1.5ms`.
+    SincePrevious,
+    /// The time elapsed since the creation of the logger, as `12.3s`.
+    SinceStart,
+    /// Both, as `12.3s +1.5ms`.
+    Both,
+}
+
+/// Prefixes the message of each logging statement with the time elapsed since the previous statement
+/// (and/or since the creation of the logger), measured with a pluggable [`ULogClock`].
+///
+/// Prefixed messages are truncated to `SIZE` bytes.
+pub struct DeltaTimeLogger<Logger, Clock, const SIZE: usize> {
+    logger: Logger,
+    clock: Clock,
+    mode: DeltaMode,
+    start: Duration,
+    previous: Cell<Duration>,
+    /// The elapsed times of the current statement, until its message gets prefixed
+    current: Cell<Option<(Duration, Duration)>>,
+    buffer: RefCell<TextBuffer<SIZE>>,
+}
+
+impl<Logger: ULog, Clock: ULogClock, const SIZE: usize> DeltaTimeLogger<Logger, Clock, SIZE> {
+    pub fn new(logger: Logger, clock: Clock, mode: DeltaMode) -> Self {
+        let start = clock.now();
+
+        Self {
+            logger,
+            clock,
+            mode,
+            start,
+            previous: Cell::new(start),
+            current: Cell::new(None),
+            buffer: RefCell::new(TextBuffer::new()),
+        }
+    }
+
+    pub fn mode(&self) -> DeltaMode {
+        self.mode
+    }
+
+    pub fn into_inner(self) -> (Logger, Clock) {
+        (self.logger, self.clock)
+    }
+}
+
+impl<Logger: ULog, Clock: ULogClock, const SIZE: usize> ULog
+    for DeltaTimeLogger<Logger, Clock, SIZE>
+{
+    fn log_str(&self, log_data: &ULogData, string: &str) {
+        self.log_fmt(log_data, format_args!("{}", string));
+    }
+
+    fn log_str_continued(&self, log_data: &ULogData, string: &str) {
+        self.logger.log_str_continued(log_data, string);
+    }
+
+    fn log_fmt(&self, log_data: &ULogData, args: core::fmt::Arguments<'_>) {
+        let Some((since_start, since_previous)) = self.current.take() else {
+            self.logger.log_fmt(log_data, args);
+            return;
+        };
+
+        let mut buffer = self.buffer.borrow_mut();
+        buffer.clear();
+        let _ = match self.mode {
+            DeltaMode::SincePrevious => write!(buffer, "+{:?} {}", since_previous, args),
+            DeltaMode::SinceStart => write!(buffer, "{:?} {}", since_start, args),
+            DeltaMode::Both => write!(buffer, "{:?} +{:?} {}", since_start, since_previous, args),
+        };
+
+        self.logger.log_str(log_data, buffer.as_str());
+    }
+
+    fn log_format<T: core::fmt::Debug>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_format(log_data, key, value);
+    }
+
+    fn log_value(&self, log_data: &ULogData, key: &str, value: Value<'_>) {
+        self.logger.log_value(log_data, key, value);
+    }
+
+    fn log_bytes(&self, log_data: &ULogData, key: &str, bytes: &[u8]) {
+        self.logger.log_bytes(log_data, key, bytes);
+    }
+
+    fn log_display<T: core::fmt::Display>(&self, log_data: &ULogData, key: &str, value: &T) {
+        self.logger.log_display(log_data, key, value);
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        self.logger.log_serialize(log_data, key, value);
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        let now = self.clock.now();
+        let previous = self.previous.replace(now);
+        self.current.set(Some((
+            now.saturating_sub(self.start),
+            now.saturating_sub(previous),
+        )));
+
+        self.logger.log_begin(log_data);
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        self.current.set(None);
+        self.logger.log_end(log_data);
+    }
+
+    fn is_available(&self) -> bool {
+        self.logger.is_available()
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        self.logger.enabled(log_data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::TestLogger;
+    use core::cell::Cell;
+
+    #[test]
+    fn test_span() {
+        let time = Cell::new(Duration::from_millis(10));
+        let logger = SpanLogger::new(TestLogger::default(), || time.get());
+
+        {
+            let _span = logger.span("boot");
+            time.set(Duration::from_millis(25));
+        }
+
+        assert_eq!(
+            &logger.into_inner().0.logs.into_inner()[..],
+            &[
+                (ULogLevel::Debug, String::from("__BEGIN__")),
+                (ULogLevel::Debug, String::from("boot")),
+                (ULogLevel::Debug, String::from("span => enter")),
+                (ULogLevel::Debug, String::from("__END__")),
+                (ULogLevel::Debug, String::from("__BEGIN__")),
+                (ULogLevel::Debug, String::from("boot")),
+                (ULogLevel::Debug, String::from("span => exit")),
+                (ULogLevel::Debug, String::from("elapsed => 15ms")),
+                (ULogLevel::Debug, String::from("__END__")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_log_expr() {
+        let logger = TestLogger::default();
+        let time = Cell::new(Duration::from_millis(3));
+        let clock = || time.get();
+
+        let value = crate::log_expr!(ULogLevel::Info, logger, clock = &clock, {
+            time.set(Duration::from_millis(8));
+            6 * 7
+        });
+        assert_eq!(value, 42);
+
+        let logs = logger.logs.into_inner();
+        let logs = logs.iter().map(|(_, log)| log.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            logs,
+            [
+                "__BEGIN__",
+                "{ time.set(Duration::from_millis(8)); 6 * 7 }",
+                "value => 42",
+                "elapsed => 5ms",
+                "__END__",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_span_macro() {
+        let time = Cell::new(Duration::from_millis(10));
+        let logger = SpanLogger::new(TestLogger::default(), || time.get());
+
+        let value = crate::ulog_span!(ULogLevel::Info, logger, "compute", {
+            crate::info!(logger, "working");
+            time.set(Duration::from_millis(12));
+            42
+        });
+
+        assert_eq!(value, 42);
+        let logs = logger.into_inner().0.logs.into_inner();
+        let logs = logs.iter().map(|(_, log)| log.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            logs,
+            [
+                "__BEGIN__",
+                "compute",
+                "span => enter",
+                "__END__",
+                "__BEGIN__",
+                "working",
+                "__END__",
+                "__BEGIN__",
+                "compute",
+                "span => exit",
+                "elapsed => 2ms",
+                "__END__",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delta_time() {
+        let time = Cell::new(Duration::from_millis(100));
+        let logger =
+            DeltaTimeLogger::<_, _, 64>::new(TestLogger::default(), || time.get(), DeltaMode::Both);
+
+        time.set(Duration::from_millis(150));
+        crate::info!(logger, "Hello");
+        time.set(Duration::from_millis(1150));
+        crate::info!(logger, "world");
+
+        let logs = logger.into_inner().0.logs.into_inner();
+        assert_eq!(logs[1].1, "50ms +50ms Hello");
+        assert_eq!(logs[4].1, "1.05s +1s world");
+    }
+
+    #[test]
+    fn test_delta_time_fmt() {
+        let time = Cell::new(Duration::from_millis(100));
+        let logger = DeltaTimeLogger::<_, _, 64>::new(
+            TestLogger::default(),
+            || time.get(),
+            DeltaMode::SincePrevious,
+        );
+
+        time.set(Duration::from_millis(120));
+        crate::info!(logger, "Sampled {} values", 3);
+        time.set(Duration::from_millis(125));
+        crate::info!(logger, "Hello");
+
+        let logs = logger.into_inner().0.logs.into_inner();
+        assert_eq!(logs[1].1, "+20ms Sampled 3 values");
+        assert_eq!(logs[4].1, "+5ms Hello");
+    }
+
+    #[test]
+    fn test_timestamp_logger() {
+        use crate::format::{FormattedLogger, JsonFormatter, TextFormatter};
+
+        let time = Cell::new(Duration::from_millis(1_700_000_000_123));
+        let output = RefCell::new(Vec::new());
+        let sink = |bytes: &[u8]| output.borrow_mut().extend_from_slice(bytes);
+        let logger = TimestampLogger::new(
+            FormattedLogger::new(TextFormatter::new(), sink)
+                .chain(FormattedLogger::new(JsonFormatter::new(), sink)),
+            || {
+                let now = time.get();
+                time.set(now + Duration::from_secs(1));
+                now
+            },
+        );
+
+        crate::info!(logger, "Hello");
+
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        assert!(output.starts_with("2023-11-14T22:13:20.123Z INFO "));
+        assert!(output.contains(r#"{"timestamp":"2023-11-14T22:13:20.123Z","#));
+    }
+
+    #[test]
+    fn test_timestamp_logger_values() {
+        use crate::format::{FormattedLogger, JsonFormatter};
+
+        let output = RefCell::new(Vec::new());
+        let logger = TimestampLogger::new(
+            FormattedLogger::new(JsonFormatter::new(), |bytes: &[u8]| {
+                output.borrow_mut().extend_from_slice(bytes)
+            }),
+            || Duration::ZERO,
+        );
+
+        logger
+            .at(ULogLevel::Info)
+            .msg("Sampled")
+            .value("count", 3)
+            .value("ok", true)
+            .emit();
+
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        assert!(output.ends_with("\"msg\":\"Sampled\",\"count\":3,\"ok\":true}\n"));
+    }
+
+    #[test]
+    fn test_rfc3339() {
+        let format = |millis: u64| Rfc3339(Duration::from_millis(millis)).to_string();
+
+        assert_eq!(format(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(format(951_782_400_000), "2000-02-29T00:00:00.000Z");
+        assert_eq!(format(1_700_000_000_123), "2023-11-14T22:13:20.123Z");
+        assert_eq!(format(4_102_444_799_999), "2099-12-31T23:59:59.999Z");
+    }
+}
@@ -0,0 +1,383 @@
+use super::record::{Record, Segment};
+#[cfg(feature = "serde")]
+use super::serialize::SerializeJson;
+use super::{Level, ULog, ULogData, ULogLevel, Value};
+use core::cell::RefCell;
+use core::fmt;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// The levels of `tracing` can be used with the macros, as `ulog!(tracing::Level::WARN, logger, "message")`.
+///
+/// As with `tracing`, they are ordered from the least to the most verbose; loggers compare the converted levels.
+impl Level for tracing::Level {
+    fn as_str(&self) -> &'static str {
+        tracing::Level::as_str(self)
+    }
+
+    fn to_ulog_level(&self) -> ULogLevel {
+        match *self {
+            tracing::Level::ERROR => ULogLevel::Error,
+            tracing::Level::WARN => ULogLevel::Warning,
+            tracing::Level::INFO => ULogLevel::Info,
+            tracing::Level::DEBUG => ULogLevel::Debug,
+            tracing::Level::TRACE => ULogLevel::Trace,
+        }
+    }
+}
+
+/// A [`Layer`] forwarding the events of `tracing` to a [`ULog`], so that the statements of the crates
+/// instrumented with `tracing` go through the same loggers as the ones of the application.
+///
+/// The `message` field of the event is logged with [`log_fmt`](ULog::log_fmt), followed by its other fields.
+/// The file, line, module path and target of the event are kept in the [`ULogData`] of the statement.
+/// Spans are not forwarded, only the events made inside of them.
+///
+/// ```
+/// use tracing_subscriber::layer::SubscriberExt;
+/// use ulog::common::StubLogger;
+/// use ulog::tracing_bridge::ULogLayer;
+///
+/// let subscriber = tracing_subscriber::registry().with(ULogLayer::new(StubLogger));
+/// tracing::subscriber::with_default(subscriber, || {
+///     tracing::info!(port = 8080, "Sent to the StubLogger");
+/// });
+/// ```
+#[derive(Debug, Default)]
+pub struct ULogLayer<Logger> {
+    logger: Logger,
+}
+
+impl<Logger: ULog> ULogLayer<Logger> {
+    pub const fn new(logger: Logger) -> Self {
+        Self { logger }
+    }
+
+    pub fn logger(&self) -> &Logger {
+        &self.logger
+    }
+
+    pub fn into_inner(self) -> Logger {
+        self.logger
+    }
+}
+
+impl<S: Subscriber, Logger: ULog + Send + Sync + 'static> Layer<S> for ULogLayer<Logger> {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut log_data = ULogData::new(
+            metadata.level().to_ulog_level(),
+            metadata.line().unwrap_or(0),
+            metadata.file().unwrap_or("<unknown>"),
+        )
+        .with_target(metadata.target());
+        if let Some(module) = metadata.module_path() {
+            log_data = log_data.with_module(module);
+        }
+
+        if !self.logger.enabled(&log_data) {
+            return;
+        }
+
+        self.logger.log_begin(&log_data);
+        // The message comes last in the fields of an event, but first in a statement
+        event.record(&mut FieldVisitor {
+            logger: &self.logger,
+            log_data: &log_data,
+            message: true,
+        });
+        event.record(&mut FieldVisitor {
+            logger: &self.logger,
+            log_data: &log_data,
+            message: false,
+        });
+        self.logger.log_end(&log_data);
+    }
+}
+
+/// Logs either the `message` field of an event, or its other fields.
+struct FieldVisitor<'a, Logger> {
+    logger: &'a Logger,
+    log_data: &'a ULogData,
+    message: bool,
+}
+
+impl<Logger: ULog> Visit for FieldVisitor<'_, Logger> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() != "message" {
+            self.record_debug(field, &value);
+        } else if self.message {
+            self.logger.log_str(self.log_data, value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() != "message" {
+            if !self.message {
+                self.logger.log_format(self.log_data, field.name(), &value);
+            }
+        } else if self.message {
+            self.logger
+                .log_fmt(self.log_data, format_args!("{:?}", value));
+        }
+    }
+}
+
+/// Forwards statements to the current subscriber of `tracing`, so that libraries written against ulog can be
+/// used in applications set up with `tracing` subscribers, like the ones of `tracing-subscriber`.
+///
+/// Each statement is captured into a [`Record`] of `SIZE` bytes, then emitted as an event when it ends:
+/// its strings, joined with spaces, make up the message, and its key-value pairs are joined into an
+/// `ulog.fields` field. Since `tracing` needs the target of an event to be known at compile time, events
+/// are all emitted with the `ulog` target, and the target, module path, file and line of the statement are given
+/// as the `ulog.target`, `ulog.module_path`, `ulog.file` and `ulog.line` fields.
+/// [`Notice`](ULogLevel::Notice) is logged as `INFO`, and [`Critical`](ULogLevel::Critical) as `ERROR`.
+///
+/// Statements must not be sent back to a [`ULogLayer`] forwarding to this logger, as they would loop forever.
+///
+/// ```
+/// use ulog::tracing_bridge::TracingLogger;
+///
+/// let logger = TracingLogger::<128>::new();
+/// ulog::info!(logger, "Connected", "port" => 8080); // Emitted with `tracing::event!`
+/// ```
+pub struct TracingLogger<const SIZE: usize> {
+    current: RefCell<Option<Record<SIZE>>>,
+}
+
+impl<const SIZE: usize> TracingLogger<SIZE> {
+    pub const fn new() -> Self {
+        Self {
+            current: RefCell::new(None),
+        }
+    }
+}
+
+impl<const SIZE: usize> Default for TracingLogger<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Emits an event with the given level and the fields of a statement; `tracing` needs the level to be a constant.
+macro_rules! emit_event {
+    ($level:expr, $log_data:expr, $record:expr) => {
+        tracing::event!(
+            target: "ulog",
+            $level,
+            ulog.target = $log_data.target,
+            ulog.module_path = $log_data.module,
+            ulog.file = $log_data.file,
+            ulog.line = $log_data.line,
+            ulog.fields = %Fields($record),
+            "{}",
+            Message($record)
+        )
+    };
+}
+
+impl<const SIZE: usize> ULog for TracingLogger<SIZE> {
+    fn log_str(&self, _log_data: &ULogData, string: &str) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_str(string);
+        }
+    }
+
+    fn log_str_continued(&self, _log_data: &ULogData, string: &str) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_str_continued(string);
+        }
+    }
+
+    fn log_fmt(&self, _log_data: &ULogData, args: fmt::Arguments<'_>) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_fmt(args);
+        }
+    }
+
+    fn log_format<T: fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_format(key, value);
+        }
+    }
+
+    fn log_value(&self, _log_data: &ULogData, key: &str, value: Value<'_>) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_value(key, value);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn log_serialize(&self, _log_data: &ULogData, key: &str, value: &dyn SerializeJson) {
+        if let Some(record) = self.current.borrow_mut().as_mut() {
+            record.push_serialize(key, value);
+        }
+    }
+
+    fn log_begin(&self, log_data: &ULogData) {
+        *self.current.borrow_mut() = Some(Record::new(log_data.clone()));
+    }
+
+    fn log_end(&self, log_data: &ULogData) {
+        let Some(record) = self.current.borrow_mut().take() else {
+            return;
+        };
+
+        match log_data.level {
+            ULogLevel::Trace => emit_event!(tracing::Level::TRACE, log_data, &record),
+            ULogLevel::Debug => emit_event!(tracing::Level::DEBUG, log_data, &record),
+            ULogLevel::Info | ULogLevel::Notice => {
+                emit_event!(tracing::Level::INFO, log_data, &record)
+            }
+            ULogLevel::Warning => emit_event!(tracing::Level::WARN, log_data, &record),
+            ULogLevel::Error | ULogLevel::Critical => {
+                emit_event!(tracing::Level::ERROR, log_data, &record)
+            }
+        }
+    }
+
+    fn enabled(&self, log_data: &ULogData) -> bool {
+        match log_data.level {
+            ULogLevel::Trace => tracing::enabled!(target: "ulog", tracing::Level::TRACE),
+            ULogLevel::Debug => tracing::enabled!(target: "ulog", tracing::Level::DEBUG),
+            ULogLevel::Info | ULogLevel::Notice => {
+                tracing::enabled!(target: "ulog", tracing::Level::INFO)
+            }
+            ULogLevel::Warning => tracing::enabled!(target: "ulog", tracing::Level::WARN),
+            ULogLevel::Error | ULogLevel::Critical => {
+                tracing::enabled!(target: "ulog", tracing::Level::ERROR)
+            }
+        }
+    }
+}
+
+/// The strings of a [`Record`], joined with spaces.
+struct Message<'a, const SIZE: usize>(&'a Record<SIZE>);
+
+impl<const SIZE: usize> fmt::Display for Message<'_, SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let strings = self.0.segments().filter_map(|segment| match segment {
+            Segment::Str(string) => Some(string),
+            Segment::Field(..) | Segment::Value(..) | Segment::Serialized(..) => None,
+        });
+        for (index, string) in strings.enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(string)?;
+        }
+        Ok(())
+    }
+}
+
+/// The key-value pairs of a [`Record`], as `key=value` joined with spaces.
+struct Fields<'a, const SIZE: usize>(&'a Record<SIZE>);
+
+impl<const SIZE: usize> fmt::Display for Fields<'_, SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = self
+            .0
+            .segments()
+            .filter(|segment| !matches!(segment, Segment::Str(_)));
+        for (index, segment) in fields.enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            match segment {
+                Segment::Field(key, value) | Segment::Serialized(key, value) => {
+                    write!(f, "{key}={value}")?
+                }
+                Segment::Value(key, value) => write!(f, "{key}={value:?}")?,
+                Segment::Str(_) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Mutex;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Default)]
+    struct SyncLogger {
+        logs: Mutex<Vec<String>>,
+    }
+
+    impl ULog for SyncLogger {
+        fn log_str(&self, _log_data: &ULogData, string: &str) {
+            self.logs.lock().unwrap().push(string.to_string());
+        }
+
+        fn log_format<T: fmt::Debug>(&self, _log_data: &ULogData, key: &str, value: &T) {
+            self.logs
+                .lock()
+                .unwrap()
+                .push(format!("{key} => {value:?}"));
+        }
+
+        fn log_begin(&self, log_data: &ULogData) {
+            self.logs
+                .lock()
+                .unwrap()
+                .push(format!("{} {:?}", log_data.level, log_data.target));
+        }
+
+        fn log_end(&self, _log_data: &ULogData) {}
+    }
+
+    #[test]
+    fn test_ulog_layer() {
+        let logger: &'static SyncLogger = Box::leak(Box::default());
+        let subscriber = tracing_subscriber::registry().with(ULogLayer::new(logger));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(port = 8080, name = "eth0", "read {} bytes", 12);
+            tracing::debug!(target: "net", "Hello");
+        });
+
+        assert_eq!(
+            logger.logs.lock().unwrap()[..],
+            [
+                "WARN Some(\"ulog::tracing_bridge::test\")",
+                "read 12 bytes",
+                "port => 8080",
+                "name => \"eth0\"",
+                "DEBUG Some(\"net\")",
+                "Hello",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tracing_logger() {
+        // Statements make a round trip through `tracing`
+        let logger: &'static SyncLogger = Box::leak(Box::default());
+        let subscriber = tracing_subscriber::registry().with(ULogLayer::new(logger));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let tracing_logger = TracingLogger::<64>::new();
+            let log_data = ULogData::new(ULogLevel::Critical, 1, "main.rs").with_target("app");
+            tracing_logger.log_begin(&log_data);
+            tracing_logger.log_str(&log_data, "Connected to");
+            tracing_logger.log_str(&log_data, "server");
+            tracing_logger.log_format(&log_data, "port", &8080);
+            tracing_logger.log_end(&log_data);
+        });
+
+        assert_eq!(
+            logger.logs.lock().unwrap()[..],
+            [
+                "ERROR Some(\"ulog\")",
+                "Connected to server",
+                "ulog.target => \"app\"",
+                "ulog.file => \"main.rs\"",
+                "ulog.line => 1",
+                "ulog.fields => port=8080",
+            ]
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! Procedural macros for [ulog](https://docs.rs/ulog), re-exported by it with the `macros` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    parse_macro_input, Block, Data, DeriveInput, Expr, Fields, Ident, Index, Item, ItemFn, LitStr,
+    Macro, Member, Path, ReturnType, Stmt, Token, Type,
+};
+
+/// The arguments of `#[instrument]`.
+struct InstrumentArgs {
+    logger: Expr,
+    level: Option<Expr>,
+    fields: Vec<Ident>,
+    ret: bool,
+}
+
+impl Parse for InstrumentArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut logger = None;
+        let mut level = None;
+        let mut fields = Vec::new();
+        let mut ret = false;
+
+        while !input.is_empty() {
+            let name: Ident = input.parse()?;
+            match name.to_string().as_str() {
+                "logger" => {
+                    input.parse::<Token![=]>()?;
+                    logger = Some(input.parse()?);
+                }
+                "level" => {
+                    input.parse::<Token![=]>()?;
+                    level = Some(input.parse()?);
+                }
+                "fields" => {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    fields.extend(Punctuated::<Ident, Token![,]>::parse_terminated(&content)?);
+                }
+                "ret" => ret = true,
+                _ => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        "expected `logger`, `level`, `fields` or `ret`",
+                    ))
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        let logger = logger.ok_or_else(|| input.error("missing `logger = ...`"))?;
+
+        Ok(Self {
+            logger,
+            level,
+            fields,
+            ret,
+        })
+    }
+}
+
+/// Logs when the function is entered, with the arguments listed in `fields(...)`, and when it returns,
+/// with its return value if `ret` is given.
+///
+/// `logger` is an expression evaluated each time a statement is logged, like `self.logger`;
+/// `level` is a [`ULogLevel`](https://docs.rs/ulog/latest/ulog/enum.ULogLevel.html) variant or an expression
+/// implementing `Level`, and defaults to `Debug`.
+#[proc_macro_attribute]
+pub fn instrument(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as InstrumentArgs);
+    let function = parse_macro_input!(item as ItemFn);
+
+    match instrument_function(args, function) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn instrument_function(args: InstrumentArgs, function: ItemFn) -> syn::Result<TokenStream2> {
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = function;
+
+    if let Some(asyncness) = sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "`#[instrument]` does not support async functions",
+        ));
+    }
+
+    let logger = &args.logger;
+    let level = match &args.level {
+        Some(Expr::Path(path)) if path.path.get_ident().is_some() => {
+            quote!(::ulog::ULogLevel::#path)
+        }
+        Some(level) => level.to_token_stream(),
+        None => quote!(::ulog::ULogLevel::Debug),
+    };
+    let name = LitStr::new(&sig.ident.to_string(), sig.ident.span());
+    let fields = args.fields.iter().map(|field| {
+        let key = LitStr::new(&field.to_string(), field.span());
+        quote!(#key => #field)
+    });
+
+    // Closures cannot be annotated with an `impl Trait` return type, so it is left to inference then
+    let return_type = match &sig.output {
+        ReturnType::Type(_, ty) if !matches!(**ty, Type::ImplTrait(_)) => quote!(-> #ty),
+        _ => TokenStream2::new(),
+    };
+    let exit = if args.ret {
+        quote!(::ulog::ulog!(#level, #logger, #name, "span" =>% "exit", "return" => __ulog_return))
+    } else {
+        quote!(::ulog::ulog!(#level, #logger, #name, "span" =>% "exit"))
+    };
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis #sig {
+            ::ulog::ulog!(#level, #logger, #name, "span" =>% "enter" #(, #fields)*);
+
+            #[allow(clippy::redundant_closure_call)]
+            let __ulog_return = (|| #return_type #block)();
+
+            #exit;
+            __ulog_return
+        }
+    })
+}
+
+/// Implements `ULog` for a struct by forwarding every method to the field marked with `#[ulog(delegate)]`.
+#[proc_macro_derive(ULog, attributes(ulog))]
+pub fn derive_ulog(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+
+    match derive_delegate(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn derive_delegate(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`#[derive(ULog)]` only supports structs",
+        ));
+    };
+
+    let mut delegate = None;
+    let fields: Box<dyn Iterator<Item = _>> = match &data.fields {
+        Fields::Named(fields) => Box::new(fields.named.iter()),
+        Fields::Unnamed(fields) => Box::new(fields.unnamed.iter()),
+        Fields::Unit => Box::new(core::iter::empty()),
+    };
+    for (index, field) in fields.enumerate() {
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("ulog"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("delegate") {
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `delegate`"))
+                }
+            })?;
+
+            if delegate.is_some() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "only one field can be marked with `#[ulog(delegate)]`",
+                ));
+            }
+            let member = match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(Index::from(index)),
+            };
+            delegate = Some((member, field.ty.clone()));
+        }
+    }
+
+    let Some((member, ty)) = delegate else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`#[derive(ULog)]` needs a field marked with `#[ulog(delegate)]`",
+        ));
+    };
+
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(syn::parse_quote!(#ty: ::ulog::ULog));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::ulog::ULog for #name #ty_generics #where_clause {
+            ::ulog::__ulog_delegate!(#member);
+        }
+    })
+}
+
+/// The levels known to `#[module_level]`, from the most to the least verbose.
+const LEVELS: [(&str, &str); 7] = [
+    ("Trace", "trace"),
+    ("Debug", "debug"),
+    ("Info", "info"),
+    ("Notice", "notice"),
+    ("Warning", "warn"),
+    ("Error", "error"),
+    ("Critical", "critical"),
+];
+
+/// Removes the ulog statements below a level from the item it is placed on, such as an inline module,
+/// so that they cost nothing at runtime or in the binary.
+///
+/// The level is a `ULogLevel` variant, or `Off` to remove every statement. The statements are recognized by
+/// the name of their macro, called either directly or through the `ulog::` path; the level of `ulog!` and
+/// `log_once!` statements is only known when it is written as a variant, like `ULogLevel::Debug`.
+/// The arguments of removed statements are not evaluated.
+#[proc_macro_attribute]
+pub fn module_level(args: TokenStream, item: TokenStream) -> TokenStream {
+    let level = parse_macro_input!(args as Ident);
+    let mut item = parse_macro_input!(item as Item);
+
+    let min_rank = if level == "Off" {
+        LEVELS.len()
+    } else {
+        match LEVELS.iter().position(|(name, _)| level == name) {
+            Some(rank) => rank,
+            None => {
+                return syn::Error::new(level.span(), "expected a `ULogLevel` variant or `Off`")
+                    .to_compile_error()
+                    .into()
+            }
+        }
+    };
+
+    StatementRemover { min_rank }.visit_item_mut(&mut item);
+    item.into_token_stream().into()
+}
+
+struct StatementRemover {
+    min_rank: usize,
+}
+
+impl StatementRemover {
+    fn removes(&self, mac: &Macro) -> bool {
+        statement_rank(mac).is_some_and(|rank| rank < self.min_rank)
+    }
+}
+
+impl VisitMut for StatementRemover {
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        block
+            .stmts
+            .retain(|stmt| !matches!(stmt, Stmt::Macro(stmt) if stmt.semi_token.is_some() && self.removes(&stmt.mac)));
+
+        for stmt in &mut block.stmts {
+            if matches!(stmt, Stmt::Macro(stmt) if self.removes(&stmt.mac)) {
+                *stmt = Stmt::Expr(syn::parse_quote!(()), None);
+            }
+        }
+
+        visit_mut::visit_block_mut(self, block);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if matches!(expr, Expr::Macro(expr) if self.removes(&expr.mac)) {
+            *expr = syn::parse_quote!(());
+        } else {
+            visit_mut::visit_expr_mut(self, expr);
+        }
+    }
+}
+
+/// Returns the rank of the level of a ulog statement, or `None` if it is not one or its level is unknown.
+fn statement_rank(mac: &Macro) -> Option<usize> {
+    let name = ulog_macro_name(&mac.path)?;
+
+    if name == "ulog" || name == "log_once" {
+        // The level is the first argument, after an optional `target: expr,` and an optional `user: expr,`
+        let mut args = mac.tokens.clone().into_iter().peekable();
+        while matches!(args.peek(), Some(TokenTree::Ident(ident)) if ident == "target" || ident == "user")
+        {
+            args.by_ref()
+                .find(|token| matches!(token, TokenTree::Punct(punct) if punct.as_char() == ','));
+        }
+        let level: TokenStream2 = args
+            .take_while(|token| !matches!(token, TokenTree::Punct(punct) if punct.as_char() == ','))
+            .collect();
+        let level: Path = syn::parse2(level).ok()?;
+        let variant = level.segments.last()?.ident.to_string();
+        LEVELS.iter().position(|(name, _)| *name == variant)
+    } else {
+        let name = name.strip_suffix("_once").unwrap_or(&name);
+        LEVELS
+            .iter()
+            .position(|(_, macro_name)| *macro_name == name)
+    }
+}
+
+/// Returns the name of a macro called either as `name!` or `ulog::name!`.
+fn ulog_macro_name(path: &Path) -> Option<String> {
+    match path.segments.len() {
+        1 => {}
+        2 if path.segments[0].ident == "ulog" => {}
+        _ => return None,
+    }
+    path.segments
+        .last()
+        .map(|segment| segment.ident.to_string())
+}